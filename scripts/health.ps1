@@ -51,6 +51,12 @@ if (Test-Path $HBPath) {
       LastNew    = [int]$hb.new
       Alive      = ($age -lt 120)
     } | Format-List
+    if ($hb.pipeline) {
+      $hb.pipeline | Select-Object depth, max_depth, capacity, dropped, coalesced | Format-List
+    }
+    if ($hb.caches) {
+      $hb.caches | Select-Object name, len, capacity, hits, misses | Format-Table -AutoSize
+    }
   } catch {
     Write-Warning "Failed to parse heartbeat.json: $_"
     Get-Content $HBPath
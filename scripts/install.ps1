@@ -12,6 +12,7 @@ $Root = Split-Path -Parent $Src
 $exeSrc   = Join-Path $Root "target\release\glpi-notifier-rs.exe"
 $snoreSrc = Join-Path $Root "snoretoast.exe"
 $logoSrc  = Join-Path $Root "assets\logo.png"
+$contrastSrc = Join-Path $Root "assets\logo-contrast.png"
 $healthSrc = Join-Path $Src "health.ps1"   # <-- copy if present
 
 if (-not (Test-Path $exeSrc)) {
@@ -23,6 +24,7 @@ if (-not (Test-Path $exeSrc)) {
 if (Test-Path $snoreSrc)  { Copy-Item -Path $snoreSrc -Destination $InstallDir -Force }
 if (Test-Path $logoSrc)   { New-Item -ItemType Directory -Force -Path (Join-Path $InstallDir "assets") | Out-Null
                              Copy-Item -Path $logoSrc -Destination (Join-Path $InstallDir "assets") -Force }
+if (Test-Path $contrastSrc) { Copy-Item -Path $contrastSrc -Destination (Join-Path $InstallDir "assets") -Force }
 
 # .env – create from template if missing
 $EnvDest = Join-Path $InstallDir ".env"
@@ -52,7 +54,8 @@ $Launcher = Join-Path $InstallDir "Run-GlpiNotifier.cmd"
 cd /d "%~dp0"
 set "RUST_LOG=info"
 set "LOG=%LOCALAPPDATA%\Programs\GlpiNotifier\glpi-notifier.log"
-"%~dp0glpi-notifier-rs.exe" >> "%LOG%" 2>&1
+set "LOG_FILE=%LOG%"
+"%~dp0glpi-notifier-rs.exe" --env-file "%~dp0.env" >> "%LOG%" 2>&1
 "@ | Out-File -FilePath $Launcher -Encoding ASCII -Force
 
 # Health script: prefer copying from repo; if missing, generate fallback
@@ -140,6 +143,6 @@ Register-ScheduledTask -TaskName $TaskName -Action $Action -Trigger $Trigger -Se
 
 # Start now and fire a test toast
 Start-ScheduledTask -TaskName $TaskName
-Start-Process -FilePath (Join-Path $InstallDir "glpi-notifier-rs.exe") -ArgumentList "--test-toast"
+Start-Process -FilePath (Join-Path $InstallDir "glpi-notifier-rs.exe") -ArgumentList "test-toast"
 
 Write-Host "Installed to $InstallDir. Scheduled Task 'GlpiNotifier' registered."
\ No newline at end of file
@@ -0,0 +1,56 @@
+//! Accessibility mode (ACCESSIBILITY=true) for technicians working with a screen reader or a high-contrast
+//! theme: notifications lose their emoji and symbols and put each detail on a line of its own behind an explicit
+//! label (`Location: Lisbon > Building B`), read in the same order every time; ACCESSIBILITY_VERBOSITY sets how
+//! many details are read out. The toast image becomes the high-contrast logo (`logo-contrast.png`), and the
+//! flyout rows and the `queue` listing drop their symbols too.
+
+use crate::glpi::Ticket;
+
+/// How much a ticket notification says (ACCESSIBILITY_VERBOSITY).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// The title only.
+    Brief,
+    /// Title, major incident, location, requester, opening time and time-to-own countdown (default).
+    Normal,
+    /// Everything, including priority, category, entity, attachments and references.
+    Full,
+}
+
+pub fn enabled() -> bool {
+    mode().is_some()
+}
+
+/// The verbosity of the accessibility mode, None when it is off.
+pub fn mode() -> Option<Verbosity> {
+    crate::config::get().accessibility
+}
+
+/// Body of a ticket notification: one labelled detail per line, the title first.
+pub fn ticket_body(t: &Ticket, verbosity: Verbosity) -> String {
+    let mut lines = vec![format!("Title: {}", if t.name.is_empty() { "New ticket" } else { t.name.as_str() })];
+    if verbosity == Verbosity::Brief {
+        return lines.join("\n");
+    }
+    lines.extend(t.major_incident.map(|mi| format!("Linked to major incident: #{mi}")));
+    lines.extend(t.location.as_ref().map(|l| format!("Location: {l}")));
+    lines.push(format!("Requester: {}", t.requester.as_deref().unwrap_or("unknown")));
+    lines.extend(t.opened_display().map(|o| format!("Opened: {o}")));
+    lines.extend(t.time_to_own.as_deref().and_then(|d| crate::calendar::countdown("Time to own", d)));
+    if verbosity == Verbosity::Full {
+        lines.extend(t.priority.map(|p| format!("Priority: {p} ({:?})", crate::severity::of(Some(p)))));
+        lines.extend(t.category.as_ref().map(|c| format!("Category: {c}")));
+        lines.extend(t.entity.as_ref().map(|e| format!("Entity: {e}")));
+        lines.extend(t.documents.filter(|n| *n > 0).map(|n| format!("Attachments: {n}")));
+        lines.extend(crate::refs::summary(&crate::refs::of(t)).map(|r| format!("References: {r}")));
+    }
+    lines.join("\n")
+}
+
+/// One change of a ticket update, as a sentence: `Status changed from Pending to Processing (assigned)`.
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast", feature = "notify-dbus", feature = "notify-macos"))]
+pub fn change(field: &str, from: &str, to: &str) -> String {
+    let mut chars = field.chars();
+    let field: String = chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default();
+    format!("{field} changed from {from} to {to}")
+}
@@ -0,0 +1,98 @@
+//! `bench-search [--iterations N]`: times the two calls the poller depends on against the configured server,
+//! `listSearchOptions/Ticket` (uncached) and the New-ticket search (with TICKET_FILTER, same window as a poll),
+//! and prints latency percentiles. Slow plugins hooking the search show up here, not in the notifier.
+
+use crate::config::Connection;
+use crate::glpi::GlpiClient;
+
+use anyhow::{anyhow, Result};
+use std::time::{Duration, Instant};
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let iterations: usize = match args.iter().position(|a| a == "--iterations") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|s| s.parse().ok())
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!("--iterations needs a positive number"))?,
+        None => 20,
+    };
+    let filter = crate::filter::from_env()?;
+    let conn = Connection::from_env()?;
+    println!("Benchmarking {} ({iterations} iterations)...", conn.base_url);
+
+    let mut client = conn.connect().await?;
+    let timings = measure(&mut client, &filter, iterations).await;
+    let _ = client.kill_session().await;
+    let (options, search) = timings?;
+
+    report("listSearchOptions/Ticket", &options);
+    report("search/Ticket (New)", &search);
+
+    // A poll is essentially one search: keep it under ~5% of the interval
+    let poll_secs = crate::config::get().poll_seconds;
+    let slow = percentile(&search, 90);
+    let suggested = (slow.as_secs_f64() * 20.0).ceil().max(10.0) as u64;
+    println!(
+        "\nPOLL_SECONDS={poll_secs}; with a p90 search of {} ms, {suggested} s or more is advised.",
+        slow.as_millis()
+    );
+    if poll_secs < suggested {
+        println!("Consider raising POLL_SECONDS, or check the GLPI plugins that hook the ticket search.");
+    }
+    Ok(())
+}
+
+async fn measure(
+    client: &mut GlpiClient,
+    filter: &[crate::filter::Criterion],
+    iterations: usize,
+) -> Result<(Vec<Duration>, Vec<Duration>)> {
+    let fields = client.resolve_ticket_fields().await?;
+    let (mut options, mut search) = (Vec::new(), Vec::new());
+    for _ in 0..iterations {
+        let started = Instant::now();
+        client.fetch_search_options("Ticket").await?;
+        options.push(started.elapsed());
+
+        let started = Instant::now();
+        client.search_new_page(&fields, filter, crate::SEARCH_WINDOW).await?;
+        search.push(started.elapsed());
+    }
+    Ok((options, search))
+}
+
+fn report(what: &str, samples: &[Duration]) {
+    let ms = |p| percentile(samples, p).as_millis();
+    println!(
+        "{what:<26} min {:>5} ms   p50 {:>5} ms   p90 {:>5} ms   p99 {:>5} ms   max {:>5} ms",
+        ms(0),
+        ms(50),
+        ms(90),
+        ms(99),
+        ms(100)
+    );
+}
+
+/// Nearest-rank percentile (`p` in 0..=100) of unsorted samples.
+fn percentile(samples: &[Duration], p: usize) -> Duration {
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let samples: Vec<Duration> = (1..=20).rev().map(Duration::from_millis).collect();
+        assert_eq!(percentile(&samples, 0), Duration::from_millis(1));
+        assert_eq!(percentile(&samples, 50), Duration::from_millis(10));
+        assert_eq!(percentile(&samples, 90), Duration::from_millis(18));
+        assert_eq!(percentile(&samples, 100), Duration::from_millis(20));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Size- and age-bounded LRU cache with hit/miss counters, safe to share from a static.
+#[derive(Debug)]
+pub struct Cache<K, V> {
+    name: &'static str,
+    capacity: usize,
+    ttl: Duration,
+    entries: Mutex<HashMap<K, Entry<V>>>,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Entry<V> {
+    value: V,
+    inserted: Instant,
+    last_used: u64,
+}
+
+/// Counters of one cache, written to heartbeat.json.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub name: String,
+    pub len: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> Cache<K, V> {
+    pub fn new(name: &'static str, capacity: usize, ttl: Duration) -> Self {
+        Self {
+            name,
+            capacity: capacity.max(1),
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Cached value, unless missing or older than the TTL (expired entries are dropped).
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        let fresh = entries.get(key).is_some_and(|e| e.inserted.elapsed() < self.ttl);
+        if !fresh {
+            entries.remove(key);
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let entry = entries.get_mut(key)?;
+        entry.last_used = self.clock.fetch_add(1, Ordering::Relaxed);
+        Some(entry.value.clone())
+    }
+
+    /// Store a value, evicting the least recently used entry when full.
+    pub fn insert(&self, key: K, value: V) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.capacity && !entries.contains_key(&key) {
+            let oldest = entries.iter().min_by_key(|(_, e)| e.last_used).map(|(k, _)| k.clone());
+            if let Some(k) = oldest {
+                entries.remove(&k);
+            }
+        }
+        let last_used = self.clock.fetch_add(1, Ordering::Relaxed);
+        entries.insert(key, Entry { value, inserted: Instant::now(), last_used });
+    }
+
+    /// Every unexpired value, without touching the counters or the LRU order.
+    pub fn values(&self) -> Vec<V> {
+        let entries = self.entries.lock().unwrap();
+        entries.values().filter(|e| e.inserted.elapsed() < self.ttl).map(|e| e.value.clone()).collect()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            name: self.name.to_string(),
+            len: self.entries.lock().unwrap().len(),
+            capacity: self.capacity,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counters of every process-wide cache.
+pub fn all_stats() -> Vec<CacheStats> {
+    vec![crate::glpi::SEARCH_OPTIONS.stats(), crate::glpi::MAJOR_INCIDENTS.stats(), crate::tickets::TICKETS.stats()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used_and_counts() {
+        let cache = Cache::new("test", 2, Duration::from_secs(60));
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        assert_eq!(cache.get(&1), Some("a")); // 2 is now the least recently used
+        cache.insert(3, "c");
+
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&3), Some("c"));
+        let stats = cache.stats();
+        assert_eq!((stats.len, stats.hits, stats.misses), (2, 2, 1));
+    }
+
+    #[test]
+    fn values_leave_the_counters_alone() {
+        let cache = Cache::new("test", 4, Duration::from_secs(60));
+        cache.insert(1, "a");
+        cache.insert(2, "b");
+        let mut values = cache.values();
+        values.sort_unstable();
+        assert_eq!(values, vec!["a", "b"]);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (0, 0));
+
+        let expired = Cache::new("test", 2, Duration::ZERO);
+        expired.insert(1, "a");
+        assert!(expired.values().is_empty());
+    }
+
+    #[test]
+    fn expired_entries_miss() {
+        let cache = Cache::new("test", 2, Duration::ZERO);
+        cache.insert(1, "a");
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.stats().len, 0);
+    }
+}
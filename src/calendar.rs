@@ -0,0 +1,241 @@
+//! Business-hours calendar for SLA countdowns (SLA_CALENDAR).
+//!
+//! Either a local week, e.g. `SLA_CALENDAR=mon-fri 08:00-12:00,13:00-18:00; sat 09:00-12:00`, or
+//! `SLA_CALENDAR=glpi:<id>` to read the opening hours and holidays of a GLPI calendar at startup.
+//! Hours are in the GLPI server's timezone (GLPI_SERVER_TZ), like the SLA due dates.
+//! Without a calendar, countdowns are wall-clock time.
+
+use crate::clock;
+use crate::exit::ConfigError;
+use crate::glpi::{CalendarSegment, GlpiClient, Holiday};
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use log::warn;
+use once_cell::sync::OnceCell;
+
+/// Calendar countdowns are measured against, resolved at startup (None: wall-clock).
+pub static CALENDAR: OnceCell<Option<Calendar>> = OnceCell::new();
+
+/// Longest span walked day by day; SLA targets are hours or days away, not years.
+const MAX_DAYS: i64 = 366;
+
+/// Where SLA_CALENDAR takes its hours from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Local(Calendar),
+    /// Id of a GLPI Calendar (Setup > Dropdowns > Calendars).
+    Glpi(i64),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Calendar {
+    /// Opening periods per weekday, Monday first.
+    week: [Vec<(NaiveTime, NaiveTime)>; 7],
+    /// Closed days (first, last, every year).
+    holidays: Vec<(NaiveDate, NaiveDate, bool)>,
+}
+
+/// Parse SLA_CALENDAR; empty means wall-clock.
+pub fn parse(raw: &str) -> Result<Option<Source>> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    if let Some(id) = raw.strip_prefix("glpi:") {
+        let id = id
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| ConfigError(format!("SLA_CALENDAR: invalid calendar id in '{raw}'")))?;
+        return Ok(Some(Source::Glpi(id)));
+    }
+
+    let mut cal = Calendar::default();
+    for clause in raw.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+        let bad = |why: &str| {
+            ConfigError(format!("SLA_CALENDAR: {why} in '{clause}' (expected e.g. mon-fri 08:00-12:00,13:00-18:00)"))
+        };
+        let (days, hours) = clause.split_once(char::is_whitespace).ok_or_else(|| bad("missing hours"))?;
+        let day = |s: &str| weekday(s).ok_or_else(|| bad("unknown day (mon ... sun)"));
+        let (first, last) = match days.split_once('-') {
+            Some((a, b)) => (day(a)?, day(b)?),
+            None => (day(days)?, day(days)?),
+        };
+        if first > last {
+            return Err(bad("days must run Monday to Sunday").into());
+        }
+        for period in hours.split(',').map(str::trim) {
+            let time = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").map_err(|_| bad("invalid time"));
+            let (begin, end) = period.split_once('-').ok_or_else(|| bad("invalid hours"))?;
+            let (begin, end) = (time(begin)?, time(end)?);
+            if begin >= end {
+                return Err(bad("hours must end after they begin").into());
+            }
+            for d in first..=last {
+                cal.week[d].push((begin, end));
+            }
+        }
+    }
+    Ok(Some(Source::Local(cal)))
+}
+
+/// Resolve SLA_CALENDAR once, reading the GLPI calendar when one is named.
+pub async fn init(client: &mut GlpiClient) {
+    if CALENDAR.get().is_some() {
+        return;
+    }
+    let cal = match &crate::config::get().sla_calendar {
+        Some(Source::Local(cal)) => Some(cal.clone()),
+        Some(Source::Glpi(id)) => match client.calendar(*id).await {
+            Ok((segments, holidays)) => Some(Calendar::from_glpi(&segments, &holidays)),
+            Err(e) => {
+                warn!("SLA_CALENDAR: GLPI calendar {id} unavailable, SLA countdowns use wall-clock time: {e:#}");
+                None
+            }
+        },
+        None => None,
+    };
+    let _ = CALENDAR.set(cal);
+}
+
+fn weekday(s: &str) -> Option<usize> {
+    ["mon", "tue", "wed", "thu", "fri", "sat", "sun"].iter().position(|d| s.trim().eq_ignore_ascii_case(d))
+}
+
+impl Calendar {
+    /// Opening hours and holidays of a GLPI calendar (segment days run Sunday = 0 to Saturday = 6).
+    pub fn from_glpi(segments: &[CalendarSegment], holidays: &[Holiday]) -> Self {
+        let mut cal = Calendar::default();
+        let time = |s: &str| NaiveTime::parse_from_str(s, "%H:%M:%S").ok();
+        for s in segments {
+            let (Some(begin), Some(end)) = (time(&s.begin), time(&s.end)) else { continue };
+            if (0..7).contains(&s.day) && begin < end {
+                cal.week[(s.day as usize + 6) % 7].push((begin, end));
+            }
+        }
+        let date = |s: &str| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok();
+        for h in holidays {
+            if let (Some(first), Some(last)) = (date(&h.begin_date), date(&h.end_date)) {
+                cal.holidays.push((first, last, h.is_perpetual != 0));
+            }
+        }
+        cal
+    }
+
+    fn closed(&self, day: NaiveDate) -> bool {
+        self.holidays.iter().any(|(first, last, yearly)| {
+            if *yearly {
+                let md = (day.month(), day.day());
+                let (from, to) = ((first.month(), first.day()), (last.month(), last.day()));
+                // A yearly holiday over New Year (Dec 24 to Jan 2) ends before it starts in the year
+                if from <= to {
+                    from <= md && md <= to
+                } else {
+                    md >= from || md <= to
+                }
+            } else {
+                *first <= day && day <= *last
+            }
+        })
+    }
+
+    /// Open time between two server-local instants, in seconds (0 when `to` is before `from`).
+    pub fn business_secs(&self, from: NaiveDateTime, to: NaiveDateTime) -> i64 {
+        let mut total = 0;
+        let mut day = from.date();
+        while day <= to.date() && (day - from.date()).num_days() <= MAX_DAYS {
+            if !self.closed(day) {
+                for (begin, end) in &self.week[day.weekday().num_days_from_monday() as usize] {
+                    let start = day.and_time(*begin).max(from);
+                    let stop = day.and_time(*end).min(to);
+                    total += (stop - start).num_seconds().max(0);
+                }
+            }
+            day = day.succ_opt().unwrap_or(day);
+        }
+        total
+    }
+}
+
+/// `TTO: 1h 20m business time left` (or `overdue by ...`) for an SLA target date as returned by GLPI.
+pub fn countdown(label: &str, due: &str) -> Option<String> {
+    let due = clock::parse_glpi_date(due)?;
+    let now = Utc::now();
+    let (secs, unit) = match CALENDAR.get().and_then(Option::as_ref) {
+        Some(cal) => {
+            let (now, due) = (clock::server_local(now), clock::server_local(due));
+            let secs = if due >= now { cal.business_secs(now, due) } else { -cal.business_secs(due, now) };
+            (secs, " business time")
+        }
+        None => ((due - now).num_seconds(), ""),
+    };
+    Some(render(label, secs, unit))
+}
+
+fn render(label: &str, secs: i64, unit: &str) -> String {
+    let minutes = secs.abs() / 60;
+    let span = match minutes / 60 {
+        0 => format!("{}m", minutes % 60),
+        h => format!("{h}h {}m", minutes % 60),
+    };
+    if secs < 0 {
+        format!("{label}: overdue by {span}{unit}")
+    } else {
+        format!("{label}: {span}{unit} left")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn counts_only_opening_hours() {
+        let Some(Source::Local(mut cal)) = parse("mon-fri 08:00-12:00,13:00-18:00").unwrap() else { panic!() };
+        // Friday 17:00 to Monday 09:30: 1h on Friday, 1h30 on Monday
+        assert_eq!(cal.business_secs(at("2025-10-17 17:00"), at("2025-10-20 09:30")), 2 * 3600 + 30 * 60);
+        // Lunch break
+        assert_eq!(cal.business_secs(at("2025-10-20 11:30"), at("2025-10-20 13:30")), 3600);
+
+        cal.holidays.push((
+            NaiveDate::from_ymd_opt(2000, 10, 20).unwrap(),
+            NaiveDate::from_ymd_opt(2000, 10, 20).unwrap(),
+            true,
+        ));
+        assert_eq!(cal.business_secs(at("2025-10-17 17:00"), at("2025-10-20 09:30")), 3600);
+
+        assert_eq!(render("TTO", 80 * 60, " business time"), "TTO: 1h 20m business time left");
+        assert_eq!(render("TTO", -15 * 60, ""), "TTO: overdue by 15m");
+    }
+
+    #[test]
+    fn yearly_holidays_span_the_new_year() {
+        let Some(Source::Local(mut cal)) = parse("mon-sun 08:00-18:00").unwrap() else { panic!() };
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        cal.holidays.push((date(2023, 12, 24), date(2024, 1, 2), true));
+        for closed in [date(2025, 12, 24), date(2025, 12, 31), date(2026, 1, 1), date(2026, 1, 2)] {
+            assert!(cal.closed(closed), "{closed} should be closed");
+        }
+        for open in [date(2025, 12, 23), date(2026, 1, 3), date(2026, 6, 15)] {
+            assert!(!cal.closed(open), "{open} should be open");
+        }
+        // Dec 30 08:00 to Jan 5 10:00: only Jan 3 and Jan 4 (10h each) and two hours on Jan 5
+        assert_eq!(cal.business_secs(at("2025-12-30 08:00"), at("2026-01-05 10:00")), 22 * 3600);
+
+        cal.holidays = vec![(date(2025, 12, 31), date(2026, 1, 1), false)];
+        assert!(cal.closed(date(2025, 12, 31)) && cal.closed(date(2026, 1, 1)));
+        assert!(!cal.closed(date(2026, 12, 31)));
+    }
+
+    #[test]
+    fn rejects_malformed_calendars() {
+        assert_eq!(parse("glpi:3").unwrap(), Some(Source::Glpi(3)));
+        assert!(parse("mon-fri").is_err());
+        assert!(parse("fri-mon 08:00-18:00").is_err());
+        assert!(parse("mon 18:00-08:00").is_err());
+    }
+}
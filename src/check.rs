@@ -0,0 +1,122 @@
+//! `check`: Nagios/Zabbix-compatible plugin over heartbeat.json and status.json.
+//!
+//! Prints one line `GLPI NOTIFIER <STATE> - <summary> | <perfdata>` and exits with the plugin codes
+//! (0 OK, 1 WARNING, 2 CRITICAL, 3 UNKNOWN), not the codes of [`crate::exit::Exit`].
+
+use crate::heartbeat::read_heartbeat;
+use crate::state::now_secs;
+use crate::widget::read_status;
+
+use std::process::ExitCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum State {
+    Ok = 0,
+    Warning = 1,
+    Critical = 2,
+    Unknown = 3,
+}
+
+impl State {
+    fn label(self) -> &'static str {
+        match self {
+            State::Ok => "OK",
+            State::Warning => "WARNING",
+            State::Critical => "CRITICAL",
+            State::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+/// Warning/critical thresholds; a value at or above the threshold triggers it.
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    warn: Option<u64>,
+    crit: Option<u64>,
+}
+
+impl Limits {
+    fn state(self, value: u64) -> State {
+        if self.crit.is_some_and(|c| value >= c) {
+            State::Critical
+        } else if self.warn.is_some_and(|w| value >= w) {
+            State::Warning
+        } else {
+            State::Ok
+        }
+    }
+
+    fn perf(self) -> String {
+        let show = |v: Option<u64>| v.map(|v| v.to_string()).unwrap_or_default();
+        format!("{};{}", show(self.warn), show(self.crit))
+    }
+}
+
+/// `check [--warn-age S] [--crit-age S] [--warn-errors N] [--crit-errors N] [--warn-queue N] [--crit-queue N]`
+pub fn run(args: &[String]) -> ExitCode {
+    let (state, line) = match evaluate(args) {
+        Ok(result) => result,
+        Err(e) => (State::Unknown, e),
+    };
+    println!("GLPI NOTIFIER {} - {line}", state.label());
+    ExitCode::from(state as u8)
+}
+
+fn evaluate(args: &[String]) -> Result<(State, String), String> {
+    let poll = crate::config::init().map_err(|e| format!("{e:#}"))?.poll_seconds;
+    let flag = |name: &str, default: Option<u64>| -> Result<Option<u64>, String> {
+        match args.iter().position(|a| a == name) {
+            Some(i) => {
+                let raw = args.get(i + 1).map(String::as_str).unwrap_or_default();
+                raw.parse().map(Some).map_err(|_| format!("{name}: expected a number, got '{raw}'"))
+            }
+            None => Ok(default),
+        }
+    };
+    let age_limits = Limits { warn: flag("--warn-age", Some(3 * poll))?, crit: flag("--crit-age", Some(10 * poll))? };
+    let error_limits = Limits { warn: flag("--warn-errors", Some(3))?, crit: flag("--crit-errors", Some(10))? };
+    let queue_limits = Limits { warn: flag("--warn-queue", None)?, crit: flag("--crit-queue", None)? };
+
+    let hb = read_heartbeat().ok_or("no heartbeat.json (notifier never ran for this user?)")?;
+    let now = now_secs();
+    let age = if hb.last_ok == 0 { now.saturating_sub(hb.ts) } else { now.saturating_sub(hb.last_ok) };
+    let queue = read_status().map_or(0, |s| s.queue as u64);
+    let pending = hb.pipeline.as_ref().map_or(0, |p| p.depth as u64);
+
+    // GLPI down for planned maintenance: no successful poll is expected until the window ends
+    let maintenance = hb.maintenance_until.filter(|until| !hb.ok && *until > now);
+    let age_check = match maintenance {
+        Some(until) => (State::Ok, format!("last successful poll {age}s ago, maintenance for {}s", until - now)),
+        None => (age_limits.state(age), format!("last successful poll {age}s ago")),
+    };
+    let checks = [
+        age_check,
+        (error_limits.state(hb.errors.into()), format!("{} consecutive poll error(s)", hb.errors)),
+        (queue_limits.state(queue), format!("{queue} New ticket(s)")),
+    ];
+    let state = checks.iter().map(|(s, _)| *s).max().unwrap_or(State::Ok);
+    let summary: Vec<&str> = checks.iter().map(|(_, text)| text.as_str()).collect();
+    let perfdata = format!(
+        "age={age}s;{} errors={};{} queue={queue};{} pending={pending}",
+        age_limits.perf(),
+        hb.errors,
+        error_limits.perf(),
+        queue_limits.perf()
+    );
+    Ok((state, format!("{} | {perfdata}", summary.join(", "))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_pick_the_worst_threshold_reached() {
+        let limits = Limits { warn: Some(180), crit: Some(600) };
+        assert_eq!(limits.state(60), State::Ok);
+        assert_eq!(limits.state(180), State::Warning);
+        assert_eq!(limits.state(900), State::Critical);
+        assert_eq!(Limits { warn: None, crit: None }.state(u64::MAX), State::Ok);
+        assert_eq!(Limits { warn: Some(5), crit: None }.perf(), "5;");
+    }
+}
@@ -0,0 +1,334 @@
+use crate::exit::Exit;
+
+use anyhow::{anyhow, Result};
+use clap::error::ErrorKind;
+use clap::{Arg, ArgAction, Command};
+use clap_complete::Shell;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Options accepted before or after any subcommand, each taking a value.
+pub const GLOBAL_OPTIONS: &[&str] = &["--env-file", "--config", "--log-level", "--poll-interval", "--instance"];
+
+/// Flags accepted before or after any subcommand.
+pub const GLOBAL_FLAGS: &[&str] = &["--demo"];
+
+/// Options of the poller, accepted by `run` and (for existing Scheduled Tasks) without a subcommand.
+fn run_args() -> [Arg; 3] {
+    [
+        Arg::new("once")
+            .long("once")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("max-runtime")
+            .help("Poll once (sign in, search, notify, save state, sign out) and exit, for Task Scheduler or cron"),
+        Arg::new("max-runtime")
+            .long("max-runtime")
+            .value_name("DURATION")
+            .help("Stop cleanly after the given time (seconds, or 30m / 8h)"),
+        Arg::new("kiosk")
+            .long("kiosk")
+            .action(ArgAction::SetTrue)
+            .help("Read-only wallboard mode: toasts without buttons, nothing written to GLPI"),
+    ]
+}
+
+/// The command line: parsed in `main()`, and the source of the shell completions and the man page.
+pub fn command() -> Command {
+    let id = || Arg::new("ticket-id").help("GLPI ticket id");
+    Command::new("glpi-notifier-rs")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about(env!("CARGO_PKG_DESCRIPTION"))
+        .long_about(
+            "Polls GLPI for New tickets and shows a notification for each one. \
+             Without a subcommand, runs the poller (configured through .env, --env-file, GLPI_NOTIFIER_ENV \
+             or config.toml).",
+        )
+        .disable_version_flag(true)
+        .arg(
+            Arg::new("version")
+                .short('V')
+                .long("version")
+                .action(ArgAction::SetTrue)
+                .help("Print the version, build target, supported GLPI versions and features"),
+        )
+        .arg(Arg::new("json").long("json").action(ArgAction::SetTrue).requires("version").help("With --version: JSON"))
+        .arg(Arg::new("test-toast").long("test-toast").action(ArgAction::SetTrue).hide(true))
+        .arg(
+            Arg::new("service").long("service").action(ArgAction::SetTrue).help(
+                "Run the poller as a Windows Service (started by the Service Control Manager, see install-service)",
+            ),
+        )
+        .args(run_args())
+        .arg(
+            Arg::new("env-file")
+                .long("env-file")
+                .value_name("PATH")
+                .action(ArgAction::Append)
+                .global(true)
+                .help("Read settings from this file instead of ./.env; repeatable, later files win"),
+        )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("PATH")
+                .value_parser(clap::value_parser!(PathBuf))
+                .global(true)
+                .help("Read config.toml from this path instead of next to the exe / the user config directory"),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .value_parser(["error", "warn", "info", "debug", "trace"])
+                .global(true)
+                .help("Log level (default: RUST_LOG)"),
+        )
+        .arg(
+            Arg::new("poll-interval")
+                .long("poll-interval")
+                .value_name("SECONDS")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .global(true)
+                .help("Seconds between polls, overriding POLL_SECONDS"),
+        )
+        .arg(
+            Arg::new("instance")
+                .long("instance")
+                .value_name("NAME")
+                .global(true)
+                .help("With [instances]: run the one-shot command on this instance (its server and state file)"),
+        )
+        .arg(
+            Arg::new("demo")
+                .long("demo")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .help("Show placeholder ticket titles and requester names (screenshots, training); ids stay real"),
+        )
+        .subcommand(Command::new("run").about("Run the poller (the default without a subcommand)").args(run_args()))
+        .subcommand(Command::new("test-toast").about("Show a sample notification through NOTIFIER and exit"))
+        .subcommand(
+            Command::new("status")
+                .about("State of the running poller: paused or polling, last poll, errors, New tickets")
+                .arg(Arg::new("json").long("json").action(ArgAction::SetTrue).help("Machine-readable reply")),
+        )
+        .subcommand(
+            Command::new("install")
+                .alias("install-systemd-user-unit")
+                .about("Start the poller at logon: Scheduled Task on Windows, systemd user unit on Linux"),
+        )
+        .subcommand(
+            Command::new("install-service")
+                .about("Windows: run the poller as a service at boot (administrator; no toasts from a service)")
+                .arg(
+                    Arg::new("account")
+                        .long("account")
+                        .value_name("DOMAIN\\USER")
+                        .help("Account the service runs as (default: yours, its password is asked), or LocalSystem"),
+                ),
+        )
+        .subcommand(Command::new("uninstall-service").about("Windows: stop and remove the service"))
+        .subcommand(
+            Command::new("mutes")
+                .about("List or clear requester/category mutes")
+                .arg(Arg::new("action").value_parser(["list", "clear"]).default_value("list")),
+        )
+        .subcommand(
+            Command::new("new-ticket")
+                .about("Create a ticket (prompts for missing values)")
+                .arg(Arg::new("title").long("title").value_name("TEXT"))
+                .arg(Arg::new("description").long("description").value_name("TEXT"))
+                .arg(Arg::new("category").long("category").value_name("ID").help("ITILCategory id")),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Tickets whose title contains TEXT, with ids and URLs")
+                .arg(Arg::new("text").required(true)),
+        )
+        .subcommand(
+            Command::new("queue")
+                .about("New tickets, sorted by QUEUE_SORT or --sort; --save makes it the default")
+                .arg(Arg::new("sort").long("sort").value_name("KEY").value_parser(crate::sort::KEYS.to_vec()))
+                .arg(Arg::new("save").long("save").action(ArgAction::SetTrue).help("Store --sort as QUEUE_SORT")),
+        )
+        .subcommand(
+            Command::new("stop-timer")
+                .about("Post the running timer of a ticket as a TicketTask; without an id, list timers")
+                .arg(id())
+                .arg(Arg::new("note").long("note").value_name("TEXT")),
+        )
+        .subcommand(
+            Command::new("timeline")
+                .about("Ticket history: follow-ups, tasks and validations in order")
+                .arg(id().required(true)),
+        )
+        .subcommand(Command::new("explain").about("Tell why a ticket was not notified").arg(id().required(true)))
+        .subcommand(
+            Command::new("focus")
+                .about("Hold toasts below High severity while working on a ticket (`off` ends it)")
+                .arg(Arg::new("ticket-id").help("GLPI ticket id, or `off`"))
+                .arg(Arg::new("minutes").long("minutes").value_name("N")),
+        )
+        .subcommand(
+            Command::new("focus-assist")
+                .about("Windows: add GlpiNotifier to the Focus Assist priority list (asks first)"),
+        )
+        .subcommand(
+            Command::new("config")
+                .about(
+                    "`show` lists the env files and effective settings; `export` prints a TOML profile \
+                     (tokens excluded), `import` merges one into the env file",
+                )
+                .arg(Arg::new("action").required(true).value_parser(["show", "export", "import"]))
+                .arg(Arg::new("file").help("Profile to import (TOML)")),
+        )
+        .subcommand(Command::new("configure-filters").about(
+            "Pick entities, categories and groups from lists read from GLPI and save the matching TICKET_FILTER",
+        ))
+        .subcommand(
+            Command::new("smoke-test")
+                .about("Create a throwaway ticket in SMOKE_TEST_ENTITY, check it is notified, then delete it"),
+        )
+        .subcommand(
+            Command::new("bench-search")
+                .about("Time listSearchOptions and the New-ticket search, print latency percentiles")
+                .arg(Arg::new("iterations").long("iterations").value_name("N").help("Calls of each kind (default 20)")),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Nagios/Zabbix plugin: OK/WARNING/CRITICAL from the heartbeat, with perfdata")
+                .arg(
+                    Arg::new("warn-age")
+                        .long("warn-age")
+                        .value_name("N")
+                        .help("Seconds since the last successful poll (default 3 x POLL_SECONDS)"),
+                )
+                .arg(Arg::new("crit-age").long("crit-age").value_name("N").help("Default 10 x POLL_SECONDS"))
+                .arg(
+                    Arg::new("warn-errors")
+                        .long("warn-errors")
+                        .value_name("N")
+                        .help("Consecutive failed polls (default 3)"),
+                )
+                .arg(Arg::new("crit-errors").long("crit-errors").value_name("N").help("Default 10"))
+                .arg(
+                    Arg::new("warn-queue")
+                        .long("warn-queue")
+                        .value_name("N")
+                        .help("Tickets waiting in New (default: no threshold)"),
+                )
+                .arg(Arg::new("crit-queue").long("crit-queue").value_name("N").help("Default: no threshold")),
+        )
+        .subcommand(
+            Command::new("ctl")
+                .about("Control the running poller; --json prints a reply matching `ctl schema`")
+                .arg(
+                    Arg::new("command")
+                        .value_parser(["status", "pause", "resume", "poll-now", "kiosk", "schema"])
+                        .default_value("status"),
+                )
+                .arg(Arg::new("switch").value_parser(["on", "off"]).help("kiosk: on or off"))
+                .arg(Arg::new("minutes").long("minutes").value_name("N").help("pause: resume after N minutes"))
+                .arg(Arg::new("json").long("json").action(ArgAction::SetTrue).help("Machine-readable reply")),
+        )
+        .subcommand(
+            Command::new("stats").about("Notified tickets picked up per technician").subcommand(
+                Command::new("export")
+                    .about("Poll history (New queue depth, tickets notified, poll duration) per day, as CSV or JSON")
+                    .arg(Arg::new("format").long("format").value_parser(["csv", "json"]).default_value("csv"))
+                    .arg(
+                        Arg::new("raw")
+                            .long("raw")
+                            .action(ArgAction::SetTrue)
+                            .help("One row per poll instead of per day"),
+                    )
+                    .arg(
+                        Arg::new("days")
+                            .long("days")
+                            .value_name("N")
+                            .value_parser(clap::value_parser!(usize))
+                            .help("Only the last N days"),
+                    ),
+            ),
+        )
+        .subcommand(Command::new("session").about("Show the active GLPI profile and entity"))
+        .subcommand(
+            Command::new("sign-in")
+                .about("OAuth: approve the notifier in a browser (device flow) and keep its refresh token"),
+        )
+        .subcommand(Command::new("sign-out").about("OAuth: forget the refresh token kept in the keyring"))
+        .subcommand(Command::new("doctor").about("Check sign-in and ticket access; name the setting to fix"))
+        .subcommand(Command::new("reset-seen").about("Forget seen ticket ids, snoozes and timers"))
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script")
+                .arg(Arg::new("shell").required(true).value_parser(clap::value_parser!(Shell))),
+        )
+        .subcommand(Command::new("man").about("Print the man page (roff)"))
+}
+
+/// Print a parse error or the help, with the exit code for it: 0 for `--help`, [`Exit::Config`] for a usage
+/// error, except under `check`, where monitoring reads 2 as CRITICAL and a bad plugin command line is UNKNOWN.
+pub fn usage_error(e: clap::Error) -> ExitCode {
+    let _ = e.print();
+    match e.kind() {
+        ErrorKind::DisplayHelp | ErrorKind::DisplayVersion => ExitCode::SUCCESS,
+        _ if std::env::args().nth(1).as_deref() == Some("check") => ExitCode::from(3),
+        _ => Exit::Config.into(),
+    }
+}
+
+/// `completions <shell>`: completion script on stdout (bash, zsh, fish, powershell, elvish).
+pub fn completions(args: &[String]) -> Result<()> {
+    let raw = args.first().ok_or_else(|| anyhow!("usage: completions <bash|zsh|fish|powershell|elvish>"))?;
+    let shell: Shell = raw.parse().map_err(|_| anyhow!("unsupported shell '{raw}'"))?;
+    // Buffered: clap_complete panics on write errors (e.g. `| head`)
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command(), "glpi-notifier-rs", &mut script);
+    std::io::stdout().write_all(&script)?;
+    Ok(())
+}
+
+/// `man`: man page in roff format on stdout.
+pub fn man() -> Result<()> {
+    clap_mangen::Man::new(command()).render(&mut std::io::stdout())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn command_definition_is_valid() {
+        super::command().debug_assert();
+    }
+
+    #[test]
+    fn global_options_go_anywhere() {
+        let m = super::command()
+            .try_get_matches_from([
+                "glpi-notifier-rs",
+                "--log-level",
+                "debug",
+                "run",
+                "--poll-interval",
+                "30",
+                "--kiosk",
+                "--demo",
+            ])
+            .unwrap();
+        let (name, run) = m.subcommand().unwrap();
+        assert_eq!(name, "run");
+        assert_eq!(run.get_one::<String>("log-level").map(String::as_str), Some("debug"));
+        assert_eq!(run.get_one::<u64>("poll-interval"), Some(&30));
+        assert!(run.get_flag("kiosk"));
+        assert!(run.get_flag("demo"));
+        assert!(super::command().try_get_matches_from(["glpi-notifier-rs", "--poll-interval", "0"]).is_err());
+
+        let m = super::command()
+            .try_get_matches_from(["glpi-notifier-rs", "stop-timer", "101", "--instance", "prod"])
+            .unwrap();
+        let (_, stop) = m.subcommand().unwrap();
+        assert_eq!(stop.get_one::<String>("instance").map(String::as_str), Some("prod"));
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Local, Locale, NaiveDateTime, TimeZone, Utc};
+use std::fmt::Display;
+
+/// Parse a GLPI date ("YYYY-MM-DD HH:MM:SS", server local time).
+pub fn parse_glpi_date(raw: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S").ok()?;
+    match crate::config::get().server_tz {
+        Some(tz) => tz.from_local_datetime(&naive).earliest().map(|d| d.with_timezone(&Utc)),
+        None => Local.from_local_datetime(&naive).earliest().map(|d| d.with_timezone(&Utc)),
+    }
+}
+
+/// A UTC instant as GLPI_SERVER_TZ (default: this machine's) wall-clock time.
+pub fn server_local(at: DateTime<Utc>) -> NaiveDateTime {
+    match crate::config::get().server_tz {
+        Some(tz) => at.with_timezone(&tz).naive_local(),
+        None => at.with_timezone(&Local).naive_local(),
+    }
+}
+
+/// Short opening time for notifications in the display timezone/locale: `09:05` today, `Mon 14 Oct 09:05` otherwise.
+pub fn format_opened(at: DateTime<Utc>) -> String {
+    let now = Utc::now();
+    match crate::config::get().display_tz {
+        Some(tz) => render(at, &tz, now, crate::config::get().date_locale),
+        None => render(at, &Local, now, crate::config::get().date_locale),
+    }
+}
+
+fn render<T: TimeZone>(at: DateTime<Utc>, tz: &T, now: DateTime<Utc>, locale: Option<Locale>) -> String
+where
+    T::Offset: Display,
+{
+    let local = at.with_timezone(tz);
+    let fmt = if local.date_naive() == now.with_timezone(tz).date_naive() { "%H:%M" } else { "%a %d %b %H:%M" };
+    match locale {
+        Some(l) => local.format_localized(fmt, l).to_string(),
+        None => local.format(fmt).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_in_display_timezone_and_locale() {
+        let at = Utc.with_ymd_and_hms(2025, 10, 13, 7, 5, 0).unwrap();
+        let tz: chrono_tz::Tz = "Europe/Paris".parse().unwrap();
+
+        assert_eq!(render(at, &tz, at, None), "09:05");
+        let next_week = at + chrono::Duration::days(7);
+        assert_eq!(render(at, &tz, next_week, None), "Mon 13 Oct 09:05");
+        assert_eq!(render(at, &tz, next_week, Some(Locale::fr_FR)), "lun. 13 oct. 09:05");
+    }
+}
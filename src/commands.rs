@@ -0,0 +1,609 @@
+use crate::config::Connection;
+use crate::exit::ConfigError;
+use crate::glpi::Api;
+use crate::metrics;
+use crate::oauth;
+use crate::state::{load_state, now_secs};
+use crate::toast::ticket_url;
+
+use anyhow::{anyhow, Result};
+use std::io::{self, BufRead, Write};
+
+/// Value following `name` in the argument list (`--title "Printer down"`).
+fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter().position(|a| a == name).and_then(|i| args.get(i + 1)).map(String::as_str)
+}
+
+/// Ask for a value on stdin (empty answer allowed).
+fn prompt(label: &str) -> Result<String> {
+    print!("{label}: ");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// `mutes list` / `mutes clear`: inspect or reset the persisted mutes.
+pub fn mutes(sub: Option<&str>) -> Result<()> {
+    use crate::control::{self, StateEdit};
+
+    let mut st = control::current_state()?;
+    st.prune_mutes();
+    match sub.unwrap_or("list") {
+        "list" => {
+            if st.mutes.is_empty() {
+                println!("No active mutes.");
+            }
+            let now = now_secs();
+            for m in &st.mutes {
+                println!("{} (expires in {} min)", m.target, m.until.saturating_sub(now).div_ceil(60));
+            }
+        }
+        "clear" => {
+            println!("Cleared {} mute(s).", st.mutes.len());
+            // Through the control channel: the running poller would otherwise save its mutes back
+            control::edit_state(&mut st, StateEdit::ClearMutes)?;
+        }
+        other => return Err(anyhow!("unknown mutes command '{other}' (expected: list, clear)")),
+    }
+    Ok(())
+}
+
+/// `timeline <ticket-id>`: the ticket's follow-ups, tasks and validations in chronological order.
+pub async fn timeline(args: &[String]) -> Result<()> {
+    let raw = args.first().ok_or_else(|| anyhow!("usage: timeline <ticket-id>"))?;
+    let id: i64 = raw.parse().map_err(|_| anyhow!("ticket id must be a number, got '{raw}'"))?;
+    let conn = Connection::from_env()?;
+
+    let mut client = conn.connect().await?;
+    let timeline = client.ticket_timeline(id).await;
+    let _ = client.kill_session().await;
+    let (name, events) = timeline?;
+
+    println!("#{id} {}", crate::demo::title(id, name));
+    if let Some(url) = ticket_url(id) {
+        println!("{url}");
+    }
+    for e in events.into_iter().map(crate::demo::event) {
+        let author = e.author.map(|a| format!("{a}: ")).unwrap_or_default();
+        println!("{}  {:<10} {author}{}", e.at, e.kind, e.text);
+    }
+    Ok(())
+}
+
+/// `ctl <status|pause [--minutes N]|resume|poll-now|kiosk on|off|schema> [--json]`: control the running poller.
+pub fn ctl(args: &[String]) -> Result<()> {
+    use crate::control::{self, CtlReply};
+
+    let json = args.iter().any(|a| a == "--json");
+    let command = args.iter().find(|a| !a.starts_with("--") && Some(a.as_str()) != flag_value(args, "--minutes"));
+    // `ctl kiosk on|off`: the first word is the command
+    let command = command.map(String::as_str).unwrap_or("status");
+    if command == "schema" {
+        println!("{}", serde_json::to_string_pretty(&control::schema())?);
+        return Ok(());
+    }
+
+    let result = (|| {
+        let mut c = control::load();
+        match command {
+            "status" => return Ok(()),
+            "pause" => {
+                c.paused_until = Some(match flag_value(args, "--minutes") {
+                    Some(m) => {
+                        let minutes: u64 = m.parse().map_err(|_| anyhow!("--minutes must be a number"))?;
+                        now_secs().saturating_add(minutes.saturating_mul(60))
+                    }
+                    None => u64::MAX,
+                })
+            }
+            "resume" => c.paused_until = None,
+            "poll-now" => c.poll_requested = now_secs().max(c.poll_served + 1),
+            "kiosk" => {
+                c.kiosk = match args.iter().find(|a| *a == "on" || *a == "off").map(String::as_str) {
+                    Some("on") => Some(true),
+                    Some(_) => Some(false),
+                    None => return Err(anyhow!("usage: ctl kiosk on|off")),
+                }
+            }
+            other => {
+                return Err(anyhow!("unknown ctl command '{other}' (status, pause, resume, poll-now, kiosk, schema)"))
+            }
+        }
+        control::save(&c)
+    })();
+
+    let state = control::state();
+    if json {
+        let error = result.as_ref().err().map(|e| format!("{e:#}"));
+        let reply =
+            CtlReply { schema: control::SCHEMA_VERSION, command: command.into(), ok: error.is_none(), error, state };
+        println!("{}", serde_json::to_string(&reply)?);
+        return result;
+    }
+    result?;
+    match state.paused_until {
+        Some(u64::MAX) => println!("Paused until `ctl resume`."),
+        Some(until) => println!("Paused for another {} min.", until.saturating_sub(now_secs()).div_ceil(60)),
+        None => println!("Polling."),
+    }
+    if state.kiosk {
+        println!("Kiosk mode: toasts without buttons, nothing written to GLPI.");
+    }
+    if state.poll_pending {
+        println!("Poll requested; the poller picks it up within a second.");
+    }
+    let ago = |t: u64| if t == 0 { "never".to_string() } else { format!("{}s ago", now_secs().saturating_sub(t)) };
+    println!(
+        "Last poll {}, last success {}, {} error(s) in a row, {} New ticket(s).",
+        ago(state.last_poll),
+        ago(state.last_ok),
+        state.errors,
+        state.queue
+    );
+    Ok(())
+}
+
+/// `config show` (env files and effective settings) / `config export` (TOML on stdout) /
+/// `config import <file>` (merged into the last env file, else ./.env).
+pub fn config(args: &[String]) -> Result<()> {
+    match (args.first().map(String::as_str), args.get(1)) {
+        (Some("show"), _) => print!("{}", crate::config::show()),
+        (Some("export"), _) => print!("{}", crate::config::export_profile()?),
+        (Some("import"), Some(file)) => {
+            let target = crate::config::ENV_FILES.get().and_then(|f| f.last().cloned()).unwrap_or(".env".into());
+            let n = crate::config::import_profile(std::path::Path::new(file), &target)?;
+            println!("Imported {n} setting(s) into {}; restart the notifier to apply them.", target.display());
+        }
+        _ => return Err(anyhow!("usage: config show | config export | config import <file.toml>")),
+    }
+    Ok(())
+}
+
+/// `explain <ticket-id>`: why a ticket was (or would not be) notified, checked against the live state.
+pub async fn explain(args: &[String]) -> Result<()> {
+    let raw = args.first().ok_or_else(|| anyhow!("usage: explain <ticket-id>"))?;
+    let id: i64 = raw.parse().map_err(|_| anyhow!("ticket id must be a number, got '{raw}'"))?;
+    let mut st = load_state()?;
+    st.focus = crate::focus::load();
+    let filter = crate::filter::from_env()?;
+    let conn = Connection::from_env()?;
+
+    let mut client = conn.connect().await?;
+    let found = async {
+        let fields = client.resolve_ticket_fields().await?;
+        let found = client.ticket_with_status(&fields, &[], id).await?;
+        let session = if found.is_none() { Some(client.session_info().await?) } else { None };
+        let filtered =
+            found.is_some() && !filter.is_empty() && client.ticket_with_status(&fields, &filter, id).await?.is_none();
+        Ok::<_, anyhow::Error>((found, session, filtered))
+    }
+    .await;
+    let _ = client.kill_session().await;
+
+    let (t, status, filtered) = match found? {
+        (Some((t, status)), _, filtered) => (t, status, filtered),
+        (None, session, _) => {
+            println!("#{id}: not found. It does not exist or is not visible to this session:");
+            crate::tickets::load();
+            if let Some(k) = crate::tickets::get(id) {
+                println!("  last seen by the poller {} min ago: {}", k.age() / 60, k.ticket.name);
+            }
+            if let Some(s) = session {
+                println!(
+                    "  profile {} (#{}), entity {} (#{})",
+                    s.profile_name, s.profile_id, s.entity_name, s.entity_id
+                );
+            }
+            println!("  Check GLPI_PROFILE_ID / GLPI_ACTIVE_ENTITY / GLPI_ENTITY_RECURSIVE (see `session`).");
+            return Ok(());
+        }
+    };
+
+    println!("#{} {} (by {})", t.id, t.name, t.requester.as_deref().unwrap_or("unknown"));
+    if let Some(refs) = crate::refs::summary(&crate::refs::of(&t)) {
+        println!("References (TICKET_REFS): {refs}");
+    }
+    let mut reasons = crate::suppression_reasons(&t, status, &st, now_secs());
+    if filtered {
+        reasons.insert(0, "excluded by TICKET_FILTER".to_string());
+    }
+    if reasons.is_empty() {
+        println!("Would be notified on the next poll.");
+    }
+    for r in reasons {
+        println!("  - {r}");
+    }
+    Ok(())
+}
+
+/// `focus <ticket-id> [--minutes N]` / `focus off` / `focus`: hold toasts below High severity while
+/// working on a ticket; the running notifier shows them as one digest when focus ends.
+pub fn focus(args: &[String]) -> Result<()> {
+    match args.first().map(String::as_str) {
+        None => match crate::focus::load() {
+            Some(f) => println!(
+                "Focusing on #{} for another {} min.",
+                f.ticket_id,
+                f.until.saturating_sub(now_secs()).div_ceil(60)
+            ),
+            None => println!("Focus mode is off."),
+        },
+        Some("off") => {
+            crate::focus::clear()?;
+            println!("Focus mode off; held tickets will be shown as a digest on the next poll.");
+        }
+        Some(raw) => {
+            let ticket_id: i64 = raw.parse().map_err(|_| anyhow!("usage: focus <ticket-id> [--minutes N] | off"))?;
+            let minutes: u64 = match flag_value(args, "--minutes") {
+                Some(m) => m.parse().map_err(|_| anyhow!("--minutes must be a number, got '{m}'"))?,
+                None => crate::config::get().focus_minutes,
+            };
+            let until = now_secs().saturating_add(minutes.saturating_mul(60));
+            crate::focus::save(&crate::focus::Focus { ticket_id, until })?;
+            println!("Focusing on #{ticket_id} for {minutes} min: only High/Critical tickets will pop up.");
+        }
+    }
+    Ok(())
+}
+
+/// `focus-assist`: let Critical tickets break through Windows Focus Assist. Windows only lets the user add an
+/// app to the priority list, so this explains the trade-off, asks for consent and opens that settings page.
+pub fn focus_assist() -> Result<()> {
+    if !cfg!(windows) {
+        return Err(anyhow!("focus-assist is only available on Windows"));
+    }
+    crate::toast::ensure_app_id();
+    println!("Apps on the Focus Assist priority list break through Focus Assist with every toast.");
+    println!("Once GlpiNotifier is on it, keep non-critical tickets quiet with `focus <ticket-id>`:");
+    println!("only High/Critical tickets pop up then, the rest come as one digest afterwards.");
+    let answer = prompt("Open the Focus Assist priority list to add GlpiNotifier? [y/N]")?;
+    if !answer.eq_ignore_ascii_case("y") {
+        println!("Nothing changed.");
+        return Ok(());
+    }
+    crate::toast::open_url_windows("ms-settings:quiethours")?;
+    println!("In \"Customize your priority list\" > \"Add an app\", pick GlpiNotifier.");
+    Ok(())
+}
+
+/// `stats`: how many notified tickets each technician picked up (assignment fairness).
+pub fn stats(args: &[String]) -> Result<()> {
+    if args.first().is_some_and(|a| a == "export") {
+        return stats_export(&args[1..]);
+    }
+    let st = load_state()?;
+    let total: u64 = st.pickups.values().sum();
+    if total == 0 {
+        println!("No pickups recorded yet.");
+        return Ok(());
+    }
+    let mut rows: Vec<(&String, &u64)> = st.pickups.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    println!("Notified tickets picked up ({total}):");
+    for (name, count) in rows {
+        println!("  {name:<30} {count:>5}  {:>3}%", count * 100 / total);
+    }
+    Ok(())
+}
+
+/// `stats export [--format csv|json] [--raw] [--days N]`: the poll history, per day (today so far included) or per
+/// poll with `--raw`.
+fn stats_export(args: &[String]) -> Result<()> {
+    let value = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).map(String::as_str);
+    let json = match value("--format").unwrap_or("csv") {
+        "csv" => false,
+        "json" => true,
+        other => return Err(anyhow!("unknown format '{other}' (expected: csv, json)")),
+    };
+    let days = match value("--days") {
+        Some(raw) => Some(raw.parse::<usize>().map_err(|_| anyhow!("--days must be a number, got '{raw}'"))?),
+        None => None,
+    };
+    let out = if args.iter().any(|a| a == "--raw") {
+        let mut samples = metrics::samples();
+        if let Some(days) = days {
+            let from = now_secs().saturating_sub(days as u64 * 86400);
+            samples.retain(|s| s.ts >= from);
+        }
+        if json {
+            serde_json::to_string_pretty(&samples)?
+        } else {
+            metrics::samples_csv(&samples)
+        }
+    } else {
+        let rows = metrics::daily_with_today(days);
+        if json {
+            serde_json::to_string_pretty(&rows)?
+        } else {
+            metrics::daily_csv(&rows)
+        }
+    };
+    println!("{}", out.trim_end());
+    Ok(())
+}
+
+/// `session`: active profile and entity of the notifier's GLPI session, plus the profiles it may switch to.
+pub async fn session() -> Result<()> {
+    let conn = Connection::from_env()?;
+    let mut client = conn.connect().await?;
+    let info = async { Ok::<_, anyhow::Error>((client.session_info().await?, client.my_profiles().await?)) }.await;
+    let _ = client.kill_session().await;
+    let (info, profiles) = info?;
+
+    println!("User:    {} (#{})", info.user_name, info.user_id);
+    println!("Profile: {} (#{})", info.profile_name, info.profile_id);
+    let scope = if info.recursive { "including sub-entities" } else { "this entity only" };
+    println!("Entity:  {} (#{}), {scope}", info.entity_name, info.entity_id);
+    if !info.language.is_empty() {
+        println!("Language: {}", info.language);
+    }
+    println!("Available profiles (GLPI_PROFILE_ID):");
+    for (id, name) in profiles {
+        println!("  {id}: {name}");
+    }
+    Ok(())
+}
+
+/// `sign-in`: approve the notifier at the OAuth provider once (device flow), then check that GLPI accepts the
+/// token; the refresh token stays in the keyring for the following runs.
+pub async fn sign_in() -> Result<()> {
+    let conn = Connection::from_env()?;
+    let (Api::Gateway { provider, .. } | Api::Hl(provider)) = &conn.api else {
+        return Err(ConfigError("sign-in is for OAuth: set GLPI_OAUTH_FLOW (or GLPI_API=hl)".into()).into());
+    };
+    let mut client = conn.client().await?;
+    if matches!(provider.grant, oauth::Grant::DeviceCode) {
+        client
+            .device_sign_in(|auth| {
+                println!("Open {} and enter the code {}", auth.verification_uri, auth.user_code);
+                if let Some(uri) = &auth.verification_uri_complete {
+                    println!("(or open {uri})");
+                }
+                println!("Waiting for approval ({} min)...", auth.expires_in / 60);
+            })
+            .await?;
+    }
+    client.init_session().await?;
+    let _ = client.kill_session().await;
+    println!("Signed in; GLPI accepted the token.");
+    Ok(())
+}
+
+/// `sign-out`: forget the refresh token kept in the keyring.
+pub async fn sign_out() -> Result<()> {
+    let client = Connection::from_env()?.client().await?;
+    match client.sign_out()? {
+        true => println!("Refresh token removed from the keyring; run `sign-in` to approve the notifier again."),
+        false => println!("No refresh token saved."),
+    }
+    Ok(())
+}
+
+/// `doctor`: sign in and read tickets step by step; a failure names the setting to fix.
+pub async fn doctor() -> Result<()> {
+    let conn = Connection::from_env()?;
+    println!("GLPI API:  {}", conn.base_url);
+    let app_token = if conn.app_token.is_some() { "sent (GLPI_APP_TOKEN)" } else { "none (GLPI_APP_TOKEN empty)" };
+    println!("App-Token: {app_token}");
+
+    let mut client = conn.connect().await.map_err(|e| e.context("Sign-in failed"))?;
+    println!("Sign-in:   OK");
+    let checks = async {
+        let info = client.session_info().await?;
+        println!(
+            "Session:   {} (#{}), profile {}, entity {}",
+            info.user_name, info.user_id, info.profile_name, info.entity_name
+        );
+        let fields = client.resolve_ticket_fields().await?;
+        println!("Fields:    OK (listSearchOptions)");
+        let (_, total) = client.search_new_page(&fields, &[], 1).await?;
+        println!("Tickets:   {} New ticket(s) visible", total.unwrap_or_default());
+        Ok::<_, anyhow::Error>(())
+    }
+    .await;
+    let _ = client.kill_session().await;
+    checks?;
+    crate::toast::ensure_app_id();
+    match crate::toast::app_id_status() {
+        Some((backend, Ok(what))) => println!("Toasts:    {backend}, {what}"),
+        Some((backend, Err(why))) => println!("Toasts:    {backend}, toasts or their buttons may not show: {why}"),
+        None => println!("Toasts:    not on Windows, no AUMID needed"),
+    }
+    println!("All checks passed.");
+    Ok(())
+}
+
+/// `reset-seen`: forget seen ticket ids, snoozes and timers (e.g. after GLPI was reinstalled).
+/// The next poll behaves like a first run.
+pub fn reset_seen() -> Result<()> {
+    use crate::control::{self, StateEdit};
+
+    let mut st = control::current_state()?;
+    let count = st.seen_ticket_ids.len();
+    // Through the control channel: the running poller would otherwise save its seen ids back
+    control::edit_state(&mut st, StateEdit::ResetSeen)?;
+    println!("Forgot {count} seen ticket id(s).");
+    Ok(())
+}
+
+/// `new-ticket [--title T] [--description D] [--category ID]`: log a walk-up request; prompts for what is missing.
+pub async fn new_ticket(args: &[String]) -> Result<()> {
+    let conn = Connection::from_env()?;
+
+    let title = match flag_value(args, "--title") {
+        Some(t) => t.to_string(),
+        None => prompt("Title")?,
+    };
+    if title.is_empty() {
+        return Err(anyhow!("a ticket title is required"));
+    }
+    let description = match flag_value(args, "--description") {
+        Some(d) => d.to_string(),
+        None => prompt("Description (optional)")?,
+    };
+    let category = match flag_value(args, "--category") {
+        Some(c) => c.to_string(),
+        None => prompt("Category id (optional)")?,
+    };
+    let category = match category.as_str() {
+        "" => None,
+        c => Some(c.parse::<i64>().map_err(|_| anyhow!("category must be a numeric ITILCategory id, got '{c}'"))?),
+    };
+
+    let mut client = conn.connect().await?;
+    let created = client.create_ticket(&title, &description, category, None).await;
+    let _ = client.kill_session().await;
+    let id = created?;
+
+    println!("Created ticket #{id}");
+    if let Some(url) = ticket_url(id) {
+        println!("{url}");
+    }
+    Ok(())
+}
+
+/// `search <text>`: tickets whose title contains the text, with ids and URLs (duplicate check before `new-ticket`).
+pub async fn search(args: &[String]) -> Result<()> {
+    let text = args.first().map(|s| s.trim()).filter(|s| !s.is_empty());
+    let text = text.ok_or_else(|| anyhow!("usage: search <text>"))?;
+    let conn = Connection::from_env()?;
+
+    let mut client = conn.connect().await?;
+    let found = async {
+        let fields = client.resolve_ticket_fields().await?;
+        client.search_tickets_by_title(&fields, text, 50).await
+    }
+    .await;
+    let _ = client.kill_session().await;
+    let found = found?;
+
+    if found.is_empty() {
+        println!("No ticket title contains '{text}'.");
+    }
+    for t in &found {
+        let by = t.requester.as_deref().map(|r| format!(" (by {r})")).unwrap_or_default();
+        match ticket_url(t.id) {
+            Some(url) => println!("#{} {}{}\n    {}", t.id, t.name, by, url),
+            None => println!("#{} {}{}", t.id, t.name, by),
+        }
+    }
+    Ok(())
+}
+
+/// `queue [--sort KEY] [--save]`: the New tickets in QUEUE_SORT order (or KEY's); `--save` makes KEY the default.
+pub async fn queue(args: &[String]) -> Result<()> {
+    let key = match args.iter().position(|a| a == "--sort") {
+        Some(i) => {
+            let raw = args.get(i + 1).ok_or_else(|| anyhow!("--sort needs one of {}", crate::sort::KEYS.join(", ")))?;
+            crate::sort::parse(raw)?;
+            Some(raw.trim().to_lowercase())
+        }
+        None => None,
+    };
+    if args.iter().any(|a| a == "--save") {
+        let key = key.as_deref().ok_or_else(|| anyhow!("usage: queue --sort <key> --save"))?;
+        let file = crate::config::save_setting("QUEUE_SORT", key)?;
+        println!("Default queue order is now '{key}' (QUEUE_SORT in {}).", file.display());
+    }
+    let order = key.as_deref().map_or_else(|| Ok(crate::sort::from_env()), crate::sort::parse)?;
+    let filter = crate::filter::from_env()?;
+    let conn = Connection::from_env()?;
+
+    let mut client = conn.connect().await?;
+    let found = async {
+        let fields = client.resolve_ticket_fields().await?;
+        crate::calendar::init(&mut client).await;
+        // Sorted here: every page, not the newest ones only
+        client.all_new_tickets(&fields, &filter).await
+    }
+    .await;
+    let _ = client.kill_session().await;
+    let mut tickets = found?;
+    crate::sort::sort(&mut tickets, order);
+
+    if tickets.is_empty() {
+        println!("No New tickets.");
+    }
+    // Symbols a screen reader would spell out are replaced with words in accessibility mode
+    let (location, separator) = if crate::accessibility::enabled() { ("location ", ", ") } else { ("📍 ", " · ") };
+    for t in &tickets {
+        let mut details: Vec<String> = Vec::new();
+        details.extend(t.priority.map(|p| format!("priority {p}")));
+        details.extend(t.opened_display().map(|o| format!("opened {o}")));
+        details.extend(t.entity.clone());
+        details.extend(t.location.as_ref().map(|l| format!("{location}{l}")));
+        details.extend(t.time_to_own.as_deref().and_then(|d| crate::calendar::countdown("TTO", d)));
+        let by = t.requester.as_deref().map(|r| format!(" (by {r})")).unwrap_or_default();
+        println!("#{} {}{}\n    {}", t.id, t.name, by, details.join(separator));
+    }
+    Ok(())
+}
+
+/// `stop-timer [<ticket-id>] [--note TEXT]`: post the elapsed time as a TicketTask; without an id, list running timers.
+pub async fn stop_timer(args: &[String]) -> Result<()> {
+    use crate::control::{self, StateEdit};
+
+    // A running poller may not have saved the timers stopped before
+    let mut st = control::current_state()?;
+    let now = now_secs();
+
+    let Some(id) = args.first().filter(|a| !a.starts_with("--")) else {
+        if st.timers.is_empty() {
+            println!("No running timers.");
+        }
+        for (id, started) in &st.timers {
+            println!("#{id}: running for {} min", now.saturating_sub(*started) / 60);
+        }
+        return Ok(());
+    };
+    let id: i64 = id.parse().map_err(|_| anyhow!("ticket id must be a number, got '{id}'"))?;
+    let started = *st.timers.get(&id).ok_or_else(|| match crate::instance::all() {
+        [_, ..] if crate::instance::current().is_none() => {
+            anyhow!("no timer running for #{id} in state.json; pass --instance <name> for the timers of an instance")
+        }
+        _ => anyhow!("no timer running for #{id}"),
+    })?;
+
+    // GLPI rounds task durations; never post less than a minute
+    let elapsed = now.saturating_sub(started).max(60);
+    let note = flag_value(args, "--note").unwrap_or("Time logged from GlpiNotifier timer");
+
+    let conn = Connection::from_env()?;
+    let mut client = conn.connect().await?;
+    let posted = client.add_ticket_task(id, note, elapsed).await;
+    let _ = client.kill_session().await;
+    let task_id = posted?;
+
+    // Through the control channel: the running poller would otherwise save the timer back
+    control::edit_state(&mut st, StateEdit::StopTimer { ticket: id })?;
+    println!("Logged {} min on #{id} (task {task_id})", elapsed.div_ceil(60));
+    Ok(())
+}
+
+/// `install`: start the poller at logon, with the settings files of this run. Windows: a Scheduled Task for the
+/// current user (scripts\install.ps1 also copies the files and writes the launcher); Linux: a systemd user unit.
+pub fn install() -> Result<()> {
+    if !cfg!(windows) {
+        return crate::systemd::install_user_unit();
+    }
+    let mut command = format!("\"{}\" run", std::env::current_exe()?.display());
+    for file in crate::config::ENV_FILES.get().into_iter().flatten() {
+        command.push_str(&format!(" --env-file \"{}\"", file.display()));
+    }
+    if let Some(file) = crate::config::CONFIG_FILE.get() {
+        command.push_str(&format!(" --config \"{}\"", file.display()));
+    }
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    // /IT: only while the user is logged on, in their session (toasts need the desktop), so no password is stored
+    let status = std::process::Command::new("schtasks")
+        .args(["/Create", "/F", "/TN", "GlpiNotifier", "/SC", "ONLOGON", "/RL", "LIMITED", "/RU", &user, "/IT", "/TR"])
+        .arg(&command)
+        .status()
+        .map_err(|e| anyhow!("schtasks failed to start: {e}"))?;
+    if !status.success() {
+        return Err(anyhow!("schtasks could not create the task ({status}); try scripts\\install.ps1"));
+    }
+    println!("Scheduled Task 'GlpiNotifier' runs at logon: {command}");
+    Ok(())
+}
@@ -0,0 +1,1104 @@
+use crate::depth::Thresholds;
+use crate::exit::ConfigError;
+use crate::glpi::{hl_root, Api, GlpiClient, SessionScope, Severity};
+use crate::oauth;
+
+use anyhow::{Context, Result};
+use chrono::Locale;
+use chrono_tz::Tz;
+use once_cell::sync::OnceCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The env files settings were loaded from, lowest precedence first.
+pub static ENV_FILES: OnceCell<Vec<PathBuf>> = OnceCell::new();
+
+/// The config.toml settings were loaded from, if one was found.
+pub static CONFIG_FILE: OnceCell<PathBuf> = OnceCell::new();
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// Load settings from the `--env-file PATH` options (`files`), else from GLPI_NOTIFIER_ENV (one path, or
+/// several separated like PATH), else from a .env in the current directory (or a parent). Later files override
+/// earlier ones; variables already set in the environment override them all. A listed file that does not exist
+/// is skipped with a warning (it may be written later, e.g. by the token rotation [`reload_tokens`] waits for).
+pub fn load_env_files(mut files: Vec<PathBuf>) -> Result<()> {
+    if files.is_empty() {
+        if let Some(list) = env::var_os("GLPI_NOTIFIER_ENV").filter(|v| !v.is_empty()) {
+            files = env::split_paths(&list).collect();
+        }
+    }
+    if files.is_empty() {
+        match dotenvy::dotenv() {
+            Ok(path) => {
+                let _ = ENV_FILES.set(vec![path]);
+            }
+            Err(e) if !e.not_found() => return Err(ConfigError(format!(".env: {e}")).into()),
+            Err(_) => {}
+        }
+        return Ok(());
+    }
+
+    let files: Vec<PathBuf> = files.into_iter().map(|p| std::path::absolute(&p).unwrap_or(p)).collect();
+    // dotenvy never replaces a variable that is already set: load the highest-precedence file first
+    for path in files.iter().rev() {
+        match dotenvy::from_path(path) {
+            Err(e) if e.not_found() => log::warn!("env file {} not found, skipping it", path.display()),
+            loaded => loaded.map_err(|e| ConfigError(format!("env file {}: {e}", path.display())))?,
+        }
+    }
+    let _ = ENV_FILES.set(files);
+    Ok(())
+}
+
+/// Where config.toml is looked for: next to the exe (portable installs), then in the user config directory
+/// (`%APPDATA%\GlpiNotifier`, `~/.config/GlpiNotifier`, `~/Library/Application Support/GlpiNotifier`).
+fn config_file_candidates() -> Vec<PathBuf> {
+    let exe_dir = env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let config_dir = dirs::config_dir().map(|d| d.join("GlpiNotifier"));
+    exe_dir.into_iter().chain(config_dir).map(|d| d.join("config.toml")).collect()
+}
+
+/// Load `explicit` (`--config`), else the first config.toml found, as the lowest layer: its settings apply only
+/// where neither the environment nor an env file sets them. Unlike .env it does not depend on the working
+/// directory, which Task Scheduler and services do not set to the install folder. Call after [`load_env_files`].
+pub fn load_config_file(explicit: Option<&Path>) -> Result<()> {
+    let path = match explicit {
+        Some(path) if !path.is_file() => {
+            return Err(ConfigError(format!("--config {}: no such file", path.display())).into())
+        }
+        Some(path) => path.to_path_buf(),
+        None => match config_file_candidates().into_iter().find(|p| p.is_file()) {
+            Some(path) => path,
+            None => return Ok(()),
+        },
+    };
+    let text = std::fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let settings = parse_config_file(&text).map_err(|e| ConfigError(format!("{}: {e:#}", path.display())))?;
+    let instances = toml::from_str::<toml::Table>(&text)
+        .map_err(anyhow::Error::from)
+        .and_then(|doc| crate::instance::parse(&doc))
+        .map_err(|e| ConfigError(format!("{}: {e:#}", path.display())))?;
+    crate::instance::set(instances);
+    for (key, value) in settings {
+        if env::var_os(&key).is_none() {
+            env::set_var(&key, value);
+        }
+    }
+    let _ = CONFIG_FILE.set(path);
+    Ok(())
+}
+
+/// config.toml: one top-level key per setting, named like the environment variable in any case
+/// (`poll_seconds = 60`, `glpi_base_url = "https://..."`), besides the `[instances]` tables.
+fn parse_config_file(text: &str) -> Result<BTreeMap<String, String>> {
+    let doc: toml::Table = toml::from_str(text).map_err(|e| anyhow::anyhow!("invalid TOML: {e}"))?;
+    let mut settings = BTreeMap::new();
+    for (key, value) in doc.iter().filter(|(key, _)| *key != "instances") {
+        let name = key.to_uppercase();
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("'{key}' is not a setting name");
+        }
+        settings.insert(name.clone(), setting_value(&name, value)?);
+    }
+    Ok(settings)
+}
+
+/// Command-line arguments after the program name, without the global options (`--env-file`, `--config`...),
+/// for the subcommands that read their own arguments.
+pub fn args() -> Vec<String> {
+    let mut out = Vec::new();
+    let mut raw = env::args().skip(1);
+    while let Some(arg) = raw.next() {
+        match arg.split_once('=').map_or(arg.as_str(), |(name, _)| name) {
+            name if crate::cli::GLOBAL_OPTIONS.contains(&name) => {
+                if !arg.contains('=') {
+                    raw.next();
+                }
+            }
+            name if crate::cli::GLOBAL_FLAGS.contains(&name) => {}
+            _ => out.push(arg),
+        }
+    }
+    out
+}
+
+/// GLPI connection settings from .env, shared by the poller and the one-shot commands.
+#[derive(Debug, Clone)]
+pub struct Connection {
+    pub base_url: String,
+    pub app_token: Option<String>,
+    pub user_token: String,
+    pub verify_ssl: bool,
+    pub scope: SessionScope,
+    pub api: Api,
+    /// Language of dropdown values in GLPI replies (GLPI_LANGUAGE, else DATE_LOCALE).
+    pub language: Option<String>,
+}
+
+impl Connection {
+    pub fn from_env() -> Result<Self> {
+        // Within an instance's scope, its own settings (see crate::instance)
+        let var = crate::instance::var;
+        let base_url = var("GLPI_BASE_URL").unwrap_or_default().trim().trim_end_matches('/').to_string();
+        let app_token = var("GLPI_APP_TOKEN").map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let user_token = var("GLPI_USER_TOKEN").unwrap_or_default().trim().to_string();
+        let verify_ssl = var("VERIFY_SSL").map(|s| s.to_lowercase() == "true").unwrap_or(true);
+        let api = api_from_env(&base_url)?;
+
+        // An SSO gateway may sign GLPI users in by itself, and the high-level API never uses the user token
+        if base_url.is_empty() || (user_token.is_empty() && matches!(api, Api::Legacy)) {
+            return Err(ConfigError(
+                "Please set GLPI_BASE_URL and GLPI_USER_TOKEN in .env (no quotes, no extra spaces).".into(),
+            )
+            .into());
+        }
+        let scope = SessionScope {
+            profile_id: env_id("GLPI_PROFILE_ID")?,
+            entity_id: env_id("GLPI_ACTIVE_ENTITY")?,
+            is_recursive: var("GLPI_ENTITY_RECURSIVE").map(|s| s.trim().to_lowercase() == "true").unwrap_or(true),
+        };
+        // Invalid values were already reported by the settings check
+        let language = ["GLPI_LANGUAGE", "DATE_LOCALE"]
+            .iter()
+            .filter_map(|key| env::var(key).ok().filter(|v| !v.trim().is_empty()))
+            .next()
+            .and_then(|v| crate::glpi::parse_language(&v).ok());
+        Ok(Self { base_url, app_token, user_token, verify_ssl, scope, api, language })
+    }
+
+    /// Build a client and open a session.
+    pub async fn connect(&self) -> Result<GlpiClient> {
+        let mut client = self.client().await?;
+        client.init_session().await?;
+        Ok(client)
+    }
+
+    /// Build a client without signing in.
+    pub async fn client(&self) -> Result<GlpiClient> {
+        Ok(GlpiClient::new(self.base_url.clone(), self.app_token.clone(), self.user_token.clone(), self.verify_ssl)
+            .await?
+            .with_scope(self.scope)
+            .with_api(self.api.clone())
+            .with_language(self.language.clone()))
+    }
+}
+
+/// With TOKEN_RELOAD=true, the tokens currently in the env files as (user token, app token), so a
+/// rotated token is picked up without a restart. They replace the ones in the environment and are read back
+/// through [`Connection::from_env`], so clients built afterwards sign in with them too.
+pub fn reload_tokens() -> Option<(String, Option<String>)> {
+    // The env files hold the top-level tokens, not an instance's
+    if crate::instance::current().is_some() || !get().token_reload {
+        return None;
+    }
+    let (mut user, mut app) = (None, None);
+    // Later files override earlier ones; one missing or unreadable now is skipped
+    for file in ENV_FILES.get()? {
+        let Ok(items) = dotenvy::from_path_iter(file) else { continue };
+        for (key, value) in items.flatten() {
+            match key.as_str() {
+                "GLPI_USER_TOKEN" => user = Some(value),
+                "GLPI_APP_TOKEN" => app = Some(value),
+                _ => {}
+            }
+        }
+    }
+    env::set_var("GLPI_USER_TOKEN", user?);
+    match app {
+        Some(app) => env::set_var("GLPI_APP_TOKEN", app),
+        None => env::remove_var("GLPI_APP_TOKEN"),
+    }
+    let conn = Connection::from_env().ok()?;
+    Some((conn.user_token, conn.app_token))
+}
+
+/// GLPI_API: `legacy` (apirest.php, default) or `hl`, the GLPI 11 high-level API. `hl` signs in with its
+/// OAuth client (GLPI_OAUTH_CLIENT_ID/SECRET) and the user's GLPI_USERNAME/GLPI_PASSWORD; `legacy` signs in
+/// to an SSO gateway in front of apirest.php when GLPI_OAUTH_FLOW is set.
+fn api_from_env(base_url: &str) -> Result<Api> {
+    if crate::instance::current().is_some() {
+        return Ok(Api::Legacy); // instances sign in with their user token
+    }
+    let var = |key: &str| env::var(key).map(|s| s.trim().to_string()).unwrap_or_default();
+    let hl = match var("GLPI_API").to_lowercase().as_str() {
+        "" | "legacy" => false,
+        "hl" => true,
+        other => return Err(ConfigError(format!("GLPI_API must be legacy or hl, got '{other}'")).into()),
+    };
+    let flow = var("GLPI_OAUTH_FLOW").to_lowercase();
+    if !hl && flow.is_empty() {
+        return Ok(Api::Legacy);
+    }
+    let (grant, required) = match flow.as_str() {
+        "" | "password" => (
+            oauth::Grant::Password { username: var("GLPI_USERNAME"), password: var("GLPI_PASSWORD") },
+            vec!["GLPI_OAUTH_CLIENT_ID", "GLPI_OAUTH_CLIENT_SECRET", "GLPI_USERNAME", "GLPI_PASSWORD"],
+        ),
+        "client_credentials" => {
+            (oauth::Grant::ClientCredentials, vec!["GLPI_OAUTH_CLIENT_ID", "GLPI_OAUTH_CLIENT_SECRET"])
+        }
+        "device_code" => (oauth::Grant::DeviceCode, vec!["GLPI_OAUTH_CLIENT_ID"]),
+        other => {
+            return Err(ConfigError(format!(
+                "GLPI_OAUTH_FLOW must be password, client_credentials or device_code, got '{other}'"
+            ))
+            .into())
+        }
+    };
+    let issuer = Some(var("GLPI_OAUTH_ISSUER")).filter(|s| !s.is_empty());
+    let token_url = match var("GLPI_OAUTH_TOKEN_URL") {
+        url if url.is_empty() && hl => format!("{}/token", hl_root(base_url)),
+        url => url,
+    };
+    let mut missing: Vec<&str> = required.into_iter().filter(|k| var(k).is_empty()).collect();
+    if token_url.is_empty() && issuer.is_none() {
+        missing.push("GLPI_OAUTH_TOKEN_URL (or GLPI_OAUTH_ISSUER)");
+    }
+    if !missing.is_empty() {
+        let what = if hl { "GLPI_API=hl" } else { "GLPI_OAUTH_FLOW" };
+        return Err(ConfigError(format!("{what} needs {}", missing.join(", "))).into());
+    }
+    let default_scope = if hl { "api" } else { "" };
+    let provider = oauth::Provider {
+        client: oauth::Client {
+            id: var("GLPI_OAUTH_CLIENT_ID"),
+            secret: var("GLPI_OAUTH_CLIENT_SECRET"),
+            scope: env::var("GLPI_OAUTH_SCOPE").map(|s| s.trim().to_string()).unwrap_or(default_scope.into()),
+        },
+        grant,
+        token_url,
+        device_url: Some(var("GLPI_OAUTH_DEVICE_URL")).filter(|s| !s.is_empty()),
+        issuer,
+    };
+    Ok(match hl {
+        true => Api::Hl(provider),
+        false => {
+            let header = Some(var("GLPI_OAUTH_HEADER")).filter(|s| !s.is_empty()).unwrap_or("Authorization".into());
+            Api::Gateway { provider, header }
+        }
+    })
+}
+
+/// Optional numeric id setting; empty means unset.
+fn env_id(key: &str) -> Result<Option<i64>> {
+    match crate::instance::var(key).map(|s| s.trim().to_string()) {
+        Some(s) if !s.is_empty() => {
+            Ok(Some(s.parse().map_err(|_| ConfigError(format!("{key} must be a numeric id, got '{s}'")))?))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Poller settings, parsed once from the layered environment (variables, env files, config.toml). Invalid
+/// values fall back to the default of their [`RULES`] entry, as reported by [`check_settings`].
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub poll_seconds: u64,
+    pub first_run_notify: bool,
+    pub debug_list: bool,
+    pub kiosk: bool,
+    /// Re-read the tokens from the env files when GLPI rejects them (TOKEN_RELOAD).
+    pub token_reload: bool,
+    /// Polls to wait for a rotated token to land in .env (TOKEN_RELOAD_CHECKS).
+    pub token_reload_checks: u64,
+    pub notify_restored: bool,
+    pub notify_max_attempts: u32,
+    /// 0 disables the offline backlog summary.
+    pub offline_summary_minutes: u64,
+    pub snooze_minutes: u64,
+    pub mute_hours: u64,
+    /// Default length of `focus <ticket>`.
+    pub focus_minutes: u64,
+    pub customer_replied: bool,
+    pub ticket_updates: bool,
+    pub group_tasks: bool,
+    /// Toast the tickets newly assigned to me (ASSIGNED_TO_ME).
+    pub assigned_to_me: bool,
+    /// Toast follow-ups by others on the tickets I requested or am assigned to (FOLLOWUPS).
+    pub followups: bool,
+    /// Status changes to toast on watched tickets (STATUS_TRANSITIONS); empty when off.
+    pub status_transitions: Vec<crate::transition::Transition>,
+    pub satisfaction_reminders: bool,
+    /// Priority from which an open ticket is a major incident; None when unset or 0.
+    pub major_incident_priority: Option<i64>,
+    /// ID_RESET=auto (default): reset the seen state when ticket ids restart; warn only otherwise.
+    pub id_reset_auto: bool,
+    /// New-queue depth alerts (QUEUE_WARN_AT / QUEUE_CRIT_AT).
+    pub queue_depth: Thresholds,
+    /// Home Assistant MQTT discovery configs with the MQTT sink (HA_DISCOVERY).
+    #[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+    pub ha_discovery: bool,
+    /// Tray icon of the resident poller (TRAY).
+    pub tray: bool,
+    /// Title text of the GLPI reminders that plan a maintenance window (MAINTENANCE_REMINDER).
+    pub maintenance_reminder: Option<String>,
+    /// Where the toast shortcut goes first (SHORTCUT_PATH), as a .lnk file.
+    pub shortcut_path: Option<PathBuf>,
+    /// Simultaneous requests per GLPI host (GLPI_MAX_CONCURRENT, at least 1).
+    pub max_concurrent: usize,
+    /// Minimum gap between two request starts on the same host (GLPI_REQUEST_DELAY_MS).
+    pub request_delay: Duration,
+    /// Largest response body accepted from GLPI (GLPI_MAX_RESPONSE_MB, at least 1 MiB).
+    pub max_response_bytes: usize,
+    /// Tickets looked up per search when reconciling many at once (GLPI_BATCH_SIZE, at least 1).
+    pub batch_size: usize,
+    /// Timezone of the dates GLPI returns (GLPI_SERVER_TZ); None for this machine's.
+    pub server_tz: Option<Tz>,
+    /// Timezone dates are shown in (DISPLAY_TZ); None for this machine's.
+    pub display_tz: Option<Tz>,
+    /// Day and month names (DATE_LOCALE); None for English.
+    pub date_locale: Option<Locale>,
+    /// NOTIFIER as set (lowercase); empty for the platform default.
+    pub notifier: &'static str,
+    /// `auto`, `winrt` or `snoretoast` (TOAST_BACKEND).
+    pub toast_backend: &'static str,
+    /// `short`, `long`, or empty to follow the scenario (TOAST_DURATION).
+    pub toast_duration: &'static str,
+    pub timer_action: bool,
+    pub action_center_cleanup: bool,
+    /// Notifications waiting for the backend before NOTIFY_OVERFLOW applies (NOTIFY_QUEUE_MAX).
+    pub notify_queue_max: usize,
+    /// NOTIFY_OVERFLOW=drop-lowest; a digest otherwise.
+    pub notify_drop_lowest: bool,
+    /// Priority value to severity overrides (SEVERITY_MAP).
+    pub severity_map: Vec<(i64, i64, Severity)>,
+    /// Re-notification cadence per severity, in seconds, for the active sink (REPEAT_MINUTES*).
+    pub repeat: BTreeMap<Severity, u64>,
+    /// Named ticket reference patterns (TICKET_REFS).
+    pub ticket_refs: Vec<crate::refs::RefRule>,
+    /// Ticket URL template per entity id of the top-level server (GLPI_TICKET_URL_TEMPLATES).
+    pub entity_url_templates: Vec<(i64, String)>,
+    /// Languages of the service desk and of this user (LANGUAGES / MY_LANGUAGES).
+    pub languages: Vec<whatlang::Lang>,
+    pub my_languages: Vec<whatlang::Lang>,
+    /// The user's default order of the queue (QUEUE_SORT).
+    pub queue_sort: crate::sort::SortKey,
+    /// Working hours of the SLA countdowns (SLA_CALENDAR); None for wall-clock time.
+    pub sla_calendar: Option<crate::calendar::Source>,
+    pub maintenance_windows: Vec<crate::maintenance::Window>,
+    /// Minutes a notified Critical ticket may stay New before the on-call phone escalation; 0 when off.
+    pub phone_escalation_minutes: u64,
+    /// TWILIO_MODE=call; an SMS otherwise.
+    pub twilio_call: bool,
+    /// The accessibility mode's verbosity (ACCESSIBILITY_VERBOSITY); None when ACCESSIBILITY is off.
+    pub accessibility: Option<crate::accessibility::Verbosity>,
+    pub demo: bool,
+    pub trace_http: bool,
+    /// TICKET_CACHE=persist keeps the ticket cache across runs.
+    pub ticket_cache_persist: bool,
+    /// state.json sizes, in KiB, above which it is reported (STATE_WARN_KB) and compressed (STATE_COMPRESS_KB).
+    pub state_warn_kb: u64,
+    pub state_compress_kb: u64,
+    pub stats_history: bool,
+    pub stats_raw_days: u64,
+    pub crash_toast: bool,
+    pub crash_report_url: Option<String>,
+    /// Entity the smoke test creates its ticket in (SMOKE_TEST_ENTITY).
+    pub smoke_test_entity: Option<i64>,
+    pub smoke_test_timeout: u64,
+}
+
+impl Config {
+    /// Read every setting from the environment.
+    pub fn from_env() -> Result<Self> {
+        Self::read(&|key| env::var(key).ok())
+    }
+
+    /// Every setting at its default.
+    fn defaults() -> Result<Self> {
+        Self::read(&|_| None)
+    }
+
+    /// An error for a value that cannot be obeyed ([`Rule::AtLeast`], the settings read with
+    /// [`Settings::parsed`]) or when a field reads a setting under a [`Rule`] of another kind.
+    fn read(var: &dyn Fn(&str) -> Option<String>) -> Result<Self> {
+        let s = Settings(var);
+        let notifier = s.choice("NOTIFIER")?;
+        let legacy = s.choice("GLPI_API")? == "legacy";
+        Ok(Self {
+            poll_seconds: s.number("POLL_SECONDS")?,
+            first_run_notify: s.bool("FIRST_RUN_NOTIFY")?,
+            debug_list: s.bool("DEBUG_LIST")?,
+            kiosk: s.bool("KIOSK")?,
+            token_reload: s.bool("TOKEN_RELOAD")?,
+            token_reload_checks: s.number("TOKEN_RELOAD_CHECKS")?,
+            notify_restored: s.bool("NOTIFY_RESTORED")?,
+            notify_max_attempts: s.number("NOTIFY_MAX_ATTEMPTS")?.try_into().unwrap_or(u32::MAX),
+            offline_summary_minutes: s.number("OFFLINE_SUMMARY_MINUTES")?,
+            snooze_minutes: s.number("SNOOZE_MINUTES")?,
+            mute_hours: s.number("MUTE_HOURS")?,
+            focus_minutes: s.number("FOCUS_MINUTES")?,
+            customer_replied: s.bool("CUSTOMER_REPLIED")? && legacy,
+            ticket_updates: s.bool("TICKET_UPDATES")? && legacy,
+            group_tasks: s.bool("GROUP_TASKS")? && legacy,
+            assigned_to_me: s.bool("ASSIGNED_TO_ME")? && legacy,
+            followups: s.bool("FOLLOWUPS")? && legacy,
+            status_transitions: s
+                .text("STATUS_TRANSITIONS")?
+                .map_or(Ok(Vec::new()), |v| crate::transition::parse(&v))?
+                .into_iter()
+                .filter(|_| legacy)
+                .collect(),
+            satisfaction_reminders: s.bool("SATISFACTION_REMINDERS")? && legacy,
+            major_incident_priority: i64::try_from(s.number("MAJOR_INCIDENT_PRIORITY")?)
+                .ok()
+                .filter(|p| *p > 0 && legacy),
+            id_reset_auto: s.choice("ID_RESET")? == "auto",
+            queue_depth: Thresholds { warn: s.number("QUEUE_WARN_AT")?, crit: s.number("QUEUE_CRIT_AT")? },
+            ha_discovery: s.bool("HA_DISCOVERY")?,
+            tray: s.bool("TRAY")?,
+            maintenance_reminder: s.text("MAINTENANCE_REMINDER")?.filter(|_| legacy),
+            shortcut_path: s.text("SHORTCUT_PATH")?.map(|p| PathBuf::from(p).with_extension("lnk")),
+            max_concurrent: usize::try_from(s.number("GLPI_MAX_CONCURRENT")?).unwrap_or(usize::MAX),
+            request_delay: Duration::from_millis(s.number("GLPI_REQUEST_DELAY_MS")?),
+            max_response_bytes: usize::try_from(s.number("GLPI_MAX_RESPONSE_MB")?)
+                .unwrap_or(usize::MAX)
+                .saturating_mul(1024 * 1024),
+            batch_size: usize::try_from(s.number("GLPI_BATCH_SIZE")?).unwrap_or(usize::MAX),
+            server_tz: s.text("GLPI_SERVER_TZ")?.and_then(|v| v.parse().ok()),
+            display_tz: s.text("DISPLAY_TZ")?.and_then(|v| v.parse().ok()),
+            date_locale: s.text("DATE_LOCALE")?.and_then(|v| Locale::try_from(v.as_str()).ok()),
+            notifier,
+            toast_backend: s.choice("TOAST_BACKEND")?,
+            toast_duration: s.choice("TOAST_DURATION")?,
+            timer_action: s.bool("TIMER_ACTION")?,
+            action_center_cleanup: s.bool("ACTION_CENTER_CLEANUP")?,
+            notify_queue_max: usize::try_from(s.number("NOTIFY_QUEUE_MAX")?).unwrap_or(usize::MAX),
+            notify_drop_lowest: s.choice("NOTIFY_OVERFLOW")? == "drop-lowest",
+            severity_map: s.parsed("SEVERITY_MAP", crate::severity::parse)?,
+            repeat: crate::severity::repeat_for(notifier, var)?,
+            ticket_refs: s.parsed("TICKET_REFS", crate::refs::parse)?,
+            entity_url_templates: s
+                .text("GLPI_TICKET_URL_TEMPLATES")?
+                .map_or(Ok(Vec::new()), |v| crate::toast::parse_entity_templates(&v))?,
+            languages: s.text("LANGUAGES")?.map_or(Ok(Vec::new()), |v| crate::lang::parse(&v))?,
+            my_languages: s.text("MY_LANGUAGES")?.map_or(Ok(Vec::new()), |v| crate::lang::parse(&v))?,
+            queue_sort: s.text("QUEUE_SORT")?.map_or(Ok(crate::sort::SortKey::Id), |v| crate::sort::parse(&v))?,
+            sla_calendar: s.text("SLA_CALENDAR")?.map_or(Ok(None), |v| crate::calendar::parse(&v))?,
+            maintenance_windows: s
+                .text("MAINTENANCE_WINDOWS")?
+                .map_or(Ok(Vec::new()), |v| crate::maintenance::parse(&v))?,
+            phone_escalation_minutes: s.number("PHONE_ESCALATION_MINUTES")?,
+            twilio_call: s.choice("TWILIO_MODE")? == "call",
+            accessibility: match s.bool("ACCESSIBILITY")? {
+                true => Some(match s.choice("ACCESSIBILITY_VERBOSITY")? {
+                    "brief" => crate::accessibility::Verbosity::Brief,
+                    "full" => crate::accessibility::Verbosity::Full,
+                    _ => crate::accessibility::Verbosity::Normal,
+                }),
+                false => None,
+            },
+            demo: s.bool("DEMO")?,
+            trace_http: s.bool("TRACE_HTTP")?,
+            ticket_cache_persist: s.choice("TICKET_CACHE")? == "persist",
+            state_warn_kb: s.number("STATE_WARN_KB")?,
+            state_compress_kb: s.number("STATE_COMPRESS_KB")?,
+            stats_history: s.bool("STATS_HISTORY")?,
+            stats_raw_days: s.number("STATS_RAW_DAYS")?,
+            crash_toast: s.bool("CRASH_TOAST")?,
+            crash_report_url: s.text("CRASH_REPORT_URL")?,
+            smoke_test_entity: s.text("SMOKE_TEST_ENTITY")?.and_then(|v| v.parse().ok()),
+            smoke_test_timeout: s.number("SMOKE_TEST_TIMEOUT")?,
+        })
+    }
+}
+
+/// The settings read by [`init`] at startup, or their defaults where startup did not run (unit tests).
+pub fn get() -> &'static Config {
+    // A field reading a setting of another kind is the only error of the defaults, and the tests rule it out
+    CONFIG.get_or_init(|| Config::defaults().unwrap_or_else(|e| unreachable!("{e:#}")))
+}
+
+/// Read the settings once, after [`load_env_files`] and [`load_config_file`]; the error names the setting.
+pub fn init() -> Result<&'static Config> {
+    CONFIG.get_or_try_init(Config::from_env)
+}
+
+fn rule(key: &str) -> Option<&'static Rule> {
+    RULES.iter().find(|(k, _)| *k == key).map(|(_, r)| r)
+}
+
+/// A setting read as a kind its [`RULES`] entry does not have.
+fn not_a(key: &str, kind: &str) -> anyhow::Error {
+    anyhow::anyhow!("{key} is not a {kind} setting in config::RULES")
+}
+
+/// Where [`Config`] reads its settings: the environment, or nothing at all for the defaults.
+struct Settings<'a>(&'a dyn Fn(&str) -> Option<String>);
+
+impl Settings<'_> {
+    fn value(&self, key: &str) -> String {
+        (self.0)(key).unwrap_or_default().trim().to_string()
+    }
+
+    /// Value of a [`Rule::Number`] or [`Rule::AtLeast`] setting; its default when unset or invalid. A value
+    /// below the minimum is an error rather than a fallback: it is set, readable and would be obeyed.
+    fn number(&self, key: &str) -> Result<u64> {
+        let (min, default) = match rule(key) {
+            Some(Rule::Number(default)) => (0, *default),
+            Some(Rule::AtLeast(min, default)) => (*min, *default),
+            _ => return Err(not_a(key, "number")),
+        };
+        match number(&self.value(key)).ok() {
+            Some(n) if n < min => Err(ConfigError(format!("{key}={n}: must be at least {min}")).into()),
+            n => Ok(n.unwrap_or(default)),
+        }
+    }
+
+    /// Value of a [`Rule::Bool`] setting; its default when unset or invalid.
+    fn bool(&self, key: &str) -> Result<bool> {
+        let Some(Rule::Bool(default)) = rule(key) else { return Err(not_a(key, "true/false")) };
+        Ok(match self.value(key).to_lowercase().as_str() {
+            "true" => true,
+            "false" => false,
+            _ => *default,
+        })
+    }
+
+    /// Value of a [`Rule::Choice`] setting, lowercase; the first choice when unset or invalid.
+    fn choice(&self, key: &str) -> Result<&'static str> {
+        let Some(Rule::Choice(choices)) = rule(key) else { return Err(not_a(key, "choice")) };
+        let value = self.value(key);
+        Ok(choices.iter().copied().find(|c| c.eq_ignore_ascii_case(&value)).unwrap_or(choices[0]))
+    }
+
+    /// Value of a [`Rule::Url`], [`Rule::TimeZone`] or [`Rule::Custom`] setting, trimmed; None when unset or
+    /// invalid.
+    fn text(&self, key: &str) -> Result<Option<String>> {
+        let rule = match rule(key) {
+            Some(rule @ (Rule::Url | Rule::TimeZone | Rule::Custom(_))) => rule,
+            _ => return Err(not_a(key, "text")),
+        };
+        Ok(Some(self.value(key)).filter(|s| !s.is_empty() && check(rule, s).is_ok()))
+    }
+
+    /// A [`Rule::Custom`] setting parsed by `parse`, its default when unset. Unlike the other kinds an invalid
+    /// value is an error: for the settings whose module never had a fallback for one.
+    fn parsed<T>(&self, key: &str, parse: impl Fn(&str) -> Result<T>) -> Result<T> {
+        let Some(Rule::Custom(_)) = rule(key) else { return Err(not_a(key, "custom")) };
+        parse(&self.value(key))
+    }
+}
+
+/// Accepted values of one setting, for [`validate`].
+enum Rule {
+    /// Non-negative integer; the default used when the value is invalid.
+    Number(u64),
+    /// Integer no lower than the first value, which is enforced; the second is the default.
+    AtLeast(u64, u64),
+    /// `true` / `false`.
+    Bool(bool),
+    /// One of the listed words (case-insensitive); the first one is the default.
+    Choice(&'static [&'static str]),
+    /// http(s) URL.
+    Url,
+    /// Ticket URL template: http(s) URL containing `{id}`.
+    Template,
+    /// IANA timezone name.
+    TimeZone,
+    /// Parsed by the module that uses it.
+    Custom(fn(&str) -> Result<()>),
+}
+
+const RULES: &[(&str, Rule)] = &[
+    ("GLPI_BASE_URL", Rule::Url),
+    ("GLPI_API", Rule::Choice(&["legacy", "hl"])),
+    ("GLPI_OAUTH_FLOW", Rule::Choice(&["password", "client_credentials", "device_code"])),
+    ("GLPI_OAUTH_TOKEN_URL", Rule::Url),
+    ("GLPI_OAUTH_DEVICE_URL", Rule::Url),
+    ("GLPI_OAUTH_ISSUER", Rule::Url),
+    ("GLPI_OAUTH_HEADER", Rule::Custom(|v| Ok(reqwest::header::HeaderName::try_from(v.trim()).map(drop)?))),
+    ("VERIFY_SSL", Rule::Bool(true)),
+    ("GLPI_PROFILE_ID", Rule::Custom(|v| number(v).map(drop))),
+    ("GLPI_ACTIVE_ENTITY", Rule::Custom(|v| number(v).map(drop))),
+    ("GLPI_ENTITY_RECURSIVE", Rule::Bool(true)),
+    ("GLPI_TICKET_URL_TEMPLATE", Rule::Template),
+    ("GLPI_TICKET_URL_TEMPLATES", Rule::Custom(|v| crate::toast::parse_entity_templates(v).map(drop))),
+    ("GLPI_MAX_CONCURRENT", Rule::Number(4)),
+    ("GLPI_REQUEST_DELAY_MS", Rule::Number(0)),
+    ("GLPI_MAX_RESPONSE_MB", Rule::Number(32)),
+    ("GLPI_BATCH_SIZE", Rule::Number(50)),
+    ("GLPI_SERVER_TZ", Rule::TimeZone),
+    ("DISPLAY_TZ", Rule::TimeZone),
+    (
+        "DATE_LOCALE",
+        Rule::Custom(|v| {
+            chrono::Locale::try_from(v).map(drop).map_err(|_| anyhow::anyhow!("unknown locale (e.g. fr_FR)"))
+        }),
+    ),
+    ("GLPI_LANGUAGE", Rule::Custom(|v| crate::glpi::parse_language(v).map(drop))),
+    ("POLL_SECONDS", Rule::AtLeast(1, 60)),
+    ("FIRST_RUN_NOTIFY", Rule::Bool(false)),
+    ("DEBUG_LIST", Rule::Bool(false)),
+    ("TICKET_FILTER", Rule::Custom(|v| crate::filter::parse(v).map(drop))),
+    ("MY_LOCATIONS", Rule::Custom(|v| crate::filter::parse_locations(v).map(drop))),
+    ("LANGUAGES", Rule::Custom(|v| crate::lang::parse(v).map(drop))),
+    ("MY_LANGUAGES", Rule::Custom(|v| crate::lang::parse(v).map(drop))),
+    ("SEVERITY_MAP", Rule::Custom(|v| crate::severity::parse(v).map(drop))),
+    ("TICKET_REFS", Rule::Custom(|v| crate::refs::parse(v).map(drop))),
+    ("MAJOR_INCIDENT_PRIORITY", Rule::Number(0)),
+    ("REPEAT_MINUTES", Rule::Custom(|v| crate::severity::parse_repeat("REPEAT_MINUTES", v).map(drop))),
+    ("REPEAT_MINUTES_TOAST", Rule::Custom(|v| crate::severity::parse_repeat("REPEAT_MINUTES_TOAST", v).map(drop))),
+    ("REPEAT_MINUTES_DBUS", Rule::Custom(|v| crate::severity::parse_repeat("REPEAT_MINUTES_DBUS", v).map(drop))),
+    ("REPEAT_MINUTES_MACOS", Rule::Custom(|v| crate::severity::parse_repeat("REPEAT_MINUTES_MACOS", v).map(drop))),
+    ("ID_RESET", Rule::Choice(&["auto", "warn"])),
+    ("QUEUE_WARN_AT", Rule::Number(0)),
+    ("QUEUE_CRIT_AT", Rule::Number(0)),
+    ("NOTIFIER", Rule::Choice(&["", "toast", "dbus", "macos", "null", "none"])),
+    ("TOAST_BACKEND", Rule::Choice(&["auto", "winrt", "snoretoast"])),
+    ("SHORTCUT_PATH", Rule::Custom(crate::toast::check_shortcut_path)),
+    ("NOTIFY_MAX_ATTEMPTS", Rule::Number(3)),
+    ("NOTIFY_RESTORED", Rule::Bool(false)),
+    ("NOTIFY_QUEUE_MAX", Rule::Number(20)),
+    ("NOTIFY_OVERFLOW", Rule::Choice(&["digest", "drop-lowest"])),
+    ("OFFLINE_SUMMARY_MINUTES", Rule::Number(240)),
+    ("SNOOZE_MINUTES", Rule::Number(15)),
+    ("MUTE_HOURS", Rule::Number(24)),
+    ("FOCUS_MINUTES", Rule::Number(60)),
+    ("TIMER_ACTION", Rule::Bool(false)),
+    ("TOAST_DURATION", Rule::Choice(&["", "short", "long"])),
+    ("ACTION_CENTER_CLEANUP", Rule::Bool(false)),
+    ("CRASH_TOAST", Rule::Bool(true)),
+    ("CRASH_REPORT_URL", Rule::Url),
+    ("TRACE_HTTP", Rule::Bool(false)),
+    ("CONFIG_STRICT", Rule::Bool(false)),
+    ("KIOSK", Rule::Bool(false)),
+    ("PHONE_ESCALATION_MINUTES", Rule::Number(0)),
+    ("TWILIO_MODE", Rule::Choice(&["sms", "call"])),
+    ("TWILIO_FROM", Rule::Custom(crate::phone::check_number)),
+    ("ONCALL_NUMBER", Rule::Custom(crate::phone::check_number)),
+    ("MQTT_URL", Rule::Custom(crate::mqtt::check_url)),
+    ("MQTT_TOPIC", Rule::Custom(crate::mqtt::check_topic)),
+    ("HA_DISCOVERY", Rule::Bool(false)),
+    ("HA_DISCOVERY_PREFIX", Rule::Custom(crate::mqtt::check_topic)),
+    ("SATISFACTION_REMINDERS", Rule::Bool(false)),
+    ("CUSTOMER_REPLIED", Rule::Bool(false)),
+    ("TICKET_UPDATES", Rule::Bool(false)),
+    ("GROUP_TASKS", Rule::Bool(false)),
+    ("ASSIGNED_TO_ME", Rule::Bool(false)),
+    ("FOLLOWUPS", Rule::Bool(false)),
+    ("STATUS_TRANSITIONS", Rule::Custom(|v| crate::transition::parse(v).map(drop))),
+    ("SLA_CALENDAR", Rule::Custom(|v| crate::calendar::parse(v).map(drop))),
+    ("MAINTENANCE_WINDOWS", Rule::Custom(|v| crate::maintenance::parse(v).map(drop))),
+    ("MAINTENANCE_REMINDER", Rule::Custom(crate::maintenance::check_reminder)),
+    ("TICKET_CACHE", Rule::Choice(&["memory", "persist"])),
+    ("STATE_COMPRESS_KB", Rule::Number(1024)),
+    ("STATE_WARN_KB", Rule::Number(5120)),
+    ("STATS_HISTORY", Rule::Bool(true)),
+    ("STATS_RAW_DAYS", Rule::Number(7)),
+    ("TRAY", Rule::Bool(true)),
+    ("DEMO", Rule::Bool(false)),
+    ("ACCESSIBILITY", Rule::Bool(false)),
+    ("ACCESSIBILITY_VERBOSITY", Rule::Choice(&["normal", "brief", "full"])),
+    ("WEBHOOK_LISTEN", Rule::Custom(|v| crate::webhook::parse_listen(v).map(drop))),
+    ("QUEUE_SORT", Rule::Custom(|v| crate::sort::parse(v).map(drop))),
+    ("TOKEN_RELOAD", Rule::Bool(false)),
+    ("TOKEN_RELOAD_CHECKS", Rule::Number(10)),
+    ("SMOKE_TEST_ENTITY", Rule::Custom(|v| number(v).map(drop))),
+    ("SMOKE_TEST_TIMEOUT", Rule::Number(120)),
+];
+
+fn number(v: &str) -> Result<u64> {
+    v.parse().map_err(|_| anyhow::anyhow!("not a whole number"))
+}
+
+/// Check every set value against [`RULES`]; one message per invalid setting, naming the key.
+pub fn validate() -> Vec<String> {
+    let mut problems: Vec<String> = RULES
+        .iter()
+        .filter_map(|(key, rule)| {
+            let value = env::var(key).ok()?.trim().to_string();
+            if value.is_empty() {
+                return None;
+            }
+            check(rule, &value).err().map(|why| format!("{key}={value}: {why}"))
+        })
+        .collect();
+    for instance in crate::instance::all() {
+        for (key, value) in instance.settings() {
+            if let Some(why) = rule(key).and_then(|rule| check(rule, value.trim()).err()) {
+                problems.push(format!("[instances.{}] {key}={value}: {why}", instance.name));
+            }
+        }
+    }
+    if Settings(&|key| env::var(key).ok()).choice("GLPI_API").is_ok_and(|api| api == "hl") {
+        for key in LEGACY_ONLY {
+            let value = env::var(key).unwrap_or_default().trim().to_string();
+            if !matches!(value.to_lowercase().as_str(), "" | "false" | "0") {
+                problems.push(format!("{key}={value}: needs the legacy apirest.php, turned off with GLPI_API=hl"));
+            }
+        }
+    }
+    problems
+}
+
+/// Watchers reading more than the New queue, which the high-level API does not cover (see `glpi/hl.rs`):
+/// [`Config`] turns them off with GLPI_API=hl and [`validate`] reports the ones that were set.
+const LEGACY_ONLY: &[&str] = &[
+    "CUSTOMER_REPLIED",
+    "TICKET_UPDATES",
+    "ASSIGNED_TO_ME",
+    "FOLLOWUPS",
+    "STATUS_TRANSITIONS",
+    "GROUP_TASKS",
+    "SATISFACTION_REMINDERS",
+    "MAINTENANCE_REMINDER",
+    "MAJOR_INCIDENT_PRIORITY",
+];
+
+fn check(rule: &Rule, value: &str) -> std::result::Result<(), String> {
+    let url = |v: &str| match reqwest::Url::parse(v) {
+        Ok(u) if matches!(u.scheme(), "http" | "https") => Ok(()),
+        Ok(_) => Err("must be an http(s) URL".to_string()),
+        Err(e) => Err(format!("malformed URL ({e})")),
+    };
+    match rule {
+        Rule::Number(default) => number(value).map(drop).map_err(|e| format!("{e}; default is {default}")),
+        Rule::AtLeast(min, default) => match number(value) {
+            Ok(n) if n < *min => Err(format!("must be at least {min}; default is {default}")),
+            n => n.map(drop).map_err(|e| format!("{e}; default is {default}")),
+        },
+        Rule::Bool(default) => match value.to_lowercase().as_str() {
+            "true" | "false" => Ok(()),
+            _ => Err(format!("expected true or false; default is {default}")),
+        },
+        Rule::Choice(choices) => match choices.iter().any(|c| c.eq_ignore_ascii_case(value)) {
+            true => Ok(()),
+            false => Err(format!(
+                "expected one of {}; default is {}",
+                choices.iter().filter(|c| !c.is_empty()).copied().collect::<Vec<_>>().join(", "),
+                if choices[0].is_empty() { "unset" } else { choices[0] }
+            )),
+        },
+        Rule::Url => url(value),
+        Rule::Template if !value.contains("{id}") => Err("missing the {id} placeholder".into()),
+        Rule::Template => url(&value.replace("{id}", "1")),
+        Rule::TimeZone => value
+            .parse::<chrono_tz::Tz>()
+            .map(drop)
+            .map_err(|_| "unknown timezone (IANA name like Europe/Lisbon); default is this machine's".into()),
+        Rule::Custom(parse) => parse(value).map_err(|e| format!("{e:#}")),
+    }
+}
+
+/// Report invalid settings at startup. With CONFIG_STRICT=true they stop the program (exit code 2);
+/// otherwise each is logged and the setting falls back to its default.
+pub fn check_settings() -> Result<()> {
+    let problems = validate();
+    let strict = Settings(&|key| env::var(key).ok()).bool("CONFIG_STRICT")?;
+    if strict && !problems.is_empty() {
+        return Err(ConfigError(format!("invalid settings (CONFIG_STRICT=true):\n  {}", problems.join("\n  "))).into());
+    }
+    for p in &problems {
+        log::warn!("Invalid setting {p}");
+    }
+    init().map(drop)
+}
+
+/// Settings carried by `config export` / `config import`. Credentials (GLPI_USER_TOKEN, GLPI_APP_TOKEN),
+/// machine-local values (GLPI_LOGO_PATH, NOTIFY_SOCKET) and personal ones (GLPI_LANGUAGE) are never shared.
+pub const SHARED_SETTINGS: &[&str] = &[
+    "GLPI_BASE_URL",
+    "GLPI_API",
+    "GLPI_OAUTH_FLOW",
+    "GLPI_OAUTH_CLIENT_ID",
+    "GLPI_OAUTH_SCOPE",
+    "GLPI_OAUTH_TOKEN_URL",
+    "GLPI_OAUTH_DEVICE_URL",
+    "GLPI_OAUTH_ISSUER",
+    "GLPI_OAUTH_HEADER",
+    "VERIFY_SSL",
+    "GLPI_PROFILE_ID",
+    "GLPI_ACTIVE_ENTITY",
+    "GLPI_ENTITY_RECURSIVE",
+    "GLPI_TICKET_URL_TEMPLATE",
+    "GLPI_TICKET_URL_TEMPLATES",
+    "GLPI_MAX_CONCURRENT",
+    "GLPI_REQUEST_DELAY_MS",
+    "GLPI_MAX_RESPONSE_MB",
+    "GLPI_BATCH_SIZE",
+    "GLPI_SERVER_TZ",
+    "DISPLAY_TZ",
+    "DATE_LOCALE",
+    "POLL_SECONDS",
+    "FIRST_RUN_NOTIFY",
+    "TICKET_FILTER",
+    "MY_LOCATIONS",
+    "LANGUAGES",
+    "ID_RESET",
+    "QUEUE_WARN_AT",
+    "QUEUE_CRIT_AT",
+    "NOTIFIER",
+    "TOAST_BACKEND",
+    "NOTIFY_MAX_ATTEMPTS",
+    "NOTIFY_RESTORED",
+    "NOTIFY_QUEUE_MAX",
+    "NOTIFY_OVERFLOW",
+    "OFFLINE_SUMMARY_MINUTES",
+    "SNOOZE_MINUTES",
+    "MUTE_HOURS",
+    "FOCUS_MINUTES",
+    "TIMER_ACTION",
+    "SEVERITY_MAP",
+    "TICKET_REFS",
+    "MAJOR_INCIDENT_PRIORITY",
+    "REPEAT_MINUTES",
+    "REPEAT_MINUTES_TOAST",
+    "REPEAT_MINUTES_DBUS",
+    "REPEAT_MINUTES_MACOS",
+    "TOAST_DURATION",
+    "ACTION_CENTER_CLEANUP",
+    "CRASH_TOAST",
+    "CRASH_REPORT_URL",
+    "SMOKE_TEST_ENTITY",
+    "SMOKE_TEST_TIMEOUT",
+    "CONFIG_STRICT",
+    "KIOSK",
+    "PHONE_ESCALATION_MINUTES",
+    "TWILIO_MODE",
+    "TWILIO_FROM",
+    "ONCALL_NUMBER",
+    "MQTT_URL",
+    "MQTT_TOPIC",
+    "HA_DISCOVERY",
+    "HA_DISCOVERY_PREFIX",
+    "SATISFACTION_REMINDERS",
+    "CUSTOMER_REPLIED",
+    "TICKET_UPDATES",
+    "GROUP_TASKS",
+    "ASSIGNED_TO_ME",
+    "FOLLOWUPS",
+    "STATUS_TRANSITIONS",
+    "SLA_CALENDAR",
+    "MAINTENANCE_WINDOWS",
+    "MAINTENANCE_REMINDER",
+    "TICKET_CACHE",
+    "STATE_COMPRESS_KB",
+    "STATE_WARN_KB",
+    "STATS_HISTORY",
+    "STATS_RAW_DAYS",
+    "TRAY",
+    "QUEUE_SORT",
+    "TOKEN_RELOAD",
+    "TOKEN_RELOAD_CHECKS",
+];
+
+/// `config export`: the shared settings currently set, as a TOML profile.
+pub fn export_profile() -> Result<String> {
+    let settings: BTreeMap<String, String> = SHARED_SETTINGS
+        .iter()
+        .filter_map(|k| env::var(k).ok().map(|v| (k.to_string(), v.trim().to_string())))
+        .filter(|(_, v)| !v.is_empty())
+        .collect();
+    let mut doc = toml::Table::new();
+    doc.insert("settings".into(), toml::Value::try_from(settings)?);
+    Ok(format!("# glpi-notifier-rs profile (tokens excluded)\n{}", toml::to_string(&doc)?))
+}
+
+/// `config show`: the env files in use and the effective value of every setting that is set (tokens masked).
+pub fn show() -> String {
+    let mut out = String::from("Env files (later ones override earlier ones; the environment overrides all):\n");
+    match ENV_FILES.get().filter(|f| !f.is_empty()) {
+        Some(files) => files.iter().for_each(|f| out.push_str(&format!("  {}\n", f.display()))),
+        None => out.push_str("  (none: no --env-file, GLPI_NOTIFIER_ENV or .env found)\n"),
+    }
+    match CONFIG_FILE.get() {
+        Some(path) => out.push_str(&format!("Config file (below the env files):\n  {}\n", path.display())),
+        None => out.push_str("Config file: none (config.toml next to the exe or in the user config directory)\n"),
+    }
+    out.push_str("Settings:\n");
+    for key in ["GLPI_USER_TOKEN", "GLPI_APP_TOKEN"] {
+        if env::var(key).is_ok_and(|v| !v.trim().is_empty()) {
+            out.push_str(&format!("  {key}=(set)\n"));
+        }
+    }
+    for (key, _) in RULES {
+        if let Some(value) = env::var(key).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+            let secret = ["TOKEN", "SID", "SECRET", "PASSWORD"].iter().any(|s| key.contains(s));
+            let value = if secret { "(set)".to_string() } else { value };
+            out.push_str(&format!("  {key}={value}\n"));
+        }
+    }
+    for instance in crate::instance::all() {
+        out.push_str(&format!("Instance {} (config.toml):\n", instance.name));
+        for (key, value) in instance.settings() {
+            let value = if key.contains("TOKEN") { "(set)" } else { value };
+            out.push_str(&format!("  {key}={value}\n"));
+        }
+    }
+    out
+}
+
+/// `config import <file>`: merge a profile into `env_file`. Returns the number of settings written.
+pub fn import_profile(profile: &Path, env_file: &Path) -> Result<usize> {
+    let text = std::fs::read_to_string(profile).with_context(|| format!("reading {}", profile.display()))?;
+    let settings = parse_profile(&text)?;
+    let current = std::fs::read_to_string(env_file).unwrap_or_default();
+    std::fs::write(env_file, merge_env(&current, &settings))
+        .with_context(|| format!("writing {}", env_file.display()))?;
+    Ok(settings.len())
+}
+
+/// Write one setting into the env file (the last one loaded, else ./.env), e.g. a default picked from the CLI.
+pub fn save_setting(key: &str, value: &str) -> Result<PathBuf> {
+    let env_file = ENV_FILES.get().and_then(|f| f.last().cloned()).unwrap_or(".env".into());
+    let current = std::fs::read_to_string(&env_file).unwrap_or_default();
+    let settings = BTreeMap::from([(key.to_string(), value.to_string())]);
+    std::fs::write(&env_file, merge_env(&current, &settings))
+        .with_context(|| format!("writing {}", env_file.display()))?;
+    Ok(env_file)
+}
+
+fn parse_profile(text: &str) -> Result<BTreeMap<String, String>> {
+    let doc: toml::Table = toml::from_str(text).map_err(|e| ConfigError(format!("invalid profile: {e}")))?;
+    let Some(table) = doc.get("settings").and_then(toml::Value::as_table) else {
+        return Err(ConfigError("profile has no [settings] table".into()).into());
+    };
+    let mut settings = BTreeMap::new();
+    for (key, value) in table {
+        if !SHARED_SETTINGS.contains(&key.as_str()) {
+            return Err(ConfigError(format!("profile setting {key} is unknown or not shareable")).into());
+        }
+        let value = setting_value(key, value).map_err(|e| ConfigError(format!("profile {e}")))?;
+        settings.insert(key.clone(), value);
+    }
+    Ok(settings)
+}
+
+/// A TOML value as the one-line string an env file would hold.
+pub(crate) fn setting_value(key: &str, value: &toml::Value) -> Result<String> {
+    let value = match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Integer(_) | toml::Value::Boolean(_) | toml::Value::Float(_) => value.to_string(),
+        toml::Value::Array(_) if key == "TICKET_FILTER" => crate::filter::from_toml(value)?,
+        _ => anyhow::bail!("setting {key} must be a string, number or boolean"),
+    };
+    if value.contains('\n') {
+        anyhow::bail!("setting {key} must fit on one line");
+    }
+    Ok(value)
+}
+
+/// Replace `KEY=` lines of a .env file (commented defaults included) and append the missing keys,
+/// leaving every other line untouched.
+fn merge_env(current: &str, settings: &BTreeMap<String, String>) -> String {
+    let mut pending = settings.clone();
+    let mut out: Vec<String> = current
+        .lines()
+        .map(|line| {
+            let key = line.trim_start_matches(['#', ' ']).split('=').next().unwrap_or("").trim();
+            match pending.remove(key) {
+                Some(value) if line.contains('=') => format!("{key}={}", env_value(&value)),
+                Some(value) => {
+                    pending.insert(key.to_string(), value);
+                    line.to_string()
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    out.extend(pending.into_iter().map(|(k, v)| format!("{k}={}", env_value(&v))));
+    out.join("\n") + "\n"
+}
+
+/// A value as written to an env file: double-quoted when dotenv would not read it back as is (spaces, quotes...).
+fn env_value(value: &str) -> String {
+    if value.chars().any(|c| c.is_whitespace() || "#'\"\\$".contains(c)) {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$"))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_report_the_reason() {
+        assert!(check(&Rule::Number(60), "60").is_ok());
+        assert_eq!(check(&Rule::Number(60), "1m").unwrap_err(), "not a whole number; default is 60");
+        assert_eq!(check(&Rule::AtLeast(1, 60), "0").unwrap_err(), "must be at least 1; default is 60");
+        assert!(check(&Rule::AtLeast(1, 60), "1").is_ok());
+        assert!(check(&Rule::Choice(&["auto", "warn"]), "WARN").is_ok());
+        assert!(check(&Rule::Template, "https://glpi/front/ticket.form.php?id={id}").is_ok());
+        assert_eq!(
+            check(&Rule::Template, "https://glpi/front/ticket.form.php").unwrap_err(),
+            "missing the {id} placeholder"
+        );
+        assert!(check(&Rule::Url, "glpi.example.org/apirest.php").is_err());
+        for key in ["REPEAT_MINUTES_TOAST", "REPEAT_MINUTES_DBUS", "REPEAT_MINUTES_MACOS"] {
+            assert!(SHARED_SETTINGS.contains(&key));
+            assert!(check(rule(key).unwrap(), "urgent=5").unwrap_err().contains(key));
+        }
+    }
+
+    #[test]
+    fn config_reads_each_setting_as_its_rule() {
+        // Settings no other test reads: the environment is shared by the tests
+        env::set_var("SNOOZE_MINUTES", " 45 ");
+        env::set_var("MUTE_HOURS", "a day");
+        env::set_var("ID_RESET", "WARN");
+        env::set_var("SHORTCUT_PATH", r"C:\Users\you\Desktop\GlpiNotifier.lnk");
+        let config = Config::from_env().unwrap();
+        assert_eq!((config.snooze_minutes, config.mute_hours, config.focus_minutes), (45, 24, 60));
+        assert!(!config.id_reset_auto);
+        assert_eq!(config.shortcut_path, None);
+
+        env::set_var("SHORTCUT_PATH", r"C:\ProgramData\Microsoft\Windows\Start Menu\Programs\GlpiNotifier");
+        let config = Config::from_env().unwrap();
+        let expected = r"C:\ProgramData\Microsoft\Windows\Start Menu\Programs\GlpiNotifier.lnk";
+        assert_eq!(config.shortcut_path, Some(PathBuf::from(expected)));
+        for key in ["SNOOZE_MINUTES", "MUTE_HOURS", "ID_RESET", "SHORTCUT_PATH"] {
+            env::remove_var(key);
+        }
+
+        let settings = Settings(&|key| env::var(key).ok());
+        assert!(settings.bool("POLL_SECONDS").unwrap_err().to_string().contains("not a true/false setting"));
+        assert!(settings.number("NOT_A_SETTING").is_err());
+        assert_eq!(Config::defaults().unwrap().poll_seconds, 60);
+
+        let zero = Config::read(&|key| (key == "POLL_SECONDS").then(|| "0".to_string())).unwrap_err();
+        assert_eq!(zero.to_string(), "POLL_SECONDS=0: must be at least 1");
+
+        let hl = |key: &str| match key {
+            "GLPI_API" => Some("hl".to_string()),
+            "CUSTOMER_REPLIED" | "GROUP_TASKS" => Some("true".to_string()),
+            _ => None,
+        };
+        let config = Config::read(&hl).unwrap();
+        assert!(!config.customer_replied && !config.group_tasks);
+    }
+
+    #[test]
+    fn import_merges_into_env_and_rejects_tokens() {
+        let settings = parse_profile("[settings]\nPOLL_SECONDS = 30\nTICKET_FILTER = 'category under 12'\n").unwrap();
+        let env = "# Poll interval\n# POLL_SECONDS=60\nGLPI_USER_TOKEN=secret\n";
+        assert_eq!(
+            merge_env(env, &settings),
+            "# Poll interval\nPOLL_SECONDS=30\nGLPI_USER_TOKEN=secret\nTICKET_FILTER=\"category under 12\"\n"
+        );
+
+        assert!(parse_profile("[settings]\nGLPI_USER_TOKEN = 'x'\n").is_err());
+        assert!(parse_profile("POLL_SECONDS = 30\n").is_err());
+    }
+
+    #[test]
+    fn config_file_keys_are_setting_names() {
+        let settings =
+            parse_config_file("poll_seconds = 30\nGLPI_BASE_URL = 'https://glpi/apirest.php'\nkiosk = true\n").unwrap();
+        assert_eq!(
+            settings.into_iter().collect::<Vec<_>>(),
+            [
+                ("GLPI_BASE_URL".to_string(), "https://glpi/apirest.php".to_string()),
+                ("KIOSK".into(), "true".into()),
+                ("POLL_SECONDS".into(), "30".into())
+            ]
+        );
+        assert!(parse_config_file("[glpi]\nbase_url = 'x'\n").is_err());
+        assert!(parse_config_file("poll-seconds = 30\n").is_err());
+    }
+}
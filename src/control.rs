@@ -0,0 +1,290 @@
+//! Control channel between `ctl` and the running poller: `control.json` in the data directory.
+//!
+//! `ctl pause [--minutes N]`, `ctl resume`, `ctl poll-now` and `ctl kiosk on|off` write requests the poller reads every second;
+//! `ctl status` reports the poller state. With `--json`, every command prints one [`CtlReply`] document
+//! (layout published by `ctl schema`), so scripts (PowerShell remoting, fleet tooling) need no screen-scraping.
+//!
+//! One-shot commands that change the seen state (`stop-timer`, `mutes clear`, `reset-seen`) go through it too: the running poller holds the
+//! state in memory and saves it every poll, over whatever they wrote to state.json, so it applies their
+//! [`StateEdit`]s to its own copy.
+
+use crate::heartbeat::read_heartbeat;
+use crate::state::{load_state, now_secs, save_state, SeenState};
+use crate::widget::read_status;
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Version of the `ctl --json` reply layout.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Requests pending for the poller.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Control {
+    /// Polling suspended until this UNIX time (`u64::MAX` = until `ctl resume`).
+    #[serde(default)]
+    pub paused_until: Option<u64>,
+    /// UNIX time of the last `poll-now` request.
+    #[serde(default)]
+    pub poll_requested: u64,
+    /// UNIX time the poller last served a `poll-now` request.
+    #[serde(default)]
+    pub poll_served: u64,
+    /// Kiosk mode: set at poller start (`--kiosk` / KIOSK), switched at runtime with `ctl kiosk on|off`.
+    #[serde(default)]
+    pub kiosk: Option<bool>,
+    /// Latest state edits of the one-shot commands, oldest first.
+    #[serde(default)]
+    pub state_edits: Vec<PendingEdit>,
+}
+
+/// A change to the seen state asked for by a one-shot command.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "edit", rename_all = "kebab-case")]
+pub enum StateEdit {
+    /// `stop-timer`: the time of this ticket's timer was posted to GLPI.
+    StopTimer { ticket: i64 },
+    /// `mutes clear`: every mute lifted.
+    ClearMutes,
+    /// `reset-seen`: seen ticket ids, snoozes and timers forgotten; the next poll is a first run.
+    ResetSeen,
+}
+
+impl StateEdit {
+    fn apply(&self, st: &mut SeenState) {
+        match self {
+            StateEdit::StopTimer { ticket } => {
+                st.timers.remove(ticket);
+            }
+            StateEdit::ClearMutes => st.mutes.clear(),
+            StateEdit::ResetSeen => st.reset_ticket_ids(0),
+        }
+    }
+}
+
+impl std::fmt::Display for StateEdit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateEdit::StopTimer { ticket } => write!(f, "timer of #{ticket} stopped"),
+            StateEdit::ClearMutes => f.write_str("mutes cleared"),
+            StateEdit::ResetSeen => f.write_str("seen tickets forgotten"),
+        }
+    }
+}
+
+/// A [`StateEdit`] of the state of `instance` (None: the top-level server), numbered in request order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingEdit {
+    pub seq: u64,
+    #[serde(default)]
+    pub instance: Option<String>,
+    #[serde(flatten)]
+    pub edit: StateEdit,
+}
+
+/// State edits kept in control.json: plenty for a poller checking every second, bounded when none runs.
+const KEPT_EDITS: usize = 50;
+
+impl Control {
+    pub fn paused(&self) -> bool {
+        self.paused_until.is_some_and(|until| until > now_secs())
+    }
+
+    pub fn poll_pending(&self) -> bool {
+        self.poll_requested > self.poll_served
+    }
+}
+
+/// One `ctl --json` reply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CtlReply {
+    pub schema: u32,
+    pub command: String,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub state: CtlState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CtlState {
+    pub paused: bool,
+    pub paused_until: Option<u64>,
+    pub poll_pending: bool,
+    pub kiosk: bool,
+    /// UNIX time of the last poll (0 = never).
+    pub last_poll: u64,
+    /// UNIX time of the last successful poll (0 = never).
+    pub last_ok: u64,
+    /// Failed polls in a row.
+    pub errors: u32,
+    /// Tickets with status New at the last successful poll.
+    pub queue: usize,
+}
+
+/// Poller start: kiosk mode as requested by `--kiosk` / KIOSK=true, until switched with `ctl kiosk`.
+pub fn init_kiosk(requested: bool) -> anyhow::Result<()> {
+    save(&Control { kiosk: Some(requested), ..load() })
+}
+
+/// Read-only wallboard mode: toasts without buttons, nothing written to GLPI.
+pub fn kiosk() -> bool {
+    load().kiosk.unwrap_or(false)
+}
+
+fn control_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("GlpiNotifier");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("control.json"))
+}
+
+pub fn load() -> Control {
+    control_path()
+        .and_then(|p| std::fs::read(p).ok())
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Write control.json through a temporary file renamed over it: the poller reading it while `ctl` writes it
+/// sees the old or the new file, never half of one. The temporary file is per process, as both write it.
+pub fn save(control: &Control) -> anyhow::Result<()> {
+    if let Some(p) = control_path() {
+        let tmp = p.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, serde_json::to_vec(control)?)?;
+        std::fs::rename(&tmp, &p)?;
+    }
+    Ok(())
+}
+
+/// Poller side: take a `poll-now` request newer than `served`, the last one this poll loop served (each
+/// instance's loop keeps its own, so one request wakes them all).
+pub fn take_poll_request(served: &mut u64) -> bool {
+    let mut control = load();
+    if control.poll_requested <= *served {
+        return false;
+    }
+    *served = control.poll_requested;
+    control.poll_served = now_secs();
+    save(&control).is_ok()
+}
+
+/// Apply to `st` the edits of its instance it has not seen yet. Returns them, oldest first.
+fn apply_pending(st: &mut SeenState, control: &Control) -> Vec<StateEdit> {
+    let instance = crate::instance::current_name();
+    let mut applied = Vec::new();
+    let seen = st.edits_applied;
+    for pending in control.state_edits.iter().filter(|e| e.seq > seen && e.instance == instance) {
+        pending.edit.apply(st);
+        st.edits_applied = pending.seq;
+        applied.push(pending.edit.clone());
+    }
+    applied
+}
+
+/// One-shot command side: the state of the current instance, with the edits a running poller has not saved yet.
+pub fn current_state() -> anyhow::Result<SeenState> {
+    let mut st = load_state()?;
+    apply_pending(&mut st, &load());
+    Ok(st)
+}
+
+/// One-shot command side: apply `edit` to `st` (from [`current_state`]) and save it, then ask a running poller
+/// to apply it to the state it holds.
+pub fn edit_state(st: &mut SeenState, edit: StateEdit) -> anyhow::Result<()> {
+    let mut control = load();
+    let seq = control.state_edits.iter().map(|e| e.seq).max().unwrap_or(0).max(st.edits_applied) + 1;
+    edit.apply(st);
+    st.edits_applied = seq;
+    save_state(st)?;
+    control.state_edits.push(PendingEdit { seq, instance: crate::instance::current_name(), edit });
+    let excess = control.state_edits.len().saturating_sub(KEPT_EDITS);
+    control.state_edits.drain(..excess);
+    save(&control)
+}
+
+/// Poller side: apply to `st`, the state this loop holds, the edits of the one-shot commands it has not
+/// applied yet. Returns them, oldest first; the caller saves the state.
+pub fn take_state_edits(st: &mut SeenState) -> Vec<StateEdit> {
+    apply_pending(st, &load())
+}
+
+/// Current state, from control.json, heartbeat.json and status.json.
+pub fn state() -> CtlState {
+    let control = load();
+    let hb = read_heartbeat();
+    CtlState {
+        paused: control.paused(),
+        paused_until: control.paused_until.filter(|_| control.paused()),
+        poll_pending: control.poll_pending(),
+        kiosk: control.kiosk.unwrap_or(false),
+        last_poll: hb.as_ref().map_or(0, |hb| hb.ts),
+        last_ok: hb.as_ref().map_or(0, |hb| hb.last_ok),
+        errors: hb.as_ref().map_or(0, |hb| hb.errors),
+        queue: read_status().map_or(0, |s| s.queue),
+    }
+}
+
+/// JSON Schema (draft 2020-12) of [`CtlReply`].
+pub fn schema() -> serde_json::Value {
+    let time = serde_json::json!({ "type": "integer", "minimum": 0, "description": "UNIX time, 0 = never" });
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "glpi-notifier-rs ctl --json reply",
+        "type": "object",
+        "required": ["schema", "command", "ok", "error", "state"],
+        "properties": {
+            "schema": { "const": SCHEMA_VERSION },
+            "command": { "enum": ["status", "pause", "resume", "poll-now", "kiosk"] },
+            "ok": { "type": "boolean" },
+            "error": { "type": ["string", "null"] },
+            "state": {
+                "type": "object",
+                "required": ["paused", "paused_until", "poll_pending", "kiosk", "last_poll", "last_ok", "errors", "queue"],
+                "properties": {
+                    "paused": { "type": "boolean" },
+                    "paused_until": {
+                        "type": ["integer", "null"],
+                        "description": "UNIX time polling resumes; 18446744073709551615 = until `ctl resume`"
+                    },
+                    "poll_pending": { "type": "boolean", "description": "poll-now requested, not yet served" },
+                    "kiosk": { "type": "boolean", "description": "read-only wallboard mode" },
+                    "last_poll": time,
+                    "last_ok": time,
+                    "errors": { "type": "integer", "minimum": 0 },
+                    "queue": { "type": "integer", "minimum": 0 }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_edits_apply_once_to_their_instance() {
+        let edit = |seq, instance: Option<&str>, ticket| PendingEdit {
+            seq,
+            instance: instance.map(str::to_string),
+            edit: StateEdit::StopTimer { ticket },
+        };
+        let control = Control {
+            state_edits: vec![edit(1, None, 10), edit(2, Some("lab"), 11), edit(3, None, 12)],
+            ..Control::default()
+        };
+        let mut st = SeenState::default();
+        st.timers.extend([(10, 100), (11, 100), (12, 100)]);
+
+        let applied = apply_pending(&mut st, &control);
+        assert_eq!(applied, vec![StateEdit::StopTimer { ticket: 10 }, StateEdit::StopTimer { ticket: 12 }]);
+        assert_eq!(st.timers.keys().copied().collect::<Vec<_>>(), vec![11]);
+        assert_eq!(st.edits_applied, 3);
+        assert!(apply_pending(&mut st, &control).is_empty());
+
+        // The layout of control.json written by an older version still reads
+        let old: Control = serde_json::from_str(r#"{"paused_until":null,"poll_requested":5}"#).unwrap();
+        assert!(old.state_edits.is_empty());
+        let json = serde_json::to_string(&control.state_edits[1]).unwrap();
+        assert_eq!(json, r#"{"seq":2,"instance":"lab","edit":"stop-timer","ticket":11}"#);
+    }
+}
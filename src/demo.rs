@@ -0,0 +1,131 @@
+//! Demo mode (`--demo`, or DEMO=true) for screenshots and training: ticket titles and requester names are
+//! replaced with generated placeholders as tickets are read from GLPI, so every view shows them (notifications,
+//! the tray flyout, status.json, MQTT, `queue`, `search`, `explain`, `timeline`), and follow-up texts are hidden.
+//! Ids, priorities, dates, categories, locations and entities stay real, and the same ticket always gets the
+//! same placeholders.
+
+use crate::glpi::{Ticket, TimelineEvent};
+
+const TITLES: &[&str] = &[
+    "Printer on the second floor jams",
+    "Cannot connect to the VPN",
+    "Outlook keeps asking for the password",
+    "New laptop for a new starter",
+    "Shared drive not mapped after login",
+    "Screen flickers on the docking station",
+    "Access request for the finance folder",
+    "Phone extension does not ring",
+    "Software install: PDF editor",
+    "Wi-Fi drops in the meeting room",
+    "Password reset",
+    "Keyboard missing keys",
+];
+
+const PEOPLE: &[&str] = &[
+    "Alex Martin",
+    "Sam Costa",
+    "Jordan Lee",
+    "Taylor Moreau",
+    "Morgan Silva",
+    "Casey Novak",
+    "Robin Weber",
+    "Jamie Rossi",
+    "Charlie Dubois",
+    "Drew Santos",
+];
+
+/// Text of a followup, task or solution in demo mode.
+const TEXT: &str = "(text hidden in demo mode)";
+
+pub fn enabled() -> bool {
+    crate::config::get().demo
+}
+
+/// Spread small numbers over a list.
+fn pick<'a>(list: &[&'a str], seed: u64) -> &'a str {
+    list[(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32) as usize % list.len()]
+}
+
+/// Same seed for the same text (FNV-1a), to keep an author's placeholder stable.
+fn seed_of(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |h, b| (h ^ u64::from(b)).wrapping_mul(0x100_0000_01b3))
+}
+
+/// The ticket as shown in demo mode: placeholder title and requester (when it has one).
+pub fn ticket(t: Ticket) -> Ticket {
+    if enabled() {
+        redact(t)
+    } else {
+        t
+    }
+}
+
+fn redact(t: Ticket) -> Ticket {
+    let seed = t.id as u64;
+    Ticket {
+        name: placeholder_title(t.id),
+        requester: t.requester.as_ref().map(|_| pick(PEOPLE, seed.rotate_left(17)).to_string()),
+        ..t
+    }
+}
+
+fn placeholder_title(id: i64) -> String {
+    pick(TITLES, id as u64).to_string()
+}
+
+/// Title of ticket `id` as shown (`timeline`).
+pub fn title(id: i64, name: String) -> String {
+    if enabled() {
+        placeholder_title(id)
+    } else {
+        name
+    }
+}
+
+/// Text of a follow-up as shown (customer reply and follow-up toasts).
+pub fn text(text: String) -> String {
+    if enabled() {
+        TEXT.to_string()
+    } else {
+        text
+    }
+}
+
+/// A timeline entry as shown: placeholder author and text in demo mode.
+pub fn event(e: TimelineEvent) -> TimelineEvent {
+    if !enabled() {
+        return e;
+    }
+    TimelineEvent { author: e.author.map(|a| pick(PEOPLE, seed_of(&a)).to_string()), text: TEXT.to_string(), ..e }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_keep_the_ids_and_stay_stable() {
+        let t = Ticket {
+            id: 4711,
+            name: "ACME Corp: payroll server down".into(),
+            requester: Some("Jane Customer".into()),
+            category: Some("Servers".into()),
+            priority: Some(5),
+            date: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            major_incident: None,
+            instance: None,
+            language: None,
+        };
+        let shown = redact(t.clone());
+        assert_eq!((shown.id, shown.priority, shown.category.as_deref()), (4711, Some(5), Some("Servers")));
+        assert!(TITLES.contains(&shown.name.as_str()) && PEOPLE.contains(&shown.requester.as_deref().unwrap()));
+        assert_eq!(redact(t.clone()).name, shown.name);
+        assert_eq!(placeholder_title(4711), shown.name);
+        assert_ne!(placeholder_title(4712), shown.name);
+        assert_eq!(redact(Ticket { requester: None, ..t }).requester, None);
+    }
+}
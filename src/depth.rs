@@ -0,0 +1,123 @@
+//! Queue-depth alerts (QUEUE_WARN_AT / QUEUE_CRIT_AT): a message through the notifier when the number of New
+//! tickets crosses a threshold, and one when it recovers. The count is GLPI's `totalcount` of the New search,
+//! so it costs no extra request.
+
+use serde::{Deserialize, Serialize};
+
+/// Depth level of the New queue, persisted so a restart does not alert again.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    #[default]
+    Ok,
+    Warn,
+    Crit,
+}
+
+/// Thresholds in New tickets; 0 disables a level.
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    pub warn: u64,
+    pub crit: u64,
+}
+
+impl Thresholds {
+    pub fn enabled(self) -> bool {
+        self.warn > 0 || self.crit > 0
+    }
+
+    /// Level of a queue of `count` tickets that was at `previous`. A level is left only once the count is
+    /// 10% (at least one ticket) below its threshold, so a queue hovering around it does not flap.
+    pub fn level(self, count: u64, previous: Level) -> Level {
+        let reached = |at: u64| at > 0 && count >= at;
+        let holds = |at: u64| at > 0 && count + (at / 10).max(1) > at;
+        if reached(self.crit) || (previous == Level::Crit && holds(self.crit)) {
+            Level::Crit
+        } else if reached(self.warn) || (previous >= Level::Warn && holds(self.warn)) {
+            Level::Warn
+        } else {
+            Level::Ok
+        }
+    }
+
+    fn threshold(self, level: Level) -> u64 {
+        match level {
+            Level::Ok => 0,
+            Level::Warn => self.warn,
+            Level::Crit => self.crit,
+        }
+    }
+}
+
+/// Title and body of the message for a change of level, None when the level did not change.
+pub fn alert(th: Thresholds, count: u64, from: Level, to: Level) -> Option<(String, String)> {
+    let tickets = if count == 1 { "ticket" } else { "tickets" };
+    match to {
+        _ if to == from => None,
+        Level::Ok => {
+            let lowest = if th.warn > 0 { Level::Warn } else { Level::Crit };
+            Some((
+                format!("GLPI queue recovered: {count} New {tickets}"),
+                format!("Back under the {} threshold ({})", name(lowest), th.threshold(lowest)),
+            ))
+        }
+        _ if to > from => Some((
+            format!("GLPI queue {}: {count} New {tickets}", name(to).to_uppercase()),
+            format!("At or above the {} threshold ({})", name(to), th.threshold(to)),
+        )),
+        _ => Some((
+            format!("GLPI queue down to {}: {count} New {tickets}", name(to)),
+            format!("Back under the {} threshold ({})", name(from), th.threshold(from)),
+        )),
+    }
+}
+
+fn name(level: Level) -> &'static str {
+    match level {
+        Level::Ok => "ok",
+        Level::Warn => "warning",
+        Level::Crit => "critical",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_change_with_hysteresis() {
+        let th = Thresholds { warn: 20, crit: 40 };
+        assert_eq!(th.level(19, Level::Ok), Level::Ok);
+        assert_eq!(th.level(20, Level::Ok), Level::Warn);
+        assert_eq!(th.level(45, Level::Ok), Level::Crit);
+        // Leaves critical only 10% under 40, warning 10% under 20
+        assert_eq!(th.level(37, Level::Crit), Level::Crit);
+        assert_eq!(th.level(36, Level::Crit), Level::Warn);
+        assert_eq!(th.level(19, Level::Warn), Level::Warn);
+        assert_eq!(th.level(18, Level::Warn), Level::Ok);
+        assert_eq!(th.level(5, Level::Crit), Level::Ok);
+        // Warning only
+        let th = Thresholds { warn: 5, crit: 0 };
+        assert_eq!(th.level(500, Level::Ok), Level::Warn);
+        assert_eq!(th.level(5, Level::Warn), Level::Warn);
+        assert_eq!(th.level(4, Level::Warn), Level::Ok);
+    }
+
+    #[test]
+    fn alerts_name_the_threshold() {
+        let th = Thresholds { warn: 20, crit: 40 };
+        assert_eq!(alert(th, 25, Level::Warn, Level::Warn), None);
+        assert_eq!(
+            alert(th, 41, Level::Warn, Level::Crit).unwrap(),
+            ("GLPI queue CRITICAL: 41 New tickets".into(), "At or above the critical threshold (40)".into())
+        );
+        assert_eq!(
+            alert(th, 30, Level::Crit, Level::Warn).unwrap(),
+            ("GLPI queue down to warning: 30 New tickets".into(), "Back under the critical threshold (40)".into())
+        );
+        assert_eq!(
+            alert(th, 1, Level::Crit, Level::Ok).unwrap(),
+            ("GLPI queue recovered: 1 New ticket".into(), "Back under the warning threshold (20)".into())
+        );
+    }
+}
@@ -0,0 +1,95 @@
+//! What changed on a tracked ticket between two polls (TICKET_UPDATES): the stored snapshot of the fields a
+//! technician acts on, compared with the fresh read.
+
+use serde::{Deserialize, Serialize};
+
+/// The compared fields of a ticket, as last read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// GLPI status (1 New … 6 Closed).
+    pub status: Option<i64>,
+    /// Assigned technicians, comma-separated as GLPI lists them.
+    pub assignee: Option<String>,
+    pub priority: Option<i64>,
+}
+
+/// One changed field, values already rendered for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub field: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+/// GLPI ticket status label.
+fn status_name(status: i64) -> String {
+    match status {
+        1 => "New".into(),
+        2 => "Processing (assigned)".into(),
+        3 => "Processing (planned)".into(),
+        4 => "Pending".into(),
+        5 => "Solved".into(),
+        6 => "Closed".into(),
+        other => format!("#{other}"),
+    }
+}
+
+fn shown<T>(value: Option<T>, render: impl Fn(T) -> String) -> String {
+    value.map(render).unwrap_or_else(|| "none".into())
+}
+
+/// Fields that differ from `old` to `new`, in a fixed order (status, assignee, priority). A field the fresh read
+/// did not include (column unavailable) is not reported as removed.
+pub fn diff(old: &Snapshot, new: &Snapshot) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if new.status.is_some() && old.status != new.status {
+        changes.push(Change {
+            field: "status",
+            from: shown(old.status, status_name),
+            to: shown(new.status, status_name),
+        });
+    }
+    if old.assignee != new.assignee {
+        changes.push(Change {
+            field: "assignee",
+            from: shown(old.assignee.clone(), |a| a),
+            to: shown(new.assignee.clone(), |a| a),
+        });
+    }
+    if new.priority.is_some() && old.priority != new.priority {
+        changes.push(Change {
+            field: "priority",
+            from: shown(old.priority, |p| p.to_string()),
+            to: shown(new.priority, |p| p.to_string()),
+        });
+    }
+    changes
+}
+
+/// A status change, rendered like the ones of [`diff`].
+pub fn status_change(from: i64, to: i64) -> Change {
+    Change { field: "status", from: status_name(from), to: status_name(to) }
+}
+
+/// One-line summary: `status: Pending→Processing (assigned); assignee: none→J. Costa; priority: 3→4`.
+pub fn summary(changes: &[Change]) -> String {
+    changes.iter().map(|c| format!("{}: {}→{}", c.field, c.from, c.to)).collect::<Vec<_>>().join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_changed_fields_only() {
+        let old = Snapshot { status: Some(4), assignee: None, priority: Some(3) };
+        let new = Snapshot { status: Some(2), assignee: Some("J. Costa".into()), priority: Some(4) };
+        assert_eq!(
+            summary(&diff(&old, &new)),
+            "status: Pending→Processing (assigned); assignee: none→J. Costa; priority: 3→4"
+        );
+        assert!(diff(&new, &new).is_empty());
+        // Columns missing from the fresh read are not changes
+        assert!(diff(&new, &Snapshot { assignee: new.assignee.clone(), ..Snapshot::default() }).is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::glpi::AuthError;
+use crate::watchdog::Stalled;
+
+use std::fmt;
+use std::process::ExitCode;
+
+/// Process exit codes, so Task Scheduler and monitoring can tell failure modes apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Clean stop (`--max-runtime` reached, `--once` poll done, command finished).
+    Ok = 0,
+    /// Any failure not covered below.
+    Failure = 1,
+    /// Missing or invalid configuration.
+    Config = 2,
+    /// GLPI rejected the credentials or the session (initSession, or any 401/403 reply).
+    Auth = 3,
+    /// GLPI unreachable (DNS, TLS, connect, timeout).
+    Network = 4,
+    /// The watchdog gave up after repeated poller stalls.
+    Stalled = 5,
+}
+
+impl Exit {
+    /// Classify an error by the typed causes in its chain.
+    pub fn from_error(e: &anyhow::Error) -> Self {
+        if e.chain().any(|c| c.is::<ConfigError>()) {
+            Exit::Config
+        } else if e.chain().any(|c| c.is::<AuthError>()) {
+            Exit::Auth
+        } else if e.chain().any(|c| c.is::<Stalled>()) {
+            Exit::Stalled
+        } else if e.chain().any(|c| c.is::<reqwest::Error>()) {
+            Exit::Network
+        } else {
+            Exit::Failure
+        }
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(e: Exit) -> Self {
+        ExitCode::from(e as u8)
+    }
+}
+
+/// Configuration missing or unusable; maps to [`Exit::Config`].
+#[derive(Debug)]
+pub struct ConfigError(pub String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,255 @@
+use crate::exit::ConfigError;
+use crate::glpi::TicketFields;
+
+use anyhow::Result;
+use std::fmt;
+
+/// GLPI search operators usable in TICKET_FILTER.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchType {
+    Contains,
+    Equals,
+    NotEquals,
+    MoreThan,
+    LessThan,
+    /// Tree fields (categories, entities, locations): the item and its children; value is an id.
+    Under,
+}
+
+impl SearchType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SearchType::Contains => "contains",
+            SearchType::Equals => "equals",
+            SearchType::NotEquals => "notequals",
+            SearchType::MoreThan => "morethan",
+            SearchType::LessThan => "lessthan",
+            SearchType::Under => "under",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        [Self::Contains, Self::Equals, Self::NotEquals, Self::MoreThan, Self::LessThan, Self::Under]
+            .into_iter()
+            .find(|t| t.as_str().eq_ignore_ascii_case(s))
+    }
+}
+
+/// Ticket column a criterion applies to: a known alias, a search option uid or a raw search option id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldRef {
+    /// Status: a criterion on it replaces the built-in `status equals 1` (New).
+    Status,
+    Name,
+    Requester,
+    Category,
+    Priority,
+    Date,
+    /// Entity of the ticket (`under` an entity id).
+    Entity,
+    /// Assigned group.
+    Group,
+    /// Location of the ticket (`under` a location id).
+    Location,
+    /// Search option uid (`Ticket.urgency`), resolved to its id by the client.
+    Uid(String),
+    Id(i64),
+}
+
+/// One extra search criterion ANDed to the New-tickets search, e.g. `priority morethan 3`.
+/// A value of `,`-separated alternatives matches any of them (`category under 12,15`), as clauses are `;`-separated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Criterion {
+    pub field: FieldRef,
+    pub searchtype: SearchType,
+    pub value: String,
+}
+
+impl Criterion {
+    /// Search option id of the field, when the instance exposes it.
+    pub fn field_id(&self, fields: &TicketFields) -> Option<i64> {
+        match self.field {
+            FieldRef::Status => Some(fields.status),
+            FieldRef::Name => Some(fields.name),
+            FieldRef::Requester => fields.requester,
+            FieldRef::Category => fields.category,
+            FieldRef::Priority => fields.priority,
+            FieldRef::Date => fields.date,
+            FieldRef::Entity => fields.entity,
+            FieldRef::Group => fields.group,
+            FieldRef::Location => fields.location,
+            FieldRef::Uid(_) => None,
+            FieldRef::Id(id) => Some(id),
+        }
+    }
+
+    /// The alternatives of the value (one for a plain value).
+    pub fn values(&self) -> Vec<&str> {
+        self.value.split(',').map(str::trim).filter(|v| !v.is_empty()).collect()
+    }
+}
+
+impl fmt::Display for FieldRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldRef::Status => f.write_str("status"),
+            FieldRef::Name => f.write_str("name"),
+            FieldRef::Requester => f.write_str("requester"),
+            FieldRef::Category => f.write_str("category"),
+            FieldRef::Priority => f.write_str("priority"),
+            FieldRef::Date => f.write_str("date"),
+            FieldRef::Entity => f.write_str("entity"),
+            FieldRef::Group => f.write_str("group"),
+            FieldRef::Location => f.write_str("location"),
+            FieldRef::Uid(uid) => f.write_str(uid),
+            FieldRef::Id(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+impl fmt::Display for Criterion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} '{}'", self.field, self.searchtype.as_str(), self.value)
+    }
+}
+
+/// True when `filter` has its own status criterion, which then replaces the built-in `status equals 1`.
+pub fn sets_status(filter: &[Criterion], fields: &TicketFields) -> bool {
+    filter.iter().any(|c| c.field_id(fields) == Some(fields.status))
+}
+
+/// Parse `field searchtype value` clauses separated by `;`, e.g.
+/// `category under 12; priority morethan 3; name notequals 'test'; Ticket.urgency morethan 3`.
+pub fn parse(raw: &str) -> Result<Vec<Criterion>> {
+    raw.split(';').map(str::trim).filter(|c| !c.is_empty()).map(parse_clause).collect()
+}
+
+fn parse_clause(clause: &str) -> Result<Criterion> {
+    let bad = |why: &str| ConfigError(format!("TICKET_FILTER: {why} in '{clause}' (expected: field searchtype value)"));
+    let mut parts = clause.splitn(3, char::is_whitespace);
+    let (Some(field), Some(op), Some(value)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(bad("missing part").into());
+    };
+    let field = match field.to_lowercase().as_str() {
+        "status" => FieldRef::Status,
+        "name" | "title" => FieldRef::Name,
+        "requester" => FieldRef::Requester,
+        "category" => FieldRef::Category,
+        "priority" => FieldRef::Priority,
+        "date" => FieldRef::Date,
+        "entity" => FieldRef::Entity,
+        "group" => FieldRef::Group,
+        "location" => FieldRef::Location,
+        _ if field.contains('.') => FieldRef::Uid(field.to_string()),
+        other => FieldRef::Id(other.parse().map_err(|_| bad("unknown field"))?),
+    };
+    let searchtype = SearchType::parse(op).ok_or_else(|| bad("unknown searchtype"))?;
+    let value = value.trim().trim_matches(|c| c == '\'' || c == '"').to_string();
+    Ok(Criterion { field, searchtype, value })
+}
+
+/// TICKET_FILTER given in config.toml as a list of `[field, searchtype, value]` triples, as clauses.
+pub fn from_toml(value: &toml::Value) -> Result<String> {
+    let bad = || ConfigError("TICKET_FILTER: expected a string or a list of [field, searchtype, value]".into());
+    let triples = value.as_array().ok_or_else(bad)?;
+    let clauses = triples
+        .iter()
+        .map(|triple| {
+            let parts = triple.as_array().filter(|parts| parts.len() == 3).ok_or_else(bad)?;
+            let parts = parts
+                .iter()
+                .map(|part| match part {
+                    toml::Value::String(s) => Ok(s.clone()),
+                    toml::Value::Integer(_) | toml::Value::Float(_) => Ok(part.to_string()),
+                    _ => Err(bad()),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            if parts.iter().any(|p| p.contains([';', '\n'])) {
+                return Err(ConfigError(format!("TICKET_FILTER: ';' is not allowed in {parts:?}")).into());
+            }
+            let criterion = parse_clause(&format!("{} {} '{}'", parts[0], parts[1], parts[2]))?;
+            Ok(criterion.to_string())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(clauses.join("; "))
+}
+
+/// MY_LOCATIONS, GLPI Location ids separated by `,` (or spaces), as one `location under a,b` criterion.
+pub fn parse_locations(raw: &str) -> Result<Option<Criterion>> {
+    let ids = raw
+        .split([',', ' '])
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<i64>().map_err(|_| ConfigError(format!("MY_LOCATIONS: '{s}' is not a location id"))))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((!ids.is_empty()).then(|| Criterion {
+        field: FieldRef::Location,
+        searchtype: SearchType::Under,
+        value: ids.iter().map(i64::to_string).collect::<Vec<_>>().join(","),
+    }))
+}
+
+/// Extra criteria from TICKET_FILTER and MY_LOCATIONS (empty when unset).
+pub fn from_env() -> Result<Vec<Criterion>> {
+    let mut criteria = parse(&crate::instance::var("TICKET_FILTER").unwrap_or_default())?;
+    criteria.extend(parse_locations(&crate::instance::var("MY_LOCATIONS").unwrap_or_default())?);
+    Ok(criteria)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clauses_and_rejects_unknown_operators() {
+        let criteria = parse("category under 12; priority MoreThan 3;  name notequals 'Printer test' ;").unwrap();
+        assert_eq!(criteria.len(), 3);
+        assert_eq!(
+            criteria[0],
+            Criterion { field: FieldRef::Category, searchtype: SearchType::Under, value: "12".into() }
+        );
+        assert_eq!(criteria[1].searchtype, SearchType::MoreThan);
+        assert_eq!(criteria[2].value, "Printer test");
+
+        assert!(parse("83 equals 4").is_ok());
+        assert_eq!(parse("entity under 3, 7 ").unwrap()[0].values(), ["3", "7"]);
+        assert_eq!(parse_locations("12, 15").unwrap().unwrap().to_string(), "location under '12,15'");
+        assert!(parse_locations("Lisbon").is_err());
+        assert!(parse("priority above 3").is_err());
+        assert!(parse("priority").is_err());
+    }
+
+    #[test]
+    fn search_option_uids_and_status_criteria() {
+        let criteria = parse("Ticket.urgency morethan 3; entity under 5; status equals notold").unwrap();
+        assert_eq!(criteria[0].field, FieldRef::Uid("Ticket.urgency".into()));
+        assert_eq!(criteria[0].to_string(), "Ticket.urgency morethan '3'");
+        assert_eq!(criteria[2].field, FieldRef::Status);
+
+        let fields = TicketFields {
+            id: 2,
+            name: 1,
+            status: 12,
+            requester: None,
+            category: None,
+            priority: None,
+            date: None,
+            assignee: None,
+            group: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            content: None,
+        };
+        assert!(sets_status(&criteria, &fields));
+        assert!(sets_status(&parse("12 equals 2").unwrap(), &fields));
+        assert!(!sets_status(&criteria[..2], &fields));
+
+        let toml: toml::Table =
+            toml::from_str("f = [['Ticket.urgency', 'morethan', 3], ['name', 'contains', 'VPN down']]").unwrap();
+        assert_eq!(from_toml(&toml["f"]).unwrap(), "Ticket.urgency morethan '3'; name contains 'VPN down'");
+        let toml: toml::Table = toml::from_str("a = [['name', 'contains']]\nb = [['name', 'equals', 'a;b']]").unwrap();
+        assert!(from_toml(&toml["a"]).is_err());
+        assert!(from_toml(&toml["b"]).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+//! Flyout of the tray icon: a left click opens a small always-on-top window above the taskbar listing the New
+//! (unassigned) tickets of the last poll, with **Open** and **Assign to me** buttons — a mini queue without
+//! the browser. The rows come from the ticket cache the poll fills (no extra GLPI call); the buttons go back
+//! to the poll loop through the tray channel and are handled like the toast buttons (kiosk mode included).
+//!
+//! Plain Win32 controls (a list box and two buttons) on the tray thread, so no extra windowing crate. They follow
+//! the Windows high-contrast theme, and the flyout works from the keyboard: **New tickets** in the tray menu
+//! opens it with the focus on the list, arrows pick a ticket, Enter opens it, Alt+A assigns it, Tab moves to the
+//! buttons and Esc closes the flyout. A label names the list for screen readers.
+#![cfg_attr(not(all(windows, feature = "tray")), allow(dead_code))]
+
+use crate::glpi::{Severity, Ticket};
+
+/// New tickets of the last successful poll, newest first, as last read from GLPI.
+pub fn pending() -> Vec<Ticket> {
+    crate::widget::queue_ids()
+        .into_iter()
+        .filter_map(|(instance, id)| crate::tickets::get_in(instance, id))
+        .map(|k| k.ticket)
+        .collect()
+}
+
+/// One row of the list: `#101  Printer down — alice, opened 14:02` (`[prod] #101 ...` with instances).
+pub fn row(t: &Ticket) -> String {
+    row_as(t, crate::accessibility::enabled())
+}
+
+/// [`row`], or without symbols for a screen reader: `Critical: #101 Printer down, by alice, opened 14:02`.
+fn row_as(t: &Ticket, accessible: bool) -> String {
+    let name = if t.name.is_empty() { "New ticket" } else { t.name.as_str() };
+    let mut row = if accessible { format!("#{} {name}", t.id) } else { format!("#{}  {name}", t.id) };
+    if let Some(instance) = &t.instance {
+        row = format!("[{instance}] {row}");
+    }
+    if t.severity() == Severity::Critical {
+        row = if accessible { format!("Critical: {row}") } else { format!("‼ {row}") };
+    }
+    if let Some(requester) = &t.requester {
+        row.push_str(&if accessible { format!(", by {requester}") } else { format!(" — {requester}") });
+    }
+    if let Some(opened) = t.opened_display() {
+        row.push_str(&format!(", opened {opened}"));
+    }
+    row
+}
+
+#[cfg(all(windows, feature = "tray"))]
+pub use window::Flyout;
+
+#[cfg(all(windows, feature = "tray"))]
+mod window {
+    use crate::toast::ToastAction;
+    use crate::tray::Command;
+
+    use anyhow::Result;
+    use std::cell::RefCell;
+    use std::sync::mpsc::Sender;
+    use std::time::{Duration, Instant};
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM};
+    use windows::Win32::Graphics::Gdi::{GetStockObject, COLOR_WINDOW, DEFAULT_GUI_FONT, HBRUSH};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, IsDialogMessageW, IsWindowVisible, LoadCursorW, RegisterClassW, SendMessageW,
+        SetForegroundWindow, SetWindowPos, ShowWindow, SystemParametersInfoW, BN_CLICKED, BS_PUSHBUTTON, HMENU,
+        HWND_TOPMOST, IDCANCEL, IDC_ARROW, IDOK, LBN_DBLCLK, LBS_NOINTEGRALHEIGHT, LBS_NOTIFY, LB_ADDSTRING,
+        LB_GETCURSEL, LB_RESETCONTENT, LB_SETCURSEL, MSG, SPI_GETWORKAREA, SWP_SHOWWINDOW, SW_HIDE,
+        SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, WA_INACTIVE, WINDOW_STYLE, WM_ACTIVATE, WM_CLOSE, WM_COMMAND, WM_SETFONT,
+        WNDCLASSW, WS_BORDER, WS_CHILD, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP, WS_TABSTOP, WS_VISIBLE, WS_VSCROLL,
+    };
+
+    const WIDTH: i32 = 420;
+    const HEIGHT: i32 = 300;
+    const ID_LIST: usize = 100;
+    const ID_OPEN: usize = 101;
+    const ID_ASSIGN: usize = 102;
+    const ID_LABEL: usize = 103;
+    /// What the dialog keys send: Enter outside the buttons, and Esc.
+    const ID_ENTER: usize = IDOK.0 as usize;
+    const ID_ESCAPE: usize = IDCANCEL.0 as usize;
+
+    /// What the window procedure needs (the flyout lives on the tray thread only).
+    struct State {
+        list: HWND,
+        /// Instance and ticket id of each row.
+        ids: Vec<crate::tickets::TicketKey>,
+        commands: Sender<Command>,
+        /// When the flyout last lost the focus: a click on the tray icon hides it first, and must not reopen it.
+        hidden_at: Option<Instant>,
+    }
+
+    thread_local! {
+        static STATE: RefCell<Option<State>> = const { RefCell::new(None) };
+    }
+
+    pub struct Flyout {
+        hwnd: HWND,
+    }
+
+    impl Flyout {
+        pub fn new(commands: Sender<Command>) -> Result<Self> {
+            // SAFETY: Win32 window creation on the calling (tray) thread, which pumps its messages
+            unsafe {
+                let instance = HINSTANCE(GetModuleHandleW(None)?.0);
+                let class = WNDCLASSW {
+                    lpfnWndProc: Some(window_proc),
+                    hInstance: instance,
+                    hCursor: LoadCursorW(None, IDC_ARROW)?,
+                    hbrBackground: HBRUSH((COLOR_WINDOW.0 + 1) as isize as *mut _),
+                    lpszClassName: w!("GlpiNotifierFlyout"),
+                    ..Default::default()
+                };
+                RegisterClassW(&class);
+                let hwnd = CreateWindowExW(
+                    WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+                    w!("GlpiNotifierFlyout"),
+                    w!("GLPI Notifier"),
+                    WS_POPUP | WS_BORDER,
+                    0,
+                    0,
+                    WIDTH,
+                    HEIGHT,
+                    None,
+                    None,
+                    Some(instance),
+                    None,
+                )?;
+                let child = |class: PCWSTR, text: PCWSTR, style: WINDOW_STYLE, rect: (i32, i32, i32, i32), id| {
+                    CreateWindowExW(
+                        Default::default(),
+                        class,
+                        text,
+                        WS_CHILD | WS_VISIBLE | style,
+                        rect.0,
+                        rect.1,
+                        rect.2,
+                        rect.3,
+                        Some(hwnd),
+                        Some(HMENU(id as *mut _)),
+                        Some(instance),
+                        None,
+                    )
+                };
+                // Screen readers name the list after the label created right before it
+                let label = child(w!("STATIC"), w!("New tickets:"), WINDOW_STYLE(0), (8, 8, WIDTH - 18, 18), ID_LABEL)?;
+                let list_style =
+                    WS_TABSTOP | WS_VSCROLL | WS_BORDER | WINDOW_STYLE((LBS_NOTIFY | LBS_NOINTEGRALHEIGHT) as u32);
+                let list = child(w!("LISTBOX"), w!(""), list_style, (8, 28, WIDTH - 18, HEIGHT - 76), ID_LIST)?;
+                let button = WS_TABSTOP | WINDOW_STYLE(BS_PUSHBUTTON as u32);
+                let open = child(w!("BUTTON"), w!("&Open"), button, (8, HEIGHT - 40, 100, 30), ID_OPEN)?;
+                let assign = child(w!("BUTTON"), w!("&Assign to me"), button, (116, HEIGHT - 40, 120, 30), ID_ASSIGN)?;
+                let font = GetStockObject(DEFAULT_GUI_FONT);
+                for control in [label, list, open, assign] {
+                    SendMessageW(control, WM_SETFONT, Some(WPARAM(font.0 as usize)), Some(LPARAM(1)));
+                }
+                STATE.with_borrow_mut(|s| *s = Some(State { list, ids: Vec::new(), commands, hidden_at: None }));
+                Ok(Self { hwnd })
+            }
+        }
+
+        /// Left click on the tray icon at `anchor` (screen pixels): show the flyout next to it, or hide it. None
+        /// (opened from the menu, e.g. with the keyboard): in the corner of the work area next to the taskbar.
+        pub fn toggle(&self, anchor: Option<(f64, f64)>) {
+            // SAFETY: calls on our own window, from its thread
+            unsafe {
+                if IsWindowVisible(self.hwnd).as_bool() {
+                    let _ = ShowWindow(self.hwnd, SW_HIDE);
+                    return;
+                }
+                let just_hidden = STATE.with_borrow(|s| {
+                    s.as_ref().and_then(|s| s.hidden_at).is_some_and(|at| at.elapsed() < Duration::from_millis(300))
+                });
+                if just_hidden {
+                    return;
+                }
+                self.fill();
+                let mut work = RECT::default();
+                let _ = SystemParametersInfoW(
+                    SPI_GETWORKAREA,
+                    0,
+                    Some(&mut work as *mut RECT as *mut _),
+                    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+                );
+                let anchor = anchor.map_or((work.right, work.bottom), |(x, y)| (x as i32, y as i32));
+                let x = (anchor.0 - WIDTH / 2).clamp(work.left, (work.right - WIDTH).max(work.left));
+                // Above a bottom taskbar, below a top one
+                let y = if anchor.1 > (work.top + work.bottom) / 2 { work.bottom - HEIGHT } else { work.top };
+                let _ = SetWindowPos(self.hwnd, Some(HWND_TOPMOST), x, y, WIDTH, HEIGHT, SWP_SHOWWINDOW);
+                let _ = SetForegroundWindow(self.hwnd);
+                // Arrows work right away, and a screen reader reads the selected ticket
+                if let Some(list) = STATE.with_borrow(|s| s.as_ref().map(|s| s.list)) {
+                    let _ = SetFocus(Some(list));
+                }
+            }
+        }
+
+        /// Keyboard navigation (Tab, Enter, Esc, Alt+letter): true when `msg` was a key for the open flyout and
+        /// has been handled; the message pump must not dispatch it again.
+        pub fn dialog_message(&self, msg: &MSG) -> bool {
+            // SAFETY: a message of this thread's queue, checked against our own window
+            unsafe { IsWindowVisible(self.hwnd).as_bool() && IsDialogMessageW(self.hwnd, msg).as_bool() }
+        }
+
+        /// A poll ended: refresh the rows if the flyout is open.
+        pub fn refresh(&self) {
+            // SAFETY: call on our own window, from its thread
+            if unsafe { IsWindowVisible(self.hwnd) }.as_bool() {
+                self.fill();
+            }
+        }
+
+        fn fill(&self) {
+            let tickets = super::pending();
+            let Some(list) = STATE.with_borrow_mut(|s| {
+                let s = s.as_mut()?;
+                s.ids = tickets.iter().map(|t| (t.instance.clone(), t.id)).collect();
+                Some(s.list)
+            }) else {
+                return;
+            };
+            let rows: Vec<String> = match tickets.is_empty() {
+                true => vec!["No New ticket.".to_string()],
+                false => tickets.iter().map(super::row).collect(),
+            };
+            // SAFETY: list box messages with NUL-terminated UTF-16 strings that outlive the call
+            unsafe {
+                SendMessageW(list, LB_RESETCONTENT, None, None);
+                for row in rows {
+                    let wide: Vec<u16> = row.encode_utf16().chain([0]).collect();
+                    SendMessageW(list, LB_ADDSTRING, None, Some(LPARAM(wide.as_ptr() as isize)));
+                }
+                SendMessageW(list, LB_SETCURSEL, Some(WPARAM(0)), None);
+            }
+        }
+    }
+
+    /// Send `action` on the selected ticket to the poll loop, and close the flyout.
+    unsafe fn act(hwnd: HWND, action: ToastAction) {
+        let Some((list, ids, commands)) =
+            STATE.with_borrow(|s| s.as_ref().map(|s| (s.list, s.ids.clone(), s.commands.clone())))
+        else {
+            return;
+        };
+        let selected = SendMessageW(list, LB_GETCURSEL, None, None).0;
+        if let Some((instance, id)) = usize::try_from(selected).ok().and_then(|i| ids.get(i)) {
+            let _ = commands.send(Command::Ticket(instance.clone(), *id, action));
+            let _ = ShowWindow(hwnd, SW_HIDE);
+        }
+    }
+
+    unsafe extern "system" fn window_proc(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        match msg {
+            WM_COMMAND => {
+                let (id, code) = (wparam.0 & 0xffff, (wparam.0 >> 16) as u32 & 0xffff);
+                match (id, code) {
+                    (ID_OPEN, BN_CLICKED) | (ID_LIST, LBN_DBLCLK) | (ID_ENTER, _) => act(hwnd, ToastAction::Open),
+                    (ID_ASSIGN, BN_CLICKED) => act(hwnd, ToastAction::AssignToMe),
+                    (ID_ESCAPE, _) => {
+                        let _ = ShowWindow(hwnd, SW_HIDE);
+                    }
+                    _ => {}
+                }
+                LRESULT(0)
+            }
+            // Clicking elsewhere closes the flyout, like the system ones
+            WM_ACTIVATE if (wparam.0 & 0xffff) as u32 == WA_INACTIVE => {
+                STATE.with_borrow_mut(|s| s.iter_mut().for_each(|s| s.hidden_at = Some(Instant::now())));
+                let _ = ShowWindow(hwnd, SW_HIDE);
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = ShowWindow(hwnd, SW_HIDE);
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_show_the_ticket_at_a_glance() {
+        let t = Ticket {
+            id: 101,
+            name: "Printer down".into(),
+            requester: Some("alice".into()),
+            category: None,
+            priority: Some(6),
+            date: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            major_incident: None,
+            instance: None,
+            language: None,
+        };
+        assert_eq!(row(&t), "‼ #101  Printer down — alice");
+        let t = Ticket { name: String::new(), requester: None, priority: Some(3), ..t };
+        assert_eq!(row(&t), "#101  New ticket");
+        let t = Ticket { instance: Some("prod".into()), ..t };
+        assert_eq!(row(&t), "[prod] #101  New ticket");
+        let t = Ticket { priority: Some(6), requester: Some("alice".into()), ..t };
+        assert_eq!(row_as(&t, true), "Critical: [prod] #101 New ticket, by alice");
+    }
+}
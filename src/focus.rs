@@ -0,0 +1,46 @@
+use crate::state::now_secs;
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Focus mode set from the CLI (`focus <ticket-id>`): while active, toasts below High severity are held
+/// and shown as one digest afterwards. Kept in its own file so the running poller picks it up each poll.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Focus {
+    /// Ticket being worked on.
+    pub ticket_id: i64,
+    /// UNIX timestamp when focus ends.
+    pub until: u64,
+}
+
+impl Focus {
+    pub fn active(&self) -> bool {
+        self.until > now_secs()
+    }
+}
+
+fn focus_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("GlpiNotifier");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("focus.json"))
+}
+
+/// Current focus, if set and not expired.
+pub fn load() -> Option<Focus> {
+    let data = std::fs::read(focus_path()?).ok()?;
+    serde_json::from_slice::<Focus>(&data).ok().filter(Focus::active)
+}
+
+pub fn save(focus: &Focus) -> anyhow::Result<()> {
+    if let Some(p) = focus_path() {
+        std::fs::write(p, serde_json::to_vec(focus)?)?;
+    }
+    Ok(())
+}
+
+pub fn clear() -> anyhow::Result<()> {
+    match focus_path() {
+        Some(p) if p.exists() => Ok(std::fs::remove_file(p)?),
+        _ => Ok(()),
+    }
+}
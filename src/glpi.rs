@@ -1,7 +1,40 @@
+use crate::cache::Cache;
+use crate::clock;
+use crate::diff::Snapshot;
+use crate::exit::ConfigError;
+use crate::filter::{Criterion, FieldRef, SearchType};
+use crate::{oauth, severity, throttle, trace};
+
 use anyhow::{anyhow, Result};
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, LOCATION};
-use serde::Deserialize;
-use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT_LANGUAGE, AUTHORIZATION, CONTENT_TYPE, LOCATION};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod hl;
+
+pub use hl::hl_root;
+
+/// Search option ids by uid, per (base URL, itemtype): listSearchOptions is large and nearly static,
+/// re-read on poller restarts.
+pub static SEARCH_OPTIONS: Lazy<Cache<(String, String), OptionIds>> =
+    Lazy::new(|| Cache::new("search_options", 8, Duration::from_secs(3600)));
+
+/// Major incident of each linked ticket, per instance, re-read after five minutes: links and the incidents'
+/// priority change rarely, and repeat notifications would otherwise read them on every poll.
+pub static MAJOR_INCIDENTS: Lazy<Cache<Option<String>, IncidentMap>> =
+    Lazy::new(|| Cache::new("major_incidents", 8, Duration::from_secs(300)));
+
+/// Search option id per uid (e.g. `Ticket.name` -> 1).
+pub type OptionIds = Arc<HashMap<String, i64>>;
+
+/// Major incident per linked ticket id.
+pub type IncidentMap = Arc<HashMap<i64, i64>>;
 
 /// Thin client for GLPI REST API endpoints we need.
 #[derive(Debug, Clone)]
@@ -11,14 +44,479 @@ pub struct GlpiClient {
     user_token: String,
     http: reqwest::Client,
     session_token: Option<String>,
+    user_id: Option<i64>,
+    scope: SessionScope,
+    /// GLPI_API=hl: the high-level API instead of apirest.php.
+    hl: bool,
+    /// OAuth sign-in: the high-level API's own, or the SSO gateway's in front of apirest.php.
+    oauth: Option<oauth::Provider>,
+    /// Header carrying the bearer token.
+    bearer_header: HeaderName,
+    bearer: Option<oauth::Token>,
+    /// GLPI language code (`fr_FR`) asked for with Accept-Language (GLPI_LANGUAGE).
+    language: Option<String>,
 }
 
-/// Minimal ticket surface used by the notifier.
+/// Which GLPI API the client talks to (GLPI_API), and how it signs in.
+#[derive(Debug, Clone, Default)]
+pub enum Api {
+    /// apirest.php, user_token + initSession.
+    #[default]
+    Legacy,
+    /// apirest.php behind an OAuth2 / OpenID Connect gateway: the bearer token goes in `header` on every
+    /// request, alongside the user_token (or instead of it when GLPI trusts the gateway's user).
+    Gateway { provider: oauth::Provider, header: String },
+    /// api.php/v2, OAuth2.
+    Hl(oauth::Provider),
+}
+
+/// Profile and entity to activate right after initSession (GLPI_PROFILE_ID / GLPI_ACTIVE_ENTITY).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionScope {
+    pub profile_id: Option<i64>,
+    pub entity_id: Option<i64>,
+    /// Include sub-entities when switching entity (GLPI_ENTITY_RECURSIVE).
+    pub is_recursive: bool,
+}
+
+/// Who the session runs as, from /getFullSession.
 #[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub user_id: i64,
+    pub user_name: String,
+    pub profile_id: i64,
+    pub profile_name: String,
+    pub entity_id: i64,
+    pub entity_name: String,
+    /// Sub-entities are included in the active scope.
+    pub recursive: bool,
+    /// Language the session answers in (`fr_FR`), empty when GLPI does not say.
+    pub language: String,
+}
+
+/// Minimal ticket surface used by the notifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ticket {
     pub id: i64,
     pub name: String,
     pub requester: Option<String>,
+    pub category: Option<String>,
+    /// GLPI priority (1 very low … 5 very high, 6 major).
+    pub priority: Option<i64>,
+    /// Opening date as returned by GLPI ("YYYY-MM-DD HH:MM:SS", server local time).
+    pub date: Option<String>,
+    /// Number of attached documents (screenshots, logs), when the column is available.
+    pub documents: Option<i64>,
+    /// Entity full name (`Root entity > Site A`), when the column is available.
+    pub entity: Option<String>,
+    /// Time-to-own SLA target ("YYYY-MM-DD HH:MM:SS", server local time), when one applies.
+    pub time_to_own: Option<String>,
+    /// Location full name (`Lisbon > Building B > Floor 2`), when set.
+    #[serde(default)]
+    pub location: Option<String>,
+    /// Open major incident this ticket is linked to (MAJOR_INCIDENT_PRIORITY), found by [`GlpiClient::major_incident`].
+    #[serde(default)]
+    pub major_incident: Option<i64>,
+    /// Instance of config.toml the ticket comes from (see [`crate::instance`]), when several are polled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Language of the title and description (ISO 639-3, see [`crate::lang`]), when LANGUAGES is set and it
+    /// was detected with confidence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// Notification severity derived from the GLPI priority.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Normal,
+    High,
+    Critical,
+}
+
+impl Ticket {
+    /// Opening date as a UNIX timestamp, reading the GLPI date in GLPI_SERVER_TZ (default: this machine's).
+    pub fn created_ts(&self) -> Option<u64> {
+        u64::try_from(clock::parse_glpi_date(self.date.as_deref()?)?.timestamp()).ok()
+    }
+
+    /// Opening time as shown to the user (DISPLAY_TZ / DATE_LOCALE).
+    pub fn opened_display(&self) -> Option<String> {
+        Some(clock::format_opened(clock::parse_glpi_date(self.date.as_deref()?)?))
+    }
+
+    /// Severity of the priority under SEVERITY_MAP (default scale: 1-2 Low, 3-4 Normal, 5 High, 6 Critical);
+    /// always Critical when linked to a major incident.
+    pub fn severity(&self) -> Severity {
+        if self.major_incident.is_some() {
+            return Severity::Critical; // inherited from the major incident
+        }
+        severity::of(self.priority)
+    }
+}
+
+/// One dated event in a ticket's history, for `timeline`.
+#[derive(Debug, Clone)]
+pub struct TimelineEvent {
+    /// GLPI date (`YYYY-MM-DD HH:MM:SS`, server time).
+    pub at: String,
+    pub kind: &'static str,
+    pub author: Option<String>,
+    /// Plain-text summary (HTML removed).
+    pub text: String,
+}
+
+/// GET /Ticket/:id, the columns `timeline` shows.
+#[derive(Deserialize)]
+struct TicketItem {
+    name: String,
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    users_id_recipient: serde_json::Value,
+    #[serde(default)]
+    solvedate: Option<String>,
+    #[serde(default)]
+    closedate: Option<String>,
+}
+
+/// Satisfaction survey of a closed ticket (GET /TicketSatisfaction).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Satisfaction {
+    pub id: i64,
+    pub tickets_id: i64,
+    /// Unset until the requester answers.
+    #[serde(default)]
+    pub date_answered: Option<String>,
+}
+
+/// Follow-up as listed by GET /Ticket/:id/ITILFollowup (dropdowns not expanded).
+#[derive(Deserialize)]
+struct Followup {
+    id: i64,
+    #[serde(default)]
+    users_id: i64,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// The follow-ups not written by user `me`, as (follow-up id, plain text), oldest first.
+fn by_others(mut followups: Vec<Followup>, me: i64) -> Vec<(i64, String)> {
+    followups.sort_by_key(|f| f.id);
+    followups
+        .into_iter()
+        .filter(|f| f.users_id != me)
+        .map(|f| (f.id, crate::demo::text(plain_text(f.content.as_deref().unwrap_or_default()))))
+        .collect()
+}
+
+/// Ticket task as listed by GET /TicketTask (dropdowns not expanded).
+#[derive(Debug, Clone, Deserialize)]
+pub struct TicketTask {
+    pub id: i64,
+    pub tickets_id: i64,
+    /// 0 information, 1 to do, 2 done.
+    #[serde(default)]
+    pub state: i64,
+    /// Group in charge of the task, 0 when none.
+    #[serde(default)]
+    pub groups_id_tech: i64,
+    /// Description, as plain text.
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// Link between two tickets (GET /Ticket/:id/Ticket_Ticket); the ticket asked about is on either side.
+#[derive(Deserialize)]
+struct TicketLink {
+    tickets_id_1: i64,
+    tickets_id_2: i64,
+}
+
+/// Requester/technician/observer link of a ticket (GET /Ticket/:id/Ticket_User).
+#[derive(Deserialize)]
+struct TicketUser {
+    users_id: i64,
+    /// 1 requester, 2 assigned, 3 observer.
+    #[serde(rename = "type")]
+    kind: i64,
+}
+
+/// Opening period of a GLPI calendar (GET /Calendar/:id/CalendarSegment).
+#[derive(Deserialize)]
+pub struct CalendarSegment {
+    /// 0 Sunday … 6 Saturday.
+    pub day: i64,
+    /// "HH:MM:SS".
+    pub begin: String,
+    pub end: String,
+}
+
+/// Closed days of a GLPI calendar (GET /Holiday/:id).
+#[derive(Deserialize)]
+pub struct Holiday {
+    pub begin_date: String,
+    pub end_date: String,
+    /// 1 when the dates repeat every year.
+    #[serde(default)]
+    pub is_perpetual: i64,
+}
+
+/// A reminder (GET /Reminder), planned when it has begin and end dates.
+#[derive(Deserialize)]
+pub struct Reminder {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub begin: Option<String>,
+    #[serde(default)]
+    pub end: Option<String>,
+}
+
+/// Link between a calendar and a holiday (GET /Calendar/:id/Calendar_Holiday).
+#[derive(Deserialize)]
+struct CalendarHoliday {
+    holidays_id: i64,
+}
+
+/// Follow-up, task or validation (GET /Ticket/:id/<itemtype>); unused columns stay None.
+#[derive(Deserialize)]
+struct TicketSubItem {
+    #[serde(default)]
+    date: Option<String>,
+    #[serde(default)]
+    users_id: serde_json::Value,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    actiontime: Option<i64>,
+    #[serde(default)]
+    submission_date: Option<String>,
+    #[serde(default)]
+    validation_date: Option<String>,
+    #[serde(default)]
+    users_id_validate: serde_json::Value,
+    #[serde(default)]
+    status: Option<i64>,
+    #[serde(default)]
+    comment_submission: Option<String>,
+    #[serde(default)]
+    comment_validation: Option<String>,
+}
+
+/// One of my open tickets, as read by [`GlpiClient::my_open_tickets`].
+#[derive(Debug, Clone)]
+pub struct OpenTicket {
+    pub ticket: Ticket,
+    /// Last-update date ("YYYY-MM-DD HH:MM:SS", server local time).
+    pub date_mod: String,
+    /// Status, assignees and priority, compared between polls for update notifications.
+    pub snapshot: Snapshot,
+}
+
+/// Numeric search option ids of the ticket columns we read (resolved from listSearchOptions).
+#[derive(Debug, Clone, Copy)]
+pub struct TicketFields {
+    pub id: i64,
+    pub name: i64,
+    pub status: i64,
+    pub requester: Option<i64>,
+    pub category: Option<i64>,
+    pub priority: Option<i64>,
+    pub date: Option<i64>,
+    /// Assigned technicians (multi-valued).
+    pub assignee: Option<i64>,
+    /// Assigned groups (multi-valued).
+    pub group: Option<i64>,
+    /// Number of documents attached to the ticket.
+    pub documents: Option<i64>,
+    /// Entity full name (for per-entity URL templates).
+    pub entity: Option<i64>,
+    /// Time-to-own SLA target.
+    pub time_to_own: Option<i64>,
+    /// Location full name (tree: MY_LOCATIONS filters `under` it).
+    pub location: Option<i64>,
+    /// Description, read for the language detection only (LANGUAGES).
+    pub content: Option<i64>,
+}
+
+/// GLPI rejected the sign-in, the session or the credentials; the variant tells which setting to fix.
+#[derive(Debug)]
+pub enum AuthError {
+    /// GLPI_APP_TOKEN matches no API client (`ERROR_WRONG_APP_TOKEN_PARAMETER`).
+    WrongAppToken(String),
+    /// The instance requires an App-Token and none was sent (`ERROR_APP_TOKEN_PARAMETERS_MISSING`).
+    MissingAppToken(String),
+    /// No API client allows this machine's address (`ERROR_NOT_ALLOWED_IP`).
+    NotAllowedIp(String),
+    /// User token rejected: wrong or regenerated token, user disabled, API access not allowed...
+    Rejected(String),
+    /// The session is no longer valid (`ERROR_SESSION_TOKEN_INVALID`): expired, killed, or GLPI restarted.
+    SessionInvalid(String),
+    /// The OAuth provider refused the token request (GLPI_OAUTH_*), or the device sign-in lapsed.
+    OAuth(String),
+}
+
+impl AuthError {
+    /// Classify a failed `what` request (initSession) by the GLPI error code (`["ERROR_...", "message"]` body).
+    fn from_reply(what: &str, status: StatusCode, body: &str) -> Self {
+        let detail = format!("{what} failed: {status} | body: {body}");
+        match error_code(body).as_deref() {
+            Some("ERROR_WRONG_APP_TOKEN_PARAMETER") => AuthError::WrongAppToken(detail),
+            Some("ERROR_APP_TOKEN_PARAMETERS_MISSING") => AuthError::MissingAppToken(detail),
+            Some("ERROR_NOT_ALLOWED_IP") => AuthError::NotAllowedIp(detail),
+            Some("ERROR_SESSION_TOKEN_INVALID") => AuthError::SessionInvalid(detail),
+            _ => AuthError::Rejected(detail),
+        }
+    }
+
+    /// A failed request after sign-in is an authentication error when GLPI refused the session or the
+    /// credentials: 401, 403 or `ERROR_SESSION_TOKEN_INVALID` whatever the status.
+    fn of_reply(what: &str, status: StatusCode, body: &str) -> Option<Self> {
+        let refused = matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+            || error_code(body).as_deref() == Some("ERROR_SESSION_TOKEN_INVALID");
+        refused.then(|| Self::from_reply(what, status, body))
+    }
+
+    /// Which configuration to fix, in the user's terms.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            AuthError::WrongAppToken(_) => {
+                "GLPI_APP_TOKEN matches no API client (Setup > General > API): copy the client's App-Token, \
+                 or leave GLPI_APP_TOKEN empty if the client has none"
+            }
+            AuthError::MissingAppToken(_) => {
+                "this GLPI requires an App-Token: set GLPI_APP_TOKEN to the token of an API client \
+                 (Setup > General > API) that allows this machine's address"
+            }
+            AuthError::NotAllowedIp(_) => {
+                "no API client allows this machine's address: add its IP range to an API client \
+                 (Setup > General > API) or use that client's App-Token"
+            }
+            AuthError::Rejected(_) => {
+                "check GLPI_USER_TOKEN (regenerated? copy it again from the user's Remote access keys) \
+                 and that the user is active"
+            }
+            AuthError::SessionInvalid(_) => {
+                "nothing if it happens once: the next poll signs in again; if it keeps happening, raise the \
+                 session timeout in GLPI (Setup > General > Security) above POLL_SECONDS"
+            }
+            AuthError::OAuth(_) => {
+                "check GLPI_OAUTH_CLIENT_ID/SECRET and GLPI_OAUTH_FLOW against the OAuth client, or run \
+                 `glpi-notifier-rs sign-in` again (device flow)"
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AuthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (AuthError::WrongAppToken(detail)
+        | AuthError::MissingAppToken(detail)
+        | AuthError::NotAllowedIp(detail)
+        | AuthError::Rejected(detail)
+        | AuthError::SessionInvalid(detail)
+        | AuthError::OAuth(detail)) = self;
+        write!(f, "{detail}. To fix: {}", self.hint())
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Fully buffered HTTP reply.
+struct Reply {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+
+    /// Error of a failed `what` request: an [`AuthError`] when GLPI refused the session or the credentials,
+    /// else `<what> failed: <status> | body: <body>`.
+    fn failure(&self, what: &str) -> anyhow::Error {
+        let body = self.text();
+        match AuthError::of_reply(what, self.status, &body) {
+            Some(e) => e.into(),
+            None => anyhow!("{what} failed: {} | body: {body}", self.status),
+        }
+    }
+}
+
+/// GLPI error code of a `["ERROR_...", "message"]` body.
+fn error_code(body: &str) -> Option<String> {
+    serde_json::from_str::<Vec<String>>(body).ok().and_then(|v| v.into_iter().next())
+}
+
+/// A GLPI response body exceeded GLPI_MAX_RESPONSE_MB.
+#[derive(Debug)]
+pub struct ResponseTooLarge {
+    path: String,
+    limit: usize,
+}
+
+impl fmt::Display for ResponseTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GLPI response for {} exceeds {} MB; raise GLPI_MAX_RESPONSE_MB if this is expected",
+            self.path,
+            self.limit / (1024 * 1024)
+        )
+    }
+}
+
+impl std::error::Error for ResponseTooLarge {}
+
+/// listSearchOptions reply reduced to `uid -> option id`. Section headers (`"common": "Characteristics"`)
+/// and every other attribute of an option are skipped without being materialized.
+struct SearchOptionIds(HashMap<String, i64>);
+
+impl<'de> Deserialize<'de> for SearchOptionIds {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Entry {
+            uid: Option<String>,
+        }
+
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = SearchOptionIds;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a listSearchOptions object")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut ids = HashMap::new();
+                while let Some(key) = map.next_key::<std::borrow::Cow<'de, str>>()? {
+                    match key.parse::<i64>() {
+                        Ok(id) => {
+                            if let Some(uid) = map.next_value::<Entry>()?.uid {
+                                ids.insert(uid, id);
+                            }
+                        }
+                        Err(_) => {
+                            map.next_value::<serde::de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(SearchOptionIds(ids))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
 }
 
 #[derive(Deserialize)]
@@ -49,9 +547,100 @@ impl GlpiClient {
             user_token,
             http: client,
             session_token: None,
+            user_id: None,
+            scope: SessionScope::default(),
+            hl: false,
+            oauth: None,
+            bearer_header: AUTHORIZATION,
+            bearer: None,
+            language: None,
         })
     }
 
+    /// Profile/entity switched to after every initSession (also when the session is re-opened).
+    pub fn with_scope(mut self, scope: SessionScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Language of dropdown values (status, category, location names) in the replies (GLPI_LANGUAGE).
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// The API to talk to (GLPI_API): apirest.php, or the GLPI 11 high-level API.
+    pub fn with_api(mut self, api: Api) -> Self {
+        self.hl = matches!(api, Api::Hl(_));
+        self.bearer_header = AUTHORIZATION;
+        self.oauth = match api {
+            Api::Legacy => None,
+            Api::Gateway { provider, header } => {
+                self.bearer_header = HeaderName::try_from(header).unwrap_or(AUTHORIZATION);
+                Some(provider)
+            }
+            Api::Hl(provider) => Some(provider),
+        };
+        self
+    }
+
+    /// Send a request and buffer the reply; every exchange is traced when TRACE_HTTP=true.
+    /// Requests share per-host slots (GLPI_MAX_CONCURRENT) and pacing (GLPI_REQUEST_DELAY_MS).
+    /// With the high-level API, calls only the legacy API has are refused here.
+    async fn send(&self, rb: reqwest::RequestBuilder) -> Result<Reply> {
+        // Lets admins find this exchange in reverse-proxy / GLPI logs (Apache: %{X-Correlation-Id}i)
+        let correlation_id = trace::next_correlation_id();
+        let req = rb.header("X-Correlation-Id", &correlation_id).build()?;
+        let path = req.url().path();
+        if self.hl
+            && req.url().as_str().starts_with(&self.base_url)
+            && !path.contains("/v2/")
+            && !path.ends_with("/token")
+        {
+            return Err(anyhow!("{} is not available with GLPI_API=hl (legacy apirest.php only)", req.url().path()));
+        }
+        log::debug!("GLPI {} {} [{correlation_id}]", req.method(), req.url().path());
+        let _slot = throttle::acquire(req.url().host_str().unwrap_or_default()).await;
+        let started = Instant::now();
+        let traced = trace::enabled().then(|| trace::describe_request(&req));
+
+        let path = req.url().path().to_string();
+        let result = async {
+            let mut r = self.http.execute(req).await?;
+            let status = r.status();
+            let headers = r.headers().clone();
+            let limit = crate::config::get().max_response_bytes.max(1024 * 1024);
+            let too_large = || anyhow::Error::new(ResponseTooLarge { path: path.clone(), limit });
+            if r.content_length().is_some_and(|len| len > limit as u64) {
+                return Err(too_large());
+            }
+            let mut body = Vec::new();
+            while let Some(chunk) = r.chunk().await? {
+                if body.len() + chunk.len() > limit {
+                    return Err(too_large());
+                }
+                body.extend_from_slice(&chunk);
+            }
+            Ok(Reply { status, headers, body })
+        }
+        .await;
+
+        match &result {
+            Ok(r) if r.status.is_client_error() || r.status.is_server_error() => {
+                log::info!("GLPI {path} -> {} [X-Correlation-Id: {correlation_id}]", r.status)
+            }
+            Err(e) => log::info!("GLPI {path} -> {e} [X-Correlation-Id: {correlation_id}]"),
+            Ok(_) => {}
+        }
+        if let Some(request) = traced {
+            match &result {
+                Ok(r) => trace::record_reply(&request, r.status, &r.headers, &r.body, started.elapsed()),
+                Err(e) => trace::record_error(&request, e, started.elapsed()),
+            }
+        }
+        result
+    }
+
     fn hdrs(&self) -> HeaderMap {
         let mut h = HeaderMap::new();
         h.insert("Accept", HeaderValue::from_static("application/json"));
@@ -59,184 +648,1374 @@ impl GlpiClient {
         if let Some(ref s) = self.session_token {
             h.insert("Session-Token", HeaderValue::from_str(s).unwrap());
         }
+        if let Some(ref token) = self.bearer {
+            h.insert(&self.bearer_header, HeaderValue::from_str(&format!("Bearer {}", token.access)).unwrap());
+        }
         if let Some(ref a) = self.app_token {
             h.insert("App-Token", HeaderValue::from_str(a).unwrap());
         }
+        if let Some(lang) = self.language.as_deref().and_then(|l| HeaderValue::from_str(&accept_language(l)).ok()) {
+            h.insert(ACCEPT_LANGUAGE, lang);
+        }
         h
     }
 
     /// Authenticate (initSession). Also follows simple 30x to a new base URL if needed.
     pub async fn init_session(&mut self) -> Result<()> {
-        let mut hdrs = HeaderMap::new();
-        hdrs.insert("Accept", HeaderValue::from_static("application/json"));
-        hdrs.insert("User-Agent", HeaderValue::from_static("glpi-notifier-rs/0.1"));
-        hdrs.insert("Authorization", HeaderValue::from_str(&format!("user_token {}", self.user_token))?);
-        if let Some(ref a) = self.app_token {
-            hdrs.insert("App-Token", HeaderValue::from_str(a)?);
+        // The high-level API has no session: signing in again is getting a new token
+        if self.hl || (self.oauth.is_some() && self.bearer.as_ref().is_none_or(oauth::Token::expiring)) {
+            self.oauth_sign_in().await?;
+        }
+        if self.hl {
+            return Ok(());
+        }
+        let mut hdrs = self.hdrs();
+        hdrs.remove("Session-Token");
+        // user_token goes in the query when the gateway's bearer token holds the Authorization header
+        let mut query = Vec::new();
+        if !self.user_token.is_empty() {
+            if hdrs.contains_key(AUTHORIZATION) {
+                query.push(("user_token", self.user_token.clone()));
+            } else {
+                hdrs.insert(AUTHORIZATION, HeaderValue::from_str(&format!("user_token {}", self.user_token))?);
+            }
         }
 
         let url = format!("{}/initSession", self.base_url.trim_end_matches('/'));
-        let mut r = self.http.get(&url).headers(hdrs.clone()).send().await?;
+        let mut r = self.send(self.http.get(&url).headers(hdrs.clone()).query(&query)).await?;
 
-        if r.status().is_redirection() {
-            if let Some(loc) = r.headers().get(LOCATION).and_then(|v| v.to_str().ok()) {
+        if r.status.is_redirection() {
+            if let Some(loc) = r.headers.get(LOCATION).and_then(|v| v.to_str().ok()) {
                 let new_base = loc.trim_end_matches('/').trim_end_matches("/initSession");
                 self.base_url = new_base.to_string();
                 let url2 = format!("{}/initSession", self.base_url);
-                r = self.http.get(&url2).headers(hdrs.clone()).send().await?;
+                r = self.send(self.http.get(&url2).headers(hdrs.clone()).query(&query)).await?;
             }
         }
 
-        if !r.status().is_success() {
-            let status = r.status();
-            let body = r.text().await.unwrap_or_default();
-            return Err(anyhow!("initSession failed: {status} | body: {body}"));
+        if !r.status.is_success() {
+            return Err(AuthError::from_reply("initSession", r.status, &r.text()).into());
         }
 
-        let data: InitSessionResp = r.json().await?;
+        let data: InitSessionResp = r.json()?;
+        crate::scrub::register(&data.session_token);
         self.session_token = Some(data.session_token);
+        self.apply_scope().await
+    }
+
+    /// Switch to the configured profile/entity; a rejected id is a configuration problem.
+    async fn apply_scope(&mut self) -> Result<()> {
+        if let Some(id) = self.scope.profile_id {
+            let url = format!("{}/changeActiveProfile", self.base_url);
+            let body = serde_json::json!({ "profiles_id": id });
+            let r = self.send(self.http.post(url).headers(self.hdrs()).json(&body)).await?;
+            if !r.status.is_success() {
+                let available = match self.fetch_profiles().await {
+                    Ok(p) => p.iter().map(|(id, name)| format!("{id} ({name})")).collect::<Vec<_>>().join(", "),
+                    Err(_) => "unknown".into(),
+                };
+                return Err(ConfigError(format!(
+                    "GLPI_PROFILE_ID={id} rejected: {} | available profiles: {available}",
+                    r.status
+                ))
+                .into());
+            }
+        }
+        if let Some(id) = self.scope.entity_id {
+            let url = format!("{}/changeActiveEntities", self.base_url);
+            let body = serde_json::json!({ "entities_id": id, "is_recursive": self.scope.is_recursive });
+            let r = self.send(self.http.post(url).headers(self.hdrs()).json(&body)).await?;
+            if !r.status.is_success() {
+                return Err(ConfigError(format!(
+                    "GLPI_ACTIVE_ENTITY={id} rejected: {} | body: {}",
+                    r.status,
+                    r.text()
+                ))
+                .into());
+            }
+        }
         Ok(())
     }
 
     pub async fn kill_session(&mut self) -> Result<()> {
-        if self.session_token.is_none() {
-            return Ok(());
+        if self.session_token.is_some() {
+            let url = format!("{}/killSession", self.base_url);
+            let _ = self.send(self.http.get(url).headers(self.hdrs())).await?;
+            self.session_token = None;
         }
-        let url = format!("{}/killSession", self.base_url);
-        let _ = self.http.get(url).headers(self.hdrs()).send().await?;
-        self.session_token = None;
+        self.bearer = None; // bearer tokens just expire
         Ok(())
     }
 
+    /// Switch to new credentials (rotated tokens); the next call opens a fresh session.
+    /// Returns false when they are the ones already in use.
+    pub fn rotate_tokens(&mut self, user_token: String, app_token: Option<String>) -> bool {
+        if user_token == self.user_token && app_token == self.app_token {
+            return false;
+        }
+        crate::scrub::register(&user_token); // the environment still holds the old one
+        if let Some(app) = &app_token {
+            crate::scrub::register(app);
+        }
+        self.user_token = user_token;
+        self.app_token = app_token;
+        self.session_token = None;
+        true
+    }
+
     async fn ensure_session(&mut self) -> Result<()> {
-        if self.session_token.is_none() {
-            self.init_session().await?;
+        if self.session_token.is_none() && !self.hl {
+            return self.init_session().await;
+        }
+        if self.oauth.is_some() && self.bearer.as_ref().is_none_or(oauth::Token::expiring) {
+            self.oauth_sign_in().await?;
+        }
+        Ok(())
+    }
+
+    /// Get (or renew) the bearer token: refresh token first (this run's, else the one kept in the keyring),
+    /// then the configured grant.
+    async fn oauth_sign_in(&mut self) -> Result<()> {
+        let provider = self.oauth_endpoints().await?;
+        let refresh = match self.bearer.take() {
+            Some(token) => token.refresh,
+            None => provider.stored_refresh(),
+        };
+        if let Some(refresh) = refresh {
+            let r = self
+                .send(self.http.post(&provider.token_url).form(&oauth::refresh_form(&provider.client, &refresh)))
+                .await?;
+            if r.status.is_success() {
+                return self.keep_token(&provider, &r.body, Some(&refresh));
+            }
+            // Expired or revoked: sign in again from scratch
+            log::info!("Refresh token rejected ({}), signing in again", r.status);
+            let _ = provider.forget();
+        }
+        let Some(form) = oauth::grant_form(&provider.client, &provider.grant) else {
+            return Err(AuthError::OAuth("no valid OAuth sign-in (device flow)".into()).into());
+        };
+        let r = self.send(self.http.post(&provider.token_url).form(&form)).await?;
+        if !r.status.is_success() {
+            return Err(AuthError::OAuth(format!("token request failed: {} | body: {}", r.status, r.text())).into());
+        }
+        self.keep_token(&provider, &r.body, None)
+    }
+
+    /// Use a token reply, saving a new refresh token in the keyring.
+    fn keep_token(&mut self, provider: &oauth::Provider, body: &[u8], previous: Option<&str>) -> Result<()> {
+        let token = oauth::parse_token(body)?;
+        if let Some(refresh) = token.refresh.as_deref().filter(|r| Some(*r) != previous) {
+            provider.store_refresh(refresh);
         }
+        self.bearer = Some(token);
         Ok(())
     }
 
+    /// The OAuth provider with its endpoints, discovered from the OpenID Connect issuer when not given.
+    async fn oauth_endpoints(&mut self) -> Result<oauth::Provider> {
+        let mut provider = self.oauth.clone().ok_or_else(|| anyhow!("OAuth sign-in is not configured"))?;
+        if let (true, Some(issuer)) = (provider.token_url.is_empty(), provider.issuer.clone()) {
+            let url = format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/'));
+            let r = self.send(self.http.get(&url)).await?;
+            if !r.status.is_success() {
+                return Err(anyhow!("OpenID discovery at {url} failed: {}", r.status));
+            }
+            provider.discovered(&r.body)?;
+            self.oauth = Some(provider.clone());
+        }
+        Ok(provider)
+    }
+
+    /// Device flow (`sign-in`): `show` tells the user where to approve the notifier, then the token endpoint
+    /// is polled until they do. The refresh token is kept in the keyring for the following runs.
+    pub async fn device_sign_in(&mut self, show: impl FnOnce(&oauth::DeviceAuthorization)) -> Result<()> {
+        let provider = self.oauth_endpoints().await?;
+        let device_url = provider.device_url.clone().ok_or_else(|| {
+            ConfigError("the device flow needs GLPI_OAUTH_DEVICE_URL (or GLPI_OAUTH_ISSUER to discover it)".into())
+        })?;
+        let r = self.send(self.http.post(&device_url).form(&oauth::device_form(&provider.client))).await?;
+        if !r.status.is_success() {
+            return Err(anyhow!("device authorization failed: {} | body: {}", r.status, r.text()));
+        }
+        let auth = oauth::parse_device_authorization(&r.body)?;
+        show(&auth);
+
+        let deadline = Instant::now() + Duration::from_secs(auth.expires_in);
+        let mut interval = auth.interval.max(1);
+        let form = oauth::device_token_form(&provider.client, &auth.device_code);
+        while Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+            let r = self.send(self.http.post(&provider.token_url).form(&form)).await?;
+            if r.status.is_success() {
+                return self.keep_token(&provider, &r.body, None);
+            }
+            match oauth::error_code(&r.body).as_deref() {
+                Some("authorization_pending") => {}
+                Some("slow_down") => interval += 5,
+                _ => {
+                    return Err(
+                        AuthError::OAuth(format!("device sign-in failed: {} | body: {}", r.status, r.text())).into()
+                    )
+                }
+            }
+        }
+        Err(AuthError::OAuth("device code expired before it was approved".into()).into())
+    }
+
+    /// Forget the refresh token kept in the keyring; false when there was none.
+    pub fn sign_out(&self) -> Result<bool> {
+        self.oauth.as_ref().ok_or_else(|| ConfigError("OAuth sign-in is not configured".into()))?.forget()
+    }
+
+    async fn full_session(&mut self) -> Result<serde_json::Value> {
+        self.ensure_session().await?;
+        let url = format!("{}/getFullSession", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("getFullSession"));
+        }
+        let mut payload: serde_json::Value = r.json()?;
+        Ok(payload["session"].take())
+    }
+
+    /// Full name of an entity (`Root entity > Site A`).
+    pub async fn entity_name(&mut self, id: i64) -> Result<String> {
+        self.ensure_session().await?;
+        let url = format!("{}/Entity/{}", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Entity #{id} read")));
+        }
+        let entity: serde_json::Value = r.json()?;
+        entity["completename"].as_str().map(str::to_string).ok_or_else(|| anyhow!("Entity #{id}: no completename"))
+    }
+
+    /// The planned reminders visible to this user (MAINTENANCE_REMINDER).
+    pub async fn planned_reminders(&mut self) -> Result<Vec<Reminder>> {
+        self.ensure_session().await?;
+        let url = format!("{}/Reminder", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&[("range", "0-999")])).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("Reminders read"));
+        }
+        let reminders: Vec<Reminder> = r.json()?;
+        Ok(reminders.into_iter().filter(|r| r.begin.is_some() && r.end.is_some()).collect())
+    }
+
+    /// Opening periods and holidays of a GLPI calendar (SLA_CALENDAR=glpi:<id>).
+    pub async fn calendar(&mut self, id: i64) -> Result<(Vec<CalendarSegment>, Vec<Holiday>)> {
+        self.ensure_session().await?;
+        let url = format!("{}/Calendar/{}/CalendarSegment", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&[("range", "0-999")])).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Calendar #{id} read")));
+        }
+        let segments: Vec<CalendarSegment> = r.json()?;
+
+        let url = format!("{}/Calendar/{}/Calendar_Holiday", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&[("range", "0-999")])).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Calendar #{id} holidays read")));
+        }
+        let mut holidays = Vec::new();
+        for link in r.json::<Vec<CalendarHoliday>>()? {
+            let url = format!("{}/Holiday/{}", self.base_url, link.holidays_id);
+            let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+            if !r.status.is_success() {
+                return Err(r.failure(&format!("Holiday #{} read", link.holidays_id)));
+            }
+            holidays.push(r.json()?);
+        }
+        Ok((segments, holidays))
+    }
+
+    /// The language asked for is not the one the session answers in: apirest.php keeps the GLPI user's
+    /// language preference (My settings), whatever Accept-Language says.
+    pub fn language_ignored(&self, session: &SessionInfo) -> Option<&str> {
+        let wanted = self.language.as_deref()?;
+        let answered = match wanted.contains('_') {
+            true => session.language.as_str(),
+            false => session.language.split('_').next().unwrap_or_default(), // `fr` is met by fr_FR and fr_CA
+        };
+        (!answered.is_empty() && !answered.eq_ignore_ascii_case(wanted)).then_some(wanted)
+    }
+
+    /// Whether an App-Token is sent with initSession.
+    pub fn has_app_token(&self) -> bool {
+        self.app_token.is_some()
+    }
+
+    /// Id of the authenticated user (`session.glpiID` from /getFullSession), cached after the first call.
+    pub async fn my_user_id(&mut self) -> Result<i64> {
+        if let Some(id) = self.user_id {
+            return Ok(id);
+        }
+        let session = self.full_session().await?;
+        let id = json_i64(&session["glpiID"]).ok_or_else(|| anyhow!("getFullSession: session.glpiID missing"))?;
+        self.user_id = Some(id);
+        Ok(id)
+    }
+
+    /// Priority values the instance can produce: the distinct cells of `priority_matrix` (/getGlpiConfig).
+    pub async fn priority_values(&mut self) -> Result<Vec<i64>> {
+        self.ensure_session().await?;
+        let url = format!("{}/getGlpiConfig", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("getGlpiConfig"));
+        }
+        let payload: serde_json::Value = r.json()?;
+        let matrix = &payload["cfg_glpi"]["priority_matrix"];
+        // Stored as a JSON string in glpi_configs; some versions decode it already
+        let matrix = match matrix.as_str() {
+            Some(raw) => serde_json::from_str(raw)?,
+            None => matrix.clone(),
+        };
+        let rows = matrix.as_object().ok_or_else(|| anyhow!("getGlpiConfig: no priority_matrix"))?;
+        let mut values: Vec<i64> =
+            rows.values().filter_map(|row| row.as_object()).flat_map(|row| row.values().filter_map(json_i64)).collect();
+        values.sort_unstable();
+        values.dedup();
+        Ok(values)
+    }
+
+    /// Active user, profile and entity of the session.
+    pub async fn session_info(&mut self) -> Result<SessionInfo> {
+        let session = self.full_session().await?;
+        let text = |v: &serde_json::Value| v.as_str().unwrap_or_default().to_string();
+        let info = SessionInfo {
+            user_id: json_i64(&session["glpiID"]).ok_or_else(|| anyhow!("getFullSession: session.glpiID missing"))?,
+            user_name: text(&session["glpiname"]),
+            profile_id: json_i64(&session["glpiactiveprofile"]["id"]).unwrap_or_default(),
+            profile_name: text(&session["glpiactiveprofile"]["name"]),
+            entity_id: json_i64(&session["glpiactive_entity"]).unwrap_or_default(),
+            entity_name: text(&session["glpiactive_entity_name"]),
+            recursive: json_i64(&session["glpiactive_entity_recursive"]).is_some_and(|r| r != 0)
+                || session["glpiactive_entity_recursive"].as_bool() == Some(true),
+            language: text(&session["glpilanguage"]),
+        };
+        self.user_id = Some(info.user_id);
+        Ok(info)
+    }
+
+    /// Profiles the user may switch to (/getMyProfiles), as (id, name).
+    pub async fn my_profiles(&mut self) -> Result<Vec<(i64, String)>> {
+        self.ensure_session().await?;
+        self.fetch_profiles().await
+    }
+
+    async fn fetch_profiles(&self) -> Result<Vec<(i64, String)>> {
+        let url = format!("{}/getMyProfiles", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("getMyProfiles"));
+        }
+        let payload: serde_json::Value = r.json()?;
+        let profiles = payload["myprofiles"].as_array().cloned().unwrap_or_default();
+        Ok(profiles
+            .iter()
+            .filter_map(|p| Some((json_i64(&p["id"])?, p["name"].as_str().unwrap_or_default().to_string())))
+            .collect())
+    }
+
+    /// Add the authenticated user as assigned technician (Ticket_User type 2) of a ticket.
+    pub async fn assign_to_me(&mut self, ticket_id: i64) -> Result<()> {
+        let users_id = self.my_user_id().await?;
+        let url = format!("{}/Ticket/{}/Ticket_User", self.base_url, ticket_id);
+        let body = serde_json::json!({ "input": { "tickets_id": ticket_id, "users_id": users_id, "type": 2 } });
+        let r = self.send(self.http.post(url).headers(self.hdrs()).json(&body)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("Ticket_User add"));
+        }
+        Ok(())
+    }
+
+    /// Create a ticket (POST /Ticket). Returns the new ticket id.
+    pub async fn create_ticket(
+        &mut self,
+        name: &str,
+        content: &str,
+        category: Option<i64>,
+        entity: Option<i64>,
+    ) -> Result<i64> {
+        self.ensure_session().await?;
+        let mut input = serde_json::json!({ "name": name, "content": content });
+        if let Some(cat) = category {
+            input["itilcategories_id"] = cat.into();
+        }
+        if let Some(entity) = entity {
+            input["entities_id"] = entity.into();
+        }
+        let url = format!("{}/Ticket", self.base_url);
+        let r =
+            self.send(self.http.post(url).headers(self.hdrs()).json(&serde_json::json!({ "input": input }))).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("Ticket creation"));
+        }
+        let payload: serde_json::Value = r.json()?;
+        payload.get("id").and_then(|v| v.as_i64()).ok_or_else(|| anyhow!("Ticket creation: no id in response"))
+    }
+
+    /// Delete a ticket for good (`force_purge`, skipping the trash bin).
+    pub async fn purge_ticket(&mut self, id: i64) -> Result<()> {
+        self.ensure_session().await?;
+        let url = format!("{}/Ticket/{}?force_purge=true", self.base_url, id);
+        let r = self.send(self.http.delete(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Ticket #{id} deletion")));
+        }
+        Ok(())
+    }
+
+    /// Log time on a ticket as a TicketTask with `actiontime` in seconds.
+    pub async fn add_ticket_task(&mut self, ticket_id: i64, content: &str, actiontime_secs: u64) -> Result<i64> {
+        self.ensure_session().await?;
+        let body = serde_json::json!({
+            "input": { "tickets_id": ticket_id, "content": content, "actiontime": actiontime_secs }
+        });
+        let url = format!("{}/Ticket/{}/TicketTask", self.base_url, ticket_id);
+        let r = self.send(self.http.post(url).headers(self.hdrs()).json(&body)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("TicketTask creation"));
+        }
+        let payload: serde_json::Value = r.json()?;
+        payload.get("id").and_then(|v| v.as_i64()).ok_or_else(|| anyhow!("TicketTask creation: no id in response"))
+    }
+
     /// /listSearchOptions/Ticket – map UID -> numeric field id
-    pub async fn list_search_options(&mut self, itemtype: &str) -> Result<serde_json::Value> {
+    /// Search option ids by uid (listSearchOptions), keeping only the uids: descriptions, names and
+    /// section headers are skipped while parsing.
+    pub async fn search_option_ids(&mut self, itemtype: &str) -> Result<OptionIds> {
+        let key = (self.base_url.clone(), itemtype.to_string());
+        if let Some(ids) = SEARCH_OPTIONS.get(&key) {
+            return Ok(ids);
+        }
+        let ids = self.fetch_search_options(itemtype).await?;
+        SEARCH_OPTIONS.insert(key, ids.clone());
+        Ok(ids)
+    }
+
+    /// listSearchOptions, bypassing the cache (`bench-search` times it).
+    pub async fn fetch_search_options(&mut self, itemtype: &str) -> Result<OptionIds> {
         self.ensure_session().await?;
         let url = format!("{}/listSearchOptions/{}", self.base_url, itemtype);
-        let r = self.http.get(url).headers(self.hdrs()).send().await?;
-        if !r.status().is_success() {
-            return Err(anyhow!("listSearchOptions failed: {}", r.status()));
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("listSearchOptions"));
         }
-        Ok(r.json().await?)
+        Ok(Arc::new(r.json::<SearchOptionIds>()?.0))
     }
 
     pub async fn resolve_field_ids(&mut self, uids: &[&str]) -> Result<HashMap<String, i64>> {
-        let opts = self.list_search_options("Ticket").await?;
-        let mut map = HashMap::new();
-        if let Some(obj) = opts.as_object() {
-            for (k, v) in obj {
-                if let (Ok(id_num), Some(uid)) = (k.parse::<i64>(), v.get("uid")) {
-                    if let Some(uid_s) = uid.as_str() {
-                        if uids.contains(&uid_s) {
-                            map.insert(uid_s.to_string(), id_num);
-                        }
-                    }
-                }
-            }
+        let ids = self.search_option_ids("Ticket").await?;
+        Ok(uids.iter().filter_map(|uid| ids.get(*uid).map(|id| (uid.to_string(), *id))).collect())
+    }
+
+    /// Resolve the ticket columns we need (requester and category are optional).
+    pub async fn resolve_ticket_fields(&mut self) -> Result<TicketFields> {
+        if self.hl {
+            return Ok(hl::FIELDS);
         }
-        Ok(map)
+        let ids = self
+            .resolve_field_ids(&[
+                "Ticket.id",
+                "Ticket.name",
+                "Ticket.status",
+                "Ticket._users_id_recipient",
+                "Ticket.ITILCategory.completename",
+                "Ticket.priority",
+                "Ticket.date",
+                "Ticket._users_id_assign",
+                "Ticket._groups_id_assign",
+                "Ticket.Document_Item.id",
+                "Ticket.Entity.completename",
+                "Ticket.time_to_own",
+                "Ticket.Location.completename",
+                "Ticket.content",
+            ])
+            .await?;
+        Ok(TicketFields {
+            id: *ids.get("Ticket.id").ok_or_else(|| anyhow!("field id not found"))?,
+            name: *ids.get("Ticket.name").ok_or_else(|| anyhow!("field name not found"))?,
+            status: *ids.get("Ticket.status").ok_or_else(|| anyhow!("field status not found"))?,
+            requester: ids.get("Ticket._users_id_recipient").copied(),
+            category: ids.get("Ticket.ITILCategory.completename").copied(),
+            priority: ids.get("Ticket.priority").copied(),
+            date: ids.get("Ticket.date").copied(),
+            assignee: ids.get("Ticket._users_id_assign").copied(),
+            group: ids.get("Ticket._groups_id_assign").copied(),
+            documents: ids.get("Ticket.Document_Item.id").copied(),
+            entity: ids.get("Ticket.Entity.completename").copied(),
+            time_to_own: ids.get("Ticket.time_to_own").copied(),
+            location: ids.get("Ticket.Location.completename").copied(),
+            content: ids.get("Ticket.content").copied().filter(|_| crate::lang::enabled()),
+        })
     }
 
-    /// Search tickets with status=New. Optionally include requester field.
+    /// Search tickets with status=New (or the status criterion of TICKET_FILTER). Optional columns (requester,
+    /// category) are included when resolved. Newest first, narrowed by the extra TICKET_FILTER criteria.
     pub async fn search_new_tickets(
         &mut self,
-        id_field: i64,
-        name_field: i64,
-        status_field: i64,
-        requester_field: Option<i64>,
+        fields: &TicketFields,
+        filter: &[Criterion],
         max_rows: usize,
     ) -> Result<Vec<Ticket>> {
+        Ok(self.search_new_page(fields, filter, max_rows).await?.0)
+    }
+
+    /// Every New ticket matching `filter`, newest first, read page by page (below the last page's oldest id, so
+    /// concurrent changes do not shift pages): for views that order the whole queue.
+    pub async fn all_new_tickets(&mut self, fields: &TicketFields, filter: &[Criterion]) -> Result<Vec<Ticket>> {
+        const PAGE: usize = 200;
+        let mut all = Vec::new();
+        let mut below = None;
+        loop {
+            let mut criteria = filter.to_vec();
+            criteria.extend(below.map(|id: i64| Criterion {
+                field: FieldRef::Id(fields.id),
+                searchtype: SearchType::LessThan,
+                value: id.to_string(),
+            }));
+            let (page, _) = self.search_new_page(fields, &criteria, PAGE).await?;
+            below = next_page_below(&page, PAGE);
+            all.extend(page);
+            if below.is_none() {
+                return Ok(all);
+            }
+        }
+    }
+
+    /// [`Self::search_new_tickets`] plus GLPI's `totalcount` of matching tickets (all pages).
+    pub async fn search_new_page(
+        &mut self,
+        fields: &TicketFields,
+        filter: &[Criterion],
+        max_rows: usize,
+    ) -> Result<(Vec<Ticket>, Option<i64>)> {
+        if self.hl {
+            return self.hl_new_page(filter, max_rows).await;
+        }
         self.ensure_session().await?;
+        let extra = self.new_criteria_params(fields, filter, 1).await?;
 
         let mut params: Vec<(&str, String)> = vec![
-            ("criteria[0][field]", status_field.to_string()),
-            ("criteria[0][searchtype]", "equals".into()),
-            ("criteria[0][value]", "1".into()), // 1 = New
-            ("sort", id_field.to_string()),
+            ("is_deleted", "0".into()), // never tickets in the trash
+            ("sort", fields.id.to_string()),
             ("order", "DESC".into()),
             ("range", format!("0-{}", max_rows)),
-            ("forcedisplay[0]", id_field.to_string()),
-            ("forcedisplay[1]", name_field.to_string()),
-            ("forcedisplay[2]", status_field.to_string()),
         ];
+        params.extend(Self::display_params(fields));
 
-        if let Some(req) = requester_field {
-            params.push(("forcedisplay[3]", req.to_string()));
+        let url = format!("{}/search/Ticket", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params).query(&extra)).await?;
+
+        if !r.status.is_success() {
+            return Err(r.failure("search/Ticket"));
+        }
+
+        let payload: serde_json::Value = r.json()?;
+        let total = payload.get("totalcount").and_then(|v| v.as_i64());
+        if let Some(total) = total {
+            log::info!("DEBUG: totalcount(status=New) = {}", total);
+        }
+
+        Ok((Self::parse_ticket_rows(payload.get("data").cloned().unwrap_or_default(), fields)?, total))
+    }
+
+    /// Ids of the New tickets (matching `filter`) below `below`, newest first: the part of the New list a
+    /// truncated [`Self::search_new_page`] did not return. Only the id column is read.
+    pub async fn new_ticket_ids_below(
+        &mut self,
+        fields: &TicketFields,
+        filter: &[Criterion],
+        below: i64,
+    ) -> Result<Vec<i64>> {
+        const PAGE: usize = 1000;
+        if self.hl {
+            return self.hl_ids_below(filter, below).await;
+        }
+        self.ensure_session().await?;
+        let extra = self.new_criteria_params(fields, filter, 2).await?;
+        let mut ids = Vec::new();
+        let mut below = below;
+        loop {
+            let params: Vec<(&str, String)> = vec![
+                ("criteria[1][link]", "AND".into()),
+                ("criteria[1][field]", fields.id.to_string()),
+                ("criteria[1][searchtype]", "lessthan".into()),
+                ("criteria[1][value]", below.to_string()),
+                ("is_deleted", "0".into()),
+                ("sort", fields.id.to_string()),
+                ("order", "DESC".into()),
+                ("range", format!("0-{}", PAGE - 1)),
+                ("forcedisplay[0]", fields.id.to_string()),
+            ];
+            let url = format!("{}/search/Ticket", self.base_url);
+            let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params).query(&extra)).await?;
+            if !r.status.is_success() {
+                return Err(r.failure("search/Ticket(ids)"));
+            }
+            let payload: serde_json::Value = r.json()?;
+            let rows = match payload.get("data") {
+                Some(serde_json::Value::Array(rows)) => rows.clone(),
+                Some(serde_json::Value::Object(map)) => map.values().cloned().collect(),
+                _ => Vec::new(),
+            };
+            let page: Vec<i64> =
+                rows.iter().filter_map(|row| row.get(fields.id.to_string()).and_then(json_i64)).collect();
+            ids.extend(page.iter().copied().filter(|id| *id < below));
+            match page.iter().min() {
+                Some(&min) if page.len() == PAGE && min < below => below = min,
+                _ => break,
+            }
+        }
+        Ok(ids)
+    }
+
+    /// The tickets among `ids` that are still New and match `filter` (one search per GLPI_BATCH_SIZE ids).
+    pub async fn new_tickets_by_ids(
+        &mut self,
+        fields: &TicketFields,
+        filter: &[Criterion],
+        ids: &[i64],
+    ) -> Result<Vec<Ticket>> {
+        if self.hl {
+            return self.hl_by_ids(filter, ids).await;
+        }
+        self.ensure_session().await?;
+        let extra = self.new_criteria_params(fields, filter, 2).await?;
+        let mut tickets = Vec::new();
+        for chunk in ids.chunks(crate::config::get().batch_size.max(1)) {
+            let mut params = vec![
+                ("criteria[1][link]".to_string(), "AND".into()),
+                ("is_deleted".to_string(), "0".into()),
+                ("range".to_string(), format!("0-{}", chunk.len() - 1)),
+            ];
+            // Nested group: status New AND (id = a OR id = b ...)
+            for (i, id) in chunk.iter().enumerate() {
+                if i > 0 {
+                    params.push((format!("criteria[1][criteria][{i}][link]"), "OR".into()));
+                }
+                params.push((format!("criteria[1][criteria][{i}][field]"), fields.id.to_string()));
+                params.push((format!("criteria[1][criteria][{i}][searchtype]"), "equals".into()));
+                params.push((format!("criteria[1][criteria][{i}][value]"), id.to_string()));
+            }
+            params.extend(Self::display_params(fields).into_iter().map(|(k, v)| (k.to_string(), v)));
+            let url = format!("{}/search/Ticket", self.base_url);
+            let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params).query(&extra)).await?;
+            if !r.status.is_success() {
+                return Err(r.failure("search/Ticket(by id)"));
+            }
+            let payload: serde_json::Value = r.json()?;
+            let rows = Self::parse_ticket_rows(payload.get("data").cloned().unwrap_or_default(), fields)?;
+            tickets.extend(rows.into_iter().filter(|t| chunk.contains(&t.id)));
         }
+        Ok(tickets)
+    }
+
+    /// One ticket (any status) with its status code, or None when it does not exist
+    /// or is not visible with the active profile/entity.
+    /// With a non-empty `filter`, the ticket must also match those criteria.
+    pub async fn ticket_with_status(
+        &mut self,
+        fields: &TicketFields,
+        filter: &[Criterion],
+        id: i64,
+    ) -> Result<Option<(Ticket, Option<i64>)>> {
+        self.ensure_session().await?;
+        let extra = self.criteria_params(fields, filter, 1).await?;
+
+        let mut params: Vec<(&str, String)> = vec![
+            ("criteria[0][field]", fields.id.to_string()),
+            ("criteria[0][searchtype]", "equals".into()),
+            ("criteria[0][value]", id.to_string()),
+            ("is_deleted", "0".into()), // never tickets in the trash
+        ];
+        params.extend(Self::display_params(fields));
 
         let url = format!("{}/search/Ticket", self.base_url);
-        let r = self.http.get(url).headers(self.hdrs()).query(&params).send().await?;
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params).query(&extra)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("search/Ticket(id)"));
+        }
+        let payload: serde_json::Value = r.json()?;
+        let rows = match payload.get("data") {
+            Some(serde_json::Value::Array(rows)) => rows.clone(),
+            Some(serde_json::Value::Object(map)) => map.values().cloned().collect(),
+            _ => Vec::new(),
+        };
+        Ok(rows.iter().find_map(|row| {
+            let t = Self::row_to_ticket(row, fields).filter(|t| t.id == id)?;
+            Some((t, row.get(fields.status.to_string()).and_then(json_i64)))
+        }))
+    }
 
-        if !r.status().is_success() {
-            let status = r.status();
-            let body = r.text().await.unwrap_or_default();
-            return Err(anyhow!("search/Ticket failed: {status} | body: {body}"));
+    /// The tickets among `ids` (any status) with their status code, one search per GLPI_BATCH_SIZE ids. Tickets
+    /// deleted or not visible with the active profile/entity are left out.
+    pub async fn tickets_with_status(
+        &mut self,
+        fields: &TicketFields,
+        ids: &[i64],
+    ) -> Result<Vec<(Ticket, Option<i64>)>> {
+        self.ensure_session().await?;
+        let mut found = Vec::new();
+        for chunk in ids.chunks(crate::config::get().batch_size.max(1)) {
+            let mut params =
+                vec![("is_deleted".to_string(), "0".into()), ("range".to_string(), format!("0-{}", chunk.len() - 1))];
+            for (i, id) in chunk.iter().enumerate() {
+                if i > 0 {
+                    params.push((format!("criteria[{i}][link]"), "OR".into()));
+                }
+                params.push((format!("criteria[{i}][field]"), fields.id.to_string()));
+                params.push((format!("criteria[{i}][searchtype]"), "equals".into()));
+                params.push((format!("criteria[{i}][value]"), id.to_string()));
+            }
+            params.extend(Self::display_params(fields).into_iter().map(|(k, v)| (k.to_string(), v)));
+            let url = format!("{}/search/Ticket", self.base_url);
+            let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params)).await?;
+            if !r.status.is_success() {
+                return Err(r.failure("search/Ticket(statuses)"));
+            }
+            let payload: serde_json::Value = r.json()?;
+            let rows = match payload.get("data") {
+                Some(serde_json::Value::Array(rows)) => rows.clone(),
+                Some(serde_json::Value::Object(map)) => map.values().cloned().collect(),
+                _ => Vec::new(),
+            };
+            found.extend(rows.iter().filter_map(|row| {
+                let t = Self::row_to_ticket(row, fields).filter(|t| chunk.contains(&t.id))?;
+                Some((t, row.get(fields.status.to_string()).and_then(json_i64)))
+            }));
         }
+        Ok(found)
+    }
 
-        let payload: serde_json::Value = r.json().await?;
-        if let Some(total) = payload.get("totalcount").and_then(|v| v.as_i64()) {
-            log::info!("DEBUG: totalcount(status=New) = {}", total);
+    /// Satisfaction surveys not answered yet (the newest 50 visible to the active profile).
+    pub async fn unanswered_satisfactions(&mut self) -> Result<Vec<Satisfaction>> {
+        self.ensure_session().await?;
+        let query = [("range", "0-49"), ("sort", "id"), ("order", "DESC")];
+        let url = format!("{}/TicketSatisfaction", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&query)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("TicketSatisfaction read"));
         }
+        let surveys: Vec<Satisfaction> = r.json()?;
+        Ok(surveys.into_iter().filter(|s| s.date_answered.as_deref().is_none_or(str::is_empty)).collect())
+    }
 
-        Self::parse_ticket_rows(payload.get("data").cloned().unwrap_or_default(), id_field, name_field, requester_field)
+    /// Open tickets (not solved or closed) assigned to the authenticated user, with their last-update date.
+    pub async fn my_open_tickets(&mut self, fields: &TicketFields) -> Result<Vec<OpenTicket>> {
+        let Some(assignee) = fields.assignee else { return Ok(Vec::new()) };
+        self.open_tickets_of_mine(fields, assignee, "assigned").await
     }
 
-    /// Recent tickets (any status), useful for debug-list.
-    pub async fn search_recent_tickets(
+    /// Open tickets (not solved or closed) the authenticated user is a requester of, with their last-update date.
+    pub async fn my_requested_tickets(&mut self, fields: &TicketFields) -> Result<Vec<OpenTicket>> {
+        let requester = *self
+            .search_option_ids("Ticket")
+            .await?
+            .get("Ticket._users_id_requester")
+            .ok_or_else(|| anyhow!("field _users_id_requester not found"))?;
+        self.open_tickets_of_mine(fields, requester, "requested").await
+    }
+
+    /// Open tickets whose actor column `actor` (assignees, requesters) holds the authenticated user, read page by
+    /// page below the last page's oldest id.
+    async fn open_tickets_of_mine(&mut self, fields: &TicketFields, actor: i64, what: &str) -> Result<Vec<OpenTicket>> {
+        const PAGE: usize = 200;
+        let me = self.my_user_id().await?;
+        let date_mod = *self
+            .search_option_ids("Ticket")
+            .await?
+            .get("Ticket.date_mod")
+            .ok_or_else(|| anyhow!("field date_mod not found"))?;
+
+        let mut params: Vec<(&str, String)> = vec![
+            ("criteria[0][field]", fields.status.to_string()),
+            ("criteria[0][searchtype]", "equals".into()),
+            ("criteria[0][value]", "notold".into()), // neither solved nor closed
+            ("criteria[1][link]", "AND".into()),
+            ("criteria[1][field]", actor.to_string()),
+            ("criteria[1][searchtype]", "equals".into()),
+            ("criteria[1][value]", me.to_string()),
+            ("is_deleted", "0".into()),
+            ("sort", fields.id.to_string()),
+            ("order", "DESC".into()),
+            ("range", format!("0-{}", PAGE - 1)),
+            ("forcedisplay[9]", date_mod.to_string()),
+        ];
+        if let Some(assignee) = fields.assignee {
+            params.push(("forcedisplay[12]", assignee.to_string()));
+        }
+        params.extend(Self::display_params(fields));
+        let url = format!("{}/search/Ticket", self.base_url);
+        let mut rows = Vec::new();
+        let mut below: Option<i64> = None;
+        loop {
+            let mut query = params.clone();
+            if let Some(below) = below {
+                query.extend([
+                    ("criteria[2][link]", "AND".into()),
+                    ("criteria[2][field]", fields.id.to_string()),
+                    ("criteria[2][searchtype]", "lessthan".into()),
+                    ("criteria[2][value]", below.to_string()),
+                ]);
+            }
+            let r = self.send(self.http.get(&url).headers(self.hdrs()).query(&query)).await?;
+            if !r.status.is_success() {
+                return Err(r.failure(&format!("search/Ticket({what})")));
+            }
+            let payload: serde_json::Value = r.json()?;
+            let page = match payload.get("data") {
+                Some(serde_json::Value::Array(rows)) => rows.clone(),
+                Some(serde_json::Value::Object(map)) => map.values().cloned().collect(),
+                _ => Vec::new(),
+            };
+            let oldest = page.iter().filter_map(|row| row.get(fields.id.to_string()).and_then(json_i64)).min();
+            let full = page.len() >= PAGE;
+            rows.extend(page);
+            match oldest {
+                Some(oldest) if full && below.is_none_or(|b| oldest < b) => below = Some(oldest),
+                _ => break,
+            }
+        }
+        Ok(rows
+            .iter()
+            .filter_map(|row| {
+                let ticket = Self::row_to_ticket(row, fields)?;
+                let date_mod = row.get(date_mod.to_string()).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                let snapshot = Snapshot {
+                    status: row.get(fields.status.to_string()).and_then(json_i64),
+                    assignee: fields.assignee.and_then(|a| row.get(a.to_string())).and_then(names_list),
+                    priority: ticket.priority,
+                };
+                Some(OpenTicket { ticket, date_mod, snapshot })
+            })
+            .collect())
+    }
+
+    /// Follow-ups written by someone else than the authenticated user, as (follow-up id, plain text), oldest first.
+    pub async fn followups_by_others(&mut self, id: i64) -> Result<Vec<(i64, String)>> {
+        let me = self.my_user_id().await?;
+        let url = format!("{}/Ticket/{}/ITILFollowup", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&[("range", "0-999")])).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Ticket #{id} follow-ups read")));
+        }
+        Ok(by_others(r.json()?, me))
+    }
+
+    /// Follow-ups written by one of the ticket's requesters, as (follow-up id, plain text), oldest first.
+    pub async fn requester_followups(&mut self, id: i64) -> Result<Vec<(i64, String)>> {
+        let url = format!("{}/Ticket/{}/Ticket_User", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Ticket #{id} actors read")));
+        }
+        let requesters: Vec<i64> =
+            r.json::<Vec<TicketUser>>()?.into_iter().filter(|u| u.kind == 1).map(|u| u.users_id).collect();
+
+        let url = format!("{}/Ticket/{}/ITILFollowup", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&[("range", "0-999")])).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Ticket #{id} follow-ups read")));
+        }
+        let mut followups: Vec<Followup> = r.json()?;
+        followups.sort_by_key(|f| f.id);
+        Ok(followups
+            .into_iter()
+            .filter(|f| requesters.contains(&f.users_id))
+            .map(|f| (f.id, crate::demo::text(plain_text(f.content.as_deref().unwrap_or_default()))))
+            .collect())
+    }
+
+    /// Groups the authenticated user belongs to (session `glpigroups`).
+    pub async fn my_groups(&mut self) -> Result<Vec<i64>> {
+        let session = self.full_session().await?;
+        Ok(match &session["glpigroups"] {
+            serde_json::Value::Array(ids) => ids.iter().filter_map(json_i64).collect(),
+            serde_json::Value::Object(ids) => ids.values().filter_map(json_i64).collect(),
+            _ => Vec::new(),
+        })
+    }
+
+    /// Items of a dropdown visible to the session (`Entity`, `ITILCategory`, `Group`...), as (id, full name)
+    /// sorted by name; at most 1000.
+    pub async fn dropdown_items(&mut self, itemtype: &str) -> Result<Vec<(i64, String)>> {
+        self.ensure_session().await?;
+        let url = format!("{}/{}", self.base_url, itemtype);
+        let query = [("range", "0-999"), ("sort", "completename"), ("order", "ASC")];
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&query)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("{itemtype} list")));
+        }
+        let items: Vec<serde_json::Value> = r.json()?;
+        Ok(items
+            .iter()
+            .filter_map(|item| {
+                let name = item["completename"].as_str().or(item["name"].as_str())?;
+                Some((json_i64(&item["id"])?, name.to_string()))
+            })
+            .collect())
+    }
+
+    /// Full name of a group (`IT > Network`).
+    pub async fn group_name(&mut self, id: i64) -> Result<String> {
+        self.ensure_session().await?;
+        let url = format!("{}/Group/{}", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Group #{id} read")));
+        }
+        let group: serde_json::Value = r.json()?;
+        group["completename"].as_str().map(str::to_string).ok_or_else(|| anyhow!("Group #{id}: no completename"))
+    }
+
+    /// Tasks still to do whose technician group is one of `groups`, all the visible to-do tasks read page by page.
+    pub async fn group_tasks_to_do(&mut self, groups: &[i64]) -> Result<Vec<TicketTask>> {
+        const PAGE: usize = 200;
+        // Pages overlap: tasks done while paging shift the later ones back without any being skipped
+        const OVERLAP: usize = 20;
+        self.ensure_session().await?;
+        let url = format!("{}/TicketTask", self.base_url);
+        let mut found: Vec<TicketTask> = Vec::new();
+        let (mut start, mut last_id) = (0, 0);
+        loop {
+            let range = format!("{start}-{}", start + PAGE - 1);
+            let query = [("range", range.as_str()), ("sort", "id"), ("order", "ASC"), ("searchText[state]", "^1$")];
+            let r = self.send(self.http.get(&url).headers(self.hdrs()).query(&query)).await?;
+            if !r.status.is_success() {
+                return Err(r.failure("TicketTask read"));
+            }
+            let page: Vec<TicketTask> = r.json()?;
+            let total = r.headers.get("Content-Range").and_then(|v| v.to_str().ok()).and_then(range_total);
+            let more = page.len() == PAGE && total.is_none_or(|total| start + PAGE < total);
+            // Sorted by id, so the tasks of the overlap already read are the ones not above the last id
+            for task in page {
+                if task.id > last_id {
+                    last_id = task.id;
+                    if task.state == 1 && groups.contains(&task.groups_id_tech) {
+                        found.push(task);
+                    }
+                }
+            }
+            if !more {
+                break;
+            }
+            start += PAGE - OVERLAP;
+        }
+        Ok(found.into_iter().map(|t| TicketTask { content: t.content.as_deref().map(plain_text), ..t }).collect())
+    }
+
+    /// Major incident of each ticket linked to one: the open tickets (neither solved nor closed) of at least
+    /// `min_priority`, found in one search, then their links. Cached in [`MAJOR_INCIDENTS`].
+    pub async fn major_incidents(
         &mut self,
-        id_field: i64,
-        name_field: i64,
+        fields: &TicketFields,
+        min_priority: i64,
+    ) -> Result<Arc<HashMap<i64, i64>>> {
+        let key = crate::instance::current_name();
+        if let Some(found) = MAJOR_INCIDENTS.get(&key) {
+            return Ok(found);
+        }
+        if self.hl {
+            return Err(anyhow!("ticket links are not available through the high-level API (GLPI_API=hl)"));
+        }
+        let priority = fields.priority.ok_or_else(|| anyhow!("field priority not found"))?;
+        self.ensure_session().await?;
+        let params: Vec<(&str, String)> = vec![
+            ("criteria[0][field]", fields.status.to_string()),
+            ("criteria[0][searchtype]", "equals".into()),
+            ("criteria[0][value]", "notold".into()), // neither solved nor closed
+            ("criteria[1][link]", "AND".into()),
+            ("criteria[1][field]", priority.to_string()),
+            ("criteria[1][searchtype]", "equals".into()),
+            ("criteria[1][value]", format!("-{min_priority}")), // GLPI's "at least"
+            ("is_deleted", "0".into()),
+            ("range", "0-99".into()),
+            ("forcedisplay[0]", fields.id.to_string()),
+        ];
+        let url = format!("{}/search/Ticket", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("search/Ticket(major incidents)"));
+        }
+        let incidents = search_ids(&r.json()?, fields.id);
+
+        let mut linked = Vec::new();
+        for incident in incidents {
+            let url = format!("{}/Ticket/{}/Ticket_Ticket", self.base_url, incident);
+            let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+            if !r.status.is_success() {
+                return Err(r.failure(&format!("Ticket #{incident} links read")));
+            }
+            linked.push((incident, linked_tickets(incident, r.json()?)));
+        }
+        let found = Arc::new(incident_of_tickets(linked));
+        MAJOR_INCIDENTS.insert(key, found.clone());
+        Ok(found)
+    }
+
+    /// Title of a ticket the authenticated user is a requester of, None for anyone else's ticket.
+    pub async fn requested_ticket_name(&mut self, id: i64) -> Result<Option<String>> {
+        let me = self.my_user_id().await?;
+        let url = format!("{}/Ticket/{}/Ticket_User", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Ticket #{id} actors read")));
+        }
+        if !r.json::<Vec<TicketUser>>()?.iter().any(|u| u.users_id == me && u.kind == 1) {
+            return Ok(None);
+        }
+        if let Some(known) = crate::tickets::get(id) {
+            return Ok(Some(known.ticket.name)); // titles rarely change: no need to read it again
+        }
+        let url = format!("{}/Ticket/{}", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs())).await?;
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Ticket #{id} read")));
+        }
+        Ok(Some(r.json::<TicketItem>()?.name))
+    }
+
+    /// Ticket title and its history (opening, follow-ups, tasks, validations, solution/closure), oldest first.
+    /// Sub-items the profile may not read are left out with a warning.
+    pub async fn ticket_timeline(&mut self, id: i64) -> Result<(String, Vec<TimelineEvent>)> {
+        self.ensure_session().await?;
+        let query = [("expand_dropdowns", "true"), ("range", "0-999")];
+        let url = format!("{}/Ticket/{}", self.base_url, id);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&query[..1])).await?;
+        if r.status == StatusCode::NOT_FOUND {
+            return Err(anyhow!("Ticket #{id} not found (or not visible to the active profile/entity)"));
+        }
+        if !r.status.is_success() {
+            return Err(r.failure(&format!("Ticket #{id} read")));
+        }
+        let ticket: TicketItem = r.json()?;
+
+        let event = |at: &Option<String>, kind, author: &serde_json::Value, text: &Option<String>| {
+            at.clone().filter(|d| !d.is_empty()).map(|at| TimelineEvent {
+                at,
+                kind,
+                author: dropdown_name(author),
+                text: plain_text(text.as_deref().unwrap_or_default()),
+            })
+        };
+        let none = serde_json::Value::Null;
+        let mut events: Vec<TimelineEvent> = [
+            event(&ticket.date, "opened", &ticket.users_id_recipient, &ticket.content),
+            event(&ticket.solvedate, "solved", &none, &None),
+            event(&ticket.closedate, "closed", &none, &None),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        for itemtype in ["ITILFollowup", "TicketTask", "TicketValidation"] {
+            let url = format!("{}/Ticket/{}/{}", self.base_url, id, itemtype);
+            let r = self.send(self.http.get(url).headers(self.hdrs()).query(&query)).await?;
+            if !r.status.is_success() {
+                log::warn!("Ticket #{id}: {itemtype} not readable ({}), left out of the timeline", r.status);
+                continue;
+            }
+            for item in r.json::<Vec<TicketSubItem>>()? {
+                match itemtype {
+                    "ITILFollowup" => events.extend(event(&item.date, "follow-up", &item.users_id, &item.content)),
+                    "TicketTask" => {
+                        let mut e = event(&item.date, "task", &item.users_id, &item.content);
+                        if let (Some(e), Some(secs)) = (e.as_mut(), item.actiontime.filter(|s| *s > 0)) {
+                            e.text = format!("[{} min] {}", secs / 60, e.text);
+                        }
+                        events.extend(e);
+                    }
+                    _ => {
+                        let approver = dropdown_name(&item.users_id_validate).unwrap_or_else(|| "?".into());
+                        if let Some(mut e) =
+                            event(&item.submission_date, "validation", &item.users_id, &item.comment_submission)
+                        {
+                            e.text = format!("requested from {approver}. {}", e.text).trim_end().to_string();
+                            events.push(e);
+                        }
+                        let verdict = match item.status {
+                            Some(3) => "accepted",
+                            Some(4) => "refused",
+                            _ => continue,
+                        };
+                        if let Some(mut e) = event(
+                            &item.validation_date,
+                            "validation",
+                            &item.users_id_validate,
+                            &item.comment_validation,
+                        ) {
+                            e.text = format!("{verdict}. {}", e.text).trim_end().to_string();
+                            events.push(e);
+                        }
+                    }
+                }
+            }
+        }
+        events.sort_by(|a, b| a.at.cmp(&b.at));
+        Ok((ticket.name, events))
+    }
+
+    /// Names of the technicians assigned to each ticket (empty when unassigned or the column is unavailable).
+    /// One search per GLPI_BATCH_SIZE ids (default 50) instead of one per ticket.
+    pub async fn assignees(&mut self, fields: &TicketFields, ids: &[i64]) -> Result<BTreeMap<i64, Vec<String>>> {
+        let mut found: BTreeMap<i64, Vec<String>> = ids.iter().map(|&id| (id, Vec::new())).collect();
+        let Some(assignee) = fields.assignee else { return Ok(found) };
+        self.ensure_session().await?;
+
+        for chunk in ids.chunks(crate::config::get().batch_size.max(1)) {
+            let mut params = vec![
+                ("forcedisplay[0]".to_string(), fields.id.to_string()),
+                ("forcedisplay[1]".to_string(), assignee.to_string()),
+                ("range".to_string(), format!("0-{}", chunk.len() - 1)),
+            ];
+            for (i, id) in chunk.iter().enumerate() {
+                if i > 0 {
+                    params.push((format!("criteria[{i}][link]"), "OR".into()));
+                }
+                params.push((format!("criteria[{i}][field]"), fields.id.to_string()));
+                params.push((format!("criteria[{i}][searchtype]"), "equals".into()));
+                params.push((format!("criteria[{i}][value]"), id.to_string()));
+            }
+            let url = format!("{}/search/Ticket", self.base_url);
+            let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params)).await?;
+            if !r.status.is_success() {
+                return Err(r.failure("search/Ticket(assignees)"));
+            }
+            let payload: serde_json::Value = r.json()?;
+            let rows = match payload.get("data") {
+                Some(serde_json::Value::Array(rows)) => rows.clone(),
+                Some(serde_json::Value::Object(map)) => map.values().cloned().collect(),
+                _ => Vec::new(),
+            };
+            for row in rows {
+                let Some(id) = row.get(fields.id.to_string()).and_then(json_i64) else { continue };
+                // Multi-valued columns come as an array or as one string joined with GLPI's `$$##$$` separator
+                let names: Vec<String> = match row.get(assignee.to_string()).cloned().unwrap_or_default() {
+                    serde_json::Value::Array(values) => {
+                        values.iter().filter_map(|v| v.as_str().map(str::to_string)).collect()
+                    }
+                    serde_json::Value::String(s) => s.split("$$##$$").map(str::to_string).collect(),
+                    _ => Vec::new(),
+                };
+                if let Some(slot) = found.get_mut(&id) {
+                    *slot = names.into_iter().map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect();
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// `criteria[n..]` query parameters (ANDed) for the extra filter criteria.
+    /// `criteria` of the New-tickets searches: `status equals 1` (New) as criteria[0], unless `filter` has a
+    /// status criterion of its own (e.g. `status equals notold`), then `filter` from `first` on.
+    async fn new_criteria_params(
+        &mut self,
+        fields: &TicketFields,
+        filter: &[Criterion],
+        first: usize,
+    ) -> Result<Vec<(String, String)>> {
+        let filter = self.resolve_filter_uids(filter).await?;
+        let mut params = Vec::new();
+        if !crate::filter::sets_status(&filter, fields) {
+            params.push(("criteria[0][field]".to_string(), fields.status.to_string()));
+            params.push(("criteria[0][searchtype]".to_string(), "equals".to_string()));
+            params.push(("criteria[0][value]".to_string(), "1".to_string())); // 1 = New
+        }
+        params.extend(Self::filter_params(fields, &filter, first)?);
+        Ok(params)
+    }
+
+    /// `criteria` of the TICKET_FILTER clauses, from `first` on.
+    async fn criteria_params(
+        &mut self,
+        fields: &TicketFields,
+        filter: &[Criterion],
+        first: usize,
+    ) -> Result<Vec<(String, String)>> {
+        let filter = self.resolve_filter_uids(filter).await?;
+        Self::filter_params(fields, &filter, first)
+    }
+
+    /// `filter` with its search option uids (`Ticket.urgency`) replaced by their ids.
+    async fn resolve_filter_uids(&mut self, filter: &[Criterion]) -> Result<Vec<Criterion>> {
+        if !filter.iter().any(|c| matches!(c.field, FieldRef::Uid(_))) {
+            return Ok(filter.to_vec());
+        }
+        let ids = self.search_option_ids("Ticket").await?;
+        filter
+            .iter()
+            .map(|c| match &c.field {
+                FieldRef::Uid(uid) => match ids.get(uid) {
+                    Some(id) => Ok(Criterion { field: FieldRef::Id(*id), ..c.clone() }),
+                    None => {
+                        Err(ConfigError(format!("TICKET_FILTER: '{uid}' is not a Ticket search option here")).into())
+                    }
+                },
+                _ => Ok(c.clone()),
+            })
+            .collect()
+    }
+
+    fn filter_params(fields: &TicketFields, filter: &[Criterion], first: usize) -> Result<Vec<(String, String)>> {
+        let mut params = Vec::new();
+        for (i, c) in filter.iter().enumerate() {
+            let field = c.field_id(fields).ok_or_else(|| {
+                ConfigError(format!("TICKET_FILTER: field of '{c}' is not available on this GLPI instance"))
+            })?;
+            let n = first + i;
+            params.push((format!("criteria[{n}][link]"), "AND".to_string()));
+            let values = c.values();
+            if values.len() < 2 {
+                params.push((format!("criteria[{n}][field]"), field.to_string()));
+                params.push((format!("criteria[{n}][searchtype]"), c.searchtype.as_str().to_string()));
+                params.push((format!("criteria[{n}][value]"), c.value.clone()));
+                continue;
+            }
+            // Nested group: (field op a OR field op b ...)
+            for (j, value) in values.iter().enumerate() {
+                if j > 0 {
+                    params.push((format!("criteria[{n}][criteria][{j}][link]"), "OR".to_string()));
+                }
+                params.push((format!("criteria[{n}][criteria][{j}][field]"), field.to_string()));
+                params.push((format!("criteria[{n}][criteria][{j}][searchtype]"), c.searchtype.as_str().to_string()));
+                params.push((format!("criteria[{n}][criteria][{j}][value]"), value.to_string()));
+            }
+        }
+        Ok(params)
+    }
+
+    /// `forcedisplay` columns for every resolved ticket field.
+    fn display_params(fields: &TicketFields) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("forcedisplay[0]", fields.id.to_string()),
+            ("forcedisplay[1]", fields.name.to_string()),
+            ("forcedisplay[2]", fields.status.to_string()),
+        ];
+        if let Some(req) = fields.requester {
+            params.push(("forcedisplay[3]", req.to_string()));
+        }
+        if let Some(cat) = fields.category {
+            params.push(("forcedisplay[4]", cat.to_string()));
+        }
+        if let Some(prio) = fields.priority {
+            params.push(("forcedisplay[5]", prio.to_string()));
+        }
+        if let Some(date) = fields.date {
+            params.push(("forcedisplay[6]", date.to_string()));
+        }
+        if let Some(documents) = fields.documents {
+            params.push(("forcedisplay[7]", documents.to_string()));
+        }
+        if let Some(entity) = fields.entity {
+            params.push(("forcedisplay[8]", entity.to_string()));
+        }
+        if let Some(tto) = fields.time_to_own {
+            params.push(("forcedisplay[10]", tto.to_string()));
+        }
+        if let Some(location) = fields.location {
+            params.push(("forcedisplay[11]", location.to_string()));
+        }
+        if let Some(content) = fields.content {
+            params.push(("forcedisplay[13]", content.to_string()));
+        }
+        params
+    }
+
+    /// Tickets (any status) whose title contains `text`, newest first.
+    pub async fn search_tickets_by_title(
+        &mut self,
+        fields: &TicketFields,
+        text: &str,
         max_rows: usize,
     ) -> Result<Vec<Ticket>> {
         self.ensure_session().await?;
 
+        let mut params: Vec<(&str, String)> = vec![
+            ("criteria[0][field]", fields.name.to_string()),
+            ("criteria[0][searchtype]", "contains".into()),
+            ("criteria[0][value]", text.to_string()),
+            ("sort", fields.id.to_string()),
+            ("order", "DESC".into()),
+            ("range", format!("0-{}", max_rows)),
+            ("forcedisplay[0]", fields.id.to_string()),
+            ("forcedisplay[1]", fields.name.to_string()),
+        ];
+        if let Some(req) = fields.requester {
+            params.push(("forcedisplay[2]", req.to_string()));
+        }
+
+        let url = format!("{}/search/Ticket", self.base_url);
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("search/Ticket(title)"));
+        }
+        let payload: serde_json::Value = r.json()?;
+        let fields = TicketFields {
+            category: None,
+            priority: None,
+            date: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            ..*fields
+        };
+        Self::parse_ticket_rows(payload.get("data").cloned().unwrap_or_default(), &fields)
+    }
+
+    /// Recent tickets (any status), useful for debug-list.
+    pub async fn search_recent_tickets(&mut self, fields: &TicketFields, max_rows: usize) -> Result<Vec<Ticket>> {
+        if self.hl {
+            return Ok(self.hl_tickets("", max_rows + 1).await?.0);
+        }
+        self.ensure_session().await?;
+
         let params: Vec<(&str, String)> = vec![
-            ("sort", id_field.to_string()),
+            ("sort", fields.id.to_string()),
             ("order", "DESC".into()),
             ("range", format!("0-{}", max_rows)),
-            ("forcedisplay[0]", id_field.to_string()),
-            ("forcedisplay[1]", name_field.to_string()),
+            ("forcedisplay[0]", fields.id.to_string()),
+            ("forcedisplay[1]", fields.name.to_string()),
         ];
 
         let url = format!("{}/search/Ticket", self.base_url);
-        let r = self.http.get(url).headers(self.hdrs()).query(&params).send().await?;
-        if !r.status().is_success() {
-            let status = r.status();
-            let body = r.text().await.unwrap_or_default();
-            return Err(anyhow!("search/Ticket(recent) failed: {status} | body: {body}"));
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&params)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("search/Ticket(recent)"));
         }
-        let payload: serde_json::Value = r.json().await?;
-        Self::parse_ticket_rows(payload.get("data").cloned().unwrap_or_default(), id_field, name_field, None)
+        let payload: serde_json::Value = r.json()?;
+        let fields = TicketFields {
+            requester: None,
+            category: None,
+            priority: None,
+            date: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            ..*fields
+        };
+        Self::parse_ticket_rows(payload.get("data").cloned().unwrap_or_default(), &fields)
     }
 
-    fn parse_ticket_rows(
-        data: serde_json::Value,
-        id_field: i64,
-        name_field: i64,
-        requester_field: Option<i64>,
-    ) -> Result<Vec<Ticket>> {
+    fn parse_ticket_rows(data: serde_json::Value, fields: &TicketFields) -> Result<Vec<Ticket>> {
         let mut out = Vec::new();
-        let idk = id_field.to_string();
-        let namek = name_field.to_string();
-        let reqk = requester_field.map(|r| r.to_string());
 
         match data {
             serde_json::Value::Object(map) => {
                 for (_, row) in map {
-                    if let Some(t) = Self::row_to_ticket(&row, &idk, &namek, reqk.as_deref()) {
+                    if let Some(t) = Self::row_to_ticket(&row, fields) {
                         out.push(t);
                     }
                 }
             }
             serde_json::Value::Array(arr) => {
                 for row in arr {
-                    if let Some(t) = Self::row_to_ticket(&row, &idk, &namek, reqk.as_deref()) {
+                    if let Some(t) = Self::row_to_ticket(&row, fields) {
                         out.push(t);
                     }
                 }
@@ -246,7 +2025,7 @@ impl GlpiClient {
         Ok(out)
     }
 
-    fn row_to_ticket(row: &serde_json::Value, idk: &str, namek: &str, reqk: Option<&str>) -> Option<Ticket> {
+    fn row_to_ticket(row: &serde_json::Value, fields: &TicketFields) -> Option<Ticket> {
         use serde_json::Value;
 
         fn extract_i64(v: &Value) -> Option<i64> {
@@ -265,11 +2044,270 @@ impl GlpiClient {
             }
         }
 
-        let id_v = row.get(idk)?;
-        let id = extract_i64(id_v)?;
-        let name = row.get(namek).and_then(extract_string).unwrap_or_default();
-        let requester = reqk.and_then(|k| row.get(k)).and_then(extract_string);
+        let col = |field: i64| row.get(field.to_string());
+
+        let id = col(fields.id).and_then(extract_i64)?;
+        let name = col(fields.name).and_then(extract_string).unwrap_or_default();
+        let requester = fields.requester.and_then(col).and_then(extract_string);
+        let category = fields.category.and_then(col).and_then(extract_string).filter(|c| !c.is_empty());
+        let priority = fields.priority.and_then(col).and_then(extract_i64);
+        let date = fields.date.and_then(col).and_then(extract_string).filter(|d| !d.is_empty());
+        let documents = fields.documents.and_then(col).and_then(extract_i64);
+        let entity = fields.entity.and_then(col).and_then(extract_string).filter(|e| !e.is_empty());
+        let time_to_own = fields.time_to_own.and_then(col).and_then(extract_string).filter(|d| !d.is_empty());
+        let location = fields.location.and_then(col).and_then(extract_string).filter(|l| !l.is_empty());
+        let content = fields.content.and_then(col).and_then(extract_string).unwrap_or_default();
+        let language = crate::lang::of_ticket(&name, &plain_text(&content));
+
+        Some(crate::demo::ticket(Ticket {
+            id,
+            name,
+            requester,
+            category,
+            priority,
+            date,
+            documents,
+            entity,
+            time_to_own,
+            location,
+            major_incident: None,
+            instance: crate::instance::current_name(),
+            language,
+        }))
+    }
+}
+
+/// Dropdown column: the name with `expand_dropdowns`, else `#id` (None for 0 / missing).
+fn dropdown_name(v: &serde_json::Value) -> Option<String> {
+    match v {
+        serde_json::Value::String(s) if !s.trim().is_empty() && s != "0" => Some(s.trim().to_string()),
+        v => json_i64(v).filter(|id| *id > 0).map(|id| format!("#{id}")),
+    }
+}
+
+/// Multi-valued dropdown column (assigned technicians): sorted names, comma-separated. GLPI returns an array or
+/// one string with the values joined by `$$##$$`, depending on the version.
+fn names_list(v: &serde_json::Value) -> Option<String> {
+    let mut names: Vec<String> = match v {
+        serde_json::Value::Array(items) => items.iter().filter_map(dropdown_name).collect(),
+        v => dropdown_name(v)
+            .map(|s| s.split("$$##$$").map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect())
+            .unwrap_or_default(),
+    };
+    names.sort();
+    names.dedup();
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+/// One line of text from GLPI rich text (tags removed, entities decoded, whitespace collapsed, 160 chars max).
+fn plain_text(html: &str) -> String {
+    // Older GLPI versions store the HTML itself entity-encoded: decode first, then strip tags
+    let decoded = html.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&#39;", "'");
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in decoded.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text.replace("&amp;", "&").replace("&nbsp;", " ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(160) {
+        Some((i, _)) => format!("{}…", &text[..i]),
+        None => text,
+    }
+}
+
+/// GLPI language code (`fr_FR`) from a setting written `fr_FR`, `fr-fr` or `fr`.
+pub fn parse_language(raw: &str) -> Result<String> {
+    let raw = raw.trim();
+    let (lang, region) = match raw.split_once(['_', '-']) {
+        Some((lang, region)) => (lang, Some(region)),
+        None => (raw, None),
+    };
+    let letters = |s: &str, n: std::ops::RangeInclusive<usize>| {
+        n.contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphabetic())
+    };
+    if !letters(lang, 2..=3) || region.is_some_and(|r| !letters(r, 2..=2)) {
+        return Err(anyhow!("expected a language code such as fr_FR, pt_BR or de"));
+    }
+    Ok(match region {
+        Some(region) => format!("{}_{}", lang.to_lowercase(), region.to_uppercase()),
+        None => lang.to_lowercase(),
+    })
+}
+
+/// Accept-Language value for a GLPI language code: the exact locale first, then the bare language.
+fn accept_language(code: &str) -> String {
+    match code.split_once('_') {
+        Some((lang, region)) => format!("{lang}-{region}, {lang};q=0.9"),
+        None => code.to_string(),
+    }
+}
+
+/// GLPI returns ids as numbers or numeric strings depending on the endpoint and version.
+fn json_i64(v: &serde_json::Value) -> Option<i64> {
+    v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+}
+
+/// Ids (column `id_field`) of the rows of a search/Ticket reply, whose `data` is an array or an object.
+fn search_ids(payload: &serde_json::Value, id_field: i64) -> Vec<i64> {
+    let rows: Vec<&serde_json::Value> = match payload.get("data") {
+        Some(serde_json::Value::Array(rows)) => rows.iter().collect(),
+        Some(serde_json::Value::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    };
+    rows.iter().filter_map(|row| row.get(id_field.to_string()).and_then(json_i64)).collect()
+}
+
+/// The tickets on the other side of `id`'s links, each once.
+fn linked_tickets(id: i64, links: Vec<TicketLink>) -> Vec<i64> {
+    let mut linked: Vec<i64> = links
+        .into_iter()
+        .map(|l| if l.tickets_id_1 == id { l.tickets_id_2 } else { l.tickets_id_1 })
+        .filter(|other| *other != id)
+        .collect();
+    linked.sort_unstable();
+    linked.dedup();
+    linked
+}
+
+/// Ticket -> major incident, from each incident's linked tickets; a ticket linked to several gets the oldest.
+fn incident_of_tickets(mut linked: Vec<(i64, Vec<i64>)>) -> HashMap<i64, i64> {
+    linked.sort_unstable_by_key(|(incident, _)| *incident);
+    let mut of = HashMap::new();
+    for (incident, tickets) in linked {
+        for t in tickets {
+            of.entry(t).or_insert(incident);
+        }
+    }
+    of
+}
+
+/// Total number of items in a `Content-Range: 0-199/1234` header of the GLPI item endpoints.
+fn range_total(header: &str) -> Option<usize> {
+    header.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+/// Where paging through the New tickets goes on after `page`: below its oldest ticket when the page is full,
+/// None at the end. GLPI's `range` is inclusive, so a full page of `size` holds `size + 1` rows.
+pub fn next_page_below(page: &[Ticket], size: usize) -> Option<i64> {
+    page.iter().map(|t| t.id).min().filter(|_| page.len() >= size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_range_gives_the_total() {
+        assert_eq!(range_total("0-199/1234"), Some(1234));
+        assert_eq!(range_total("200-399/ 400"), Some(400));
+        assert_eq!(range_total("0-199"), None);
+    }
+
+    #[test]
+    fn major_incident_replies_are_parsed() {
+        let array = serde_json::json!({"totalcount": 2, "data": [{"2": 980, "1": "ERP down"}, {"2": "981"}]});
+        assert_eq!(search_ids(&array, 2), vec![980, 981]);
+        let object = serde_json::json!({"data": {"0": {"2": 982}}});
+        assert_eq!(search_ids(&object, 2), vec![982]);
+        assert!(search_ids(&serde_json::json!({"totalcount": 0}), 2).is_empty());
+
+        let links: Vec<TicketLink> = serde_json::from_value(serde_json::json!([
+            {"id": 1, "tickets_id_1": 980, "tickets_id_2": 4713, "link": 1},
+            {"id": 2, "tickets_id_1": 4714, "tickets_id_2": 980, "link": 1},
+            {"id": 3, "tickets_id_1": 980, "tickets_id_2": 4713, "link": 2},
+        ]))
+        .unwrap();
+        assert_eq!(linked_tickets(980, links), vec![4713, 4714]);
+
+        let of = incident_of_tickets(vec![(981, vec![4714, 4715]), (980, vec![4713, 4714])]);
+        assert_eq!(of.get(&4713), Some(&980));
+        assert_eq!(of.get(&4714), Some(&980), "linked to two incidents: the oldest");
+        assert_eq!(of.get(&4715), Some(&981));
+        assert_eq!(of.get(&4711), None);
+    }
+
+    #[test]
+    fn rich_text_becomes_one_line() {
+        assert_eq!(
+            plain_text("&lt;p&gt;Printer&amp;nbsp;on floor 2&lt;/p&gt;&lt;p&gt;is down&lt;/p&gt;"),
+            "Printer on floor 2 is down"
+        );
+        assert_eq!(plain_text("<p>a &amp; b</p>"), "a & b");
+    }
+
+    #[test]
+    fn assignee_lists_are_sorted_in_either_format() {
+        assert_eq!(names_list(&serde_json::json!(["tech2", "tech1"])).as_deref(), Some("tech1, tech2"));
+        assert_eq!(names_list(&serde_json::json!("tech2$$##$$tech1")).as_deref(), Some("tech1, tech2"));
+        assert_eq!(names_list(&serde_json::json!(null)), None);
+    }
+
+    #[test]
+    fn languages_become_glpi_codes_and_headers() {
+        assert_eq!(parse_language("pt-br").unwrap(), "pt_BR");
+        assert_eq!(parse_language(" de ").unwrap(), "de");
+        assert!(parse_language("french").is_err());
+        assert!(parse_language("fr_FRA").is_err());
+        assert_eq!(accept_language("fr_FR"), "fr-FR, fr;q=0.9");
+        assert_eq!(accept_language("de"), "de");
+    }
+
+    #[test]
+    fn search_options_keep_only_uids() {
+        let raw = r#"{"common":"Characteristics","1":{"name":"Title","uid":"Ticket.name","datatype":"itemlink",
+            "available_searchtypes":["contains"]},"2":{"name":"ID","uid":"Ticket.id"},"9999":{"name":"No uid"}}"#;
+        let ids: SearchOptionIds = serde_json::from_str(raw).unwrap();
+        assert_eq!(ids.0.len(), 2);
+        assert_eq!(ids.0.get("Ticket.name"), Some(&1));
+    }
+
+    #[test]
+    fn followups_by_others_leave_mine_out() {
+        let raw = r#"[{"id":12,"users_id":7,"content":"&lt;p&gt;Done on my side&lt;/p&gt;"},
+            {"id":10,"users_id":3,"content":"Any news?"},{"id":11,"users_id":7}]"#;
+        let followups: Vec<Followup> = serde_json::from_str(raw).unwrap();
+        assert_eq!(by_others(followups, 7), [(10, "Any news?".to_string())]);
+    }
+
+    #[test]
+    fn app_token_errors_are_told_apart() {
+        let wrong = r#"["ERROR_WRONG_APP_TOKEN_PARAMETER","parameter app_token seems wrong"]"#;
+        let missing = r#"["ERROR_APP_TOKEN_PARAMETERS_MISSING","missing parameter app_token"]"#;
+        let user = r#"["ERROR_GLPI_LOGIN_USER_TOKEN","parameter user_token seems invalid"]"#;
+        let classify = |body| AuthError::from_reply("initSession", StatusCode::BAD_REQUEST, body);
+        assert!(matches!(classify(wrong), AuthError::WrongAppToken(_)));
+        assert!(matches!(classify(missing), AuthError::MissingAppToken(_)));
+        assert!(matches!(classify(user), AuthError::Rejected(_)));
+        assert!(matches!(classify("<html>502</html>"), AuthError::Rejected(_)));
+        assert!(classify(missing).to_string().contains("set GLPI_APP_TOKEN"));
+    }
 
-        Some(Ticket { id, name, requester })
+    #[test]
+    fn refused_sessions_and_credentials_are_auth_errors() {
+        let reply = |status: u16, body: &str| Reply {
+            status: StatusCode::from_u16(status).unwrap(),
+            headers: HeaderMap::new(),
+            body: body.as_bytes().to_vec(),
+        };
+        let is_auth = |r: Reply| r.failure("search/Ticket").chain().any(|c| c.is::<AuthError>());
+        let expired = r#"["ERROR_SESSION_TOKEN_INVALID","session_token seems invalid"]"#;
+        assert!(is_auth(reply(401, expired)));
+        assert!(is_auth(reply(400, expired)));
+        assert!(is_auth(reply(403, r#"["ERROR_RIGHT_MISSING","You don't have permission"]"#)));
+        assert!(is_auth(reply(401, "")));
+        assert!(!is_auth(reply(400, r#"["ERROR_BAD_ARRAY","bad criteria"]"#)));
+        assert!(!is_auth(reply(500, "<html>oops</html>")));
+        let e = reply(401, expired).failure("search/Ticket");
+        assert!(matches!(e.downcast_ref::<AuthError>(), Some(AuthError::SessionInvalid(_))));
+        assert!(e.to_string().starts_with("search/Ticket failed: 401 Unauthorized"));
     }
 }
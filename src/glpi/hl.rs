@@ -0,0 +1,245 @@
+//! GLPI 11 high-level REST API (`api.php/v2`), the second implementation behind [`GlpiClient`] (GLPI_API=hl).
+//!
+//! Sign-in is an OAuth2 bearer token from `api.php/token` instead of initSession, and tickets are read from
+//! `Assistance/Ticket` filtered with RSQL instead of `search/Ticket` and search option ids. Only the poll path
+//! is covered (New tickets, tickets by id, the newest ticket); the other calls still need the legacy
+//! apirest.php and fail with a clear error (see [`GlpiClient::send`]).
+
+use super::{GlpiClient, Ticket, TicketFields};
+use crate::filter::{Criterion, FieldRef, SearchType};
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// Ticket "columns" for the high-level API: it has no search option ids, but filters and the initial sync
+/// refer to the id column by [`TicketFields::id`], so the legacy ids are kept as labels.
+pub const FIELDS: TicketFields = TicketFields {
+    id: 2,
+    name: 1,
+    status: 12,
+    requester: None,
+    category: None,
+    priority: None,
+    date: None,
+    assignee: None,
+    group: None,
+    documents: None,
+    entity: None,
+    time_to_own: None,
+    location: None,
+    content: None,
+};
+
+/// Rows per request when listing ids.
+const PAGE: usize = 1000;
+
+impl GlpiClient {
+    /// Tickets matching an RSQL filter, sorted by id (descending), with the total count when GLPI gives it.
+    pub(super) async fn hl_tickets(&mut self, rsql: &str, limit: usize) -> Result<(Vec<Ticket>, Option<i64>)> {
+        self.ensure_session().await?;
+        let url = format!("{}/v2/Assistance/Ticket", hl_root(&self.base_url));
+        let mut query = vec![("sort", "id:desc".to_string()), ("start", "0".into()), ("limit", limit.to_string())];
+        if !rsql.is_empty() {
+            query.push(("filter", rsql.to_string()));
+        }
+        let r = self.send(self.http.get(url).headers(self.hdrs()).query(&query)).await?;
+        if !r.status.is_success() {
+            return Err(r.failure("Assistance/Ticket"));
+        }
+        // Content-Range: 0-49/450
+        let total = r
+            .headers
+            .get("Content-Range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next()?.trim().parse().ok());
+        let rows: Vec<Value> = r.json()?;
+        Ok((rows.iter().filter_map(ticket_from_json).collect(), total))
+    }
+
+    pub(super) async fn hl_new_page(
+        &mut self,
+        filter: &[Criterion],
+        max_rows: usize,
+    ) -> Result<(Vec<Ticket>, Option<i64>)> {
+        // Same page size as the legacy `range=0-N` (inclusive)
+        self.hl_tickets(&rsql(&new_base(filter), filter)?, max_rows + 1).await
+    }
+
+    pub(super) async fn hl_ids_below(&mut self, filter: &[Criterion], below: i64) -> Result<Vec<i64>> {
+        let mut ids = Vec::new();
+        let mut below = below;
+        loop {
+            let base = [new_base(filter), vec![format!("id=lt={below}")]].concat();
+            let (page, _) = self.hl_tickets(&rsql(&base, filter)?, PAGE).await?;
+            ids.extend(page.iter().map(|t| t.id));
+            match page.iter().map(|t| t.id).min() {
+                Some(min) if page.len() == PAGE => below = min,
+                _ => break,
+            }
+        }
+        Ok(ids)
+    }
+
+    pub(super) async fn hl_by_ids(&mut self, filter: &[Criterion], ids: &[i64]) -> Result<Vec<Ticket>> {
+        let mut tickets = Vec::new();
+        for chunk in ids.chunks(crate::config::get().batch_size.max(1)) {
+            let list = chunk.iter().map(i64::to_string).collect::<Vec<_>>().join(",");
+            let base = [new_base(filter), vec![format!("id=in=({list})")]].concat();
+            tickets.extend(self.hl_tickets(&rsql(&base, filter)?, chunk.len()).await?.0);
+        }
+        Ok(tickets)
+    }
+}
+
+/// `api.php` root: GLPI_BASE_URL may be given with or without the `/v2` version. Its token endpoint is
+/// `{root}/token`.
+pub fn hl_root(base_url: &str) -> &str {
+    base_url.trim_end_matches('/').trim_end_matches("/v2")
+}
+
+/// `status.id==1` (New), unless the filter has a status criterion of its own.
+fn new_base(filter: &[Criterion]) -> Vec<String> {
+    if filter.iter().any(|c| c.field == FieldRef::Status) {
+        Vec::new()
+    } else {
+        vec!["status.id==1".to_string()]
+    }
+}
+
+/// Join `base` clauses and the TICKET_FILTER criteria into one RSQL expression (`;` = AND, `,` = OR).
+pub fn rsql(base: &[String], filter: &[Criterion]) -> Result<String> {
+    let mut clauses = base.to_vec();
+    for c in filter {
+        let unsupported = |why: &str| anyhow!("TICKET_FILTER '{c}' with GLPI_API=hl: {why}");
+        let property = match c.field {
+            FieldRef::Status => "status.id",
+            FieldRef::Name => "name",
+            FieldRef::Category => "category.id",
+            FieldRef::Priority => "priority",
+            FieldRef::Date => "date",
+            FieldRef::Entity => "entity.id",
+            FieldRef::Location => "location.id",
+            FieldRef::Id(id) if id == FIELDS.id => "id",
+            // user_recipient is the ticket's writer, not its requester, and team members cannot be filtered by role
+            FieldRef::Requester => return Err(unsupported("requester filters need the legacy API")),
+            FieldRef::Group | FieldRef::Uid(_) | FieldRef::Id(_) => return Err(unsupported("field not available")),
+        };
+        let op = match c.searchtype {
+            SearchType::Contains => "=like=",
+            SearchType::Equals => "==",
+            SearchType::NotEquals => "!=",
+            SearchType::MoreThan => "=gt=",
+            SearchType::LessThan => "=lt=",
+            SearchType::Under => return Err(unsupported("'under' has no equivalent; use equals with each id")),
+        };
+        let alternatives: Vec<String> = c
+            .values()
+            .iter()
+            .map(|v| match c.searchtype {
+                SearchType::Contains => format!("{property}{op}{}", quote(&format!("*{v}*"))),
+                _ => format!("{property}{op}{}", quote(v)),
+            })
+            .collect();
+        clauses.push(match alternatives.len() {
+            1 => alternatives[0].clone(),
+            _ => format!("({})", alternatives.join(",")),
+        });
+    }
+    Ok(clauses.join(";"))
+}
+
+/// RSQL argument: bare when it only holds plain characters, else double-quoted.
+fn quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_alphanumeric() || "-_.*:".contains(c)) {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// A ticket object of the high-level API as the notifier's [`Ticket`]; dates are converted to the legacy
+/// "YYYY-MM-DD HH:MM:SS" server-local form the rest of the code expects.
+fn ticket_from_json(v: &Value) -> Option<Ticket> {
+    let text = |v: &Value| v.as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+    // Linked items come as {"id": .., "name": ..} (full name in `completename` for trees)
+    let name_of = |v: &Value| text(&v["completename"]).or_else(|| text(&v["name"]));
+    let date = |v: &Value| {
+        let raw = text(v)?;
+        Some(match chrono::DateTime::parse_from_rfc3339(&raw) {
+            Ok(at) => crate::clock::server_local(at.to_utc()).format("%Y-%m-%d %H:%M:%S").to_string(),
+            Err(_) => raw,
+        })
+    };
+    let name = text(&v["name"]).unwrap_or_default();
+    let language = crate::lang::of_ticket(&name, &super::plain_text(v["content"].as_str().unwrap_or_default()));
+    Some(crate::demo::ticket(Ticket {
+        id: super::json_i64(&v["id"])?,
+        name,
+        requester: requester(v),
+        category: name_of(&v["category"]),
+        priority: super::json_i64(&v["priority"]),
+        date: date(&v["date"]).or_else(|| date(&v["date_creation"])),
+        documents: None,
+        entity: name_of(&v["entity"]),
+        time_to_own: date(&v["time_to_own"]),
+        location: name_of(&v["location"]),
+        major_incident: None,
+        instance: crate::instance::current_name(),
+        language,
+    }))
+}
+
+/// First requester among the ticket's actors (`team`, role `requester`): `user_recipient` is whoever wrote the
+/// ticket, a technician when it was opened on someone's behalf.
+fn requester(v: &Value) -> Option<String> {
+    v["team"].as_array()?.iter().filter(|m| m["role"] == "requester" && m["type"] == "User").find_map(|m| {
+        let text = |v: &Value| v.as_str().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+        text(&m["display_name"]).or_else(|| text(&m["name"]))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_filters_to_rsql() {
+        let filter =
+            crate::filter::parse("category equals 12,15; name contains 'Printer down'; priority morethan 3").unwrap();
+        assert_eq!(
+            rsql(&["status.id==1".into()], &filter).unwrap(),
+            "status.id==1;(category.id==12,category.id==15);name=like=\"*Printer down*\";priority=gt=3"
+        );
+        let status = crate::filter::parse("status equals 2").unwrap();
+        assert_eq!(rsql(&new_base(&status), &status).unwrap(), "status.id==2");
+        assert_eq!(new_base(&[]), ["status.id==1"]);
+        assert!(rsql(&[], &crate::filter::parse("Ticket.urgency morethan 3").unwrap()).is_err());
+        assert!(rsql(&[], &crate::filter::parse("category under 12").unwrap()).is_err());
+        assert!(rsql(&[], &crate::filter::parse("83 equals 4").unwrap()).is_err());
+        assert!(rsql(&[], &crate::filter::parse("requester equals alice").unwrap()).is_err());
+    }
+
+    #[test]
+    fn maps_ticket_objects() {
+        let v = serde_json::json!({
+            "id": 42, "name": "Printer down", "priority": 4,
+            "user_recipient": {"id": 9, "name": "tech"},
+            "team": [
+                {"type": "User", "id": 9, "name": "tech", "role": "assigned"},
+                {"type": "Group", "id": 4, "name": "Helpdesk", "role": "requester"},
+                {"type": "User", "id": 7, "name": "alice", "display_name": "Alice Martins", "role": "requester"}
+            ],
+            "category": {"id": 3, "name": "Printer", "completename": "Hardware > Printer"},
+            "date": "2026-10-16T07:00:00+00:00"
+        });
+        let t = ticket_from_json(&v).unwrap();
+        assert_eq!(
+            (t.id, t.requester.as_deref(), t.category.as_deref()),
+            (42, Some("Alice Martins"), Some("Hardware > Printer"))
+        );
+        let written = serde_json::json!({"id": 43, "user_recipient": {"id": 9, "name": "tech"}});
+        assert_eq!(ticket_from_json(&written).unwrap().requester, None, "the writer is not the requester");
+        assert_eq!(t.date.as_deref().map(str::len), Some(19));
+        assert!(ticket_from_json(&serde_json::json!({"name": "no id"})).is_none());
+    }
+}
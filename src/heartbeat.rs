@@ -0,0 +1,95 @@
+use crate::cache::{self, CacheStats};
+use crate::pipeline::{self, PipelineStats};
+use crate::state::now_secs;
+use crate::version::BuildInfo;
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// (last_ok, errors), seeded from the previous heartbeat so restarts keep the streak.
+static STREAK: Mutex<Option<(u64, u32)>> = Mutex::new(None);
+
+/// Contents of heartbeat.json, rewritten after every poll.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heartbeat {
+    /// UNIX timestamp of the poll.
+    pub ts: u64,
+    /// Whether the poll succeeded.
+    pub ok: bool,
+    /// Number of tickets notified by the poll.
+    pub new: usize,
+    /// UNIX timestamp of the last successful poll (0 = none yet).
+    #[serde(default)]
+    pub last_ok: u64,
+    /// Failed polls since the last successful one.
+    #[serde(default)]
+    pub errors: u32,
+    /// Size and hit/miss counters of the in-process caches.
+    #[serde(default)]
+    pub caches: Vec<CacheStats>,
+    /// Depth and overflow counters of the notification queue.
+    #[serde(default)]
+    pub pipeline: Option<PipelineStats>,
+    /// Version report of the running binary (fleet inventory).
+    #[serde(default)]
+    pub build: BuildInfo,
+    /// End (UNIX timestamp) of the maintenance window a failed poll fell in; such polls are not errors.
+    #[serde(default)]
+    pub maintenance_until: Option<u64>,
+}
+
+/// Return the path to the heartbeat JSON.
+fn heartbeat_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?;
+    let p = dir.join("GlpiNotifier").join("heartbeat.json");
+    let _ = std::fs::create_dir_all(p.parent().unwrap());
+    Some(p)
+}
+
+/// Write an always-on heartbeat file with UNIX timestamp and last result.
+pub fn write_heartbeat(ok: bool, new_count: usize) {
+    write(ok, new_count, None);
+}
+
+/// Heartbeat of a poll failed during a maintenance window ending at `until`: the error streak is left as it is.
+pub fn write_maintenance(until: u64) {
+    write(false, 0, Some(until));
+}
+
+fn write(ok: bool, new_count: usize, maintenance_until: Option<u64>) {
+    let now = now_secs();
+    let (last_ok, errors) = {
+        let mut streak = STREAK.lock().unwrap();
+        let (last_ok, errors) =
+            streak.get_or_insert_with(|| read_heartbeat().map_or((0, 0), |hb| (hb.last_ok, hb.errors)));
+        if ok {
+            (*last_ok, *errors) = (now, 0);
+        } else if maintenance_until.is_none() {
+            *errors += 1;
+        }
+        (*last_ok, *errors)
+    };
+    if let Some(p) = heartbeat_path() {
+        let hb = Heartbeat {
+            ts: now,
+            ok,
+            new: new_count,
+            last_ok,
+            errors,
+            caches: cache::all_stats(),
+            pipeline: pipeline::stats(),
+            build: BuildInfo::current(),
+            maintenance_until,
+        };
+        if let Ok(payload) = serde_json::to_string(&hb) {
+            let _ = std::fs::write(p, payload);
+        }
+    }
+}
+
+/// Last heartbeat written (by this or a previous run), if readable.
+pub fn read_heartbeat() -> Option<Heartbeat> {
+    let data = std::fs::read(heartbeat_path()?).ok()?;
+    serde_json::from_slice(&data).ok()
+}
@@ -0,0 +1,178 @@
+//! Several GLPI servers polled by one notifier: `[instances.<name>]` tables in config.toml, each with its own
+//! connection, filters and ticket URL template:
+//!
+//! ```toml
+//! [instances.prod]
+//! glpi_base_url = "https://glpi.example.com/apirest.php"
+//! glpi_user_token = "…"
+//! glpi_ticket_url_template = "https://glpi.example.com/front/ticket.form.php?id={id}"
+//!
+//! [instances.subsidiary]
+//! glpi_base_url = "https://helpdesk.subsidiary.example/apirest.php"
+//! glpi_user_token = "…"
+//! ticket_filter = "category under 12"
+//! ```
+//!
+//! Each instance is polled by its own task, with its own seen state (`state-<name>.json`); its toasts carry the
+//! instance name (`[prod] GLPI: New ticket #101`) and their buttons act on its server. Everything else (poll
+//! interval, notifier, mutes...) is shared. Without instances, the top-level GLPI_BASE_URL is polled as usual.
+//!
+//! Instances sign in with user tokens (apirest.php); the OAuth settings stay top-level only.
+
+use crate::exit::ConfigError;
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+/// Settings an instance table may set.
+pub const SETTINGS: &[&str] = &[
+    "GLPI_BASE_URL",
+    "GLPI_APP_TOKEN",
+    "GLPI_USER_TOKEN",
+    "GLPI_TICKET_URL_TEMPLATE",
+    "GLPI_PROFILE_ID",
+    "GLPI_ACTIVE_ENTITY",
+    "GLPI_ENTITY_RECURSIVE",
+    "VERIFY_SSL",
+    "TICKET_FILTER",
+    "MY_LOCATIONS",
+];
+
+/// Settings that belong to one server: an instance never inherits the top-level value (another server's
+/// token, entity or ticket pages).
+const OWN: &[&str] = &[
+    "GLPI_BASE_URL",
+    "GLPI_APP_TOKEN",
+    "GLPI_USER_TOKEN",
+    "GLPI_TICKET_URL_TEMPLATE",
+    "GLPI_PROFILE_ID",
+    "GLPI_ACTIVE_ENTITY",
+];
+
+/// One GLPI server of `[instances]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub name: String,
+    settings: BTreeMap<String, String>,
+}
+
+static INSTANCES: OnceCell<Vec<Instance>> = OnceCell::new();
+
+tokio::task_local! {
+    /// The instance the current poll loop serves.
+    static CURRENT: &'static Instance;
+}
+
+/// Read the `[instances]` tables of config.toml (`doc`); names are lowercase letters, digits, `-` and `_`.
+pub fn parse(doc: &toml::Table) -> Result<Vec<Instance>> {
+    let Some(tables) = doc.get("instances") else { return Ok(Vec::new()) };
+    let bad = |why: String| ConfigError(format!("[instances] {why}"));
+    let tables = tables.as_table().ok_or_else(|| bad("must hold [instances.<name>] tables".into()))?;
+    tables
+        .iter()
+        .map(|(name, table)| {
+            if name.is_empty()
+                || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_".contains(c))
+            {
+                return Err(bad(format!("'{name}': names take lowercase letters, digits, - and _")).into());
+            }
+            let table = table.as_table().ok_or_else(|| bad(format!("'{name}' must be a table")))?;
+            let mut settings = BTreeMap::new();
+            for (key, value) in table {
+                let key = key.to_uppercase();
+                if !SETTINGS.contains(&key.as_str()) {
+                    return Err(bad(format!("{name}: {key} cannot be set per instance")).into());
+                }
+                let value = crate::config::setting_value(&key, value).map_err(|e| bad(format!("{name}: {e}")))?;
+                settings.insert(key, value);
+            }
+            for key in ["GLPI_BASE_URL", "GLPI_USER_TOKEN"] {
+                if !settings.contains_key(key) {
+                    return Err(bad(format!("{name}: missing {key}")).into());
+                }
+            }
+            Ok(Instance { name: name.clone(), settings })
+        })
+        .collect()
+}
+
+/// Keep the instances of config.toml (at most once).
+pub fn set(instances: Vec<Instance>) {
+    let _ = INSTANCES.set(instances);
+}
+
+/// The configured instances; empty when only the top-level server is polled.
+pub fn all() -> &'static [Instance] {
+    INSTANCES.get().map(Vec::as_slice).unwrap_or_default()
+}
+
+pub fn get(name: &str) -> Option<&'static Instance> {
+    all().iter().find(|i| i.name == name)
+}
+
+/// The instance of the running poll loop, if any.
+pub fn current() -> Option<&'static Instance> {
+    CURRENT.try_with(|i| *i).ok()
+}
+
+pub fn current_name() -> Option<String> {
+    current().map(|i| i.name.clone())
+}
+
+/// Setting `key`: the current instance's value, else the top-level one (except for [`OWN`] settings).
+pub fn var(key: &str) -> Option<String> {
+    match current() {
+        Some(i) => i.value(key).or_else(|| (!OWN.contains(&key)).then(|| std::env::var(key).ok()).flatten()),
+        None => std::env::var(key).ok(),
+    }
+}
+
+impl Instance {
+    /// Value set in this instance's table.
+    pub fn value(&self, key: &str) -> Option<String> {
+        self.settings.get(key).cloned()
+    }
+
+    /// The settings of this instance's table, for the settings check.
+    pub fn settings(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.settings.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Run `f` as this instance's poll loop.
+    pub fn scope<F: Future>(&'static self, f: F) -> impl Future<Output = F::Output> {
+        CURRENT.scope(self, f)
+    }
+
+    /// Run `f` with this instance's settings (e.g. to build its connection).
+    pub fn sync_scope<R>(&'static self, f: impl FnOnce() -> R) -> R {
+        CURRENT.sync_scope(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instances_come_from_config_tables() {
+        let doc: toml::Table = toml::from_str(
+            "poll_seconds = 30\n\
+             [instances.prod]\nglpi_base_url = 'https://glpi/apirest.php'\nglpi_user_token = 'u1'\nverify_ssl = false\n\
+             [instances.sub-1]\nGLPI_BASE_URL = 'https://sub/apirest.php'\nglpi_user_token = 'u2'\n",
+        )
+        .unwrap();
+        let instances = parse(&doc).unwrap();
+        assert_eq!(instances.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), ["prod", "sub-1"]);
+        assert_eq!(instances[0].value("VERIFY_SSL").as_deref(), Some("false"));
+        assert_eq!(instances[1].value("GLPI_BASE_URL").as_deref(), Some("https://sub/apirest.php"));
+
+        let parse_str = |text: &str| parse(&toml::from_str(text).unwrap());
+        assert!(parse_str("[instances.prod]\nglpi_base_url = 'https://glpi'\n").is_err()); // no token
+        assert!(parse_str("[instances.prod]\nglpi_base_url = 'x'\nglpi_user_token = 'u'\npoll_seconds = 5\n").is_err());
+        assert!(parse_str("[instances.Prod]\nglpi_base_url = 'x'\nglpi_user_token = 'u'\n").is_err());
+        assert!(parse_str("instances = 1\n").is_err());
+        assert!(parse_str("poll_seconds = 1\n").unwrap().is_empty());
+    }
+}
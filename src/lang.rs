@@ -0,0 +1,92 @@
+//! Ticket language (LANGUAGES): every ticket read gets the language of its title and the start of its
+//! description, detected offline (whatlang) among the languages the desk works in, e.g. `LANGUAGES=fra,eng,deu`;
+//! the fewer the candidates, the more reliable the guess on a short title. The language travels with the ticket
+//! (`language` of MQTT events and status.json, `explain`), so a French ticket can be routed to the French
+//! channel by a Home Assistant automation.
+//!
+//! MY_LANGUAGES routes tickets to the technicians who speak them: a ticket detected in another language is
+//! marked seen without a toast. Tickets whose language is not detected with confidence reach everyone.
+
+use crate::exit::ConfigError;
+
+use anyhow::Result;
+use whatlang::{Detector, Lang};
+
+/// Below this confidence a ticket has no language (too short, mixed languages...).
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Languages separated by `,` or spaces: ISO 639-3 codes (`fra`) or English names (`French`).
+pub fn parse(raw: &str) -> Result<Vec<Lang>> {
+    raw.split([',', ' '])
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            Lang::from_code(s.to_lowercase())
+                .or_else(|| Lang::all().iter().copied().find(|l| l.eng_name().eq_ignore_ascii_case(s)))
+                .ok_or_else(|| {
+                    ConfigError(format!("'{s}' is not a language (ISO 639-3 code like fra, or English name)"))
+                })
+        })
+        .collect::<Result<_, _>>()
+        .map_err(Into::into)
+}
+
+/// Language of `text` among `candidates`, when detected with confidence.
+pub fn detect(text: &str, candidates: &[Lang]) -> Option<Lang> {
+    let info = Detector::with_allowlist(candidates.to_vec()).detect(text)?;
+    (info.confidence() >= MIN_CONFIDENCE).then(|| info.lang())
+}
+
+/// ISO 639-3 code of a ticket's language, from its title and description (None without LANGUAGES).
+pub fn of_ticket(title: &str, description: &str) -> Option<String> {
+    if crate::config::get().languages.is_empty() {
+        return None;
+    }
+    detect(&format!("{title}. {description}"), &crate::config::get().languages).map(|l| l.code().to_string())
+}
+
+/// Whether the description is needed (LANGUAGES set), to read its column.
+pub fn enabled() -> bool {
+    !crate::config::get().languages.is_empty()
+}
+
+/// True when a ticket in `language` is for someone speaking `mine` (everyone without MY_LANGUAGES, or when the
+/// language is unknown).
+pub fn speaks(mine: &[Lang], language: Option<&str>) -> bool {
+    match language.and_then(Lang::from_code) {
+        Some(lang) => mine.is_empty() || mine.contains(&lang),
+        None => true,
+    }
+}
+
+/// Why a ticket is not toasted here because of its language (MY_LANGUAGES), if it is not.
+pub fn not_mine(language: Option<&str>) -> Option<String> {
+    (!speaks(&crate::config::get().my_languages, language)).then(|| {
+        let names: Vec<&str> = crate::config::get().my_languages.iter().map(|l| l.eng_name()).collect();
+        let language = language.and_then(Lang::from_code).map_or("?", Lang::eng_name);
+        format!("written in {language}, not in MY_LANGUAGES ({})", names.join(", "))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_are_detected_among_the_desk_ones() {
+        let desk = parse("fra, eng deu").unwrap();
+        assert_eq!(desk, [Lang::Fra, Lang::Eng, Lang::Deu]);
+        assert_eq!(parse("French,spanish").unwrap(), [Lang::Fra, Lang::Spa]);
+        assert!(parse("fr").is_err());
+
+        let french = "Imprimante bloquée. L'imprimante du deuxième étage ne répond plus depuis ce matin";
+        assert_eq!(detect(french, &desk), Some(Lang::Fra));
+        let german = "Drucker defekt. Der Drucker im zweiten Stock funktioniert seit heute Morgen nicht mehr";
+        assert_eq!(detect(german, &desk), Some(Lang::Deu));
+        assert_eq!(detect("VPN", &desk), None);
+
+        assert!(speaks(&[Lang::Fra], Some("fra")));
+        assert!(!speaks(&[Lang::Fra], Some("deu")));
+        assert!(speaks(&[Lang::Fra], None));
+        assert!(speaks(&[], Some("deu")));
+    }
+}
@@ -1,120 +1,493 @@
+mod accessibility;
+mod bench;
+mod cache;
+mod calendar;
+mod check;
+mod cli;
+mod clock;
+mod commands;
+mod config;
+mod control;
+mod crash;
+mod demo;
+mod depth;
+mod diff;
+mod exit;
+mod filter;
+mod flyout;
+mod focus;
 mod glpi;
+mod heartbeat;
+mod instance;
+mod lang;
+mod maintenance;
+mod metrics;
+mod mqtt;
+mod notify;
+mod oauth;
+mod phone;
+mod pipeline;
+mod refs;
+mod scrub;
+mod service;
+mod severity;
+mod smoke;
+mod sort;
 mod state;
+mod systemd;
+mod throttle;
+mod tickets;
+mod toast;
+mod trace;
+mod transition;
+mod tray;
+mod version;
+mod watchdog;
+mod webhook;
+mod widget;
+mod wizard;
 
-use crate::glpi::{GlpiClient, Ticket};
-use crate::state::{load_state, save_state, SeenState};
+use crate::config::Connection;
+use crate::exit::{ConfigError, Exit};
+use crate::filter::{Criterion, FieldRef, SearchType};
+use crate::glpi::{GlpiClient, OpenTicket, SessionScope, Severity, Ticket, TicketFields};
+use crate::heartbeat::{read_heartbeat, write_heartbeat};
+use crate::notify::Notifier;
+use crate::pipeline::Delivery;
+use crate::state::{load_state, now_secs, save_state, MuteTarget, SeenState};
+use crate::toast::{ensure_app_id, open_ticket, ticket_url, ToastAction, ENTITY_URL_TEMPLATES, URL_TEMPLATE};
+use crate::watchdog::LIVENESS;
 
-use anyhow::{anyhow, Result};
-use dotenvy::dotenv;
-use log::{error, info, warn};
-use once_cell::sync::OnceCell;
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, BTreeSet};
 use std::env;
-use std::process::Command;
-use std::{thread, time::Duration};
-
-// URL template (e.g. https://your-glpi/front/ticket.form.php?id={id})
-static URL_TEMPLATE: OnceCell<Option<String>> = OnceCell::new();
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 
 #[tokio::main(flavor = "multi_thread")]
-async fn main() -> Result<()> {
-    env_logger::init();
-    dotenv().ok(); // loads .env if present in current directory
+async fn main() -> ExitCode {
+    let matches = match cli::command().try_get_matches() {
+        Ok(m) => m,
+        Err(e) => return cli::usage_error(e),
+    };
+    // Global options, wherever they were given (clap copies them down to the subcommand)
+    let global = matches.subcommand().map_or(&matches, |(_, sub)| sub);
+    let log_file = (cfg!(windows) && matches.get_flag("service")).then(service::log_path).flatten();
+    crash::init_logging(global.get_one::<String>("log-level").map(String::as_str), log_file.as_deref());
+    crash::install_panic_hook();
+    // --env-file / GLPI_NOTIFIER_ENV, else .env in the current directory (or a parent); config.toml below them
+    let env_files = global.get_many::<String>("env-file").into_iter().flatten().map(PathBuf::from).collect();
+    let config_file = global.get_one::<PathBuf>("config").map(PathBuf::as_path);
+    if let Err(e) = config::load_env_files(env_files).and_then(|()| config::load_config_file(config_file)) {
+        error!("{e:#}");
+        return Exit::from_error(&e).into();
+    }
+    // --poll-interval outranks POLL_SECONDS from every source
+    if let Some(secs) = global.get_one::<u64>("poll-interval") {
+        env::set_var("POLL_SECONDS", secs.to_string());
+    }
+    if global.get_flag("demo") {
+        env::set_var("DEMO", "true");
+    }
+
+    // Windows Service: the SCM runs the poller on its own thread; this returns once the service stopped
+    if matches.get_flag("service") {
+        return tokio::task::block_in_place(service::start).unwrap_or_else(|e| {
+            error!("{e:#}");
+            Exit::from_error(&e).into()
+        });
+    }
 
+    // Monitoring plugin: Nagios exit codes (0-3) instead of the usual ones
+    if let Some(("check", _)) = matches.subcommand() {
+        return check::run(&subcommand_args("check"));
+    }
+
+    match run(&matches).await {
+        Ok(()) => Exit::Ok.into(),
+        Err(e) => {
+            let code = Exit::from_error(&e);
+            match trace::last_correlation_id() {
+                Some(id) => error!("{e:#} (exit code {}, last X-Correlation-Id {id})", code as u8),
+                None => error!("{e:#} (exit code {})", code as u8),
+            }
+            code.into()
+        }
+    }
+}
+
+/// Arguments after the subcommand `name`, without the global options, for the subcommands that read their own.
+fn subcommand_args(name: &str) -> Vec<String> {
+    config::args().into_iter().skip_while(|a| a != name).skip(1).collect()
+}
+
+/// Run the one-shot command `name`; `None` for the poller (`run`) and `test-toast`.
+async fn one_shot(name: &str, sub: &ArgMatches, args: Vec<String>) -> Option<Result<()>> {
+    let result = match name {
+        "mutes" => commands::mutes(args.first().map(String::as_str)),
+        "new-ticket" => commands::new_ticket(&args).await,
+        "search" => commands::search(&args).await,
+        "queue" => commands::queue(&args).await,
+        "bench-search" => bench::run(&args).await,
+        "stop-timer" => commands::stop_timer(&args).await,
+        "reset-seen" => commands::reset_seen(),
+        "session" => commands::session().await,
+        "sign-in" => commands::sign_in().await,
+        "sign-out" => commands::sign_out().await,
+        "doctor" => commands::doctor().await,
+        "stats" => commands::stats(&args),
+        "status" => commands::ctl(&["status".to_string()].into_iter().chain(args).collect::<Vec<_>>()),
+        "focus" => commands::focus(&args),
+        "focus-assist" => commands::focus_assist(),
+        "config" => commands::config(&args),
+        "configure-filters" => wizard::run().await,
+        "smoke-test" => smoke::run().await,
+        "timeline" => commands::timeline(&args).await,
+        "ctl" => commands::ctl(&args),
+        "explain" => commands::explain(&args).await,
+        "install" => commands::install(),
+        "install-service" => service::install(sub.get_one::<String>("account").map(String::as_str)),
+        "uninstall-service" => service::uninstall(),
+        "completions" => cli::completions(&args),
+        "man" => cli::man(),
+        _ => return None,
+    };
+    Some(result)
+}
+
+async fn run(matches: &ArgMatches) -> Result<()> {
     // Read optional link template for the button
     let _ = URL_TEMPLATE.set(env::var("GLPI_TICKET_URL_TEMPLATE").ok());
+    config::check_settings()?;
 
-    // Best effort: create Start Menu shortcut (AUMID) so SnoreToast buttons show up
-    ensure_snore_shortcut("GlpiNotifier");
+    if matches.get_flag("version") {
+        return version::print(matches.get_flag("json"));
+    }
+    let (name, sub) = matches.subcommand().unwrap_or(("run", matches));
+    let args = subcommand_args(name);
+    // --instance: the one-shot commands use that instance's connection and state-<name>.json
+    let instance = match sub.get_one::<String>("instance") {
+        Some(wanted) => Some(instance::get(wanted).ok_or_else(|| match instance::all() {
+            [] => ConfigError(format!("--instance {wanted}: config.toml has no [instances]")),
+            all => ConfigError(format!(
+                "--instance {wanted}: no such instance (configured: {})",
+                all.iter().map(|i| i.name.as_str()).collect::<Vec<_>>().join(", ")
+            )),
+        })?),
+        None => None,
+    };
+    let one_shot = one_shot(name, sub, args);
+    let done = match instance {
+        Some(i) => i.scope(one_shot).await,
+        None => one_shot.await,
+    };
+    if let Some(result) = done {
+        return result;
+    }
+    if instance.is_some() && name == "run" {
+        return Err(
+            ConfigError("--instance applies to the one-shot commands; the poller polls every instance".into()).into()
+        );
+    }
 
-    // Manual test of a toast
-    if env::args().any(|a| a == "--test-toast") {
-        let dummy =
-            Ticket { id: 12345, name: "Notification test".to_string(), requester: Some("Example User".to_string()) };
-        if let Err(e) = show_toast(&dummy) {
-            eprintln!("Toast error: {e:#}");
-        }
+    // Best effort: register the toast AUMID (registry for native toasts, Start Menu shortcut for SnoreToast)
+    ensure_app_id();
+
+    // Manual test of a notification (NOTIFIER backend); `--test-toast` kept for existing shortcuts
+    if name == "test-toast" || matches.get_flag("test-toast") {
+        test_toast();
         return Ok(());
     }
 
-    // Configuration from .env
-    let base_url = env::var("GLPI_BASE_URL").unwrap_or_default().trim().trim_end_matches('/').to_string();
-    let app_token = env::var("GLPI_APP_TOKEN").ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
-    let user_token = env::var("GLPI_USER_TOKEN").unwrap_or_default().trim().to_string();
-    let poll_secs: u64 = env::var("POLL_SECONDS").ok().and_then(|s| s.trim().parse().ok()).unwrap_or(60);
-    let verify_ssl = env::var("VERIFY_SSL").map(|s| s.to_lowercase() == "true").unwrap_or(true);
-    let first_run_notify = env::var("FIRST_RUN_NOTIFY").map(|s| s.to_lowercase() == "true").unwrap_or(false);
-    let debug_list = env::var("DEBUG_LIST").map(|s| s.to_lowercase() == "true").unwrap_or(false);
+    // Configuration from the environment, env files and config.toml; one connection per [instances] table
+    let conns: Vec<(Option<&'static instance::Instance>, Connection)> = match instance::all() {
+        [] => vec![(None, Connection::from_env()?)],
+        all => all
+            .iter()
+            .map(|i| Ok((Some(i), i.sync_scope(Connection::from_env).with_context(|| format!("instance {}", i.name))?)))
+            .collect::<Result<_>>()?,
+    };
+    let cfg = config::get();
+    let (poll_secs, first_run_notify, debug_list) = (cfg.poll_seconds, cfg.first_run_notify, cfg.debug_list);
 
-    if base_url.is_empty() || user_token.is_empty() {
-        error!("Please set GLPI_BASE_URL and GLPI_USER_TOKEN in .env (no quotes, no extra spaces).");
-        return Ok(());
+    // --once: a single poll, for Task Scheduler or cron instead of the resident loop
+    let once = sub.get_flag("once");
+    // --max-runtime <secs|30m|8h>: stop cleanly (exit 0) once elapsed
+    let deadline = match sub.get_one::<String>("max-runtime") {
+        Some(raw) => {
+            let secs = parse_duration_secs(raw)
+                .ok_or_else(|| ConfigError(format!("--max-runtime: invalid duration '{raw}' (e.g. 3600, 30m, 8h)")))?;
+            Some(Instant::now() + Duration::from_secs(secs))
+        }
+        None => None,
+    };
+
+    // --kiosk: read-only wallboard profile (also KIOSK=true), switchable later with `ctl kiosk on|off`
+    let kiosk = sub.get_flag("kiosk") || cfg.kiosk;
+    control::init_kiosk(kiosk)?;
+    if kiosk {
+        info!("Kiosk mode: toasts without buttons, nothing written to GLPI");
     }
 
-    info!("GLPI notifier starting (interval: {}s)", poll_secs);
+    if once {
+        info!("GLPI notifier starting (single poll)");
+    } else {
+        info!("GLPI notifier starting (interval: {}s)", poll_secs);
+    }
+    if !instance::all().is_empty() {
+        info!("Instances: {}", instance::all().iter().map(|i| i.name.as_str()).collect::<Vec<_>>().join(", "));
+    }
+    crash::upload_pending().await;
+    systemd::listen_for_stop_signals();
+    // No tray for a single poll, nor in session 0 (Windows Service), which has no desktop
+    if !once && !matches.get_flag("service") {
+        tray::start();
+    }
+    // Push mode: GLPI webhooks wake the poll loops between polls
+    if !once {
+        webhook::start()?;
+    }
 
-    main_loop_with_flags(
-        || false,
-        first_run_notify,
-        debug_list,
-        base_url,
-        app_token,
-        user_token,
-        poll_secs,
-        verify_ssl,
-    )
-    .await;
+    let stop = move || {
+        systemd::stop_requested()
+            || service::stop_requested()
+            || tray::exit_requested()
+            || watchdog::restart_requested()
+            || deadline.is_some_and(|d| Instant::now() >= d)
+    };
+    // The watchdog restarts the poller if it stops completing ticks (hung HTTP, deadlock)
+    watchdog::supervise(poll_secs, || {
+        // One poll loop per instance; one failing leaves the others polling
+        let mut loops = tokio::task::JoinSet::new();
+        for (instance, conn) in &conns {
+            let poll = main_loop_with_flags(
+                stop,
+                first_run_notify,
+                debug_list,
+                conn.base_url.clone(),
+                conn.app_token.clone(),
+                conn.user_token.clone(),
+                poll_secs,
+                conn.verify_ssl,
+                conn.scope,
+                conn.api.clone(),
+                conn.language.clone(),
+                once,
+            );
+            match *instance {
+                Some(instance) => loops.spawn(async move { (Some(instance), instance.scope(poll).await) }),
+                None => loops.spawn(async move { (None, poll.await) }),
+            };
+        }
+        tokio::spawn(async move {
+            // The first failure is the exit code once every loop is over
+            let mut failed = None;
+            while let Some(done) = loops.join_next().await {
+                match done? {
+                    (_, Ok(())) => {}
+                    (None, Err(e)) => failed = failed.or(Some(e)),
+                    (Some(instance), Err(e)) => {
+                        error!("Instance {}: {e:#}", instance.name);
+                        failed = failed.or(Some(e));
+                    }
+                }
+            }
+            failed.map_or(Ok(()), Err)
+        })
+    })
+    .await
+}
 
-    Ok(())
+/// `test-toast`: a sample ticket through the NOTIFIER backend; Open opens its page.
+fn test_toast() {
+    let dummy = Ticket {
+        id: 12345,
+        name: "Notification test".to_string(),
+        requester: Some("Example User".to_string()),
+        category: Some("Test > Notifier".to_string()),
+        priority: Some(3),
+        date: None,
+        documents: None,
+        entity: None,
+        time_to_own: None,
+        location: None,
+        major_incident: None,
+        instance: None,
+        language: None,
+    };
+    match notify::from_env().notify(&dummy) {
+        Ok(Some(ToastAction::Open)) => open_ticket(&dummy),
+        Ok(_) => {}
+        Err(e) => eprintln!("Notification error: {e:#}"),
+    }
+}
+
+/// Parse a duration given as plain seconds or with an `s`/`m`/`h` suffix.
+fn parse_duration_secs(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    let (num, mult) = match raw.chars().last()? {
+        's' => (&raw[..raw.len() - 1], 1),
+        'm' => (&raw[..raw.len() - 1], 60),
+        'h' => (&raw[..raw.len() - 1], 3600),
+        _ => (raw, 1),
+    };
+    num.trim().parse::<u64>().ok().map(|n| n * mult)
 }
 
 // Main loop used by the console build (and previously by the Service build).
+// Returns Err only for setup failures (client, auth, field resolution); tick errors are retried,
+// except with `once` (one tick, then return), where they are the result.
 
 #[allow(clippy::too_many_arguments)]
 pub async fn main_loop_with_flags<F: Fn() -> bool>(
     stop_flag: F,
-    mut first_run_notify: bool,
+    first_run_notify: bool,
     debug_list: bool,
     base_url: String,
     app_token: Option<String>,
     user_token: String,
     poll_secs: u64,
     verify_ssl: bool,
-) {
+    scope: SessionScope,
+    api: glpi::Api,
+    language: Option<String>,
+    once: bool,
+) -> Result<()> {
     // Attempt to read the link template even if running under Scheduled Task
     let _ = URL_TEMPLATE.get_or_init(|| env::var("GLPI_TICKET_URL_TEMPLATE").ok());
-    ensure_snore_shortcut("GlpiNotifier");
+    ensure_app_id();
+
+    let filter = filter::from_env()?;
+    if !filter.is_empty() {
+        info!("Ticket filter: {}", filter.iter().map(ToString::to_string).collect::<Vec<_>>().join(" AND "));
+    }
+
+    // Read before this run overwrites it: was the notifier down long enough to summarize the backlog?
+    let offline_since = offline_since(read_heartbeat().map(|hb| hb.ts));
 
     let mut client = match GlpiClient::new(base_url, app_token, user_token, verify_ssl).await {
-        Ok(c) => c,
+        Ok(c) => c.with_scope(scope).with_api(api).with_language(language),
         Err(e) => {
-            error!("Failed to create GLPI client: {e:#}");
             write_heartbeat(false, 0);
-            return;
+            return Err(e.context("Failed to create GLPI client"));
         }
     };
 
-    // Resolve field ids (includes requester)
-    let (id_id, name_id, status_id, requester_id) = match async {
-        client.init_session().await?;
-        let ids = client
-            .resolve_field_ids(&["Ticket.id", "Ticket.name", "Ticket.status", "Ticket._users_id_recipient"])
-            .await?;
-        let id_id = *ids.get("Ticket.id").ok_or_else(|| anyhow!("field id not found"))?;
-        let name_id = *ids.get("Ticket.name").ok_or_else(|| anyhow!("field name not found"))?;
-        let status_id = *ids.get("Ticket.status").ok_or_else(|| anyhow!("field status not found"))?;
-        let requester_id = ids.get("Ticket._users_id_recipient").copied();
-        Ok::<(i64, i64, i64, Option<i64>), anyhow::Error>((id_id, name_id, status_id, requester_id))
-    }
-    .await
-    {
-        Ok(v) => v,
-        Err(e) => {
-            error!("Failed to resolve fields: {e:#}");
-            write_heartbeat(false, 0);
-            return;
+    info!("App-Token: {}", if client.has_app_token() { "sent (GLPI_APP_TOKEN)" } else { "none" });
+
+    // Resolve field ids (includes requester and category)
+    let mut token_checks = 0;
+    // Tray menu commands this loop has served (Exit is honoured while GLPI is still unreachable)
+    let mut tray_seen = 0;
+    // End of the maintenance window the last failed attempt or poll fell in
+    let mut in_maintenance: Option<u64> = None;
+    let fields = loop {
+        match async {
+            client.init_session().await?;
+            client.resolve_ticket_fields().await
+        }
+        .await
+        {
+            Ok(v) => break v,
+            Err(e) => {
+                // Started during a maintenance window (the GLPI ones as read before the downtime): wait for GLPI
+                let planned = load_state().map(|s| s.maintenance).unwrap_or_default();
+                let window = maintenance::until(&planned).filter(|_| !once);
+                if let Some(until) = window {
+                    if in_maintenance != Some(until) {
+                        info!(
+                            "GLPI unavailable during its maintenance window (until {}): {e:#}. Waiting for it.",
+                            maintenance::local(until)
+                        );
+                    }
+                    heartbeat::write_maintenance(until);
+                } else {
+                    write_heartbeat(false, 0);
+                    // Token rotated on the server: wait (TOKEN_RELOAD_CHECKS polls) for the new one to land in .env
+                    let max_checks = config::get().token_reload_checks;
+                    let rejected = e.chain().any(|c| c.is::<glpi::AuthError>());
+                    if once || !rejected || token_checks >= max_checks || config::reload_tokens().is_none() {
+                        return Err(e.context("Failed to resolve fields"));
+                    }
+                    warn!("{e:#}; waiting for a new token in .env ({}/{max_checks})", token_checks + 1);
+                    token_checks += 1;
+                }
+                in_maintenance = window;
+                for _ in 0..poll_secs {
+                    // A tray command ends the wait: Exit stops, Poll now retries at once
+                    let woken = tray::take_commands(&mut tray_seen);
+                    if stop_flag() {
+                        return Ok(());
+                    }
+                    if woken {
+                        break;
+                    }
+                    LIVENESS.touch();
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+                if let Some((user, app)) = config::reload_tokens() {
+                    if client.rotate_tokens(user, app) {
+                        info!("GLPI token changed in .env, signing in again");
+                    }
+                }
+            }
         }
     };
 
+    match client.session_info().await {
+        Ok(s) => {
+            info!(
+                "GLPI session: user {} (#{}), profile {} (#{}), entity {} (#{}){}",
+                s.user_name,
+                s.user_id,
+                s.profile_name,
+                s.profile_id,
+                s.entity_name,
+                s.entity_id,
+                if s.recursive { " + sub-entities" } else { "" }
+            );
+            if let Some(wanted) = client.language_ignored(&s) {
+                warn!(
+                    "GLPI answers in {} although {wanted} was asked for (GLPI_LANGUAGE): set the language of {} \
+                     in GLPI (My settings) for categories and locations in {wanted}",
+                    s.language, s.user_name
+                );
+            }
+        }
+        Err(e) => warn!("Could not read GLPI session info: {e:#}"),
+    }
+    match client.priority_values().await {
+        Ok(values) => {
+            let (mapped, unmapped) = severity::describe(&values);
+            info!("Priority severities: {mapped}");
+            if !unmapped.is_empty() {
+                warn!("Priorities {unmapped:?} are outside the default 1-6 scale; map them with SEVERITY_MAP");
+            }
+        }
+        Err(e) => warn!("Could not read the GLPI priority matrix, assuming the default 1-6 scale: {e:#}"),
+    }
+
+    // Entity templates are for the top-level server; instances use their own GLPI_TICKET_URL_TEMPLATE
+    if ENTITY_URL_TEMPLATES.get().is_none() && instance::current().is_none() {
+        let mut templates = Vec::new();
+        for (id, tpl) in &config::get().entity_url_templates {
+            match client.entity_name(*id).await {
+                Ok(name) => templates.push((name, tpl.clone())),
+                Err(e) => warn!("GLPI_TICKET_URL_TEMPLATES: entity {id} skipped: {e:#}"),
+            }
+        }
+        let _ = ENTITY_URL_TEMPLATES.set(templates);
+    }
+
+    calendar::init(&mut client).await;
+
+    // Authenticated and fields resolved: tell systemd (Type=notify) we are up
+    systemd::sd_notify("READY=1");
+    service::ready();
+
     let mut st: SeenState = match load_state() {
         Ok(s) => s,
         Err(e) => {
@@ -122,7 +495,21 @@ pub async fn main_loop_with_flags<F: Fn() -> bool>(
             SeenState::default()
         }
     };
-    let mut first_run = st.seen_ticket_ids.is_empty();
+    apply_state_edits(&mut st);
+    tickets::load();
+    let repeat = config::get().repeat.clone();
+    let notify_restored = config::get().notify_restored;
+    let mut flags = StartupFlags {
+        first_run: st.seen_ticket_ids.is_empty() || st.sync_below.is_some(),
+        first_run_notify,
+        offline_since,
+        notify_restored,
+        repeat,
+    };
+    let notifier = pipeline::dispatcher();
+    let mut was_paused = false;
+    // Wake-ups this loop has served: other instances' loops serve the same ones
+    let mut poll_served = control::load().poll_served;
 
     loop {
         if stop_flag() {
@@ -130,53 +517,219 @@ pub async fn main_loop_with_flags<F: Fn() -> bool>(
             break;
         }
 
-        match tick(
-            &mut client,
-            id_id,
-            name_id,
-            status_id,
-            requester_id,
-            &mut st,
-            &mut first_run,
-            &mut first_run_notify,
-            debug_list,
-        )
-        .await
-        {
-            Ok(new_count) => {
-                write_heartbeat(true, new_count);
+        let paused = control::load().paused();
+        if paused != was_paused {
+            info!("{}", if paused { "Polling paused (ctl pause)" } else { "Polling resumed" });
+            tray::update(tray::Update::Paused(paused));
+            was_paused = paused;
+        }
+        if paused && once {
+            info!("Polling paused (ctl pause); nothing to do");
+            let _ = client.kill_session().await;
+            return Ok(());
+        }
+        if paused {
+            LIVENESS.touch();
+            apply_deliveries(&mut client, &mut st).await;
+            flags.first_run |= apply_state_edits(&mut st);
+            tray::take_commands(&mut tray_seen);
+            webhook::take_event();
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+
+        let started = Instant::now();
+        let result = tick(&mut client, &fields, &filter, &mut st, &mut flags, debug_list, notifier).await;
+        metrics::record(started.elapsed(), result.as_ref().ok().map(|p| (p.queue, p.new)));
+        match result {
+            Ok(polled) => {
+                if in_maintenance.take().is_some() {
+                    info!("GLPI is back after its maintenance window");
+                }
+                write_heartbeat(true, polled.new);
+                LIVENESS.tick_done(&client);
             }
-            Err(e) => {
-                warn!("Tick error: {e:#}. Will re-authenticate on next iteration.");
+            Err(e) if once => {
                 write_heartbeat(false, 0);
+                widget::publish(None, &st);
                 let _ = client.kill_session().await;
+                return Err(e.context("Poll failed"));
+            }
+            Err(e) => {
+                let id = trace::last_correlation_id().unwrap_or_default();
+                let window = maintenance::until(&st.maintenance);
+                match window {
+                    // Planned downtime: said once per window, and not an error for the heartbeat or the tray
+                    Some(until) if in_maintenance != Some(until) => info!(
+                        "GLPI unavailable during its maintenance window (until {}): {e:#}. Alerts resume after it.",
+                        maintenance::local(until)
+                    ),
+                    Some(_) => debug!("Tick error during the maintenance window: {e:#} [X-Correlation-Id: {id}]"),
+                    None => {
+                        warn!("Tick error: {e:#} [X-Correlation-Id: {id}]. Will re-authenticate on next iteration.")
+                    }
+                }
+                in_maintenance = window;
+                if e.chain().any(|c| c.is::<glpi::AuthError>()) {
+                    if let Some((user, app)) = config::reload_tokens() {
+                        if client.rotate_tokens(user, app) {
+                            info!("GLPI token changed in .env, signing in again");
+                        }
+                    }
+                }
+                match window {
+                    Some(until) => heartbeat::write_maintenance(until),
+                    None => {
+                        write_heartbeat(false, 0);
+                        widget::publish(None, &st);
+                    }
+                }
+                let _ = client.kill_session().await;
+                LIVENESS.touch();
             }
         }
 
+        if once {
+            // Deliver this poll's notifications (and buttons pressed meanwhile) before signing out
+            if !tokio::task::block_in_place(|| notifier.flush(ONCE_FLUSH)) {
+                warn!("Notifications still on screen after {}s; exiting anyway", ONCE_FLUSH.as_secs());
+            }
+            apply_deliveries(&mut client, &mut st).await;
+            let _ = client.kill_session().await;
+            if !tokio::task::block_in_place(|| mqtt::flush(Duration::from_secs(15))) {
+                warn!("MQTT broker still not reached; exiting anyway");
+            }
+            info!("Single poll done (--once)");
+            return Ok(());
+        }
+
         for _ in 0..poll_secs {
             if stop_flag() {
                 let _ = client.kill_session().await;
                 break;
             }
-            thread::sleep(Duration::from_secs(1));
+            apply_deliveries(&mut client, &mut st).await;
+            flags.first_run |= apply_state_edits(&mut st);
+            if control::take_poll_request(&mut poll_served) {
+                info!("Poll requested (ctl poll-now)");
+                break;
+            }
+            if tray::take_commands(&mut tray_seen) || webhook::take_event() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
         }
     }
+
+    if let Err(e) = save_state(&st) {
+        warn!("Could not save the state on exit: {e:#}");
+    }
+    info!("Stop requested; poller exiting.");
+    Ok(())
 }
 
-/// Single poll iteration: fetch New tickets, notify unseen ones. Returns number of new notifications.
-#[allow(clippy::too_many_arguments)]
+/// How long `--once` waits for its notifications to be delivered before exiting.
+const ONCE_FLUSH: Duration = Duration::from_secs(60);
+
+/// One-shot conditions consumed by the first iterations of the loop.
+#[derive(Debug, Default)]
+struct StartupFlags {
+    /// No seen state yet: mark everything seen (unless `first_run_notify`).
+    first_run: bool,
+    first_run_notify: bool,
+    /// Seconds between repeated notifications of a still-New ticket, per severity (REPEAT_MINUTES).
+    repeat: BTreeMap<Severity, u64>,
+    /// Notify again tickets that come back to New (restored from the trash, status reverted) (NOTIFY_RESTORED).
+    notify_restored: bool,
+    /// Last heartbeat before this start, when the notifier was offline long enough to summarize the backlog.
+    offline_since: Option<u64>,
+}
+
+/// `Some(last_heartbeat)` when it is older than OFFLINE_SUMMARY_MINUTES (default 240, 0 disables).
+fn offline_since(last_heartbeat: Option<u64>) -> Option<u64> {
+    let minutes = config::get().offline_summary_minutes;
+    let ts = last_heartbeat?;
+    (minutes > 0 && now_secs().saturating_sub(ts) > minutes * 60).then_some(ts)
+}
+
+/// New tickets read per poll (newest first); older unseen ones are deferred to the next polls.
+const SEARCH_WINDOW: usize = 200;
+
+/// What a successful poll saw, for the heartbeat and the poll history.
+struct Polled {
+    /// Tickets notified.
+    new: usize,
+    /// New tickets in GLPI.
+    queue: u64,
+}
+
+/// Single poll iteration: fetch New tickets, notify unseen ones.
 async fn tick(
     client: &mut GlpiClient,
-    id_id: i64,
-    name_id: i64,
-    status_id: i64,
-    requester_id: Option<i64>,
+    fields: &TicketFields,
+    filter: &[Criterion],
     st: &mut SeenState,
-    first_run: &mut bool,
-    first_run_notify: &mut bool,
+    flags: &mut StartupFlags,
     debug_list: bool,
-) -> Result<usize> {
-    let tickets = client.search_new_tickets(id_id, name_id, status_id, requester_id, 200).await?;
+    notifier: &dyn Notifier,
+) -> Result<Polled> {
+    let tick_id = trace::next_tick();
+    if trace::enabled() {
+        info!("HTTP trace: tick-{tick_id}");
+    }
+    if flags.first_run && !flags.first_run_notify {
+        initial_sync(client, fields, filter, st, notifier).await?;
+        flags.first_run = false;
+        flags.offline_since = None;
+    }
+    let (mut tickets, total) = client.search_new_page(fields, filter, SEARCH_WINDOW).await?;
+    let queue = total.map_or(tickets.len() as u64, |n| n.max(0) as u64);
+    // A failed alert must not cost this poll its New tickets
+    if let Err(e) = check_queue_depth(st, queue, notifier) {
+        warn!("Queue depth: {e:#}");
+    }
+    let truncated = total.is_some_and(|n| n > tickets.len() as i64);
+    let window_min = tickets.iter().map(|t| t.id).min().unwrap_or(i64::MAX);
+
+    // Tickets deferred by earlier truncated polls: evaluated now (if still New), one window per poll
+    if !st.deferred.is_empty() {
+        let batch: Vec<i64> = st.deferred.iter().rev().take(SEARCH_WINDOW).copied().collect();
+        st.backlog = st.backlog.max(st.deferred.len()); // catch-up resumed after a restart
+        let found = client.new_tickets_by_ids(fields, filter, &batch).await?;
+        info!("Evaluating {} deferred ticket(s) ({} still New)", batch.len(), found.len());
+        for id in &batch {
+            st.deferred.remove(id);
+        }
+        let window: BTreeSet<i64> = tickets.iter().map(|t| t.id).collect();
+        tickets.extend(found.into_iter().filter(|t| !window.contains(&t.id)));
+        save_state(st)?;
+    }
+
+    // More New tickets than the window: the oldest were not returned. List them (ids only) so they are
+    // not taken as gone, and defer the unseen ones to the next poll instead of dropping them.
+    st.beyond_window.clear();
+    if truncated {
+        st.beyond_window = client.new_ticket_ids_below(fields, filter, window_min).await?.into_iter().collect();
+        let unseen: Vec<i64> = st
+            .beyond_window
+            .iter()
+            .copied()
+            .filter(|id| !st.seen_ticket_ids.contains(id) && !tickets.iter().any(|t| t.id == *id))
+            .collect();
+        if !unseen.is_empty() {
+            warn!(
+                "{} New ticket(s) beyond the {SEARCH_WINDOW}-ticket search window, deferred to the next poll",
+                unseen.len()
+            );
+            let before = st.deferred.len();
+            st.deferred.extend(unseen);
+            st.backlog += st.deferred.len() - before;
+            save_state(st)?;
+        }
+    }
+    if st.backlog > 0 {
+        report_backlog(st, notifier);
+    }
 
     if debug_list {
         info!("DEBUG: {} ticket(s) with status=New", tickets.len());
@@ -186,7 +739,7 @@ async fn tick(
     }
 
     if tickets.is_empty() && debug_list {
-        if let Ok(recent) = client.search_recent_tickets(id_id, name_id, 10).await {
+        if let Ok(recent) = client.search_recent_tickets(fields, 10).await {
             info!("DEBUG: recent tickets (any status): {}", recent.len());
             for t in recent.iter().take(10) {
                 info!("DEBUG: Recent -> #{} {}", t.id, t.name);
@@ -194,208 +747,1294 @@ async fn tick(
         }
     }
 
-    let current_ids: Vec<i64> = tickets.iter().map(|t| t.id).collect();
+    // Newest ticket of any status: ids going backwards means GLPI was purged/reinstalled
+    if let Some(latest) = client.search_recent_tickets(fields, 0).await?.first().map(|t| t.id) {
+        check_id_restart(st, latest, notifier)?;
+    }
 
-    if *first_run && !*first_run_notify {
-        st.seen_ticket_ids.extend(current_ids);
-        save_state(st)?;
-        *first_run = false;
-        info!("First run: marked {} 'New' tickets as seen. (FIRST_RUN_NOTIFY=false)", st.seen_ticket_ids.len());
-        return Ok(0);
-    } else if *first_run && *first_run_notify {
-        info!("First run WITH notifications (FIRST_RUN_NOTIFY=true).");
-        *first_run = false;
-        *first_run_notify = false; // only notify on first iteration once
+    // MAJOR_INCIDENT_PRIORITY: tickets linked to an open ticket of at least this priority inherit its urgency
+    if let Some(min_priority) = config::get().major_incident_priority {
+        link_major_incidents(client, fields, &mut tickets, st, min_priority).await;
     }
 
-    // Filter unseen -> newest first
-    let mut fresh: Vec<&Ticket> = tickets.iter().filter(|t| !st.seen_ticket_ids.contains(&t.id)).collect();
-    fresh.sort_by_key(|t| -t.id);
+    tickets::record(&tickets, Some(1));
+    tickets::save();
 
-    for t in &fresh {
-        show_toast(t)?;
-        st.seen_ticket_ids.insert(t.id);
+    st.focus = focus::load();
+    let outcome = process_tickets(&tickets, st, flags, notifier)?;
+    for (t, action) in &outcome.actions {
+        handle_action(client, st, t, *action).await;
+    }
+    if !outcome.picked_up.is_empty() {
+        record_pickups(client, fields, st, &outcome.picked_up).await;
+    }
+
+    if let Some(delay) = phone::delay_secs() {
+        if escalate_by_phone(&tickets, st, delay).await {
+            save_state(st)?;
+        }
     }
 
-    if !fresh.is_empty() {
+    if outcome.dirty || !outcome.actions.is_empty() {
         save_state(st)?;
-        info!("Notified {} new ticket(s): {:?}", fresh.len(), fresh.iter().map(|t| t.id).collect::<Vec<_>>());
     }
+    if !outcome.fresh.is_empty() {
+        info!("Notified {} new ticket(s): {:?}", outcome.fresh.len(), outcome.fresh);
+        mqtt::publish_new_tickets(tickets.iter().filter(|t| outcome.fresh.contains(&t.id)));
+    }
+    widget::publish(Some((&tickets, queue)), st);
 
-    Ok(fresh.len())
+    let cfg = config::get();
+    let (replies, updates, assigned) = (cfg.customer_replied, cfg.ticket_updates, cfg.assigned_to_me);
+    let transitions = !cfg.status_transitions.is_empty();
+    let mine = if replies || updates || assigned || cfg.followups || transitions {
+        match client.my_open_tickets(fields).await {
+            Ok(mine) => Some(mine),
+            Err(e) => {
+                warn!("My open tickets: {e:#}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if let Some(mine) = &mine {
+        tickets::record(&mine.iter().map(|m| m.ticket.clone()).collect::<Vec<_>>(), None);
+        if assigned && watch_assignments(mine, st, notifier) {
+            save_state(st)?;
+        }
+        // FOLLOWUPS toasts the requesters' follow-ups too
+        if replies && !cfg.followups {
+            if let Err(e) = watch_customer_replies(client, mine, st, notifier).await {
+                warn!("Customer replies: {e:#}");
+            }
+        }
+    }
+    // Before TICKET_UPDATES refreshes its snapshots: the changes it tells are not told twice
+    if transitions {
+        if let Err(e) = watch_transitions(client, fields, &outcome.fresh, mine.as_deref(), st, notifier).await {
+            warn!("Status transitions: {e:#}");
+        }
+    }
+    if let Some(mine) = &mine {
+        if updates && watch_ticket_updates(mine, st, notifier) {
+            save_state(st)?;
+        }
+        if cfg.followups {
+            if let Err(e) = watch_followups(client, fields, mine, st, notifier).await {
+                warn!("Follow-ups: {e:#}");
+            }
+        }
+    }
+    if cfg.group_tasks {
+        if let Err(e) = watch_group_tasks(client, st, notifier).await {
+            warn!("Group tasks: {e:#}");
+        }
+    }
+    if cfg.satisfaction_reminders {
+        if let Err(e) = remind_satisfactions(client, st, notifier).await {
+            warn!("Satisfaction reminders: {e:#}");
+        }
+    }
+    if maintenance::refresh(client, st).await {
+        save_state(st)?;
+    }
+
+    Ok(Polled { new: outcome.fresh.len(), queue })
 }
 
-/// Build and show a toast (title + subject + requester, and an optional "Open" button).
-fn show_toast(t: &Ticket) -> Result<()> {
-    let title = format!("GLPI: New ticket #{}", t.id);
-    let requester = t.requester.as_deref().unwrap_or("Unknown");
-    let msg = if t.name.is_empty() {
-        format!("New ticket\nBy: {}", requester)
-    } else {
-        format!("{}\nBy: {}", t.name, requester)
+/// Look up the major incident of the tickets that may be notified this poll (unseen, shown or snoozed), so they
+/// are notified as Critical and name the incident. A failing lookup is warned about once, until it works again.
+async fn link_major_incidents(
+    client: &mut GlpiClient,
+    fields: &TicketFields,
+    tickets: &mut [Ticket],
+    st: &SeenState,
+    min_priority: i64,
+) {
+    use std::sync::Mutex;
+    /// Instances whose last lookup failed.
+    static FAILING: Mutex<BTreeSet<Option<String>>> = Mutex::new(BTreeSet::new());
+
+    let candidate =
+        |t: &Ticket| !st.seen_ticket_ids.contains(&t.id) || st.shown.contains(&t.id) || st.snoozed.contains_key(&t.id);
+    if !tickets.iter().any(candidate) {
+        return;
+    }
+    let incidents = match client.major_incidents(fields, min_priority).await {
+        Ok(incidents) => {
+            if FAILING.lock().unwrap().remove(&instance::current_name()) {
+                info!("Major incident lookup works again");
+            }
+            incidents
+        }
+        Err(e) => {
+            if FAILING.lock().unwrap().insert(instance::current_name()) {
+                warn!("Major incident lookup: {e:#}");
+            } else {
+                debug!("Major incident lookup: {e:#}");
+            }
+            return;
+        }
     };
+    for t in tickets.iter_mut().filter(|t| candidate(t)) {
+        let Some(&mi) = incidents.get(&t.id) else { continue };
+        if !st.seen_ticket_ids.contains(&t.id) && t.severity() != Severity::Critical {
+            info!("#{} is linked to major incident #{mi}: notified as Critical", t.id);
+        }
+        t.major_incident = Some(mi);
+    }
+}
 
-    // Build URL from template if configured
-    let open_url = URL_TEMPLATE.get().and_then(|tpl| tpl.as_ref()).map(|tpl| tpl.replace("{id}", &t.id.to_string()));
+/// Call or text the on-call number about notified Critical tickets still New `delay` seconds after their first
+/// notification (PHONE_ESCALATION_MINUTES). Returns true when the state changed.
+async fn escalate_by_phone(tickets: &[Ticket], st: &mut SeenState, delay: u64) -> bool {
+    let now = now_secs();
+    let before = (st.phone_pending.len(), st.phoned.len());
+    let still_new = |id: &i64| tickets.iter().any(|t| t.id == *id) || st.beyond_window.contains(id);
+    st.phone_pending.retain(|id, _| still_new(id));
+    st.phoned.retain(|id| still_new(id));
+    let mut dirty = (st.phone_pending.len(), st.phoned.len()) != before;
 
-    show_toast_snoretoast("GlpiNotifier", &title, &msg, t.id, open_url.as_deref())
+    for t in tickets.iter().filter(|t| t.severity() == Severity::Critical && st.shown.contains(&t.id)) {
+        if st.phoned.contains(&t.id) {
+            continue;
+        }
+        let since = *st.phone_pending.entry(t.id).or_insert_with(|| {
+            dirty = true;
+            now
+        });
+        if now.saturating_sub(since) < delay {
+            continue;
+        }
+        match phone::escalate(t, now.saturating_sub(since) / 60).await {
+            Ok(()) => {
+                warn!("Critical ticket #{} unassigned for {} min: on-call number alerted", t.id, delay / 60);
+                st.phone_pending.remove(&t.id);
+                st.phoned.insert(t.id);
+                dirty = true;
+            }
+            Err(e) => warn!("Phone escalation of #{} failed, retrying next poll: {e:#}", t.id),
+        }
+    }
+    dirty
 }
 
-/// Call snoretoast.exe to display a Windows toast with optional button and image.
-fn show_toast_snoretoast(app_id: &str, title: &str, body: &str, ticket_id: i64, open_url: Option<&str>) -> Result<()> {
-    let snore =
-        find_snoretoast().ok_or_else(|| anyhow!("snoretoast.exe not found (place it next to the .exe or in PATH)"))?;
+/// Toast once per follow-up when a requester replies on one of my open tickets (CUSTOMER_REPLIED). Only tickets
+/// updated since the last check are read; a ticket's first check records its existing follow-ups without toasting.
+async fn watch_customer_replies(
+    client: &mut GlpiClient,
+    mine: &[OpenTicket],
+    st: &mut SeenState,
+    notifier: &dyn Notifier,
+) -> Result<()> {
+    let before = st.followups.len();
+    st.followups.retain(|id, _| mine.iter().any(|m| m.ticket.id == *id));
+    let mut dirty = st.followups.len() != before;
 
-    let mut cmd = Command::new(snore);
-    cmd.arg("-appID")
-        .arg(app_id)
-        .arg("-id")
-        .arg(ticket_id.to_string())
-        .arg("-t")
-        .arg(title)
-        .arg("-m")
-        .arg(body)
-        .arg("-d")
-        .arg("short");
+    for OpenTicket { ticket: t, date_mod: changed, .. } in mine {
+        let known = st.followups.get(&t.id).cloned();
+        if known.as_ref().is_some_and(|(date, _)| date == changed) {
+            continue;
+        }
+        let replies = client.requester_followups(t.id).await?;
+        let last_seen = known.as_ref().map(|(_, last)| *last);
+        for (followup, text) in replies.iter().filter(|(f, _)| last_seen.is_some_and(|last| *f > last)) {
+            info!("Requester replied on #{} (follow-up #{followup})", t.id);
+            let title = format!("Customer replied on #{}", t.id);
+            let body = format!("{}\n{}", t.name, text.chars().take(200).collect::<String>());
+            match crate::toast::ticket_url_for(t) {
+                Some(url) => notifier.reminder(&title, &body, &url)?,
+                None => notifier.message(&title, &body)?,
+            }
+        }
+        let newest = replies.iter().map(|(f, _)| *f).chain(last_seen).max().unwrap_or(0);
+        st.followups.insert(t.id, (changed.clone(), newest));
+        dirty = true;
+    }
+    if dirty {
+        save_state(st)?;
+    }
+    Ok(())
+}
+
+/// Toast once when a ticket is assigned to me (ASSIGNED_TO_ME): my open tickets that were not mine at the last
+/// check. The first check records the tickets already mine; tickets I took from a toast are not toasted, and
+/// tickets solved, closed or reassigned are forgotten (a later assignment toasts again). Returns true when the
+/// state changed.
+fn watch_assignments(mine: &[OpenTicket], st: &mut SeenState, notifier: &dyn Notifier) -> bool {
+    let Some(known) = st.assigned.as_mut() else {
+        info!("Assigned to me: {} open ticket(s) recorded as known", mine.len());
+        st.assigned = Some(mine.iter().map(|m| m.ticket.id).collect());
+        return true;
+    };
+    let before = known.clone();
+    known.retain(|id| mine.iter().any(|m| m.ticket.id == *id));
+    for t in mine.iter().map(|m| &m.ticket).filter(|t| !before.contains(&t.id)) {
+        info!("Ticket #{} assigned to me", t.id);
+        let title = format!("Assigned to you: #{}", t.id);
+        let body = match &t.requester {
+            Some(requester) => format!("{}\nfrom {requester}", t.name),
+            None => t.name.clone(),
+        };
+        let shown = match crate::toast::ticket_url_for(t) {
+            Some(url) => notifier.reminder(&title, &body, &url),
+            None => notifier.message(&title, &body),
+        };
+        match shown {
+            Ok(()) => {
+                known.insert(t.id);
+            }
+            // Not recorded: toasted again next poll
+            Err(e) => warn!("Assignment notification of #{} failed: {e:#}", t.id),
+        }
+    }
+    *known != before
+}
 
-    if let Some(img) = ensure_logo_file() {
-        log::info!("SnoreToast: attaching image {}", img);
-        cmd.arg("-p").arg(img);
+/// Toast once per follow-up written by someone else on the open tickets I requested or am assigned to
+/// (FOLLOWUPS; `assigned` are the latter). Only tickets updated since the last check are read; a ticket's first
+/// check records its existing follow-ups without toasting.
+async fn watch_followups(
+    client: &mut GlpiClient,
+    fields: &TicketFields,
+    assigned: &[OpenTicket],
+    st: &mut SeenState,
+    notifier: &dyn Notifier,
+) -> Result<()> {
+    let followed = followed_tickets(assigned, client.my_requested_tickets(fields).await?);
+    let before = (st.followed.len(), st.followups.len());
+    st.followed.retain(|id, _| followed.iter().any(|m| m.ticket.id == *id));
+    st.followups.retain(|id, _| assigned.iter().any(|m| m.ticket.id == *id));
+    let mut dirty = (st.followed.len(), st.followups.len()) != before;
+
+    for m in &followed {
+        if st.followed.get(&m.ticket.id).is_some_and(|(date, _)| *date == m.date_mod) {
+            continue;
+        }
+        let followups = client.followups_by_others(m.ticket.id).await?;
+        let mine = assigned.iter().any(|a| a.ticket.id == m.ticket.id);
+        tell_followups(m, &followups, mine, st, notifier)?;
+        dirty = true;
     }
-    if open_url.is_some() {
-        cmd.arg("-b").arg("Open");
+    if dirty {
+        save_state(st)?;
     }
+    Ok(())
+}
 
-    let out = cmd.output()?;
-    let code = out.status.code().unwrap_or(-1);
+/// The tickets FOLLOWUPS watches: the ones `assigned` to me, then the ones I `requested` that are not.
+fn followed_tickets(assigned: &[OpenTicket], requested: Vec<OpenTicket>) -> Vec<OpenTicket> {
+    let mut followed = assigned.to_vec();
+    for t in requested {
+        if !followed.iter().any(|m| m.ticket.id == t.ticket.id) {
+            followed.push(t);
+        }
+    }
+    followed
+}
 
-    // Accept all documented statuses
-    if (0..=5).contains(&code) {
-        if code == 4 {
-            // ButtonPressed
-            if let Some(url) = open_url {
-                if let Err(e) = open_url_windows(url) {
-                    warn!("Failed to open ticket URL: {e:#}");
+/// Toast the `followups` (by others) of `m` newer than the last one seen, none on its first read, and record
+/// the newest. On a ticket `assigned` to me, the CUSTOMER_REPLIED watermark moves along: it is not read while
+/// FOLLOWUPS is on, and would toast these replies again once FOLLOWUPS is off.
+fn tell_followups(
+    m: &OpenTicket,
+    followups: &[(i64, String)],
+    assigned: bool,
+    st: &mut SeenState,
+    notifier: &dyn Notifier,
+) -> Result<()> {
+    let t = &m.ticket;
+    let last_seen = st.followed.get(&t.id).map(|(_, last)| *last);
+    for (followup, text) in followups.iter().filter(|(f, _)| last_seen.is_some_and(|last| *f > last)) {
+        info!("New follow-up on #{} (#{followup})", t.id);
+        let title = format!("New follow-up on #{}", t.id);
+        let body = format!("{}\n{}", t.name, text.chars().take(200).collect::<String>());
+        match crate::toast::ticket_url_for(t) {
+            Some(url) => notifier.reminder(&title, &body, &url)?,
+            None => notifier.message(&title, &body)?,
+        }
+    }
+    let newest = followups.iter().map(|(f, _)| *f).chain(last_seen).max().unwrap_or(0);
+    st.followed.insert(t.id, (m.date_mod.clone(), newest));
+    if assigned {
+        let replied = st.followups.get(&t.id).map_or(0, |(_, last)| *last);
+        st.followups.insert(t.id, (m.date_mod.clone(), newest.max(replied)));
+    }
+    Ok(())
+}
+
+/// Toast the status changes STATUS_TRANSITIONS asks for on the watched tickets (see [`transition`]): the New
+/// tickets first seen this poll (`fresh`) and my open tickets (`mine`, when read) join the ones already watched.
+/// Statuses come from `mine`, else from one search per GLPI_BATCH_SIZE tickets; a ticket's first status is only
+/// recorded. Changes on tickets TICKET_UPDATES already tracks are left to it.
+async fn watch_transitions(
+    client: &mut GlpiClient,
+    fields: &TicketFields,
+    fresh: &[i64],
+    mine: Option<&[OpenTicket]>,
+    st: &mut SeenState,
+    notifier: &dyn Notifier,
+) -> Result<()> {
+    let cfg = config::get();
+    let mut current: BTreeMap<i64, (Ticket, Option<i64>)> =
+        mine.unwrap_or_default().iter().map(|m| (m.ticket.id, (m.ticket.clone(), m.snapshot.status))).collect();
+    // The newest ones only: the others are dropped from the state below
+    let others: Vec<i64> = st
+        .statuses
+        .keys()
+        .chain(fresh)
+        .copied()
+        .filter(|id| !current.contains_key(id))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .rev()
+        .take(transition::WATCHED)
+        .collect();
+    if !others.is_empty() {
+        for (t, status) in client.tickets_with_status(fields, &others).await? {
+            current.insert(t.id, (t, status));
+        }
+    }
+    let told_by_updates = |id: i64, st: &SeenState| cfg.ticket_updates && st.snapshots.contains_key(&id);
+    if tell_transitions(current, &cfg.status_transitions, told_by_updates, st, notifier) {
+        save_state(st)?;
+    }
+    Ok(())
+}
+
+/// Compare the statuses of the `current` watched tickets with the state and tell the transitions wanted by
+/// `rules`, except those `told_elsewhere` (by TICKET_UPDATES). Returns true when the state changed.
+fn tell_transitions(
+    current: BTreeMap<i64, (Ticket, Option<i64>)>,
+    rules: &[transition::Transition],
+    told_elsewhere: impl Fn(i64, &SeenState) -> bool,
+    st: &mut SeenState,
+    notifier: &dyn Notifier,
+) -> bool {
+    let before = st.statuses.clone();
+    // Deleted or out of sight: no longer watched
+    st.statuses.retain(|id, _| current.contains_key(id));
+    for (id, (t, status)) in current {
+        let Some(status) = status else { continue };
+        if let Some(&old) = before.get(&id) {
+            if transition::wanted(rules, old, status) && !told_elsewhere(id, st) {
+                let change = diff::status_change(old, status);
+                info!("Ticket #{id}: {}", diff::summary(std::slice::from_ref(&change)));
+                if let Err(e) = notifier.update(&t, &[change]) {
+                    // Keep the old status: told again next poll
+                    warn!("Status notification of #{id} failed: {e:#}");
+                    continue;
                 }
             }
         }
-        let label = match code {
-            0 => "Success",
-            1 => "Hidden",
-            2 => "Dismissed",
-            3 => "TimedOut",
-            4 => "ButtonPressed",
-            5 => "TextEntered",
-            _ => "Unknown",
-        };
-        log::debug!("SnoreToast: {}", label);
-        return Ok(());
+        if status == transition::CLOSED {
+            st.statuses.remove(&id);
+        } else {
+            st.statuses.insert(id, status);
+        }
     }
+    st.statuses != before
+}
 
-    let stdout = String::from_utf8_lossy(&out.stdout);
-    let stderr = String::from_utf8_lossy(&out.stderr);
-    Err(anyhow!("snoretoast failed (code {:?}). STDOUT:\n{}\nSTDERR:\n{}", out.status.code(), stdout, stderr))
+/// Tell what changed on my open tickets since the last poll (TICKET_UPDATES): status, assignees and priority,
+/// compared with the snapshot stored in the state. A ticket's first read only stores its snapshot; tickets that
+/// are solved, closed or no longer mine are forgotten. Returns true when the state changed.
+fn watch_ticket_updates(mine: &[OpenTicket], st: &mut SeenState, notifier: &dyn Notifier) -> bool {
+    let before = st.snapshots.len();
+    st.snapshots.retain(|id, _| mine.iter().any(|m| m.ticket.id == *id));
+    let mut dirty = st.snapshots.len() != before;
+
+    for m in mine {
+        let changes = match st.snapshots.get(&m.ticket.id) {
+            Some(old) if *old == m.snapshot => continue,
+            Some(old) => diff::diff(old, &m.snapshot),
+            None => Vec::new(),
+        };
+        if !changes.is_empty() {
+            info!("Ticket #{} updated: {}", m.ticket.id, diff::summary(&changes));
+            if let Err(e) = notifier.update(&m.ticket, &changes) {
+                // Keep the old snapshot: the same changes are told next poll
+                warn!("Update notification of #{} failed: {e:#}", m.ticket.id);
+                continue;
+            }
+        }
+        st.snapshots.insert(m.ticket.id, m.snapshot.clone());
+        dirty = true;
+    }
+    dirty
 }
 
-fn open_url_windows(url: &str) -> Result<()> {
-    // 'start' needs an empty title "" after /C
-    Command::new("cmd").args(["/C", "start", "", url]).spawn()?;
+/// Toast once per task when a ticket task in the "to do" state is assigned to one of my groups (GROUP_TASKS),
+/// whoever the ticket itself is assigned to. Tasks done (or reassigned) are forgotten.
+async fn watch_group_tasks(client: &mut GlpiClient, st: &mut SeenState, notifier: &dyn Notifier) -> Result<()> {
+    let groups = client.my_groups().await?;
+    let tasks = if groups.is_empty() { Vec::new() } else { client.group_tasks_to_do(&groups).await? };
+    let Some(seen) = st.group_tasks.as_mut() else {
+        info!("Group tasks: {} to-do task(s) of my groups recorded as seen", tasks.len());
+        st.group_tasks = Some(tasks.iter().map(|t| t.id).collect());
+        return save_state(st);
+    };
+    let before = seen.len();
+    seen.retain(|id| tasks.iter().any(|t| t.id == *id));
+    let mut dirty = seen.len() != before;
+
+    for task in &tasks {
+        if seen.contains(&task.id) {
+            continue;
+        }
+        let group = client.group_name(task.groups_id_tech).await.unwrap_or_else(|e| {
+            warn!("{e:#}");
+            format!("group #{}", task.groups_id_tech)
+        });
+        info!("Task #{} on ticket #{} assigned to {group}", task.id, task.tickets_id);
+        let title = format!("Task for {group} on #{}", task.tickets_id);
+        let text = task.content.as_deref().unwrap_or_default();
+        let body = match tickets::get(task.tickets_id) {
+            Some(known) => format!("{}\n{}", known.ticket.name, text.chars().take(200).collect::<String>()),
+            None => text.chars().take(200).collect(),
+        };
+        match ticket_url(task.tickets_id) {
+            Some(url) => notifier.reminder(&title, &body, &url)?,
+            None => notifier.message(&title, &body)?,
+        }
+        seen.insert(task.id);
+        dirty = true;
+    }
+    if dirty {
+        save_state(st)?;
+    }
     Ok(())
 }
 
-/// Try to locate snoretoast.exe in common places (next to exe, default install dir, PATH).
-fn find_snoretoast() -> Option<String> {
-    // 1) next to the notifier exe
-    if let Ok(exe) = std::env::current_exe() {
-        if let Some(dir) = exe.parent() {
-            let cand = dir.join("snoretoast.exe");
-            if cand.exists() {
-                return Some(cand.to_string_lossy().into_owned());
+/// Remind once per survey about satisfaction surveys waiting for our answer on tickets we opened
+/// (SATISFACTION_REMINDERS).
+async fn remind_satisfactions(client: &mut GlpiClient, st: &mut SeenState, notifier: &dyn Notifier) -> Result<()> {
+    let pending = client.unanswered_satisfactions().await?;
+    let before = st.surveys_seen.len();
+    // Answered (or expired) surveys leave the list: forget them
+    st.surveys_seen.retain(|id| pending.iter().any(|s| s.id == *id));
+    let mut dirty = st.surveys_seen.len() != before;
+
+    for survey in &pending {
+        if st.surveys_seen.contains(&survey.id) {
+            continue;
+        }
+        if let Some(name) = client.requested_ticket_name(survey.tickets_id).await? {
+            info!("Satisfaction survey #{} waiting for an answer on ticket #{}", survey.id, survey.tickets_id);
+            let title = format!("GLPI: how did ticket #{} go?", survey.tickets_id);
+            let body = format!("{name}\nPlease answer the satisfaction survey.");
+            // Satisfaction tab of the ticket form
+            match ticket_url(survey.tickets_id) {
+                Some(url) => notifier.reminder(&title, &body, &toast::with_tab(&url, "Ticket$3"))?,
+                None => notifier.message(&title, &body)?,
             }
         }
+        st.surveys_seen.insert(survey.id);
+        dirty = true;
+    }
+    if dirty {
+        save_state(st)?;
     }
-    // 2) typical Program Files location
-    if let Ok(pf) = std::env::var("ProgramFiles") {
-        let cand = std::path::Path::new(&pf).join("SnoreToast").join("snoretoast.exe");
-        if cand.exists() {
-            return Some(cand.to_string_lossy().into_owned());
+    Ok(())
+}
+
+/// First run: mark every New ticket seen, page by page (newest first, by id so concurrent changes do not shift
+/// pages). Progress is saved after each page, so an interrupted sync resumes instead of notifying the rest.
+async fn initial_sync(
+    client: &mut GlpiClient,
+    fields: &TicketFields,
+    filter: &[Criterion],
+    st: &mut SeenState,
+    notifier: &dyn Notifier,
+) -> Result<()> {
+    const PAGE: usize = 200;
+    const TITLE: &str = "GLPI notifier: initial sync";
+    let resumed = st.sync_below.is_some();
+    // Tickets to mark, once the run is long enough to show progress
+    let mut announced: Option<usize> = None;
+    let mut marked = 0;
+    loop {
+        let mut criteria = filter.to_vec();
+        if let Some(below) = st.sync_below {
+            criteria.push(Criterion {
+                field: FieldRef::Id(fields.id),
+                searchtype: SearchType::LessThan,
+                value: below.to_string(),
+            });
         }
+        let (page, total) = client.search_new_page(fields, &criteria, PAGE).await?;
+        let total = total.unwrap_or_default();
+        if announced.is_none() && total > PAGE as i64 {
+            let what = if resumed { "Resuming initial sync" } else { "Initial sync" };
+            info!("{what}: marking {total} 'New' tickets as seen (FIRST_RUN_NOTIFY=false)");
+            let _ = notifier.progress(TITLE, &format!("Marking {total} New tickets as seen…"), 0, total as usize);
+            announced = Some(total as usize);
+        }
+        st.seen_ticket_ids.extend(page.iter().map(|t| t.id));
+        marked += page.len();
+        st.sync_below = glpi::next_page_below(&page, PAGE);
+        save_state(st)?;
+        LIVENESS.touch();
+        if st.sync_below.is_none() {
+            break;
+        }
+        let all = marked as i64 + total - page.len() as i64;
+        info!("Initial sync: {marked}/{all} 'New' tickets marked seen");
+        if let Some(announced) = announced {
+            let status = format!("Marking {announced} New tickets as seen…");
+            let _ = notifier.progress(TITLE, &status, marked.min(all as usize - 1), all as usize);
+        }
+    }
+    info!("First run: marked {marked} 'New' tickets as seen. (FIRST_RUN_NOTIFY=false)");
+    if announced.is_some() {
+        let _ = notifier.progress(TITLE, &format!("Done: {marked} New tickets marked as seen."), marked, marked);
     }
-    // 3) let PATH resolve it
-    Some("snoretoast.exe".to_string())
+    Ok(())
 }
 
-/// Ensure a Start Menu shortcut exists with an AUMID so SnoreToast shows buttons.
-fn ensure_snore_shortcut(app_id: &str) {
-    if let Ok(exe) = std::env::current_exe() {
-        let exe_str = exe.to_string_lossy().into_owned();
-        if let Some(snore) = find_snoretoast() {
-            let _ = std::process::Command::new(&snore)
-                .arg("-install")
-                .arg("GlpiNotifier") // shortcut name
-                .arg(&exe_str) // executable path
-                .arg(app_id) // AUMID
-                .status();
+/// Progress toast of the deferred-ticket catch-up, ended once nothing is deferred any more.
+fn report_backlog(st: &mut SeenState, notifier: &dyn Notifier) {
+    let total = st.backlog;
+    let done = total.saturating_sub(st.deferred.len());
+    let status = if done < total {
+        format!("Processing {total} backlog tickets…")
+    } else {
+        format!("Done: {total} backlog tickets processed.")
+    };
+    let _ = notifier.progress("GLPI notifier: catching up", &status, done, total);
+    if done >= total {
+        st.backlog = 0;
+    }
+}
+
+/// QUEUE_WARN_AT / QUEUE_CRIT_AT: tell when the number of New tickets crosses a threshold, and when it recovers.
+fn check_queue_depth(st: &mut SeenState, count: u64, notifier: &dyn Notifier) -> Result<()> {
+    let th = config::get().queue_depth;
+    if !th.enabled() {
+        return Ok(());
+    }
+    let level = th.level(count, st.queue_level);
+    let Some((title, body)) = depth::alert(th, count, st.queue_level, level) else { return Ok(()) };
+    warn!("{title}. {body}");
+    st.queue_level = level;
+    save_state(st)?;
+    notifier.message(&title, &body)
+}
+
+/// Warn (and with ID_RESET=auto, the default, reset the seen state) when GLPI ticket ids restarted,
+/// otherwise new low-numbered tickets would collide with old seen ids and never be notified.
+fn check_id_restart(st: &mut SeenState, latest: i64, notifier: &dyn Notifier) -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    static WARNED: AtomicBool = AtomicBool::new(false);
+
+    let Some(previous) = st.observe_latest_id(latest) else { return Ok(()) };
+    let auto = config::get().id_reset_auto;
+    if !auto && WARNED.swap(true, Ordering::Relaxed) {
+        return Ok(()); // warn mode: once per run
+    }
+
+    warn!("GLPI ticket ids went back from #{previous} to #{latest}: instance purged or reinstalled?");
+    let body = if auto {
+        st.reset_ticket_ids(latest);
+        save_state(st)?;
+        warn!("Seen-ticket state reset (ID_RESET=auto); current New tickets will be notified.");
+        format!("Newest ticket is #{latest}, previously #{previous}. Seen state was reset.")
+    } else {
+        format!("Newest ticket is #{latest}, previously #{previous}. Run `glpi-notifier-rs reset-seen` to reset.")
+    };
+    notifier.message("GLPI: ticket ids restarted", &body)
+}
+
+/// Result of running one batch of New tickets through the notification pipeline.
+#[derive(Debug, Default)]
+struct TickOutcome<'a> {
+    /// Ids newly notified (or skipped because muted), newest first.
+    fresh: Vec<i64>,
+    /// Actions picked by the user on the notifications, to route back to GLPI/state.
+    actions: Vec<(&'a Ticket, ToastAction)>,
+    /// State changed and must be saved.
+    dirty: bool,
+    /// Notified tickets that left the New list since the last poll (assigned, solved, deleted).
+    picked_up: Vec<i64>,
+}
+
+/// Pipeline of a tick without I/O: first-run handling, snooze/mute filters, then delivery through `notifier`.
+fn process_tickets<'a>(
+    tickets: &'a [Ticket],
+    st: &mut SeenState,
+    flags: &mut StartupFlags,
+    notifier: &dyn Notifier,
+) -> Result<TickOutcome<'a>> {
+    let current_ids: Vec<i64> = tickets.iter().map(|t| t.id).chain(st.beyond_window.iter().copied()).collect();
+    let mut outcome = TickOutcome::default();
+
+    // Seen tickets absent from the previous poll: back in New after a trash/restore or a status change
+    let returned: Vec<i64> = match &st.last_new {
+        Some(previous) if flags.notify_restored => {
+            current_ids.iter().copied().filter(|id| st.seen_ticket_ids.contains(id) && !previous.contains(id)).collect()
         }
+        _ => Vec::new(),
+    };
+    let now_new: BTreeSet<i64> = current_ids.iter().copied().collect();
+    if st.last_new.as_ref() != Some(&now_new) {
+        st.last_new = Some(now_new);
+        outcome.dirty = true;
     }
+    for id in &returned {
+        info!("#{id} is back in New (restored from the trash or reopened), notifying again");
+    }
+
+    if flags.first_run && !flags.first_run_notify {
+        st.seen_ticket_ids.extend(current_ids);
+        flags.first_run = false;
+        flags.offline_since = None;
+        info!("First run: marked {} 'New' tickets as seen. (FIRST_RUN_NOTIFY=false)", st.seen_ticket_ids.len());
+        outcome.dirty = true;
+        return Ok(outcome);
+    } else if flags.first_run && flags.first_run_notify {
+        info!("First run WITH notifications (FIRST_RUN_NOTIFY=true).");
+        flags.first_run = false;
+        flags.first_run_notify = false; // only notify on first iteration once
+    }
+
+    // Drop snoozes for tickets that are no longer New (and expired mutes); collect the ones that are due again
+    let now = now_secs();
+    outcome.dirty |= st.prune_mutes();
+    st.snoozed.retain(|id, _| current_ids.contains(id));
+    st.notify_failures.retain(|id, _| current_ids.contains(id));
+    outcome.picked_up = withdraw_resolved(st, &current_ids, notifier);
+    outcome.dirty |= !outcome.picked_up.is_empty();
+    st.notified_at.retain(|id, _| st.shown.contains(id));
+    let mut due: Vec<i64> = st.snoozed.iter().filter(|(_, until)| **until <= now).map(|(id, _)| *id).collect();
+    // Severity repeat: shown tickets still New (unassigned) are notified again every REPEAT_MINUTES
+    due.extend(tickets.iter().filter(|t| !st.snoozed.contains_key(&t.id)).filter_map(|t| {
+        let every = flags.repeat.get(&t.severity())?;
+        let last = st.notified_at.get(&t.id)?;
+        (now.saturating_sub(*last) >= *every).then_some(t.id)
+    }));
+
+    // Filter unseen (or snooze expired) -> newest first
+    let mut fresh: Vec<&Ticket> = tickets
+        .iter()
+        .filter(|t| !st.seen_ticket_ids.contains(&t.id) || due.contains(&t.id) || returned.contains(&t.id))
+        .collect();
+    fresh.sort_by_key(|t| -t.id);
+
+    // Focus mode over: one digest of what was held back (and is still New)
+    if st.focus.is_none() && !st.focus_held.is_empty() {
+        let mut held: Vec<&Ticket> = tickets.iter().filter(|t| st.focus_held.contains(&t.id)).collect();
+        st.focus_held.clear();
+        outcome.dirty = true;
+        if !held.is_empty() {
+            let title = format!("GLPI: {} tickets arrived during focus", held.len());
+            notify_digest(&mut held, &title, notifier)?;
+        }
+    }
+
+    // Back from a long offline period: one summary of what was opened meanwhile instead of a flood
+    let mut summarized = Vec::new();
+    if let Some(since) = flags.offline_since.take() {
+        let mut backlog: Vec<&Ticket> = fresh
+            .iter()
+            .copied()
+            .filter(|t| t.created_ts().is_some_and(|c| c >= since))
+            .filter(|t| st.muted_by(t.requester.as_deref(), t.category.as_deref()).is_none())
+            .filter(|t| lang::not_mine(t.language.as_deref()).is_none())
+            .collect();
+        if backlog.len() >= 2 {
+            notify_offline_summary(&mut backlog, since, notifier)?;
+            summarized = backlog.iter().map(|t| t.id).collect();
+        }
+    }
+
+    for t in fresh {
+        outcome.fresh.push(t.id);
+        st.seen_ticket_ids.insert(t.id);
+        st.snoozed.remove(&t.id);
+        if summarized.contains(&t.id) {
+            continue;
+        }
+        if let Some(m) = st.muted_by(t.requester.as_deref(), t.category.as_deref()) {
+            info!("Skipping toast for #{} ({} muted)", t.id, m.target);
+            continue;
+        }
+        if let Some(why) = lang::not_mine(t.language.as_deref()) {
+            info!("Skipping toast for #{} ({why})", t.id);
+            continue;
+        }
+        if let Some(f) = st.focus.as_ref().filter(|_| t.severity() < Severity::High) {
+            info!("Holding toast for #{} while focusing on #{}", t.id, f.ticket_id);
+            st.focus_held.push(t.id);
+            continue;
+        }
+        match notifier.notify(t) {
+            Ok(action) => {
+                st.notify_failures.remove(&t.id);
+                st.shown.insert(t.id);
+                st.notified_at.insert(t.id, now);
+                if let Some(action) = action {
+                    outcome.actions.push((t, action));
+                }
+            }
+            Err(e) => record_notify_failure(st, t.id, &e),
+        }
+        LIVENESS.touch(); // a burst of toasts can outlast the stall limit
+    }
+
+    outcome.dirty |= !outcome.fresh.is_empty() || !due.is_empty();
+    Ok(outcome)
 }
 
-/// Return the path to the heartbeat JSON.
-fn heartbeat_path() -> Option<std::path::PathBuf> {
-    let dir = dirs::data_dir()?;
-    let p = dir.join("GlpiNotifier").join("heartbeat.json");
-    let _ = std::fs::create_dir_all(p.parent().unwrap());
-    Some(p)
+/// Why the pipeline would not notify `t` on the next poll (empty: it would be notified).
+/// Mirrors the checks of `process_tickets` and the New-status search, for `explain`.
+fn suppression_reasons(t: &Ticket, status: Option<i64>, st: &SeenState, now: u64) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if status != Some(1) {
+        let status = status.map_or("unknown".into(), |s| s.to_string());
+        reasons.push(format!("status is {status}: only New (1) tickets are polled"));
+    }
+    if st.seen_ticket_ids.is_empty() && !config::get().first_run_notify {
+        reasons.push("no state yet: the first poll marks current New tickets as seen (FIRST_RUN_NOTIFY=false)".into());
+    }
+    match st.snoozed.get(&t.id) {
+        Some(until) if *until > now => reasons.push(format!("snoozed for another {} min", (until - now).div_ceil(60))),
+        Some(_) => {} // snooze expired: notified again
+        None if st.seen_ticket_ids.contains(&t.id) => {
+            reasons.push("already notified or marked seen (state.json; `reset-seen` clears it)".into())
+        }
+        None => {}
+    }
+    if let Some(m) = st.muted_by(t.requester.as_deref(), t.category.as_deref()) {
+        reasons.push(format!("{} is muted for another {} min", m.target, m.until.saturating_sub(now).div_ceil(60)));
+    }
+    if let Some(why) = lang::not_mine(t.language.as_deref()) {
+        reasons.push(why);
+    }
+    if let Some(f) = st.focus.as_ref().filter(|_| t.severity() < Severity::High) {
+        reasons.push(format!("focus mode on #{} holds tickets below High severity (digest when it ends)", f.ticket_id));
+    }
+    if matches!(config::get().notifier, "null" | "none") {
+        reasons.push("NOTIFIER=null discards all notifications".into());
+    }
+    reasons
+}
+
+/// Log a failed delivery and keep the ticket unseen so the next poll retries it,
+/// up to NOTIFY_MAX_ATTEMPTS (default 3) attempts; then it stays seen and is given up.
+fn record_notify_failure(st: &mut SeenState, id: i64, e: &anyhow::Error) {
+    let max_attempts = config::get().notify_max_attempts;
+    let attempts = st.notify_failures.entry(id).or_insert(0);
+    *attempts += 1;
+    if *attempts < max_attempts {
+        warn!("Notification for #{id} failed (attempt {attempts}/{max_attempts}), will retry: {e:#}");
+        st.seen_ticket_ids.remove(&id);
+    } else {
+        error!("Notification for #{id} failed {attempts} times, giving up: {e:#}");
+        st.notify_failures.remove(&id);
+    }
 }
 
-/// Write an always-on heartbeat file with UNIX timestamp and last result.
-fn write_heartbeat(ok: bool, new_count: usize) {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    if let Some(p) = heartbeat_path() {
-        let ts = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
-        let payload = format!(r#"{{\"ts\": {ts}, \"ok\": {ok}, \"new\": {new_count}}}"#);
-        let _ = std::fs::write(p, payload);
+/// Withdraw notifications of tickets that left the New list (assigned, solved, deleted).
+/// Returns their ids.
+fn withdraw_resolved(st: &mut SeenState, current_ids: &[i64], notifier: &dyn Notifier) -> Vec<i64> {
+    let gone: Vec<i64> = st.shown.iter().copied().filter(|id| !current_ids.contains(id)).collect();
+    for &id in &gone {
+        if let Err(e) = notifier.withdraw(id) {
+            warn!("Failed to withdraw notification for #{id}: {e:#}");
+        }
+        st.shown.remove(&id);
     }
+    gone
+}
+
+/// Single "while you were away" message: count plus the top 5 tickets by priority.
+fn notify_offline_summary(backlog: &mut [&Ticket], since: u64, notifier: &dyn Notifier) -> Result<()> {
+    let hours = now_secs().saturating_sub(since) / 3600;
+    info!("Offline summary: {} ticket(s) opened since last heartbeat", backlog.len());
+    let title = format!("GLPI: {} new tickets while offline ({}h)", backlog.len(), hours);
+    notify_digest(backlog, &title, notifier)
 }
 
-/// Resolve a toast image to use:
-/// 1) GLPI_LOGO_PATH (.env) if valid PNG
-/// 2) assets/logo.png next to the exe
-/// 3) %LOCALAPPDATA%/GlpiNotifier/logo.png
-///
-/// If none found, no image is attached.
-fn ensure_logo_file() -> Option<String> {
-    use std::path::Path;
+/// One message listing the top 5 of `tickets` by priority.
+fn notify_digest(tickets: &mut [&Ticket], title: &str, notifier: &dyn Notifier) -> Result<()> {
+    use std::cmp::Reverse;
 
-    // 1) explicit path from .env
-    if let Ok(p) = std::env::var("GLPI_LOGO_PATH") {
-        let p = p.trim().to_string();
-        if !p.is_empty() && Path::new(&p).exists() {
-            return Some(p);
+    tickets.sort_by_key(|t| (Reverse(t.priority.unwrap_or(0)), Reverse(t.id)));
+    let body = tickets
+        .iter()
+        .take(5)
+        .map(|t| format!("#{} [P{}] {}", t.id, t.priority.map_or("?".into(), |p| p.to_string()), t.name))
+        .collect::<Vec<_>>()
+        .join("\n");
+    notifier.message(title, &body)
+}
+
+/// Count who picked up notified tickets, for the per-technician `stats` (one batched search for all of them).
+async fn record_pickups(client: &mut GlpiClient, fields: &TicketFields, st: &mut SeenState, ids: &[i64]) {
+    let assigned = match client.assignees(fields, ids).await {
+        Ok(a) => a,
+        Err(e) => {
+            warn!("Could not read assignment of {ids:?}: {e:#}");
+            return;
+        }
+    };
+    for (id, technicians) in assigned {
+        if technicians.is_empty() {
+            *st.pickups.entry("(unassigned)".into()).or_default() += 1;
+        }
+        for name in technicians {
+            info!("#{id} picked up by {name}");
+            *st.pickups.entry(name).or_default() += 1;
         }
     }
+}
 
-    // 2) assets/logo.png next to exe
-    if let Ok(exe) = std::env::current_exe() {
-        if let Some(dir) = exe.parent() {
-            let cand1 = dir.join("assets").join("logo.png");
-            if cand1.exists() {
-                return Some(cand1.to_string_lossy().into_owned());
+/// Route the results of the notification worker: actions picked on toasts and failed deliveries.
+async fn apply_deliveries(client: &mut GlpiClient, st: &mut SeenState) {
+    let deliveries = pipeline::dispatcher().drain();
+    if deliveries.is_empty() {
+        return;
+    }
+    for d in deliveries {
+        match d {
+            Delivery::Action(t, action) => handle_action(client, st, &t, action).await,
+            Delivery::Failed(t, e) => record_notify_failure(st, t.id, &e),
+        }
+    }
+    if let Err(e) = save_state(st) {
+        warn!("Could not save state: {e:#}");
+    }
+}
+
+/// Apply the state edits of the one-shot commands (`stop-timer`, `mutes clear`, `reset-seen`) to the state
+/// this loop holds, and save it. Returns true when the seen tickets were reset: the next poll is a first run.
+fn apply_state_edits(st: &mut SeenState) -> bool {
+    let edits = control::take_state_edits(st);
+    if edits.is_empty() {
+        return false;
+    }
+    for edit in &edits {
+        info!("State edit from the command line: {edit}");
+    }
+    if let Err(e) = save_state(st) {
+        warn!("Could not save state: {e:#}");
+    }
+    edits.contains(&control::StateEdit::ResetSeen)
+}
+
+/// Route a toast button press back to the matching GLPI call or local state change.
+async fn handle_action(client: &mut GlpiClient, st: &mut SeenState, t: &Ticket, action: ToastAction) {
+    info!("Toast action on #{}: {}", t.id, action.label());
+    if control::kiosk() && action != ToastAction::Open {
+        info!("Kiosk mode: ignoring '{}' on #{}", action.label(), t.id);
+        return;
+    }
+    match action {
+        ToastAction::Open => open_ticket(t),
+        ToastAction::AssignToMe => {
+            match client.assign_to_me(t.id).await {
+                // Taken here: no "assigned to you" toast for it
+                Ok(()) => {
+                    if let Some(known) = st.assigned.as_mut() {
+                        known.insert(t.id);
+                    }
+                }
+                Err(e) => warn!("Failed to assign #{} to current user: {e:#}", t.id),
             }
-            let cand2 = dir.join("logo.png");
-            if cand2.exists() {
-                return Some(cand2.to_string_lossy().into_owned());
+        }
+        ToastAction::Snooze => {
+            st.snoozed.insert(t.id, now_secs().saturating_add(config::get().snooze_minutes.saturating_mul(60)));
+        }
+        ToastAction::StartTimer => {
+            st.timers.entry(t.id).or_insert_with(now_secs);
+            info!("Timer started for #{} (stop with: stop-timer {})", t.id, t.id);
+        }
+        ToastAction::MuteRequester | ToastAction::MuteCategory => {
+            let target = match action {
+                ToastAction::MuteRequester => t.requester.clone().map(MuteTarget::Requester),
+                _ => t.category.clone().map(MuteTarget::Category),
+            };
+            if let Some(target) = target {
+                let hours = config::get().mute_hours;
+                info!("Muting {} for {}h", target, hours);
+                st.mute(target, hours.saturating_mul(3600));
             }
         }
     }
+}
 
-    // 3) LOCALAPPDATA cache
-    if let Some(ld) = dirs::data_dir() {
-        let cand = ld.join("GlpiNotifier").join("logo.png");
-        if cand.exists() {
-            return Some(cand.to_string_lossy().into_owned());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::CaptureSink;
+
+    fn ticket(id: i64, requester: &str) -> Ticket {
+        Ticket {
+            id,
+            name: format!("Ticket {id}"),
+            requester: Some(requester.to_string()),
+            category: None,
+            priority: None,
+            date: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            major_incident: None,
+            instance: None,
+            language: None,
         }
     }
 
-    None
+    fn ids(events: &[Ticket]) -> Vec<i64> {
+        events.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn ticket_updates_are_told_after_the_first_read() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        let open = |id: i64, status: i64, assignee: &str| OpenTicket {
+            ticket: ticket(id, "a"),
+            date_mod: String::new(),
+            snapshot: diff::Snapshot { status: Some(status), assignee: Some(assignee.into()), priority: Some(3) },
+        };
+
+        assert!(watch_ticket_updates(&[open(1, 4, "tech"), open(2, 2, "tech")], &mut st, &sink));
+        assert!(sink.updates().is_empty());
+        assert!(!watch_ticket_updates(&[open(1, 4, "tech"), open(2, 2, "tech")], &mut st, &sink));
+
+        assert!(watch_ticket_updates(&[open(1, 2, "J. Costa, tech")], &mut st, &sink));
+        assert_eq!(
+            sink.updates(),
+            [(1, "status: Pending→Processing (assigned); assignee: tech→J. Costa, tech".to_string())]
+        );
+        assert_eq!(st.snapshots.keys().copied().collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn initial_sync_goes_on_after_a_full_page() {
+        let page = |ids: std::ops::RangeInclusive<i64>| ids.rev().map(|id| ticket(id, "a")).collect::<Vec<_>>();
+        // range=0-200 answers 201 rows when there are more
+        assert_eq!(glpi::next_page_below(&page(100..=300), 200), Some(100));
+        assert_eq!(glpi::next_page_below(&page(101..=300), 200), Some(101));
+        assert_eq!(glpi::next_page_below(&page(1..=199), 200), None);
+        assert_eq!(glpi::next_page_below(&[], 200), None);
+    }
+
+    #[test]
+    fn followups_are_toasted_on_my_assigned_and_requested_tickets() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        let open = |id: i64, date: &str| OpenTicket {
+            ticket: ticket(id, "a"),
+            date_mod: date.into(),
+            snapshot: diff::Snapshot { status: Some(2), assignee: None, priority: Some(3) },
+        };
+        let followed = followed_tickets(&[open(1, "d1")], vec![open(1, "d1"), open(2, "d1")]);
+        assert_eq!(followed.iter().map(|m| m.ticket.id).collect::<Vec<_>>(), [1, 2]);
+
+        // First read: recorded, not toasted
+        tell_followups(&followed[0], &[(10, "Hello".into())], true, &mut st, &sink).unwrap();
+        tell_followups(&followed[1], &[], false, &mut st, &sink).unwrap();
+        assert!(sink.messages().is_empty());
+
+        tell_followups(&open(1, "d2"), &[(10, "Hello".into()), (11, "Any news?".into())], true, &mut st, &sink)
+            .unwrap();
+        tell_followups(&open(2, "d2"), &[(12, "Fixed?".into())], false, &mut st, &sink).unwrap();
+        assert_eq!(
+            sink.messages(),
+            [
+                ("New follow-up on #1".to_string(), "Ticket 1\nAny news?".to_string()),
+                ("New follow-up on #2".to_string(), "Ticket 2\nFixed?".to_string())
+            ]
+        );
+        // The customer replies already toasted are not told again by CUSTOMER_REPLIED
+        assert_eq!(st.followups.get(&1), Some(&("d2".to_string(), 11)));
+        assert_eq!(st.followups.get(&2), None);
+    }
+
+    #[test]
+    fn wanted_status_transitions_are_told_once() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        let rules = transition::parse("new>assigned; *>closed").unwrap();
+        let poll = |statuses: &[(i64, i64)]| -> BTreeMap<i64, (Ticket, Option<i64>)> {
+            statuses.iter().map(|&(id, status)| (id, (ticket(id, "a"), Some(status)))).collect()
+        };
+        let never = |_: i64, _: &SeenState| false;
+
+        assert!(tell_transitions(poll(&[(1, 1), (2, 4), (3, 2)]), &rules, never, &mut st, &sink));
+        assert!(sink.updates().is_empty());
+
+        // 1 new>assigned is wanted, 2 pending>assigned is not, 3 is gone
+        assert!(tell_transitions(poll(&[(1, 2), (2, 2)]), &rules, never, &mut st, &sink));
+        assert!(!tell_transitions(poll(&[(1, 2), (2, 2)]), &rules, never, &mut st, &sink));
+        assert_eq!(sink.updates(), [(1, diff::summary(&[diff::status_change(1, 2)]))]);
+        assert_eq!(st.statuses, BTreeMap::from([(1, 2), (2, 2)]));
+
+        // Told by TICKET_UPDATES instead; closed tickets are no longer watched
+        assert!(tell_transitions(poll(&[(1, 6), (2, 6)]), &rules, |id, _| id == 2, &mut st, &sink));
+        assert_eq!(sink.updates().len(), 2);
+        assert!(st.statuses.is_empty());
+    }
+
+    #[test]
+    fn new_assignments_are_toasted_once() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        let open = |id: i64| OpenTicket {
+            ticket: ticket(id, "J. Costa"),
+            date_mod: String::new(),
+            snapshot: diff::Snapshot { status: Some(2), assignee: Some("tech".into()), priority: Some(3) },
+        };
+
+        assert!(watch_assignments(&[open(1)], &mut st, &sink));
+        assert!(sink.messages().is_empty());
+        assert!(!watch_assignments(&[open(1)], &mut st, &sink));
+
+        assert!(watch_assignments(&[open(1), open(2)], &mut st, &sink));
+        assert_eq!(sink.messages(), [("Assigned to you: #2".to_string(), "Ticket 2\nfrom J. Costa".to_string())]);
+        assert!(watch_assignments(&[open(2)], &mut st, &sink));
+        assert!(watch_assignments(&[open(1), open(2)], &mut st, &sink));
+        assert_eq!(sink.messages().len(), 2);
+    }
+
+    #[test]
+    fn first_run_marks_seen_without_notifying() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        let tickets = [ticket(1, "a"), ticket(2, "b")];
+
+        let out =
+            process_tickets(&tickets, &mut st, &mut StartupFlags { first_run: true, ..Default::default() }, &sink)
+                .unwrap();
+
+        assert!(sink.events().is_empty());
+        assert!(out.dirty);
+        assert_eq!(st.seen_ticket_ids.len(), 2);
+    }
+
+    #[test]
+    fn notifies_unseen_newest_first_once() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        st.seen_ticket_ids.insert(1);
+        let tickets = [ticket(1, "a"), ticket(2, "b"), ticket(3, "c")];
+
+        let out = process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert_eq!(out.fresh, vec![3, 2]);
+        assert_eq!(ids(&sink.events()), vec![3, 2]);
+
+        let out = process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert!(out.fresh.is_empty());
+        assert_eq!(sink.events().len(), 2);
+    }
+
+    #[test]
+    fn muted_requester_is_marked_seen_but_not_notified() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        st.mute(MuteTarget::Requester("noisy".into()), 3600);
+        let tickets = [ticket(1, "noisy"), ticket(2, "quiet")];
+
+        process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+
+        assert_eq!(ids(&sink.events()), vec![2]);
+        assert!(st.seen_ticket_ids.contains(&1));
+    }
+
+    #[test]
+    fn picked_actions_are_returned_and_expired_snoozes_renotify() {
+        let sink = CaptureSink::with_action(ToastAction::Snooze);
+        let mut st = SeenState::default();
+        st.seen_ticket_ids.insert(5);
+        st.snoozed.insert(5, 0); // already expired
+        st.snoozed.insert(9, 0); // no longer New -> dropped
+        let tickets = [ticket(5, "a")];
+
+        let out = process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+
+        assert_eq!(ids(&sink.events()), vec![5]);
+        assert_eq!(out.actions.len(), 1);
+        assert_eq!(out.actions[0].1, ToastAction::Snooze);
+        assert!(st.snoozed.is_empty());
+    }
+
+    #[test]
+    fn offline_backlog_is_summarized_once() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        st.seen_ticket_ids.insert(1);
+        let dated = |id: i64, prio: i64, date: &str| Ticket {
+            priority: Some(prio),
+            date: Some(date.to_string()),
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            ..ticket(id, "a")
+        };
+        let tickets = [
+            dated(1, 3, "2020-01-01 08:00:00"),
+            dated(2, 5, "2030-01-01 08:00:00"),
+            dated(3, 3, "2030-01-01 09:00:00"),
+            dated(4, 6, "2019-01-01 09:00:00"), // opened before the outage -> toasted normally
+        ];
+        let since = Ticket { date: Some("2025-01-01 00:00:00".into()), ..ticket(0, "x") }.created_ts();
+        let mut flags = StartupFlags { offline_since: since, ..Default::default() };
+
+        let out = process_tickets(&tickets, &mut st, &mut flags, &sink).unwrap();
+
+        assert_eq!(out.fresh, vec![4, 3, 2]);
+        assert_eq!(ids(&sink.events()), vec![4]);
+        let messages = sink.messages();
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].0.contains("2 new tickets while offline"));
+        assert!(messages[0].1.starts_with("#2 [P5]"));
+        assert!(flags.offline_since.is_none());
+    }
+
+    #[test]
+    fn notifications_are_withdrawn_when_ticket_leaves_new() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+
+        process_tickets(&[ticket(1, "a"), ticket(2, "b")], &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert!(sink.withdrawn().is_empty());
+
+        let remaining = [ticket(2, "b")];
+        let out = process_tickets(&remaining, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert_eq!(sink.withdrawn(), vec![1]);
+        assert_eq!(out.picked_up, vec![1]);
+        assert_eq!(st.shown.iter().copied().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn tickets_beyond_the_search_window_are_still_new() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        process_tickets(&[ticket(1, "a"), ticket(2, "b")], &mut st, &mut StartupFlags::default(), &sink).unwrap();
+
+        // #1 fell out of the window (more New tickets than the search returns) but is still New
+        st.beyond_window = BTreeSet::from([1]);
+        let window = [ticket(3, "c"), ticket(2, "b")];
+        let out = process_tickets(&window, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert!(sink.withdrawn().is_empty());
+        assert!(out.picked_up.is_empty());
+        assert_eq!(out.fresh, vec![3]);
+    }
+
+    #[test]
+    fn backlog_progress_starts_and_ends_once() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState { deferred: (1..=300).collect(), backlog: 300, ..Default::default() };
+        report_backlog(&mut st, &sink);
+        st.deferred.retain(|id| *id <= 100);
+        report_backlog(&mut st, &sink);
+        st.deferred.clear();
+        report_backlog(&mut st, &sink);
+
+        let statuses: Vec<String> = sink.messages().into_iter().map(|(_, body)| body).collect();
+        assert_eq!(statuses, ["Processing 300 backlog tickets…", "Done: 300 backlog tickets processed."]);
+        assert_eq!(st.backlog, 0);
+    }
+
+    #[test]
+    fn critical_tickets_repeat_until_they_leave_new() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        let repeat = BTreeMap::from([(Severity::Critical, 600)]);
+        let mut flags = StartupFlags { repeat, ..Default::default() };
+        let tickets = [Ticket { priority: Some(6), ..ticket(1, "a") }, ticket(2, "b")];
+
+        process_tickets(&tickets, &mut st, &mut flags, &sink).unwrap();
+        process_tickets(&tickets, &mut st, &mut flags, &sink).unwrap();
+        assert_eq!(sink.events().len(), 2, "not due yet");
+
+        // Ten minutes later: only the Critical ticket comes back
+        st.notified_at.values_mut().for_each(|at| *at -= 600);
+        let out = process_tickets(&tickets, &mut st, &mut flags, &sink).unwrap();
+        assert_eq!(out.fresh, vec![1]);
+
+        process_tickets(&tickets[1..], &mut st, &mut flags, &sink).unwrap();
+        assert!(!st.notified_at.contains_key(&1));
+    }
+
+    #[test]
+    fn tickets_back_in_new_are_notified_again_when_enabled() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        let mut flags = StartupFlags { notify_restored: true, ..Default::default() };
+        let both = [ticket(1, "a"), ticket(2, "b")];
+
+        process_tickets(&both, &mut st, &mut flags, &sink).unwrap();
+        process_tickets(&[ticket(2, "b")], &mut st, &mut flags, &sink).unwrap(); // #1 trashed
+        let out = process_tickets(&both, &mut st, &mut flags, &sink).unwrap();
+        assert_eq!(out.fresh, vec![1]);
+        assert_eq!(sink.events().iter().map(|t| t.id).collect::<Vec<_>>(), vec![2, 1, 1]);
+
+        // Disabled (default): a seen ticket stays seen
+        let mut flags = StartupFlags::default();
+        process_tickets(&[ticket(2, "b")], &mut st, &mut flags, &sink).unwrap();
+        let out = process_tickets(&both, &mut st, &mut flags, &sink).unwrap();
+        assert!(out.fresh.is_empty());
+    }
+
+    #[test]
+    fn suppression_reasons_mirror_the_pipeline() {
+        let mut st = SeenState::default();
+        st.seen_ticket_ids.insert(1);
+        let now = now_secs();
+
+        assert!(suppression_reasons(&ticket(2, "a"), Some(1), &st, now).is_empty());
+        assert_eq!(suppression_reasons(&ticket(2, "a"), Some(2), &st, now).len(), 1);
+        assert!(suppression_reasons(&ticket(1, "a"), Some(1), &st, now)[0].contains("already notified"));
+
+        st.snoozed.insert(1, now - 1);
+        assert!(suppression_reasons(&ticket(1, "a"), Some(1), &st, now).is_empty());
+
+        st.mute(MuteTarget::Requester("a".into()), 3600);
+        assert!(suppression_reasons(&ticket(2, "a"), Some(1), &st, now)[0].contains("muted"));
+    }
+
+    #[test]
+    fn failed_delivery_does_not_block_other_tickets_and_gives_up() {
+        let sink = CaptureSink::failing_on(&[2]);
+        let mut st = SeenState::default();
+        st.seen_ticket_ids.insert(0);
+        let tickets = [ticket(1, "a"), ticket(2, "b"), ticket(3, "c")];
+
+        let out = process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert_eq!(ids(&sink.events()), vec![3, 1]);
+        assert!(out.dirty);
+        assert!(!st.seen_ticket_ids.contains(&2));
+
+        process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert!(!st.seen_ticket_ids.contains(&2));
+        process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert!(st.seen_ticket_ids.contains(&2)); // third failure: given up
+        assert!(st.notify_failures.is_empty());
+        assert_eq!(sink.events().len(), 2);
+    }
+
+    #[test]
+    fn focus_holds_low_severity_tickets_then_digests_them() {
+        let sink = CaptureSink::default();
+        let mut st = SeenState::default();
+        st.seen_ticket_ids.insert(0);
+        st.focus = Some(focus::Focus { ticket_id: 9, until: now_secs() + 600 });
+        let tickets = [ticket(1, "a"), Ticket { priority: Some(6), ..ticket(2, "b") }];
+
+        process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert_eq!(ids(&sink.events()), vec![2]);
+        assert_eq!(st.focus_held, vec![1]);
+
+        st.focus = None;
+        process_tickets(&tickets, &mut st, &mut StartupFlags::default(), &sink).unwrap();
+        assert_eq!(sink.events().len(), 1);
+        assert_eq!(sink.messages()[0].0, "GLPI: 1 tickets arrived during focus");
+        assert!(st.focus_held.is_empty());
+    }
 }
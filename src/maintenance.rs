@@ -0,0 +1,210 @@
+//! Planned GLPI downtime: while a maintenance window is on, a failed poll is expected. It is logged once per
+//! window instead of a warning per poll, and neither counts as a poll error in heartbeat.json (`check`) nor
+//! turns the tray, status.json and Home Assistant entities to error. Polling goes on: the first poll that
+//! succeeds, or the end of the window, brings back normal alerting.
+//!
+//! Windows come from MAINTENANCE_WINDOWS and from GLPI reminders:
+//!
+//! - `MAINTENANCE_WINDOWS=sun 02:00-04:00; mon-fri 22:30-23:00; 2026-11-07 20:00..2026-11-08 06:00`: weekly
+//!   windows (one ending before it starts runs past midnight) and one-off ones, in the GLPI server's timezone
+//!   (GLPI_SERVER_TZ);
+//! - `MAINTENANCE_REMINDER=Maintenance GLPI`: planned reminders (Tools > Reminders) whose title contains this
+//!   text. They are read while GLPI is up, at most every READ_EVERY, and kept in the state so a restart during
+//!   the downtime still knows them.
+
+use crate::clock;
+use crate::exit::ConfigError;
+use crate::glpi::GlpiClient;
+use crate::state::{now_secs, SeenState};
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, NaiveTime, Utc};
+use log::{info, warn};
+
+/// How often the GLPI reminders are read again.
+const READ_EVERY: u64 = 600;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Window {
+    /// Every week, on the weekdays `first..=last` (Monday = 0), from `begin` to `end` (the next day when
+    /// `end <= begin`), server local time.
+    Weekly { first: usize, last: usize, begin: NaiveTime, end: NaiveTime },
+    /// Once, between two instants.
+    Once { begin: DateTime<Utc>, end: DateTime<Utc> },
+}
+
+fn weekday(s: &str) -> Option<usize> {
+    ["mon", "tue", "wed", "thu", "fri", "sat", "sun"].iter().position(|d| s.trim().eq_ignore_ascii_case(d))
+}
+
+/// Parse MAINTENANCE_WINDOWS: windows separated by `;`, either weekly (`sun 02:00-04:00`, `mon-fri 22:00-01:00`)
+/// or one-off (`2026-11-07 20:00..2026-11-08 06:00`).
+pub fn parse(raw: &str) -> Result<Vec<Window>> {
+    raw.split(';')
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let bad = || {
+                ConfigError(format!(
+                    "'{w}': expected e.g. sun 02:00-04:00 or 2026-11-07 20:00..2026-11-08 06:00 (server time)"
+                ))
+            };
+            if let Some((begin, end)) = w.split_once("..") {
+                let instant = |s: &str| {
+                    let naive = NaiveDateTime::parse_from_str(s.trim(), "%Y-%m-%d %H:%M").ok()?;
+                    clock::parse_glpi_date(&naive.format("%Y-%m-%d %H:%M:%S").to_string())
+                };
+                let (begin, end) = (instant(begin).ok_or_else(bad)?, instant(end).ok_or_else(bad)?);
+                return if begin < end { Ok(Window::Once { begin, end }) } else { Err(bad()) };
+            }
+            let (days, hours) = w.split_once(char::is_whitespace).ok_or_else(bad)?;
+            let (first, last) = match days.split_once('-') {
+                Some((a, b)) => (weekday(a).ok_or_else(bad)?, weekday(b).ok_or_else(bad)?),
+                None => (weekday(days).ok_or_else(bad)?, weekday(days).ok_or_else(bad)?),
+            };
+            let (begin, end) = hours.trim().split_once('-').ok_or_else(bad)?;
+            let time = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M").ok();
+            Ok(Window::Weekly { first, last, begin: time(begin).ok_or_else(bad)?, end: time(end).ok_or_else(bad)? })
+        })
+        .collect::<Result<_, _>>()
+        .map_err(Into::into)
+}
+
+impl Window {
+    /// End of the window when `now` is in it.
+    pub fn end_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match *self {
+            Window::Once { begin, end } => (begin <= now && now < end).then_some(end),
+            Window::Weekly { first, last, begin, end } => {
+                let local = clock::server_local(now);
+                let today = local.weekday().num_days_from_monday() as usize;
+                let on = |day: usize| {
+                    if first <= last {
+                        (first..=last).contains(&day)
+                    } else {
+                        day >= first || day <= last
+                    }
+                };
+                let time = local.time();
+                let left = if begin < end {
+                    (on(today) && begin <= time && time < end).then(|| end - time)
+                } else if on(today) && begin <= time {
+                    Some(Duration::days(1) - (time - end))
+                } else {
+                    (on((today + 6) % 7) && time < end).then(|| end - time)
+                };
+                left.map(|left| now + left)
+            }
+        }
+    }
+}
+
+/// End (UNIX timestamp) of the maintenance window `now` is in, among `windows` and the `planned` GLPI reminders
+/// (begin, end); the latest one when windows overlap.
+pub fn until_among(windows: &[Window], planned: &[(u64, u64)], now: DateTime<Utc>) -> Option<u64> {
+    let secs = now.timestamp().max(0) as u64;
+    let configured = windows.iter().filter_map(|w| w.end_at(now)).map(|end| end.timestamp().max(0) as u64);
+    let read = planned.iter().filter(|(begin, end)| *begin <= secs && secs < *end).map(|(_, end)| *end);
+    configured.chain(read).max()
+}
+
+/// End (UNIX timestamp) of the maintenance window on now, if any.
+pub fn until(planned: &[(u64, u64)]) -> Option<u64> {
+    until_among(&crate::config::get().maintenance_windows, planned, Utc::now())
+}
+
+/// MAINTENANCE_REMINDER: a shorter text would be found in the title of unrelated reminders.
+pub fn check_reminder(raw: &str) -> Result<()> {
+    if raw.trim().chars().count() < 3 {
+        return Err(ConfigError(format!("'{}': at least 3 characters of the reminders' title", raw.trim())).into());
+    }
+    Ok(())
+}
+
+/// After a successful poll: read the planned reminders of MAINTENANCE_REMINDER again when due, keeping the
+/// windows not over yet. Returns true when the state changed.
+pub async fn refresh(client: &mut GlpiClient, st: &mut SeenState) -> bool {
+    let Some(text) = crate::config::get().maintenance_reminder.as_deref() else { return false };
+    let now = now_secs();
+    if now < st.maintenance_read + READ_EVERY {
+        return false;
+    }
+    st.maintenance_read = now;
+    let reminders = match client.planned_reminders().await {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("MAINTENANCE_REMINDER: could not read the GLPI reminders: {e:#}");
+            return true;
+        }
+    };
+    let text = text.to_lowercase();
+    let mut planned: Vec<(u64, u64)> = reminders
+        .iter()
+        .filter(|r| r.name.to_lowercase().contains(&text))
+        .filter_map(|r| {
+            let at = |d: &Option<String>| clock::parse_glpi_date(d.as_deref()?).map(|d| d.timestamp().max(0) as u64);
+            Some((at(&r.begin)?, at(&r.end)?))
+        })
+        .filter(|(begin, end)| begin < end && *end > now)
+        .collect();
+    planned.sort_unstable();
+    if planned != st.maintenance {
+        for (begin, end) in planned.iter().filter(|w| !st.maintenance.contains(w)) {
+            info!("Maintenance window planned in GLPI: {} to {}", local(*begin), local(*end));
+        }
+        st.maintenance = planned;
+    }
+    true
+}
+
+/// A UNIX timestamp as GLPI server time, for the logs.
+pub fn local(ts: u64) -> String {
+    DateTime::from_timestamp(ts as i64, 0)
+        .map(|at| clock::server_local(at).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn windows_cover_their_hours_only() {
+        // Times of the weekly windows are server local time: compare with a naive instant taken as UTC when
+        // GLPI_SERVER_TZ is not set and the machine runs in UTC, as in CI
+        let windows = parse("sun 02:00-04:00; mon-fri 22:00-01:00; 2026-11-07 20:00..2026-11-08 06:00").unwrap();
+        assert_eq!(windows.len(), 3);
+        assert!(parse("sun").is_err());
+        assert!(parse("sun 25:00-26:00").is_err());
+        assert!(parse("2026-11-08 06:00..2026-11-07 20:00").is_err());
+        assert!(check_reminder("Maintenance GLPI").is_ok());
+        assert!(check_reminder(" x ").is_err());
+
+        let once = &windows[2];
+        let Window::Once { begin, end } = *once else { panic!("not a one-off window") };
+        assert_eq!(once.end_at(begin), Some(end));
+        assert_eq!(once.end_at(end), None);
+        assert_eq!(until_among(&windows[2..], &[], begin - Duration::minutes(1)), None);
+
+        let planned = [(100, 200)];
+        let at = |ts: i64| Utc.timestamp_opt(ts, 0).unwrap();
+        assert_eq!(until_among(&[], &planned, at(150)), Some(200));
+        assert_eq!(until_among(&[], &planned, at(200)), None);
+
+        if chrono::Local::now().offset().local_minus_utc() != 0 {
+            return;
+        }
+        // 2026-10-18 is a Sunday, 2026-10-16 a Friday
+        let sunday = &windows[0];
+        let at = |d: u32, h: u32, m: u32| Utc.with_ymd_and_hms(2026, 10, d, h, m, 0).unwrap();
+        assert_eq!(sunday.end_at(at(18, 3, 0)), Some(at(18, 4, 0)));
+        assert_eq!(sunday.end_at(at(18, 4, 0)), None);
+        assert_eq!(sunday.end_at(at(17, 3, 0)), None);
+        let nightly = &windows[1];
+        assert_eq!(nightly.end_at(at(16, 23, 0)), Some(at(17, 1, 0)));
+        assert_eq!(nightly.end_at(at(17, 0, 30)), Some(at(17, 1, 0)));
+        assert_eq!(nightly.end_at(at(17, 23, 0)), None);
+        assert_eq!(nightly.end_at(at(18, 0, 30)), None);
+    }
+}
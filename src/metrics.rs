@@ -0,0 +1,238 @@
+//! Poll history for graphing queue trends (STATS_HISTORY, on by default): one sample per poll (New queue depth,
+//! tickets notified, poll duration) appended to `metrics.jsonl`, rolled up per day into `metrics-daily.json`
+//! when the day changes. Raw samples are kept STATS_RAW_DAYS days (default 7), daily rollups for good.
+//! Days are GLPI_SERVER_TZ days. `stats export` prints both as CSV or JSON, e.g. for Grafana's Infinity or
+//! CSV data sources.
+
+use crate::clock;
+use crate::state::now_secs;
+
+use anyhow::{anyhow, Result};
+use chrono::DateTime;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Day of the last rollup check in this run; a poll on another day rolls up the days before it.
+static CHECKED_DAY: Mutex<Option<String>> = Mutex::new(None);
+
+/// One poll.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    /// UNIX time the poll ended.
+    pub ts: u64,
+    pub ok: bool,
+    /// New tickets in GLPI (0 for a failed poll).
+    pub queue: u64,
+    /// Tickets notified by this poll.
+    pub new: u64,
+    pub latency_ms: u64,
+}
+
+/// One day of polls. Queue and latency figures cover the successful polls only.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Daily {
+    /// `YYYY-MM-DD`.
+    pub day: String,
+    pub polls: u64,
+    pub failed: u64,
+    pub queue_min: u64,
+    pub queue_max: u64,
+    pub queue_avg: f64,
+    /// Tickets notified that day.
+    pub new: u64,
+    pub latency_avg_ms: u64,
+    pub latency_max_ms: u64,
+}
+
+fn enabled() -> bool {
+    crate::config::get().stats_history
+}
+
+fn raw_days() -> u64 {
+    crate::config::get().stats_raw_days
+}
+
+fn path(name: &str) -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("GlpiNotifier");
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join(name))
+}
+
+fn day_of(ts: u64) -> String {
+    let at = DateTime::from_timestamp(ts as i64, 0).unwrap_or_default();
+    clock::server_local(at).format("%Y-%m-%d").to_string()
+}
+
+/// Record a poll that took `latency`; `polled` is (queue depth, tickets notified), None when it failed.
+pub fn record(latency: Duration, polled: Option<(u64, usize)>) {
+    if !enabled() {
+        return;
+    }
+    let (queue, new) = polled.unwrap_or_default();
+    let sample =
+        Sample { ts: now_secs(), ok: polled.is_some(), queue, new: new as u64, latency_ms: latency.as_millis() as u64 };
+    let today = day_of(sample.ts);
+    let mut checked = CHECKED_DAY.lock().unwrap();
+    if checked.as_deref() != Some(today.as_str()) {
+        if let Err(e) = roll_up(&today) {
+            warn!("Could not roll up the poll history: {e:#}");
+        }
+        *checked = Some(today);
+    }
+    if let Err(e) = append(&sample) {
+        warn!("Could not record the poll in metrics.jsonl: {e:#}");
+    }
+}
+
+fn append(sample: &Sample) -> Result<()> {
+    let path = path("metrics.jsonl").ok_or_else(|| anyhow!("no data directory"))?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(sample)?)?;
+    Ok(())
+}
+
+/// Raw samples, oldest first; unreadable lines (a write cut short) are skipped.
+pub fn samples() -> Vec<Sample> {
+    let Some(data) = path("metrics.jsonl").and_then(|p| fs::read_to_string(p).ok()) else { return Vec::new() };
+    data.lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+/// Persisted daily rollups, oldest first.
+pub fn daily() -> Vec<Daily> {
+    let Some(data) = path("metrics-daily.json").and_then(|p| fs::read(p).ok()) else { return Vec::new() };
+    serde_json::from_slice(&data).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable metrics-daily.json: {e}");
+        Vec::new()
+    })
+}
+
+/// Roll up the days before `today` not rolled up yet, and drop raw samples older than STATS_RAW_DAYS.
+fn roll_up(today: &str) -> Result<()> {
+    let samples = samples();
+    let mut days = daily();
+    let done = days.last().map(|d| d.day.clone()).unwrap_or_default();
+    let past: Vec<Sample> = samples.iter().filter(|s| *day_of(s.ts) < *today).cloned().collect();
+    days.extend(summarize(&past, day_of).into_iter().filter(|d| d.day > done));
+    write_atomic("metrics-daily.json", &serde_json::to_vec(&days)?)?;
+
+    let keep_from = day_of(now_secs().saturating_sub(raw_days() * 86400));
+    let kept: Vec<&Sample> = samples.iter().filter(|s| day_of(s.ts) >= keep_from).collect();
+    if kept.len() < samples.len() {
+        let mut lines = String::new();
+        for s in kept {
+            lines.push_str(&serde_json::to_string(s)?);
+            lines.push('\n');
+        }
+        write_atomic("metrics.jsonl", lines.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn write_atomic(name: &str, data: &[u8]) -> Result<()> {
+    let path = path(name).ok_or_else(|| anyhow!("no data directory"))?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, data)?;
+    Ok(fs::rename(&tmp, &path)?)
+}
+
+/// Daily rollups of `samples`, by the day `day_of` gives each timestamp.
+pub fn summarize(samples: &[Sample], day_of: impl Fn(u64) -> String) -> Vec<Daily> {
+    let mut by_day: BTreeMap<String, Vec<&Sample>> = BTreeMap::new();
+    for s in samples {
+        by_day.entry(day_of(s.ts)).or_default().push(s);
+    }
+    by_day
+        .into_iter()
+        .map(|(day, polls)| {
+            let ok: Vec<&&Sample> = polls.iter().filter(|s| s.ok).collect();
+            let n = ok.len().max(1) as u64;
+            Daily {
+                day,
+                polls: polls.len() as u64,
+                failed: (polls.len() - ok.len()) as u64,
+                queue_min: ok.iter().map(|s| s.queue).min().unwrap_or(0),
+                queue_max: ok.iter().map(|s| s.queue).max().unwrap_or(0),
+                queue_avg: (ok.iter().map(|s| s.queue).sum::<u64>() as f64 / n as f64 * 10.0).round() / 10.0,
+                new: ok.iter().map(|s| s.new).sum(),
+                latency_avg_ms: ok.iter().map(|s| s.latency_ms).sum::<u64>() / n,
+                latency_max_ms: ok.iter().map(|s| s.latency_ms).max().unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// Daily rollups including today so far (from the raw samples), the last `days` days when given.
+pub fn daily_with_today(days: Option<usize>) -> Vec<Daily> {
+    let mut rows = daily();
+    let done = rows.last().map(|d| d.day.clone()).unwrap_or_default();
+    rows.extend(summarize(&samples(), day_of).into_iter().filter(|d| d.day > done));
+    if let Some(days) = days {
+        rows.drain(..rows.len().saturating_sub(days));
+    }
+    rows
+}
+
+/// CSV with a header row; `time` is ISO 8601 UTC.
+pub fn samples_csv(samples: &[Sample]) -> String {
+    let mut out = String::from("time,ok,queue,new,latency_ms\n");
+    for s in samples {
+        let time = DateTime::from_timestamp(s.ts as i64, 0).unwrap_or_default().format("%Y-%m-%dT%H:%M:%SZ");
+        out.push_str(&format!("{time},{},{},{},{}\n", s.ok, s.queue, s.new, s.latency_ms));
+    }
+    out
+}
+
+pub fn daily_csv(days: &[Daily]) -> String {
+    let mut out = String::from("day,polls,failed,queue_min,queue_max,queue_avg,new,latency_avg_ms,latency_max_ms\n");
+    for d in days {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            d.day, d.polls, d.failed, d.queue_min, d.queue_max, d.queue_avg, d.new, d.latency_avg_ms, d.latency_max_ms
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polls_roll_up_per_day() {
+        let sample = |ts, ok, queue, new, latency_ms| Sample { ts, ok, queue, new, latency_ms };
+        let samples = [
+            sample(100, true, 4, 4, 200),
+            sample(160, true, 6, 2, 400),
+            sample(220, false, 0, 0, 10_000),
+            sample(90_000, true, 1, 0, 300),
+        ];
+        let days = summarize(&samples, |ts| format!("day{}", ts / 86_400));
+        assert_eq!(
+            days[0],
+            Daily {
+                day: "day0".into(),
+                polls: 3,
+                failed: 1,
+                queue_min: 4,
+                queue_max: 6,
+                queue_avg: 5.0,
+                new: 6,
+                latency_avg_ms: 300,
+                latency_max_ms: 400,
+            }
+        );
+        assert_eq!((days[1].day.as_str(), days[1].polls, days[1].queue_max), ("day1", 1, 1));
+        assert_eq!(
+            daily_csv(&days[1..]),
+            "day,polls,failed,queue_min,queue_max,queue_avg,new,latency_avg_ms,latency_max_ms\n\
+             day1,1,0,1,1,1,0,300,300\n"
+        );
+        assert_eq!(samples_csv(&samples[..1]), "time,ok,queue,new,latency_ms\n1970-01-01T00:01:40Z,true,4,4,200\n");
+    }
+}
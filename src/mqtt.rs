@@ -0,0 +1,103 @@
+//! MQTT sink: the status of every poll (the status.json document) and an event per notified ticket, published
+//! to a broker (MQTT_URL) for dashboards, with optional Home Assistant MQTT discovery (HA_DISCOVERY=true).
+//!
+//! Topics, under MQTT_TOPIC (default `glpi-notifier`) and this machine's node id (its hostname):
+//! - `<topic>/<node>/state`: status.json, retained;
+//! - `<topic>/<node>/event`: `{"event_type": "new_ticket", "id": 101, "name": …}` per notified ticket.
+//!
+//! With discovery, Home Assistant gets a device with a "New tickets pending" binary sensor, a "New tickets"
+//! sensor (queue depth) and a "New ticket" event entity, under `<HA_DISCOVERY_PREFIX>/…/config` (retained).
+//! They become unavailable when a poll fails, and expire after three poll intervals without news.
+//!
+//! A minimal MQTT 3.1.1 client: plain TCP (`mqtt://`, port 1883 by default) or TLS (`mqtts://`, port 8883,
+//! certificates checked against the Mozilla roots), QoS 0, optional MQTT_USERNAME / MQTT_PASSWORD. The password
+//! is refused over plain TCP unless the broker is on this machine. Publishing happens on a worker thread, one
+//! connection per poll, so a slow or unreachable broker never delays polling.
+
+use crate::exit::ConfigError;
+
+use anyhow::{anyhow, Result};
+use std::env;
+use std::net::IpAddr;
+
+#[cfg(feature = "mqtt")]
+mod client;
+
+#[cfg(feature = "mqtt")]
+pub use client::{flush, publish_new_tickets, publish_status};
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_TLS_PORT: u16 = 8883;
+
+/// `mqtt://host[:port]` or `mqtts://host[:port]`, checked at startup (MQTT_URL): host, port and whether TLS is
+/// used.
+pub fn parse_url(raw: &str) -> Result<(String, u16, bool)> {
+    let url = reqwest::Url::parse(raw.trim()).map_err(|e| anyhow!("malformed URL ({e})"))?;
+    let tls = match url.scheme() {
+        "mqtt" | "tcp" => false,
+        "mqtts" | "ssl" => true,
+        _ => return Err(anyhow!("expected mqtt://host[:port] or mqtts://host[:port]")),
+    };
+    let host = url.host_str().filter(|h| !h.is_empty()).ok_or_else(|| anyhow!("missing the broker host"))?;
+    let port = url.port().unwrap_or(if tls { DEFAULT_TLS_PORT } else { DEFAULT_PORT });
+    Ok((host.trim_matches(['[', ']']).to_string(), port, tls))
+}
+
+/// MQTT_PASSWORD is only sent over TLS, or to a broker on this machine (MQTT_URL `host`).
+fn check_password(host: &str, tls: bool) -> Result<()> {
+    let local = host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback());
+    if tls || local {
+        return Ok(());
+    }
+    Err(ConfigError(format!("MQTT_PASSWORD would go to {host} in the clear: use an mqtts:// MQTT_URL")).into())
+}
+
+/// MQTT_URL, and MQTT_PASSWORD against it, checked at startup; an error in builds without the client.
+pub fn check_url(raw: &str) -> Result<()> {
+    if cfg!(not(feature = "mqtt")) {
+        return Err(anyhow!("this build has no MQTT client (cargo feature mqtt)"));
+    }
+    let (host, _, tls) = parse_url(raw)?;
+    match env::var("MQTT_PASSWORD").is_ok_and(|p| !p.trim().is_empty()) {
+        true => check_password(&host, tls),
+        false => Ok(()),
+    }
+}
+
+/// A topic to publish under (MQTT_TOPIC, HA_DISCOVERY_PREFIX): no wildcards, no empty levels.
+pub fn check_topic(raw: &str) -> Result<()> {
+    if raw.contains(['+', '#']) {
+        return Err(anyhow!("wildcards (+, #) are not allowed in a topic to publish to"));
+    }
+    if raw.split('/').any(str::is_empty) {
+        return Err(anyhow!("empty topic level (leading, trailing or double /)"));
+    }
+    Ok(())
+}
+
+/// Built without the `mqtt` feature: MQTT_URL is reported by the settings check and nothing is published.
+#[cfg(not(feature = "mqtt"))]
+pub fn publish_status(_status: &crate::widget::WidgetStatus) {}
+
+#[cfg(not(feature = "mqtt"))]
+pub fn publish_new_tickets<'a>(_tickets: impl IntoIterator<Item = &'a crate::glpi::Ticket>) {}
+
+#[cfg(not(feature = "mqtt"))]
+pub fn flush(_timeout: std::time::Duration) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_and_topics_are_checked() {
+        assert_eq!(parse_url("mqtts://broker").unwrap(), ("broker".to_string(), 8883, true));
+        assert_eq!(parse_url("mqtt://broker").unwrap(), ("broker".to_string(), 1883, false));
+        assert!(parse_url("http://broker").is_err());
+        assert!(check_password("broker", false).is_err());
+        assert!(check_password("broker", true).is_ok() && check_password("127.0.0.1", false).is_ok());
+        assert!(check_topic("glpi/+/x").is_err() && check_topic("glpi/").is_err() && check_topic("a/b").is_ok());
+    }
+}
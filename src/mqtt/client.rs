@@ -0,0 +1,438 @@
+//! The MQTT 3.1.1 client of the sink and its worker thread (TLS through rustls).
+
+use super::{check_password, check_topic, parse_url};
+use crate::glpi::Ticket;
+use crate::toast::ticket_url_for;
+use crate::widget::WidgetStatus;
+
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use serde_json::{json, Value};
+use std::env;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_TOPIC: &str = "glpi-notifier";
+const DEFAULT_DISCOVERY_PREFIX: &str = "homeassistant";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+static SINK: OnceCell<Option<Sink>> = OnceCell::new();
+
+/// A message to publish.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Message {
+    topic: String,
+    payload: String,
+    retain: bool,
+}
+
+/// Broker and topics, from MQTT_URL, MQTT_TOPIC and the HA_DISCOVERY settings.
+#[derive(Debug, Clone)]
+struct Settings {
+    host: String,
+    port: u16,
+    tls: bool,
+    username: Option<String>,
+    password: Option<String>,
+    /// `<MQTT_TOPIC>/<node>`.
+    base: String,
+    node: String,
+    /// HA_DISCOVERY_PREFIX when HA_DISCOVERY=true.
+    discovery: Option<String>,
+    /// Seconds without a new state after which Home Assistant shows the entities as unknown.
+    expire_after: u64,
+}
+
+struct Sink {
+    queue: Sender<Vec<Message>>,
+    /// Batches sent to the worker and not yet published (or given up).
+    pending: Arc<AtomicUsize>,
+    settings: Settings,
+}
+
+impl Settings {
+    fn from_env() -> Result<Option<Self>> {
+        let var = |key: &str| env::var(key).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let Some(url) = var("MQTT_URL") else { return Ok(None) };
+        let (host, port, tls) = parse_url(&url).map_err(|e| e.context("MQTT_URL"))?;
+        let password = var("MQTT_PASSWORD");
+        if password.is_some() {
+            check_password(&host, tls)?;
+        }
+        let topic = var("MQTT_TOPIC").filter(|t| check_topic(t).is_ok()).unwrap_or_else(|| DEFAULT_TOPIC.into());
+        let node = node_id();
+        let discovery = crate::config::get().ha_discovery.then(|| {
+            var("HA_DISCOVERY_PREFIX")
+                .filter(|t| check_topic(t).is_ok())
+                .unwrap_or_else(|| DEFAULT_DISCOVERY_PREFIX.into())
+        });
+        Ok(Some(Self {
+            host,
+            port,
+            tls,
+            username: var("MQTT_USERNAME"),
+            password,
+            base: format!("{topic}/{node}"),
+            node,
+            discovery,
+            expire_after: 3 * crate::config::get().poll_seconds.max(1),
+        }))
+    }
+
+    fn state_topic(&self) -> String {
+        format!("{}/state", self.base)
+    }
+
+    fn event_topic(&self) -> String {
+        format!("{}/event", self.base)
+    }
+}
+
+/// This machine's hostname, lowercase with anything but letters and digits as `_` (topic level and HA ids).
+fn node_id() -> String {
+    let host = env::var("COMPUTERNAME")
+        .or_else(|_| env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost".into());
+    host.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect()
+}
+
+/// The sink when MQTT_URL is set; started on first use. The discovery configs go out with the first batch.
+fn sink() -> Option<&'static Sink> {
+    SINK.get_or_init(|| {
+        let settings = match Settings::from_env() {
+            Ok(s) => s?,
+            Err(e) => {
+                warn!("MQTT disabled: {e:#}");
+                return None;
+            }
+        };
+        info!(
+            "MQTT: publishing to {}:{} under {}{}",
+            settings.host,
+            settings.port,
+            settings.base,
+            if settings.discovery.is_some() { " (Home Assistant discovery)" } else { "" }
+        );
+        let (queue, jobs) = mpsc::channel();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let worker = (settings.clone(), pending.clone());
+        std::thread::spawn(move || run(worker.0, jobs, worker.1));
+        let sink = Sink { queue, pending, settings };
+        if let Some(prefix) = &sink.settings.discovery {
+            sink.send(discovery_configs(&sink.settings, prefix));
+        }
+        Some(sink)
+    })
+    .as_ref()
+}
+
+impl Sink {
+    fn send(&self, batch: Vec<Message>) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        if self.queue.send(batch).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Publish the status written to status.json (retained).
+pub fn publish_status(status: &WidgetStatus) {
+    let Some(sink) = sink() else { return };
+    let Ok(payload) = serde_json::to_string(status) else { return };
+    sink.send(vec![Message { topic: sink.settings.state_topic(), payload, retain: true }]);
+}
+
+/// Publish a `new_ticket` event per notified ticket.
+pub fn publish_new_tickets<'a>(tickets: impl IntoIterator<Item = &'a Ticket>) {
+    let Some(sink) = sink() else { return };
+    let batch: Vec<Message> = tickets
+        .into_iter()
+        .map(|t| Message { topic: sink.settings.event_topic(), payload: event_payload(t).to_string(), retain: false })
+        .collect();
+    if !batch.is_empty() {
+        sink.send(batch);
+    }
+}
+
+/// Wait up to `timeout` for the queued messages to reach the broker (`--once`, before exiting).
+pub fn flush(timeout: Duration) -> bool {
+    let Some(sink) = SINK.get().and_then(Option::as_ref) else { return true };
+    let deadline = Instant::now() + timeout;
+    while sink.pending.load(Ordering::SeqCst) > 0 {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    true
+}
+
+fn event_payload(t: &Ticket) -> Value {
+    json!({
+        "event_type": "new_ticket",
+        "id": t.id,
+        "name": t.name,
+        "priority": t.priority,
+        "severity": format!("{:?}", t.severity()).to_lowercase(),
+        "requester": t.requester,
+        "category": t.category,
+        "url": ticket_url_for(t),
+        "refs": crate::refs::of(t),
+        "instance": t.instance,
+        "language": t.language,
+    })
+}
+
+/// Home Assistant discovery configs (retained): pending binary sensor, queue depth sensor, new ticket event.
+fn discovery_configs(s: &Settings, prefix: &str) -> Vec<Message> {
+    let state = s.state_topic();
+    let common = |name: &str, key: &str| {
+        json!({
+            "name": name,
+            "unique_id": format!("glpi_notifier_{}_{key}", s.node),
+            "availability_topic": state,
+            "availability_template": "{{ 'online' if value_json.ok else 'offline' }}",
+            "device": {
+                "identifiers": [format!("glpi_notifier_{}", s.node)],
+                "name": format!("GLPI Notifier ({})", s.node),
+                "manufacturer": "glpi-notifier-rs",
+                "sw_version": env!("CARGO_PKG_VERSION"),
+            },
+            "origin": { "name": "glpi-notifier-rs", "sw_version": env!("CARGO_PKG_VERSION") },
+        })
+    };
+    let entity = |component: &str, key: &str, name: &str, fields: Value| {
+        let mut config = common(name, key);
+        config.as_object_mut().unwrap().extend(fields.as_object().unwrap().clone());
+        Message {
+            topic: format!("{prefix}/{component}/glpi_notifier_{}/{key}/config", s.node),
+            payload: config.to_string(),
+            retain: true,
+        }
+    };
+    vec![
+        entity(
+            "binary_sensor",
+            "pending",
+            "New tickets pending",
+            json!({
+                "state_topic": state,
+                "value_template": "{{ 'ON' if value_json.queue > 0 else 'OFF' }}",
+                "expire_after": s.expire_after,
+                "icon": "mdi:ticket-confirmation",
+            }),
+        ),
+        entity(
+            "sensor",
+            "queue",
+            "New tickets",
+            json!({
+                "state_topic": state,
+                "value_template": "{{ value_json.queue }}",
+                "json_attributes_topic": state,
+                "json_attributes_template": "{{ {'newest': value_json.newest, 'snoozed': value_json.snoozed} | tojson }}",
+                "unit_of_measurement": "tickets",
+                "state_class": "measurement",
+                "expire_after": s.expire_after,
+                "icon": "mdi:ticket-account",
+            }),
+        ),
+        entity(
+            "event",
+            "new_ticket",
+            "New ticket",
+            json!({
+                "state_topic": s.event_topic(),
+                "event_types": ["new_ticket"],
+                "icon": "mdi:ticket-outline",
+            }),
+        ),
+    ]
+}
+
+/// Worker: publish each batch (and whatever queued behind it) over one connection. Failures are logged once
+/// until the broker answers again; their messages are dropped (the next poll publishes a fresh state).
+fn run(settings: Settings, jobs: Receiver<Vec<Message>>, pending: Arc<AtomicUsize>) {
+    let mut failing = false;
+    // The discovery configs must reach the broker before Home Assistant can use the states
+    let mut unsent_discovery: Vec<Message> = Vec::new();
+    while let Ok(mut batch) = jobs.recv() {
+        let mut taken = 1;
+        while let Ok(more) = jobs.try_recv() {
+            batch.extend(more);
+            taken += 1;
+        }
+        let discovery = settings.discovery.as_deref().map(|p| format!("{p}/"));
+        let mut messages = std::mem::take(&mut unsent_discovery);
+        messages.extend(batch);
+        match publish(&settings, &messages) {
+            Ok(()) => {
+                if failing {
+                    info!("MQTT broker {}:{} reachable again", settings.host, settings.port);
+                }
+                failing = false;
+            }
+            Err(e) => {
+                if !failing {
+                    warn!("MQTT publish to {}:{} failed: {e:#}", settings.host, settings.port);
+                }
+                failing = true;
+                unsent_discovery = match &discovery {
+                    Some(prefix) => messages.into_iter().filter(|m| m.topic.starts_with(prefix.as_str())).collect(),
+                    None => Vec::new(),
+                };
+            }
+        }
+        pending.fetch_sub(taken, Ordering::SeqCst);
+    }
+}
+
+fn publish(s: &Settings, messages: &[Message]) -> Result<()> {
+    let addr =
+        (s.host.as_str(), s.port).to_socket_addrs()?.next().ok_or_else(|| anyhow!("{} does not resolve", s.host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    if !s.tls {
+        return session(&mut stream, s, messages);
+    }
+    let roots = rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let name = rustls::pki_types::ServerName::try_from(s.host.clone())
+        .map_err(|_| anyhow!("{} is not a valid TLS server name", s.host))?;
+    let tls = rustls::ClientConnection::new(Arc::new(config), name)?;
+    let mut stream = rustls::StreamOwned::new(tls, stream);
+    session(&mut stream, s, messages)?;
+    stream.conn.send_close_notify();
+    stream.flush()?;
+    Ok(())
+}
+
+/// CONNECT, the PUBLISHes and DISCONNECT over an open connection to the broker.
+fn session(stream: &mut (impl Read + Write), s: &Settings, messages: &[Message]) -> Result<()> {
+    let client_id = format!("glpi-notifier-{}", s.node);
+    stream.write_all(&connect_packet(&client_id, s.username.as_deref(), s.password.as_deref()))?;
+    let mut connack = [0u8; 4];
+    stream.read_exact(&mut connack)?;
+    match connack {
+        [0x20, 2, _, 0] => {}
+        [0x20, 2, _, 4 | 5] => return Err(anyhow!("broker refused the connection: not authorized")),
+        [0x20, 2, _, code] => return Err(anyhow!("broker refused the connection (code {code})")),
+        _ => return Err(anyhow!("unexpected answer to CONNECT: {connack:02x?}")),
+    }
+    for m in messages {
+        stream.write_all(&publish_packet(&m.topic, m.payload.as_bytes(), m.retain))?;
+    }
+    stream.write_all(&[0xE0, 0])?; // DISCONNECT
+    stream.flush()?;
+    Ok(())
+}
+
+/// MQTT 3.1.1 CONNECT with a clean session and a 60 s keep-alive.
+fn connect_packet(client_id: &str, username: Option<&str>, password: Option<&str>) -> Vec<u8> {
+    let mut body = Vec::new();
+    string(&mut body, b"MQTT");
+    body.push(4); // protocol level 3.1.1
+    let mut flags = 0x02; // clean session
+    if username.is_some() {
+        flags |= 0x80;
+        if password.is_some() {
+            flags |= 0x40;
+        }
+    }
+    body.push(flags);
+    body.extend(60u16.to_be_bytes());
+    string(&mut body, client_id.as_bytes());
+    if let Some(user) = username {
+        string(&mut body, user.as_bytes());
+        if let Some(password) = password {
+            string(&mut body, password.as_bytes());
+        }
+    }
+    packet(0x10, body)
+}
+
+/// QoS 0 PUBLISH.
+fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    string(&mut body, topic.as_bytes());
+    body.extend(payload);
+    packet(0x30 | u8::from(retain), body)
+}
+
+/// Length-prefixed UTF-8 string.
+fn string(buf: &mut Vec<u8>, s: &[u8]) {
+    buf.extend((s.len() as u16).to_be_bytes());
+    buf.extend(s);
+}
+
+/// Fixed header (type and flags, variable-length remaining length) followed by `body`.
+fn packet(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut out = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend(body);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packets_follow_mqtt_3_1_1() {
+        assert_eq!(
+            connect_packet("id", Some("u"), Some("p")),
+            [&[0x10, 20, 0, 4][..], b"MQTT", &[4, 0xC2, 0, 60, 0, 2], b"id", &[0, 1], b"u", &[0, 1], b"p"].concat()
+        );
+        assert_eq!(publish_packet("a/b", b"1", true), [&[0x31, 6, 0, 3][..], b"a/b", b"1"].concat());
+        // Remaining length over 127 takes two bytes
+        assert_eq!(&publish_packet("t", &[0; 200], false)[..3], &[0x30, 203, 1]);
+    }
+
+    #[test]
+    fn discovery_configs_match_snapshot() {
+        let settings = Settings {
+            host: "broker".into(),
+            port: 1883,
+            tls: false,
+            username: None,
+            password: None,
+            base: "glpi-notifier/helpdesk_01".into(),
+            node: "helpdesk_01".into(),
+            discovery: Some("homeassistant".into()),
+            expire_after: 180,
+        };
+        let configs = discovery_configs(&settings, "homeassistant");
+        let dump: Vec<String> = configs
+            .iter()
+            .map(|m| {
+                let payload: Value = serde_json::from_str(&m.payload).unwrap();
+                format!("{} (retain: {})\n{}", m.topic, m.retain, serde_json::to_string_pretty(&payload).unwrap())
+            })
+            .collect();
+        insta::assert_snapshot!(dump.join("\n\n").replace(env!("CARGO_PKG_VERSION"), "[version]"));
+    }
+}
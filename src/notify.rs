@@ -0,0 +1,226 @@
+use crate::diff::{self, Change};
+use crate::glpi::Ticket;
+use crate::toast::ToastAction;
+
+use anyhow::Result;
+use log::warn;
+use std::sync::Mutex;
+
+#[cfg(all(unix, not(target_os = "macos"), feature = "notify-dbus"))]
+mod dbus;
+#[cfg(all(unix, not(target_os = "macos"), feature = "notify-dbus"))]
+pub use dbus::DbusNotifier;
+#[cfg(all(feature = "notify-macos", any(target_os = "macos", test)))]
+mod macos;
+#[cfg(all(target_os = "macos", feature = "notify-macos"))]
+pub use macos::MacNotifier;
+
+/// Delivery backend for ticket notifications.
+pub trait Notifier: Send + Sync {
+    /// Notify about one ticket. Returns the action the user picked, when the backend supports actions.
+    fn notify(&self, t: &Ticket) -> Result<Option<ToastAction>>;
+
+    /// Show a message that is not about a single ticket (summaries, warnings).
+    fn message(&self, title: &str, body: &str) -> Result<()>;
+
+    /// Show a reminder that leads to a page (e.g. a survey). Backends without actions show it as a plain message.
+    fn reminder(&self, title: &str, body: &str, _url: &str) -> Result<()> {
+        self.message(title, body)
+    }
+
+    /// Tell what changed on one of my tickets (TICKET_UPDATES). Backends without a layout of their own show the
+    /// one-line summary under the ticket title.
+    fn update(&self, t: &Ticket, changes: &[Change]) -> Result<()> {
+        self.message(&format!("#{} updated", t.id), &format!("{}\n{}", t.name, diff::summary(changes)))
+    }
+
+    /// Remove a ticket's notification once it no longer needs attention (assigned, solved).
+    fn withdraw(&self, _ticket_id: i64) -> Result<()> {
+        Ok(())
+    }
+
+    /// Report progress of a long run (initial sync, backlog catch-up), `done` out of `total`.
+    /// Backends without a progress display show `status` as a message when the run starts and when it ends.
+    fn progress(&self, title: &str, status: &str, done: usize, total: usize) -> Result<()> {
+        if done == 0 || done >= total {
+            self.message(title, status)?;
+        }
+        Ok(())
+    }
+}
+
+/// Windows toast, native or through SnoreToast (default).
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+pub struct ToastNotifier;
+
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+impl Notifier for ToastNotifier {
+    fn notify(&self, t: &Ticket) -> Result<Option<ToastAction>> {
+        crate::toast::show_toast(t)
+    }
+
+    fn message(&self, title: &str, body: &str) -> Result<()> {
+        crate::toast::show_message(title, body)
+    }
+
+    fn reminder(&self, title: &str, body: &str, url: &str) -> Result<()> {
+        crate::toast::show_link(title, body, url)
+    }
+
+    fn update(&self, t: &Ticket, changes: &[Change]) -> Result<()> {
+        crate::toast::show_update(t, changes)
+    }
+
+    fn progress(&self, title: &str, status: &str, done: usize, total: usize) -> Result<()> {
+        crate::toast::show_progress(title, status, done, total)
+    }
+
+    fn withdraw(&self, ticket_id: i64) -> Result<()> {
+        if crate::toast::cleanup_enabled() {
+            crate::toast::close_toast(ticket_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Discards every notification (headless runs: state and heartbeat only).
+pub struct NullSink;
+
+impl Notifier for NullSink {
+    fn notify(&self, _t: &Ticket) -> Result<Option<ToastAction>> {
+        Ok(None)
+    }
+
+    fn message(&self, _title: &str, _body: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Records notified tickets and messages in memory, optionally answering each with a scripted action.
+#[derive(Default)]
+pub struct CaptureSink {
+    events: Mutex<Vec<Ticket>>,
+    messages: Mutex<Vec<(String, String)>>,
+    withdrawn: Mutex<Vec<i64>>,
+    updates: Mutex<Vec<(i64, String)>>,
+    action: Option<ToastAction>,
+    failing: Vec<i64>,
+}
+
+impl CaptureSink {
+    #[cfg(test)]
+    /// Capture sink that reports `action` as picked on every notification.
+    pub fn with_action(action: ToastAction) -> Self {
+        Self { action: Some(action), ..Self::default() }
+    }
+
+    #[cfg(test)]
+    /// Capture sink whose delivery fails for the given ticket ids.
+    pub fn failing_on(ids: &[i64]) -> Self {
+        Self { failing: ids.to_vec(), ..Self::default() }
+    }
+
+    /// Tickets notified so far, in delivery order.
+    pub fn events(&self) -> Vec<Ticket> {
+        self.events.lock().unwrap().clone()
+    }
+
+    #[cfg(test)]
+    /// Messages shown so far as (title, body).
+    pub fn messages(&self) -> Vec<(String, String)> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    #[cfg(test)]
+    /// Updates told so far as (ticket id, summary).
+    pub fn updates(&self) -> Vec<(i64, String)> {
+        self.updates.lock().unwrap().clone()
+    }
+
+    #[cfg(test)]
+    /// Ticket ids withdrawn so far.
+    pub fn withdrawn(&self) -> Vec<i64> {
+        self.withdrawn.lock().unwrap().clone()
+    }
+}
+
+impl Notifier for CaptureSink {
+    fn notify(&self, t: &Ticket) -> Result<Option<ToastAction>> {
+        if self.failing.contains(&t.id) {
+            anyhow::bail!("delivery failed for #{}", t.id);
+        }
+        self.events.lock().unwrap().push(t.clone());
+        Ok(self.action)
+    }
+
+    fn message(&self, title: &str, body: &str) -> Result<()> {
+        self.messages.lock().unwrap().push((title.to_string(), body.to_string()));
+        Ok(())
+    }
+
+    fn update(&self, t: &Ticket, changes: &[Change]) -> Result<()> {
+        self.updates.lock().unwrap().push((t.id, diff::summary(changes)));
+        Ok(())
+    }
+
+    fn withdraw(&self, ticket_id: i64) -> Result<()> {
+        self.withdrawn.lock().unwrap().push(ticket_id);
+        Ok(())
+    }
+}
+
+/// NOTIFIER values this build understands.
+pub const BACKENDS: &[&str] = &[
+    #[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+    "toast",
+    #[cfg(all(unix, not(target_os = "macos"), feature = "notify-dbus"))]
+    "dbus",
+    #[cfg(all(target_os = "macos", feature = "notify-macos"))]
+    "macos",
+    "null",
+];
+
+/// Backend used when NOTIFIER is empty: the desktop notifications of this platform (Windows toasts, macOS
+/// Notification Center, D-Bus elsewhere), else `null`.
+pub fn default_name() -> &'static str {
+    let desktop = if cfg!(windows) {
+        "toast"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        "dbus"
+    };
+    if BACKENDS.contains(&desktop) {
+        desktop
+    } else {
+        "null"
+    }
+}
+
+/// Backend selected by NOTIFIER (`toast`, `dbus`, `macos` or `null`; default: [`default_name`]).
+pub fn from_env() -> Box<dyn Notifier> {
+    let set = crate::config::get().notifier;
+    let name = match set {
+        "" => default_name(),
+        "none" => "null",
+        other if BACKENDS.contains(&other) => other,
+        other => {
+            warn!("Unknown NOTIFIER '{other}' (this build has {}), using {}", BACKENDS.join(", "), default_name());
+            default_name()
+        }
+    };
+    match name {
+        #[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+        "toast" => Box::new(ToastNotifier),
+        #[cfg(all(unix, not(target_os = "macos"), feature = "notify-dbus"))]
+        "dbus" => Box::new(DbusNotifier::default()),
+        #[cfg(all(target_os = "macos", feature = "notify-macos"))]
+        "macos" => Box::new(MacNotifier),
+        _ => {
+            if set.is_empty() {
+                warn!("Built without a desktop notification feature: notifications are discarded (NOTIFIER=null)");
+            }
+            Box::new(NullSink)
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Desktop notifications on Linux/BSD through the freedesktop notification service (D-Bus
+//! `org.freedesktop.Notifications`: GNOME Shell, KDE Plasma, dunst, mako...). Buttons come back as action
+//! signals, waited for on a thread per notification so one left on screen (Critical ones never expire) does
+//! not hold the delivery worker; body markup is escaped since most servers render a subset of HTML.
+
+use super::Notifier;
+use crate::diff::Change;
+use crate::glpi::Ticket;
+use crate::toast::{self, ToastAction, ToastScenario};
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use notify_rust::{Hint, Notification, Timeout, Urgency};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const APP_NAME: &str = "GLPI Notifier";
+
+/// D-Bus notification service backend (NOTIFIER=dbus, the default outside Windows).
+#[derive(Default)]
+pub struct DbusNotifier {
+    /// Server-side id of the notification shown per ticket, to close it on withdraw.
+    shown: Mutex<HashMap<i64, u32>>,
+    /// Id of the progress notification, replaced in place on each update.
+    progress: Mutex<Option<u32>>,
+}
+
+/// `&`, `<` and `>` are markup for servers with the `body-markup` capability.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// The notification as sent to the service, with one button per action.
+fn notification(title: &str, body: &str, scenario: ToastScenario, actions: &[ToastAction]) -> Notification {
+    let mut n = Notification::new();
+    n.appname(APP_NAME).summary(title).body(&escape(body)).hint(Hint::Category("im.received".into()));
+    match toast::ensure_logo_file() {
+        Some(logo) => n.icon(&logo),
+        None => n.icon("dialog-information"),
+    };
+    match scenario {
+        // Stays until answered, like the Windows reminder toast
+        ToastScenario::Reminder => n.urgency(Urgency::Critical).timeout(Timeout::Never),
+        // Critical urgency gets through Do not disturb, but expires as usual
+        ToastScenario::Urgent => n.urgency(Urgency::Critical),
+        ToastScenario::Default => n.urgency(Urgency::Normal),
+    };
+    for a in actions {
+        n.action(a.label(), a.label());
+    }
+    n
+}
+
+/// Show `n` and return its id at once; the button pressed among `actions` goes to `answered`, from a thread
+/// that waits for it (nothing when the notification is dismissed or expires).
+fn ask(n: &Notification, actions: &[ToastAction], answered: impl FnOnce(ToastAction) + Send + 'static) -> Result<u32> {
+    let handle = n.show().map_err(|e| anyhow!("D-Bus notification failed: {e}"))?;
+    let id = handle.id();
+    if actions.is_empty() {
+        return Ok(id);
+    }
+    let actions = actions.to_vec();
+    std::thread::spawn(move || {
+        let mut picked = None;
+        handle.wait_for_action(|key| picked = actions.iter().copied().find(|a| a.label() == key));
+        debug!("D-Bus notification {id} answered: {picked:?}");
+        if let Some(action) = picked {
+            answered(action);
+        }
+    });
+    Ok(id)
+}
+
+impl Notifier for DbusNotifier {
+    fn notify(&self, t: &Ticket) -> Result<Option<ToastAction>> {
+        let content = toast::ticket_toast(t);
+        let mut n = notification(&content.title, &content.body, content.scenario, &content.actions);
+        if let Some(id) = self.shown.lock().unwrap().get(&t.id) {
+            n.id(*id); // repeat: replace the previous one
+        }
+        // A button pressed later is handled by the poll loop like the tray flyout's
+        let ticket = t.clone();
+        let id = ask(&n, &content.actions, move |action| crate::pipeline::dispatcher().push_action(ticket, action))?;
+        self.shown.lock().unwrap().insert(t.id, id);
+        Ok(None)
+    }
+
+    fn message(&self, title: &str, body: &str) -> Result<()> {
+        ask(&notification(title, body, ToastScenario::Default, &[]), &[], drop).map(drop)
+    }
+
+    fn reminder(&self, title: &str, body: &str, url: &str) -> Result<()> {
+        let actions = if crate::control::kiosk() { &[][..] } else { &[ToastAction::Open][..] };
+        let url = url.to_string();
+        let open = move |_| {
+            if let Err(e) = toast::open_in_browser(&url) {
+                log::warn!("Could not open {url}: {e:#}");
+            }
+        };
+        ask(&notification(title, body, ToastScenario::Default, actions), actions, open).map(drop)
+    }
+
+    fn update(&self, t: &Ticket, changes: &[Change]) -> Result<()> {
+        let (title, body) = toast::update_text(t, changes);
+        match toast::ticket_url_for(t) {
+            Some(url) => self.reminder(&title, &body, &url),
+            None => self.message(&title, &body),
+        }
+    }
+
+    fn withdraw(&self, ticket_id: i64) -> Result<()> {
+        let Some(id) = self.shown.lock().unwrap().remove(&ticket_id) else { return Ok(()) };
+        if !toast::cleanup_enabled() {
+            return Ok(());
+        }
+        // The service closes by id: take the notification over with the same id, then close it
+        let handle = Notification::new().appname(APP_NAME).id(id).timeout(Timeout::Milliseconds(1)).show()?;
+        handle.close();
+        Ok(())
+    }
+
+    fn progress(&self, title: &str, status: &str, done: usize, total: usize) -> Result<()> {
+        let percent = (done * 100).checked_div(total).unwrap_or(100).min(100);
+        let mut n = notification(title, &format!("{status} ({percent}%)"), ToastScenario::Default, &[]);
+        // Progress bar on servers that support it (KDE Plasma); others show the text
+        n.hint(Hint::CustomInt("value".into(), percent as i32));
+        let mut progress = self.progress.lock().unwrap();
+        if let Some(id) = *progress {
+            n.id(id);
+        }
+        *progress = Some(ask(&n, &[], drop)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What the service receives, minus the icon (depends on the machine's logo file).
+    fn payload(n: &Notification) -> String {
+        let mut hints: Vec<String> = n.hints.iter().map(|h| format!("{h:?}")).collect();
+        hints.sort();
+        format!(
+            "summary: {}\nbody: {}\nactions: {:?}\nhints: {}\ntimeout: {:?}",
+            n.summary,
+            n.body,
+            n.actions,
+            hints.join(", "),
+            n.timeout
+        )
+    }
+
+    #[test]
+    fn ticket_payloads_match_snapshots() {
+        for (name, t) in toast::tests::sample_tickets() {
+            let content = toast::ticket_toast(&t);
+            let n = notification(&content.title, &content.body, content.scenario, &content.actions);
+            insta::assert_snapshot!(format!("{name}_dbus"), payload(&n));
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! macOS notifications through `osascript` (AppleScript, part of every macOS install; no app bundle or signing
+//! needed). Banners (`display notification`) cannot carry buttons, so Critical tickets are shown as an alert
+//! with the ticket's first actions (Open, Assign to me...) instead, like the Windows reminder toast.
+//! Text goes to the script as arguments, never spliced into it, so it needs no escaping.
+#![cfg_attr(not(target_os = "macos"), allow(dead_code))]
+
+use super::Notifier;
+use crate::diff::Change;
+use crate::glpi::Ticket;
+use crate::toast::{self, ToastAction, ToastScenario};
+
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::process::Command;
+
+/// Buttons an alert takes besides Dismiss (AppleScript allows three in all).
+const MAX_ALERT_ACTIONS: usize = 2;
+const DISMISS: &str = "Dismiss";
+
+/// osascript (NOTIFIER=macos, the default on macOS).
+pub struct MacNotifier;
+
+/// Banner in Notification Center (title and body, no buttons).
+fn banner_args(title: &str, body: &str) -> Vec<String> {
+    let script = ["on run argv", "display notification (item 2 of argv) with title (item 1 of argv)", "end run"];
+    args(&script, title, body)
+}
+
+/// Alert that stays until a button is pressed; prints the label of the pressed button.
+fn alert_args(title: &str, body: &str, actions: &[ToastAction]) -> Vec<String> {
+    let buttons: Vec<String> =
+        actions.iter().map(|a| format!("\"{}\"", a.label())).chain([format!("\"{DISMISS}\"")]).collect();
+    // The last button (Dismiss) is the default one: Return never assigns or opens by accident
+    let display = format!(
+        "display alert (item 1 of argv) message (item 2 of argv) as critical buttons {{{}}}",
+        buttons.join(", ")
+    );
+    args(&["on run argv", &format!("return button returned of ({display})"), "end run"], title, body)
+}
+
+fn args(script: &[&str], title: &str, body: &str) -> Vec<String> {
+    let mut args: Vec<String> = script.iter().flat_map(|line| ["-e".to_string(), line.to_string()]).collect();
+    args.extend([title.to_string(), body.to_string()]);
+    args
+}
+
+/// Run osascript and return what the script printed (the pressed button for alerts).
+fn run(args: &[String]) -> Result<String> {
+    let out = Command::new("osascript").args(args).output().map_err(|e| anyhow!("osascript failed to start: {e}"))?;
+    if !out.status.success() {
+        return Err(anyhow!("osascript failed ({}): {}", out.status, String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// The actions an alert has room for, in the ticket's order.
+fn offered(actions: &[ToastAction]) -> &[ToastAction] {
+    &actions[..actions.len().min(MAX_ALERT_ACTIONS)]
+}
+
+/// Show an alert with `actions` and return the one picked; None when dismissed.
+fn ask(title: &str, body: &str, actions: &[ToastAction]) -> Result<Option<ToastAction>> {
+    let actions = offered(actions);
+    let pressed = run(&alert_args(title, body, actions))?;
+    debug!("macOS alert answered: {pressed:?}");
+    Ok(actions.iter().copied().find(|a| a.label() == pressed))
+}
+
+impl Notifier for MacNotifier {
+    fn notify(&self, t: &Ticket) -> Result<Option<ToastAction>> {
+        let content = toast::ticket_toast(t);
+        match content.scenario {
+            ToastScenario::Reminder if !content.actions.is_empty() => {
+                ask(&content.title, &content.body, &content.actions)
+            }
+            _ => run(&banner_args(&content.title, &content.body)).map(|_| None),
+        }
+    }
+
+    fn message(&self, title: &str, body: &str) -> Result<()> {
+        run(&banner_args(title, body)).map(drop)
+    }
+
+    fn update(&self, t: &Ticket, changes: &[Change]) -> Result<()> {
+        let (title, body) = toast::update_text(t, changes);
+        self.message(&title, &body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticket_payloads_match_snapshots() {
+        for (name, t) in toast::tests::sample_tickets() {
+            let content = toast::ticket_toast(&t);
+            let args = match content.scenario {
+                ToastScenario::Reminder => alert_args(&content.title, &content.body, offered(&content.actions)),
+                ToastScenario::Urgent | ToastScenario::Default => banner_args(&content.title, &content.body),
+            };
+            insta::assert_snapshot!(format!("{name}_osascript_args"), args.join("\n"));
+        }
+    }
+}
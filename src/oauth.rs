@@ -0,0 +1,241 @@
+//! OAuth2 token endpoint forms and replies (RFC 6749, device flow RFC 8628). The HTTP exchange goes through
+//! the GLPI client, so token requests are throttled and traced like every other call; this module only builds
+//! and parses them, and keeps refresh tokens in the OS keyring.
+
+use crate::state::now_secs;
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+
+/// An OAuth client as registered on the authorization server (GLPI: Setup > OAuth clients).
+#[derive(Debug, Clone)]
+pub struct Client {
+    pub id: String,
+    /// Empty for public clients (device flow).
+    pub secret: String,
+    /// Space-separated scopes to request; empty = the server's default.
+    pub scope: String,
+}
+
+/// How an access token is obtained.
+#[derive(Debug, Clone)]
+pub enum Grant {
+    /// Resource owner password: the GLPI user signs in with login and password.
+    Password { username: String, password: String },
+    /// The client signs in as itself (service account of the SSO gateway).
+    ClientCredentials,
+    /// The user approves the notifier in a browser once (`sign-in`); it then lives on the refresh token.
+    DeviceCode,
+}
+
+/// Where and how tokens are obtained.
+#[derive(Debug, Clone)]
+pub struct Provider {
+    pub client: Client,
+    pub grant: Grant,
+    /// Token endpoint; empty until discovered from `issuer`.
+    pub token_url: String,
+    /// Device authorization endpoint (device flow).
+    pub device_url: Option<String>,
+    /// OpenID Connect issuer, for discovering the endpoints not given.
+    pub issuer: Option<String>,
+}
+
+impl Provider {
+    /// Fill in endpoints from the issuer's `.well-known/openid-configuration` document.
+    pub fn discovered(&mut self, body: &[u8]) -> Result<()> {
+        #[derive(Deserialize)]
+        struct Discovery {
+            token_endpoint: String,
+            #[serde(default)]
+            device_authorization_endpoint: Option<String>,
+        }
+        let doc: Discovery = serde_json::from_slice(body).map_err(|e| anyhow!("invalid OpenID configuration: {e}"))?;
+        if self.token_url.is_empty() {
+            self.token_url = doc.token_endpoint;
+        }
+        if self.device_url.is_none() {
+            self.device_url = doc.device_authorization_endpoint;
+        }
+        Ok(())
+    }
+
+    /// Keyring entry holding the refresh token: one per client and token endpoint.
+    fn keyring_entry(&self) -> keyring::Result<keyring::Entry> {
+        let endpoint = self.issuer.as_deref().unwrap_or(&self.token_url);
+        keyring::Entry::new("glpi-notifier-rs", &format!("{}@{endpoint}", self.client.id))
+    }
+
+    /// Refresh token saved by an earlier run, if the keyring has one.
+    pub fn stored_refresh(&self) -> Option<String> {
+        match self.keyring_entry().and_then(|e| e.get_password()) {
+            Ok(token) => {
+                crate::scrub::register(&token);
+                Some(token)
+            }
+            Err(keyring::Error::NoEntry) => None,
+            Err(e) => {
+                log::warn!("Keyring unavailable, the refresh token is not kept across restarts: {e}");
+                None
+            }
+        }
+    }
+
+    pub fn store_refresh(&self, token: &str) {
+        if let Err(e) = self.keyring_entry().and_then(|e| e.set_password(token)) {
+            log::warn!("Could not save the refresh token in the keyring: {e}");
+        }
+    }
+
+    /// Drop the saved refresh token; true when there was one.
+    pub fn forget(&self) -> Result<bool> {
+        match self.keyring_entry().and_then(|e| e.delete_credential()) {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(anyhow!("keyring: {e}")),
+        }
+    }
+}
+
+/// A bearer token and when it expires.
+#[derive(Debug, Clone)]
+pub struct Token {
+    pub access: String,
+    pub refresh: Option<String>,
+    /// UNIX time; renewed a minute before.
+    pub expires_at: u64,
+}
+
+impl Token {
+    pub fn expiring(&self) -> bool {
+        now_secs() + 60 >= self.expires_at
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenReply {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+    /// Seconds; assumed one hour when the server does not say.
+    #[serde(default)]
+    expires_in: Option<u64>,
+}
+
+/// Reply of the device authorization endpoint: what the user has to do in a browser.
+#[derive(Debug, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Same page with the code filled in, when the server offers it.
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    /// Seconds between token polls.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+pub fn parse_device_authorization(body: &[u8]) -> Result<DeviceAuthorization> {
+    serde_json::from_slice(body).map_err(|e| anyhow!("invalid device authorization reply: {e}"))
+}
+
+/// `error` code of a failed token request (`authorization_pending`, `invalid_grant`...).
+pub fn error_code(body: &[u8]) -> Option<String> {
+    serde_json::from_slice::<serde_json::Value>(body).ok()?["error"].as_str().map(str::to_string)
+}
+
+fn with_client(client: &Client, mut form: Vec<(&'static str, String)>) -> Vec<(&'static str, String)> {
+    form.push(("client_id", client.id.clone()));
+    if !client.secret.is_empty() {
+        form.push(("client_secret", client.secret.clone()));
+    }
+    if !client.scope.is_empty() {
+        form.push(("scope", client.scope.clone()));
+    }
+    form
+}
+
+/// Form of a token request for `grant`; None for the device flow, which needs the user (`sign-in`).
+pub fn grant_form(client: &Client, grant: &Grant) -> Option<Vec<(&'static str, String)>> {
+    let form = match grant {
+        Grant::Password { username, password } => {
+            vec![("grant_type", "password".to_string()), ("username", username.clone()), ("password", password.clone())]
+        }
+        Grant::ClientCredentials => vec![("grant_type", "client_credentials".to_string())],
+        Grant::DeviceCode => return None,
+    };
+    Some(with_client(client, form))
+}
+
+/// Form starting the device flow at the device authorization endpoint.
+pub fn device_form(client: &Client) -> Vec<(&'static str, String)> {
+    with_client(client, Vec::new())
+}
+
+/// Form polling the token endpoint while the user approves the device code.
+pub fn device_token_form(client: &Client, device_code: &str) -> Vec<(&'static str, String)> {
+    let grant = "urn:ietf:params:oauth:grant-type:device_code".to_string();
+    with_client(client, vec![("grant_type", grant), ("device_code", device_code.to_string())])
+}
+
+/// Form renewing a token with its refresh token.
+pub fn refresh_form(client: &Client, refresh: &str) -> Vec<(&'static str, String)> {
+    with_client(client, vec![("grant_type", "refresh_token".to_string()), ("refresh_token", refresh.to_string())])
+}
+
+/// Parse a successful token reply; secrets are registered for scrubbing.
+pub fn parse_token(body: &[u8]) -> Result<Token> {
+    let reply: TokenReply = serde_json::from_slice(body).map_err(|e| anyhow!("invalid token reply: {e}"))?;
+    crate::scrub::register(&reply.access_token);
+    if let Some(refresh) = &reply.refresh_token {
+        crate::scrub::register(refresh);
+    }
+    Ok(Token {
+        access: reply.access_token,
+        refresh: reply.refresh_token,
+        expires_at: now_secs() + reply.expires_in.unwrap_or(3600),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_forms_and_parses_tokens() {
+        let client = Client { id: "notifier".into(), secret: "s3cr3t".into(), scope: "api".into() };
+        let form = grant_form(&client, &Grant::Password { username: "tech".into(), password: "pw".into() }).unwrap();
+        assert_eq!(form[0], ("grant_type", "password".to_string()));
+        assert!(form.contains(&("scope", "api".to_string())));
+        assert!(grant_form(&client, &Grant::DeviceCode).is_none());
+        let public = Client { secret: String::new(), ..client };
+        assert!(!device_form(&public).iter().any(|(k, _)| *k == "client_secret"));
+
+        let token = parse_token(br#"{"token_type":"Bearer","access_token":"eyJ0eXAi.abc","expires_in":3600}"#).unwrap();
+        assert_eq!(token.access, "eyJ0eXAi.abc");
+        assert!(token.refresh.is_none() && !token.expiring());
+        assert!(parse_token(b"{}").is_err());
+        assert_eq!(error_code(br#"{"error":"authorization_pending"}"#).as_deref(), Some("authorization_pending"));
+
+        let mut provider = Provider {
+            client: public,
+            grant: Grant::DeviceCode,
+            token_url: String::new(),
+            device_url: None,
+            issuer: Some("https://sso.example.org/realms/it".into()),
+        };
+        let doc = br#"{"issuer":"https://sso.example.org/realms/it","token_endpoint":"https://sso/token",
+            "device_authorization_endpoint":"https://sso/device"}"#;
+        provider.discovered(doc).unwrap();
+        assert_eq!(
+            (provider.token_url.as_str(), provider.device_url.as_deref()),
+            ("https://sso/token", Some("https://sso/device"))
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Last-resort escalation: a Twilio SMS or voice call to the on-call number when a Critical ticket is still
+//! New (unassigned) PHONE_ESCALATION_MINUTES after it was first notified.
+//!
+//! Needs TWILIO_ACCOUNT_SID, TWILIO_AUTH_TOKEN, TWILIO_FROM and ONCALL_NUMBER; TWILIO_MODE picks `sms` (default)
+//! or `call`.
+
+use crate::glpi::Ticket;
+use crate::toast::ticket_url_for;
+
+use anyhow::{anyhow, Result};
+use std::env;
+
+/// Seconds a notified Critical ticket may stay New before the call, or None when escalation is off.
+pub fn delay_secs() -> Option<u64> {
+    let minutes = crate::config::get().phone_escalation_minutes;
+    (minutes > 0).then_some(minutes.saturating_mul(60))
+}
+
+/// E.164 phone number (`+` then 8 to 15 digits).
+pub fn check_number(raw: &str) -> Result<()> {
+    let digits = raw.strip_prefix('+').ok_or_else(|| anyhow!("expected E.164 format, e.g. +351912345678"))?;
+    if !(8..=15).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("expected E.164 format, e.g. +351912345678"));
+    }
+    Ok(())
+}
+
+/// Text read or sent to the on-call person.
+fn message(t: &Ticket, waited_min: u64) -> String {
+    let url = ticket_url_for(t).map(|u| format!(" {u}")).unwrap_or_default();
+    format!("GLPI: CRITICAL ticket #{} still unassigned after {waited_min} min: {}.{url}", t.id, t.name)
+}
+
+/// Send the SMS or place the call through the Twilio REST API.
+pub async fn escalate(t: &Ticket, waited_min: u64) -> Result<()> {
+    let var = |key: &str| {
+        env::var(key)
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| anyhow!("{key} is not set"))
+    };
+    let (sid, token, from, to) =
+        (var("TWILIO_ACCOUNT_SID")?, var("TWILIO_AUTH_TOKEN")?, var("TWILIO_FROM")?, var("ONCALL_NUMBER")?);
+    let text = message(t, waited_min);
+    let call = crate::config::get().twilio_call;
+
+    let (resource, content) = if call {
+        let say = text.replace('&', "and").replace(['<', '>'], " ");
+        ("Calls", ("Twiml", format!("<Response><Say>{say}</Say></Response>")))
+    } else {
+        ("Messages", ("Body", text))
+    };
+    let url = format!("https://api.twilio.com/2010-04-01/Accounts/{sid}/{resource}.json");
+    let form = [("To", to.as_str()), ("From", from.as_str()), (content.0, content.1.as_str())];
+    let r = reqwest::Client::new().post(url).basic_auth(&sid, Some(&token)).form(&form).send().await?;
+    if !r.status().is_success() {
+        let status = r.status();
+        return Err(anyhow!("Twilio {resource} failed: {status} | body: {}", r.text().await.unwrap_or_default()));
+    }
+    Ok(())
+}
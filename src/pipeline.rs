@@ -0,0 +1,394 @@
+use crate::diff::Change;
+use crate::glpi::Ticket;
+use crate::notify::{self, Notifier};
+use crate::toast::ToastAction;
+
+use anyhow::Result;
+use log::{info, warn};
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+static DISPATCHER: OnceCell<Dispatcher> = OnceCell::new();
+
+/// What to do when NOTIFY_QUEUE_MAX notifications are already waiting for the sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overflow {
+    /// Replace the queued tickets by one summary message.
+    Digest,
+    /// Drop the lowest-severity (then oldest) ticket.
+    DropLowest,
+}
+
+enum Job {
+    Ticket(Ticket),
+    Message(String, String),
+    /// Message with a link: (title, body, url).
+    Reminder(String, String, String),
+    /// What changed on a ticket, shown with the backend's own layout.
+    Update(Ticket, Vec<Change>),
+}
+
+/// Result of a delivery made by the worker, routed back to the poll loop.
+pub enum Delivery {
+    Action(Box<Ticket>, ToastAction),
+    Failed(Box<Ticket>, anyhow::Error),
+}
+
+impl Delivery {
+    fn ticket(&self) -> &Ticket {
+        match self {
+            Delivery::Action(t, _) | Delivery::Failed(t, _) => t,
+        }
+    }
+}
+
+/// Queue counters, written to heartbeat.json.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStats {
+    pub depth: usize,
+    pub max_depth: usize,
+    pub capacity: usize,
+    pub dropped: u64,
+    pub coalesced: u64,
+}
+
+/// Bounded queue between the poller and a worker thread that feeds the (possibly slow, blocking) sink,
+/// so a burst of toasts or a hanging backend never delays the next poll.
+pub struct Dispatcher {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    sink: Box<dyn Notifier>,
+    queue: Mutex<VecDeque<Job>>,
+    ready: Condvar,
+    results: Mutex<Vec<Delivery>>,
+    /// A job is in the sink (set under the queue lock, so an empty queue and `!busy` means idle).
+    busy: AtomicBool,
+    capacity: usize,
+    policy: Overflow,
+    max_depth: AtomicUsize,
+    dropped: AtomicU64,
+    coalesced: AtomicU64,
+}
+
+/// The process-wide dispatcher over the NOTIFIER backend (kept across poller restarts).
+pub fn dispatcher() -> &'static Dispatcher {
+    DISPATCHER.get_or_init(|| {
+        let config = crate::config::get();
+        let policy = if config.notify_drop_lowest { Overflow::DropLowest } else { Overflow::Digest };
+        Dispatcher::start(notify::from_env(), config.notify_queue_max, policy)
+    })
+}
+
+pub fn stats() -> Option<PipelineStats> {
+    DISPATCHER.get().map(Dispatcher::stats)
+}
+
+impl Dispatcher {
+    fn start(sink: Box<dyn Notifier>, capacity: usize, policy: Overflow) -> Self {
+        let shared = Arc::new(Shared {
+            sink,
+            queue: Mutex::new(VecDeque::new()),
+            ready: Condvar::new(),
+            results: Mutex::new(Vec::new()),
+            busy: AtomicBool::new(false),
+            capacity: capacity.max(1),
+            policy,
+            max_depth: AtomicUsize::new(0),
+            dropped: AtomicU64::new(0),
+            coalesced: AtomicU64::new(0),
+        });
+        let worker = shared.clone();
+        std::thread::spawn(move || worker.run());
+        Self { shared }
+    }
+
+    /// Deliveries completed since the last call (picked actions, failures) for the tickets of the calling poll
+    /// loop's instance; the other instances' loops drain theirs.
+    pub fn drain(&self) -> Vec<Delivery> {
+        let instance = crate::instance::current_name();
+        let mut results = self.shared.results.lock().unwrap();
+        let (mine, others) = std::mem::take(&mut *results).into_iter().partition(|d| d.ticket().instance == instance);
+        *results = others;
+        mine
+    }
+
+    /// An action picked outside the sink (the tray flyout), handled by the poll loop like a toast button.
+    pub fn push_action(&self, t: Ticket, action: ToastAction) {
+        self.shared.results.lock().unwrap().push(Delivery::Action(Box::new(t), action));
+    }
+
+    /// Wait up to `timeout` for the queued notifications to be delivered (`--once`, before exiting).
+    /// False when some were still waiting or on screen (a Critical toast waits for its button).
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let idle = {
+                let queue = self.shared.queue.lock().unwrap();
+                queue.is_empty() && !self.shared.busy.load(Ordering::SeqCst)
+            };
+            if idle {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    fn stats(&self) -> PipelineStats {
+        let s = &self.shared;
+        PipelineStats {
+            depth: s.queue.lock().unwrap().len(),
+            max_depth: s.max_depth.load(Ordering::Relaxed),
+            capacity: s.capacity,
+            dropped: s.dropped.load(Ordering::Relaxed),
+            coalesced: s.coalesced.load(Ordering::Relaxed),
+        }
+    }
+
+    fn enqueue(&self, job: Job) {
+        let s = &self.shared;
+        let mut queue = s.queue.lock().unwrap();
+        match admit(&mut queue, job, s.capacity, s.policy) {
+            Admitted::Queued => {}
+            Admitted::Dropped(id) => {
+                warn!("Notification queue full ({}): dropped #{id}", s.capacity);
+                s.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            Admitted::Coalesced(n) => {
+                info!("Notification queue full ({}): {n} tickets coalesced into a digest", s.capacity);
+                s.coalesced.fetch_add(n as u64, Ordering::Relaxed);
+            }
+        }
+        s.max_depth.fetch_max(queue.len(), Ordering::Relaxed);
+        s.ready.notify_one();
+    }
+}
+
+impl Shared {
+    fn run(&self) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap();
+                self.busy.store(false, Ordering::SeqCst);
+                loop {
+                    match queue.pop_front() {
+                        Some(job) => {
+                            self.busy.store(true, Ordering::SeqCst);
+                            break job;
+                        }
+                        None => queue = self.ready.wait(queue).unwrap(),
+                    }
+                }
+            };
+            match job {
+                Job::Ticket(t) => {
+                    let delivery = match self.sink.notify(&t) {
+                        Ok(Some(action)) => Delivery::Action(Box::new(t), action),
+                        Ok(None) => continue,
+                        Err(e) => Delivery::Failed(Box::new(t), e),
+                    };
+                    self.results.lock().unwrap().push(delivery);
+                }
+                Job::Message(title, body) => {
+                    if let Err(e) = self.sink.message(&title, &body) {
+                        warn!("Message '{title}' failed: {e:#}");
+                    }
+                }
+                Job::Reminder(title, body, url) => {
+                    if let Err(e) = self.sink.reminder(&title, &body, &url) {
+                        warn!("Reminder '{title}' failed: {e:#}");
+                    }
+                }
+                Job::Update(t, changes) => {
+                    if let Err(e) = self.sink.update(&t, &changes) {
+                        warn!("Update of #{} failed: {e:#}", t.id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Actions and failures arrive later through [`Dispatcher::drain`].
+impl Notifier for Dispatcher {
+    fn notify(&self, t: &Ticket) -> Result<Option<ToastAction>> {
+        self.enqueue(Job::Ticket(t.clone()));
+        Ok(None)
+    }
+
+    fn message(&self, title: &str, body: &str) -> Result<()> {
+        self.enqueue(Job::Message(title.to_string(), body.to_string()));
+        Ok(())
+    }
+
+    fn reminder(&self, title: &str, body: &str, url: &str) -> Result<()> {
+        self.enqueue(Job::Reminder(title.to_string(), body.to_string(), url.to_string()));
+        Ok(())
+    }
+
+    fn update(&self, t: &Ticket, changes: &[Change]) -> Result<()> {
+        self.enqueue(Job::Update(t.clone(), changes.to_vec()));
+        Ok(())
+    }
+
+    fn withdraw(&self, ticket_id: i64) -> Result<()> {
+        self.shared.sink.withdraw(ticket_id)
+    }
+
+    /// Not queued: updates replace each other in place and must not wait behind toasts. Drawn on a blocking
+    /// thread when called from the poll loop, as the toast sink may start PowerShell for it.
+    fn progress(&self, title: &str, status: &str, done: usize, total: usize) -> Result<()> {
+        let shared = self.shared.clone();
+        let (title, status) = (title.to_string(), status.to_string());
+        let show = move || {
+            if let Err(e) = shared.sink.progress(&title, &status, done, total) {
+                warn!("Progress notification failed: {e:#}");
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(show)),
+            Err(_) => show(),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Admitted {
+    Queued,
+    Dropped(i64),
+    Coalesced(usize),
+}
+
+/// Queue `job`, applying the overflow policy when `capacity` jobs are already waiting.
+/// Messages and reminders are always queued; only tickets are dropped or coalesced.
+fn admit(queue: &mut VecDeque<Job>, job: Job, capacity: usize, policy: Overflow) -> Admitted {
+    let queued_tickets = queue.iter().filter(|j| matches!(j, Job::Ticket(_))).count();
+    if queue.len() < capacity || queued_tickets == 0 || !matches!(job, Job::Ticket(_)) {
+        queue.push_back(job);
+        return Admitted::Queued;
+    }
+    let Job::Ticket(new) = job else { unreachable!() };
+
+    match policy {
+        Overflow::DropLowest => {
+            let rank = |t: &Ticket| (t.severity(), t.id);
+            let lowest = queue
+                .iter()
+                .enumerate()
+                .filter_map(|(i, j)| match j {
+                    Job::Ticket(t) => Some((i, rank(t))),
+                    _ => None,
+                })
+                .min_by_key(|(_, r)| *r);
+            match lowest {
+                Some((i, r)) if r < rank(&new) => {
+                    let Some(Job::Ticket(dropped)) = queue.remove(i) else { unreachable!() };
+                    queue.push_back(Job::Ticket(new));
+                    Admitted::Dropped(dropped.id)
+                }
+                _ => Admitted::Dropped(new.id),
+            }
+        }
+        Overflow::Digest => {
+            let mut tickets = vec![new];
+            queue.retain(|j| match j {
+                Job::Ticket(t) => {
+                    tickets.push(t.clone());
+                    false
+                }
+                _ => true,
+            });
+            tickets.sort_by_key(|t| (std::cmp::Reverse(t.severity()), std::cmp::Reverse(t.id)));
+            let body = tickets.iter().take(5).map(|t| format!("#{} {}", t.id, t.name)).collect::<Vec<_>>().join("\n");
+            queue.push_back(Job::Message(format!("GLPI: {} new tickets", tickets.len()), body));
+            Admitted::Coalesced(tickets.len())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: i64, priority: i64) -> Job {
+        Job::Ticket(Ticket {
+            id,
+            name: format!("Ticket {id}"),
+            requester: None,
+            category: None,
+            priority: Some(priority),
+            date: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            major_incident: None,
+            instance: None,
+            language: None,
+        })
+    }
+
+    fn queued_ids(queue: &VecDeque<Job>) -> Vec<i64> {
+        queue.iter().filter_map(|j| if let Job::Ticket(t) = j { Some(t.id) } else { None }).collect()
+    }
+
+    #[test]
+    fn drop_lowest_keeps_the_most_severe() {
+        let mut queue = VecDeque::new();
+        admit(&mut queue, job(1, 3), 2, Overflow::DropLowest);
+        admit(&mut queue, job(2, 6), 2, Overflow::DropLowest);
+
+        assert_eq!(admit(&mut queue, job(3, 5), 2, Overflow::DropLowest), Admitted::Dropped(1));
+        assert_eq!(admit(&mut queue, job(4, 1), 2, Overflow::DropLowest), Admitted::Dropped(4));
+        assert_eq!(queued_ids(&queue), vec![2, 3]);
+    }
+
+    #[test]
+    fn digest_replaces_queued_tickets_with_one_message() {
+        let mut queue = VecDeque::new();
+        admit(&mut queue, job(1, 3), 2, Overflow::Digest);
+        admit(&mut queue, job(2, 3), 2, Overflow::Digest);
+
+        assert_eq!(admit(&mut queue, job(3, 3), 2, Overflow::Digest), Admitted::Coalesced(3));
+        assert_eq!(queue.len(), 1);
+        assert!(matches!(&queue[0], Job::Message(title, _) if title == "GLPI: 3 new tickets"));
+    }
+
+    /// Hands the jobs to a capture sink the test keeps a handle on.
+    struct Handle(Arc<notify::CaptureSink>);
+
+    impl Notifier for Handle {
+        fn notify(&self, t: &Ticket) -> Result<Option<ToastAction>> {
+            self.0.notify(t)
+        }
+
+        fn message(&self, title: &str, body: &str) -> Result<()> {
+            self.0.message(title, body)
+        }
+
+        fn update(&self, t: &Ticket, changes: &[Change]) -> Result<()> {
+            self.0.update(t, changes)
+        }
+    }
+
+    #[test]
+    fn updates_reach_the_sink_with_their_changes() {
+        let sink = Arc::new(notify::CaptureSink::default());
+        let dispatcher = Dispatcher::start(Box::new(Handle(sink.clone())), 20, Overflow::Digest);
+        let Job::Ticket(t) = job(7, 3) else { unreachable!() };
+        let changes = [crate::diff::status_change(4, 2), crate::diff::status_change(2, 5)];
+        dispatcher.update(&t, &changes).unwrap();
+        assert!(dispatcher.flush(Duration::from_secs(5)));
+        assert_eq!(sink.updates(), vec![(7, crate::diff::summary(&changes))]);
+        assert!(sink.messages().is_empty());
+    }
+}
@@ -0,0 +1,86 @@
+//! External references in ticket titles (TICKET_REFS): named regular expressions, e.g.
+//! `TICKET_REFS=host=\bPRD-[A-Z0-9-]+; asset=\bAT-\d{6}\b; order=PO-(\d+)`. A rule with a capture group keeps
+//! the group (`order` → `4711`), otherwise the whole match.
+//!
+//! The references are shown on the notification, listed by `explain`, and carried as `refs` in status.json and
+//! the MQTT `new_ticket` event, where routing happens (e.g. a Home Assistant automation sending tickets with a
+//! `host` reference to the sysadmins).
+
+use crate::exit::ConfigError;
+use crate::glpi::Ticket;
+
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// A named rule of TICKET_REFS.
+#[derive(Debug, Clone)]
+pub struct RefRule {
+    pub name: String,
+    pub pattern: Regex,
+}
+
+/// Parse `name=regex` rules separated by `;`. Names are lowercase letters, digits and `_`.
+pub fn parse(raw: &str) -> Result<Vec<RefRule>> {
+    let bad = |rule: &str, why: String| ConfigError(format!("TICKET_REFS: '{rule}': {why}"));
+    raw.split(';')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|rule| {
+            let (name, pattern) = rule.split_once('=').ok_or_else(|| bad(rule, "expected name=regex".into()))?;
+            let name = name.trim().to_lowercase();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(bad(rule, "the name takes letters, digits and _ only".into()).into());
+            }
+            let pattern = Regex::new(pattern.trim()).map_err(|e| bad(rule, e.to_string()))?;
+            Ok(RefRule { name, pattern })
+        })
+        .collect()
+}
+
+/// References found in `text` by `rules`, grouped by rule name, in order of appearance without duplicates.
+pub fn extract(rules: &[RefRule], text: &str) -> BTreeMap<String, Vec<String>> {
+    let mut found: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for rule in rules {
+        for caps in rule.pattern.captures_iter(text) {
+            let Some(m) = caps.get(1).or_else(|| caps.get(0)).filter(|m| !m.as_str().is_empty()) else { continue };
+            let values = found.entry(rule.name.clone()).or_default();
+            if !values.iter().any(|v| v == m.as_str()) {
+                values.push(m.as_str().to_string());
+            }
+        }
+    }
+    found
+}
+
+/// References in the ticket's title (empty without TICKET_REFS).
+pub fn of(t: &Ticket) -> BTreeMap<String, Vec<String>> {
+    extract(&crate::config::get().ticket_refs, &t.name)
+}
+
+/// One line for the notification: `host PRD-DB01, PRD-DB02 · order 4711`.
+pub fn summary(refs: &BTreeMap<String, Vec<String>>) -> Option<String> {
+    let parts: Vec<String> = refs.iter().map(|(name, values)| format!("{name} {}", values.join(", "))).collect();
+    (!parts.is_empty()).then(|| parts.join(" · "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_come_from_named_rules() {
+        let rules = parse(r"host=\bPRD-[A-Z0-9-]+\b; Order=PO-(\d+); asset=\bAT-\d{6}\b").unwrap();
+        let refs = extract(&rules, "PRD-DB01 and PRD-WEB-2 down, PRD-DB01 again (PO-4711)");
+        assert_eq!(refs["host"], ["PRD-DB01", "PRD-WEB-2"]);
+        assert_eq!(refs["order"], ["4711"]);
+        assert!(!refs.contains_key("asset"));
+        assert_eq!(summary(&refs).unwrap(), "host PRD-DB01, PRD-WEB-2 · order 4711");
+        assert!(summary(&BTreeMap::new()).is_none());
+
+        assert!(parse("host").is_err());
+        assert!(parse("bad name=x").is_err());
+        assert!(parse("host=PRD-(").is_err());
+        assert!(parse(" ; ").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,170 @@
+//! Redaction of secrets in everything written to disk: log lines (and so crash reports) and HTTP traces.
+//!
+//! Two passes: the values of known secrets (GLPI tokens, those of every instance too, the Twilio auth token,
+//! live session tokens) are replaced wherever they appear, and any value following a secret-looking key
+//! (`Session-Token:`, `Authorization: user_token ...`, `"session_token":"..."`, `app_token=...`) is masked even
+//! when the secret itself is not known, e.g. an old token echoed back by GLPI in an error body.
+
+use once_cell::sync::Lazy;
+use std::borrow::Cow;
+use std::sync::Mutex;
+
+const MASK: &str = "<redacted>";
+
+/// Settings holding secrets, masked by value.
+const SECRET_SETTINGS: &[&str] = &[
+    "GLPI_USER_TOKEN",
+    "GLPI_APP_TOKEN",
+    "GLPI_OAUTH_CLIENT_SECRET",
+    "GLPI_PASSWORD",
+    "TWILIO_AUTH_TOKEN",
+    "TWILIO_ACCOUNT_SID",
+    "WEBHOOK_SECRET",
+    "MQTT_PASSWORD",
+];
+
+/// Keys whose value is masked (lowercase). Authorization-like keys may be followed by a scheme word.
+const SECRET_KEYS: &[&str] = &[
+    "authorization",
+    "session-token",
+    "session_token",
+    "app-token",
+    "app_token",
+    "user_token",
+    "auth_token",
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "device_code",
+    "password",
+];
+const SCHEMES: &[&str] = &["user_token", "basic", "bearer"];
+
+/// Shorter values are too likely to occur by chance to be masked by value.
+const MIN_SECRET_LEN: usize = 8;
+
+/// Secrets learned at run time (session tokens, reloaded user tokens).
+static LEARNED: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Mask this value from now on (e.g. a session token just issued by initSession).
+pub fn register(secret: &str) {
+    let secret = secret.trim();
+    if secret.len() < MIN_SECRET_LEN {
+        return;
+    }
+    let mut learned = LEARNED.lock().unwrap();
+    if !learned.iter().any(|s| s == secret) {
+        learned.push(secret.to_string());
+    }
+}
+
+/// `text` with every known secret and every value of a secret-looking key replaced by `<redacted>`.
+pub fn scrub(text: &str) -> Cow<'_, str> {
+    let mut secrets: Vec<String> = SECRET_SETTINGS
+        .iter()
+        .filter_map(|k| std::env::var(k).ok())
+        // The tokens of the [instances] of config.toml too
+        .chain(crate::instance::all().iter().flat_map(|i| SECRET_SETTINGS.iter().filter_map(|k| i.value(k))))
+        .map(|v| v.trim().to_string())
+        .filter(|v| v.len() >= MIN_SECRET_LEN)
+        .collect();
+    secrets.extend(LEARNED.lock().map(|l| l.clone()).unwrap_or_default());
+
+    let mut out = Cow::Borrowed(text);
+    for secret in &secrets {
+        if out.contains(secret.as_str()) {
+            out = Cow::Owned(out.replace(secret.as_str(), MASK));
+        }
+    }
+    match mask_keyed_values(&out) {
+        Some(masked) => Cow::Owned(masked),
+        None => out,
+    }
+}
+
+fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-_.+/=~%".contains(&b)
+}
+
+/// Mask the value after each secret key; None when nothing was masked.
+fn mask_keyed_values(text: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets, so spans found in `lower` apply to `text`
+    let lower = text.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+
+    for key in SECRET_KEYS {
+        let mut from = 0;
+        while let Some(found) = lower[from..].find(key) {
+            let mut i = from + found + key.len();
+            from = i;
+            // `key: value`, `key=value`, `"key": "value"`; a bare space only after Authorization
+            let sep_start = i;
+            while i < bytes.len() && b"\"': =\t".contains(&bytes[i]) {
+                i += 1;
+            }
+            let sep = &bytes[sep_start..i];
+            if sep.is_empty() || (*key != "authorization" && !sep.iter().any(|b| b":=\"'".contains(b))) {
+                continue;
+            }
+            if let Some(scheme) = SCHEMES.iter().find(|s| lower[i..].starts_with(**s)) {
+                let after = i + scheme.len();
+                if bytes.get(after).is_some_and(|b| *b == b' ') {
+                    i = after + 1;
+                }
+            }
+            let start = i;
+            while i < bytes.len() && is_token_char(bytes[i]) {
+                i += 1;
+            }
+            if i > start {
+                spans.push((start, i));
+            }
+        }
+    }
+    if spans.is_empty() {
+        return None;
+    }
+    spans.sort_unstable();
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    for (start, end) in spans {
+        if start < pos {
+            continue; // overlapping match, already masked
+        }
+        out.push_str(&text[pos..start]);
+        out.push_str(MASK);
+        pos = end;
+    }
+    out.push_str(&text[pos..]);
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_values_of_secret_keys() {
+        assert_eq!(
+            scrub("initSession failed: 401 | body: [\"ERROR\", \"bad user_token=abcd1234efgh\"]"),
+            "initSession failed: 401 | body: [\"ERROR\", \"bad user_token=<redacted>\"]"
+        );
+        assert_eq!(scrub("> authorization: user_token q1w2e3r4t5"), "> authorization: user_token <redacted>");
+        assert_eq!(scrub("{\"session_token\":\"83af7e620c\"}"), "{\"session_token\":\"<redacted>\"}");
+        assert_eq!(
+            scrub("Session-Token: 83af7e620c, App-Token: zz9"),
+            "Session-Token: <redacted>, App-Token: <redacted>"
+        );
+        // Mentions of a setting are not values
+        assert_eq!(scrub("Please set GLPI_USER_TOKEN in .env"), "Please set GLPI_USER_TOKEN in .env");
+    }
+
+    #[test]
+    fn masks_registered_secrets_anywhere() {
+        register("s3ss10n-t0ken-value");
+        assert_eq!(scrub("GET /Ticket?x=s3ss10n-t0ken-value failed"), "GET /Ticket?x=<redacted> failed");
+        register("short");
+        assert_eq!(scrub("short"), "short");
+    }
+}
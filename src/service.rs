@@ -0,0 +1,273 @@
+//! Windows Service mode: `--service` runs the poller under the Service Control Manager (started at boot, no
+//! logon needed, restarted by the SCM when it exits with an error), `install-service` / `uninstall-service`
+//! register and remove it. Stop and Shutdown controls end the poll loop through the same stop flag as SIGTERM
+//! under systemd.
+//!
+//! Services run in session 0, which has no desktop: toasts reach nobody, so the service is for the other sinks
+//! (MQTT, phone escalation, stats), and logs go to `service.log` in the data directory. The service runs as the
+//! account given to `install-service --account` (the installing user by default), so that directory, the state
+//! and the keyring are that user's; `--account LocalSystem` keeps them in the system profile instead. The SCM is
+//! told the service is Running once the poller has signed in to GLPI.
+
+#[cfg(not(all(windows, feature = "windows-service")))]
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by the SCM's Stop/Shutdown control; checked by the poll loop's stop flag.
+pub static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+pub fn stop_requested() -> bool {
+    STOP_REQUESTED.load(Ordering::Relaxed)
+}
+
+/// Log file of the service (the SCM discards stderr).
+pub fn log_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("GlpiNotifier");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("service.log"))
+}
+
+#[cfg(all(windows, feature = "windows-service"))]
+pub use scm::{install, ready, start, uninstall};
+
+#[cfg(not(all(windows, feature = "windows-service")))]
+fn unavailable() -> anyhow::Error {
+    if cfg!(windows) {
+        anyhow!("this build has no Windows Service support (cargo feature windows-service)")
+    } else {
+        anyhow!("Windows Service mode is only available on Windows; `install` sets up a systemd user unit")
+    }
+}
+
+#[cfg(not(all(windows, feature = "windows-service")))]
+pub fn start() -> Result<std::process::ExitCode> {
+    Err(unavailable())
+}
+
+#[cfg(not(all(windows, feature = "windows-service")))]
+pub fn install(_account: Option<&str>) -> Result<()> {
+    Err(unavailable())
+}
+
+/// Not a Windows Service: nothing to report.
+#[cfg(not(all(windows, feature = "windows-service")))]
+pub fn ready() {}
+
+#[cfg(not(all(windows, feature = "windows-service")))]
+pub fn uninstall() -> Result<()> {
+    Err(unavailable())
+}
+
+#[cfg(all(windows, feature = "windows-service"))]
+mod scm {
+    use super::STOP_REQUESTED;
+    use crate::exit::Exit;
+
+    use anyhow::{anyhow, Result};
+    use log::{error, info, warn};
+    use once_cell::sync::OnceCell;
+    use std::ffi::{OsStr, OsString};
+    use std::process::ExitCode;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceControl, ServiceControlAccept, ServiceErrorControl,
+        ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod, ServiceInfo, ServiceStartType, ServiceState,
+        ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    /// The runtime of `main()`, which the SCM's service thread runs the poller on.
+    static RUNTIME: OnceCell<tokio::runtime::Handle> = OnceCell::new();
+    /// Exit code of the poller, returned by `main()` once the dispatcher returns.
+    static EXIT: AtomicU8 = AtomicU8::new(0);
+    /// Status handle of the running service, for [`ready`].
+    static STATUS: OnceCell<ServiceStatusHandle> = OnceCell::new();
+
+    const SERVICE_NAME: &str = "GlpiNotifier";
+    /// `--account` value for the built-in system account.
+    const LOCAL_SYSTEM: &str = "LocalSystem";
+    /// How long the SCM is told to wait for the first sign-in.
+    const START_HINT: Duration = Duration::from_secs(300);
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    /// `--service`: connect to the SCM and run the poller until it is stopped. Blocks until then.
+    pub fn start() -> Result<ExitCode> {
+        let _ = RUNTIME.set(tokio::runtime::Handle::current());
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main).map_err(|e| {
+            anyhow!("not started by the Service Control Manager ({e}); install-service, then `sc start {SERVICE_NAME}`")
+        })?;
+        Ok(ExitCode::from(EXIT.load(Ordering::Relaxed)))
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(e) = serve() {
+            error!("Windows Service: {e:#}");
+        }
+    }
+
+    fn status(state: ServiceState, exit: u8) -> ServiceStatus {
+        ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: match state {
+                // Stoppable while the first sign-in is retried too
+                ServiceState::StartPending | ServiceState::Running => {
+                    ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+                }
+                _ => ServiceControlAccept::empty(),
+            },
+            exit_code: match exit {
+                0 => ServiceExitCode::Win32(0),
+                code => ServiceExitCode::ServiceSpecific(code.into()),
+            },
+            checkpoint: 0,
+            wait_hint: if state == ServiceState::StartPending { START_HINT } else { Duration::default() },
+            process_id: None,
+        }
+    }
+
+    fn serve() -> Result<()> {
+        let handler = |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                info!("Service stop requested; finishing current iteration.");
+                STOP_REQUESTED.store(true, Ordering::Relaxed);
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        };
+        let handle = service_control_handler::register(SERVICE_NAME, handler)?;
+        // Running comes from `ready`, once GLPI answered
+        handle.set_service_status(status(ServiceState::StartPending, 0))?;
+        let _ = STATUS.set(handle);
+
+        // Same command line as `main()`, which already validated it and loaded the settings
+        let matches = crate::cli::command().get_matches();
+        let runtime = RUNTIME.get().ok_or_else(|| anyhow!("no runtime"))?;
+        let code = match runtime.block_on(crate::run(&matches)) {
+            Ok(()) => Exit::Ok,
+            Err(e) => {
+                let code = Exit::from_error(&e);
+                error!("{e:#} (exit code {})", code as u8);
+                code
+            }
+        };
+        EXIT.store(code as u8, Ordering::Relaxed);
+        handle.set_service_status(status(ServiceState::Stopped, code as u8))?;
+        Ok(())
+    }
+
+    /// The poller signed in and resolved its fields: report Running to the SCM. No-op outside the service.
+    pub fn ready() {
+        if let Some(handle) = STATUS.get() {
+            if let Err(e) = handle.set_service_status(status(ServiceState::Running, 0)) {
+                warn!("Windows Service: could not report Running: {e}");
+            }
+        }
+    }
+
+    /// `DOMAIN\user` of the user running this command (`.\user` for a local account).
+    fn current_account() -> Option<String> {
+        let user = std::env::var("USERNAME").ok()?;
+        let domain = std::env::var("USERDOMAIN").unwrap_or_else(|_| ".".into());
+        Some(format!(r"{domain}\{user}"))
+    }
+
+    /// `install-service [--account DOMAIN\user]`: register the service (automatic start) under `account`, by
+    /// default the installing user, asking for its password, or LocalSystem; with this run's `--env-file` and
+    /// `--config`, restarted by the SCM a minute after an error exit, and start it.
+    pub fn install(account: Option<&str>) -> Result<()> {
+        let current = current_account();
+        let account = account.map(str::to_string).or_else(|| current.clone());
+        let account = account.filter(|a| !a.eq_ignore_ascii_case(LOCAL_SYSTEM));
+        let password = match &account {
+            Some(account) => Some(
+                dialoguer::Password::new()
+                    .with_prompt(format!("Windows password of {account}"))
+                    .allow_empty_password(true)
+                    .interact()?,
+            ),
+            None => None,
+        };
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )
+        .map_err(|e| anyhow!("cannot open the Service Control Manager ({e}); run as administrator"))?;
+        // The service starts in System32: relative paths would not resolve there
+        let absolute = |p: &std::path::Path| std::path::absolute(p).unwrap_or_else(|_| p.to_path_buf());
+        let mut arguments = vec![OsString::from("--service")];
+        for file in crate::config::ENV_FILES.get().into_iter().flatten() {
+            arguments.extend([OsString::from("--env-file"), absolute(file).into_os_string()]);
+        }
+        if let Some(file) = crate::config::CONFIG_FILE.get() {
+            arguments.extend([OsString::from("--config"), absolute(file).into_os_string()]);
+        }
+        let info = ServiceInfo {
+            name: OsString::from(SERVICE_NAME),
+            display_name: OsString::from("GLPI Notifier"),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: std::env::current_exe()?,
+            launch_arguments: arguments,
+            dependencies: vec![],
+            account_name: account.as_ref().map(OsString::from), // None: LocalSystem
+            account_password: password.map(OsString::from),
+        };
+        let service = manager.create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)?;
+        service.set_description(env!("CARGO_PKG_DESCRIPTION"))?;
+        let restart = || ServiceAction { action_type: ServiceActionType::Restart, delay: Duration::from_secs(60) };
+        service.update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(86400)),
+            reboot_msg: None,
+            command: None,
+            actions: Some(vec![restart(), restart(), restart()]),
+        })?;
+        service.set_failure_actions_on_non_crash_failures(true)?;
+        service.start::<&OsStr>(&[]).map_err(|e| {
+            anyhow!(
+                "service installed but not started ({e}); the account needs the \"Log on as a service\" right \
+                 (secpol.msc > Local Policies > User Rights Assignment)"
+            )
+        })?;
+        println!(
+            "Service '{SERVICE_NAME}' installed and started as {}: {} {:?}",
+            account.as_deref().unwrap_or(LOCAL_SYSTEM),
+            info.executable_path.display(),
+            info.launch_arguments
+        );
+        // The service's data directory is its account's, not necessarily the one of this (elevated) user
+        let logs = match &account {
+            None => {
+                let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".into());
+                format!(r"{root}\System32\config\systemprofile\AppData\Roaming\GlpiNotifier\service.log")
+            }
+            Some(account) if Some(account) == current.as_ref() => {
+                super::log_path().map(|p| p.display().to_string()).unwrap_or_default()
+            }
+            Some(account) => format!(r"%APPDATA%\GlpiNotifier\service.log of {account}"),
+        };
+        println!("Logs: {logs}");
+        Ok(())
+    }
+
+    /// `uninstall-service`: stop the service if it runs, then remove it.
+    pub fn uninstall() -> Result<()> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .map_err(|e| anyhow!("cannot open the Service Control Manager ({e}); run as administrator"))?;
+        let access = ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE;
+        let service = manager.open_service(SERVICE_NAME, access)?;
+        if service.query_status()?.current_state != ServiceState::Stopped {
+            service.stop()?;
+        }
+        service.delete()?;
+        println!("Service '{SERVICE_NAME}' removed.");
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+//! Mapping of GLPI priority values to notifier severities (SEVERITY_MAP).
+//!
+//! The default matches GLPI's stock 1-6 scale; plugins that alter the priority matrix can remap values,
+//! e.g. `SEVERITY_MAP=1-3=low; 4=normal; 5-6=high; 7=critical`.
+
+use crate::exit::ConfigError;
+use crate::glpi::Severity;
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+/// Severity of a raw priority value (unknown or missing priorities are Normal).
+pub fn of(priority: Option<i64>) -> Severity {
+    let Some(p) = priority else { return Severity::Normal };
+    if let Some(s) = lookup(&crate::config::get().severity_map, p) {
+        return s;
+    }
+    match p {
+        6 => Severity::Critical,
+        5 => Severity::High,
+        1 | 2 => Severity::Low,
+        _ => Severity::Normal,
+    }
+}
+
+/// `value=severity` pairs for the priorities the instance uses, flagging values outside the stock
+/// scale that SEVERITY_MAP does not cover (they fall back to Normal).
+pub fn describe(values: &[i64]) -> (String, Vec<i64>) {
+    let mapped = values.iter().map(|&v| format!("{v}={:?}", of(Some(v)))).collect::<Vec<_>>().join(" ");
+    let unmapped = values
+        .iter()
+        .copied()
+        .filter(|v| !(1..=6).contains(v) && lookup(&crate::config::get().severity_map, *v).is_none())
+        .collect();
+    (mapped, unmapped)
+}
+
+/// Severity of `priority` in `map`: the last clause whose range holds it wins.
+fn lookup(map: &[(i64, i64, Severity)], priority: i64) -> Option<Severity> {
+    map.iter().rev().find(|(from, to, _)| (*from..=*to).contains(&priority)).map(|(_, _, s)| *s)
+}
+
+/// Parse `values=severity` clauses separated by `;`, values being one number or a range (`1-2=low`), into
+/// `(from, to, severity)` ranges in clause order.
+pub fn parse(raw: &str) -> Result<Vec<(i64, i64, Severity)>> {
+    let mut map = Vec::new();
+    for clause in raw.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+        let bad = |why: &str| ConfigError(format!("SEVERITY_MAP: {why} in '{clause}' (expected e.g. 1-2=low)"));
+        let (values, severity) = clause.split_once('=').ok_or_else(|| bad("missing '='"))?;
+        let severity = severity_name(severity).ok_or_else(|| bad("unknown severity (low, normal, high, critical)"))?;
+        let number = |s: &str| s.trim().parse::<i64>().map_err(|_| bad("invalid priority value"));
+        let (from, to) = match values.split_once('-') {
+            Some((a, b)) => (number(a)?, number(b)?),
+            None => (number(values)?, number(values)?),
+        };
+        if from > to {
+            return Err(bad("empty range").into());
+        }
+        map.push((from, to, severity));
+    }
+    Ok(map)
+}
+
+fn severity_name(raw: &str) -> Option<Severity> {
+    match raw.trim().to_lowercase().as_str() {
+        "low" => Some(Severity::Low),
+        "normal" => Some(Severity::Normal),
+        "high" => Some(Severity::High),
+        "critical" => Some(Severity::Critical),
+        _ => None,
+    }
+}
+
+/// Re-notification cadence per severity, in seconds, for the `notifier` sink (empty for the default one):
+/// `REPEAT_MINUTES_<NOTIFIER>` (e.g. REPEAT_MINUTES_TOAST, REPEAT_MINUTES_DBUS) when set, else REPEAT_MINUTES.
+/// Severities not listed never repeat.
+pub fn repeat_for(notifier: &str, var: &dyn Fn(&str) -> Option<String>) -> Result<BTreeMap<Severity, u64>> {
+    let sink = if notifier.is_empty() { crate::notify::default_name() } else { notifier };
+    let key = format!("REPEAT_MINUTES_{}", sink.to_uppercase());
+    match var(&key) {
+        Some(raw) => parse_repeat(&key, &raw),
+        None => parse_repeat("REPEAT_MINUTES", &var("REPEAT_MINUTES").unwrap_or_default()),
+    }
+}
+
+/// `severity=minutes` clauses separated by `;`, e.g. `critical=10; high=60` (0 = never).
+pub fn parse_repeat(key: &str, raw: &str) -> Result<BTreeMap<Severity, u64>> {
+    let mut map = BTreeMap::new();
+    for clause in raw.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+        let bad = |why: &str| ConfigError(format!("{key}: {why} in '{clause}' (expected e.g. critical=10)"));
+        let (severity, minutes) = clause.split_once('=').ok_or_else(|| bad("missing '='"))?;
+        let severity = severity_name(severity).ok_or_else(|| bad("unknown severity (low, normal, high, critical)"))?;
+        let minutes: u64 = minutes.trim().parse().map_err(|_| bad("minutes must be a whole number"))?;
+        if minutes > 0 {
+            map.insert(severity, minutes.saturating_mul(60));
+        }
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values_and_ranges() {
+        let map = parse("1-3=low; 7 = Critical; 3=high").unwrap();
+        assert_eq!(lookup(&map, 2), Some(Severity::Low));
+        assert_eq!(lookup(&map, 3), Some(Severity::High));
+        assert_eq!(lookup(&map, 7), Some(Severity::Critical));
+        assert_eq!(lookup(&map, 4), None);
+        // A huge range is one entry, not one per priority
+        let wide = parse("1-9223372036854775807=normal").unwrap();
+        assert_eq!((wide.len(), lookup(&wide, i64::MAX)), (1, Some(Severity::Normal)));
+
+        assert!(parse("5=urgent").is_err());
+        assert!(parse("6-5=high").is_err());
+        assert!(parse("high").is_err());
+
+        let repeat = parse_repeat("REPEAT_MINUTES", "critical=10; low=0").unwrap();
+        assert_eq!(repeat.get(&Severity::Critical), Some(&600));
+        assert_eq!(repeat.len(), 1);
+        assert!(parse_repeat("REPEAT_MINUTES", "urgent=5").is_err());
+        let forever = parse_repeat("REPEAT_MINUTES", "low=18446744073709551615").unwrap();
+        assert_eq!(forever.get(&Severity::Low), Some(&u64::MAX));
+    }
+}
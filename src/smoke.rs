@@ -0,0 +1,89 @@
+//! `smoke-test`: proves the whole chain against a live server after an upgrade.
+//!
+//! Creates a throwaway ticket in SMOKE_TEST_ENTITY, polls the New list (with TICKET_FILTER) until the ticket
+//! shows up, runs it through the notification pipeline into a [`CaptureSink`], then purges the ticket.
+//! Nothing is written to the real seen state and no toast is shown.
+
+use crate::config::Connection;
+use crate::exit::ConfigError;
+use crate::glpi::{GlpiClient, TicketFields};
+use crate::notify::CaptureSink;
+use crate::state::{now_secs, SeenState};
+use crate::StartupFlags;
+
+use anyhow::{anyhow, Result};
+use log::warn;
+use std::time::{Duration, Instant};
+
+pub async fn run() -> Result<()> {
+    let config = crate::config::get();
+    let entity = config.smoke_test_entity.ok_or_else(|| {
+        ConfigError("smoke-test creates a real ticket: set SMOKE_TEST_ENTITY to the id of a test entity".into())
+    })?;
+    let timeout = config.smoke_test_timeout;
+    let filter = crate::filter::from_env()?;
+    let conn = Connection::from_env()?;
+
+    let mut client = conn.connect().await?;
+    let fields = client.resolve_ticket_fields().await;
+    let result = match fields {
+        Ok(fields) => exercise(&mut client, &fields, &filter, entity, Duration::from_secs(timeout)).await,
+        Err(e) => Err(e),
+    };
+    let _ = client.kill_session().await;
+    result
+}
+
+async fn exercise(
+    client: &mut GlpiClient,
+    fields: &TicketFields,
+    filter: &[crate::filter::Criterion],
+    entity: i64,
+    timeout: Duration,
+) -> Result<()> {
+    let sink = CaptureSink::default();
+    let mut st = SeenState::default();
+
+    // Baseline, as on a first run: current tickets are marked seen without notifying
+    let baseline = client.search_new_tickets(fields, filter, 200).await?;
+    let mut flags = StartupFlags { first_run: true, ..Default::default() };
+    crate::process_tickets(&baseline, &mut st, &mut flags, &sink)?;
+    flags.first_run = false;
+    println!("1/4 Baseline: {} New ticket(s) visible", baseline.len());
+
+    let name = format!("[smoke-test] glpi-notifier-rs {}", now_secs());
+    let content = "Created by `glpi-notifier-rs smoke-test`; deleted automatically. Safe to delete if left behind.";
+    let id = client.create_ticket(&name, content, None, Some(entity)).await?;
+    println!("2/4 Created ticket #{id} in entity {entity}");
+
+    let started = Instant::now();
+    let detected = async {
+        loop {
+            let tickets = client.search_new_tickets(fields, filter, 200).await?;
+            crate::process_tickets(&tickets, &mut st, &mut flags, &sink)?;
+            if sink.events().iter().any(|t| t.id == id) {
+                return Ok(started.elapsed());
+            }
+            if started.elapsed() >= timeout {
+                return Err(anyhow!(
+                    "ticket #{id} was not notified within {}s (check GLPI_ACTIVE_ENTITY / TICKET_FILTER, \
+                     or `explain {id}`)",
+                    timeout.as_secs()
+                ));
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+    }
+    .await;
+
+    let purged = client.purge_ticket(id).await;
+    if let Err(e) = &purged {
+        warn!("Ticket #{id} could not be deleted, delete it by hand: {e:#}");
+    }
+    let elapsed = detected?;
+    println!("3/4 Notification event for #{id} after {:.1}s", elapsed.as_secs_f64());
+    purged?;
+    println!("4/4 Deleted ticket #{id}");
+    println!("Smoke test passed.");
+    Ok(())
+}
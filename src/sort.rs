@@ -0,0 +1,131 @@
+//! Order of the `queue` view: QUEUE_SORT=id|priority|age|entity|sla|title (default id, newest first).
+//!
+//! Titles and entity names are compared accent- and case-insensitively (`École` sorts with `ecole`, before
+//! `Imprimante`), so French, Portuguese or German names are not pushed after `Z` by their first letter.
+
+use crate::exit::ConfigError;
+use crate::glpi::Ticket;
+
+use anyhow::Result;
+use std::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    /// Newest first.
+    Id,
+    /// Highest priority first, then newest.
+    Priority,
+    /// Oldest first.
+    Age,
+    /// Entity name, then newest.
+    Entity,
+    /// Least SLA (time to own) left first; tickets without one last.
+    Sla,
+    /// Title.
+    Title,
+}
+
+pub const KEYS: &[&str] = &["id", "priority", "age", "entity", "sla", "title"];
+
+pub fn parse(raw: &str) -> Result<SortKey> {
+    Ok(match raw.trim().to_lowercase().as_str() {
+        "" | "id" => SortKey::Id,
+        "priority" => SortKey::Priority,
+        "age" => SortKey::Age,
+        "entity" => SortKey::Entity,
+        "sla" => SortKey::Sla,
+        "title" => SortKey::Title,
+        other => return Err(ConfigError(format!("unknown sort '{other}' (expected {})", KEYS.join(", "))).into()),
+    })
+}
+
+/// QUEUE_SORT, the user's default order (invalid values were already reported by the settings check).
+pub fn from_env() -> SortKey {
+    crate::config::get().queue_sort
+}
+
+pub fn sort(tickets: &mut [Ticket], key: SortKey) {
+    let newest = |a: &Ticket, b: &Ticket| b.id.cmp(&a.id);
+    tickets.sort_by(|a, b| match key {
+        SortKey::Id => newest(a, b),
+        SortKey::Priority => b.priority.cmp(&a.priority).then_with(|| newest(a, b)),
+        SortKey::Age => {
+            a.created_ts().unwrap_or(u64::MAX).cmp(&b.created_ts().unwrap_or(u64::MAX)).then(a.id.cmp(&b.id))
+        }
+        SortKey::Entity => collate(a.entity.as_deref(), b.entity.as_deref()).then_with(|| newest(a, b)),
+        SortKey::Sla => sla_key(a).cmp(&sla_key(b)).then_with(|| newest(a, b)),
+        SortKey::Title => collate(Some(&a.name), Some(&b.name)).then_with(|| newest(a, b)),
+    });
+}
+
+/// (no SLA, due time): tickets with a time to own come first, the closest first.
+fn sla_key(t: &Ticket) -> (bool, i64) {
+    let due = t.time_to_own.as_deref().and_then(crate::clock::parse_glpi_date).map(|d| d.timestamp());
+    (due.is_none(), due.unwrap_or_default())
+}
+
+/// Accent- and case-insensitive order, exact text as the tie-break; missing values last.
+fn collate(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => fold(a).cmp(&fold(b)).then_with(|| a.cmp(b)),
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    }
+}
+
+/// Lowercase with the diacritics of Latin letters removed (`Ça été` -> `ca ete`).
+fn fold(s: &str) -> String {
+    s.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => 'a',
+            'ç' | 'ć' | 'č' => 'c',
+            'ď' | 'đ' => 'd',
+            'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => 'e',
+            'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' => 'i',
+            'ł' | 'ľ' => 'l',
+            'ñ' | 'ń' | 'ň' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => 'o',
+            'ř' => 'r',
+            'ś' | 'š' | 'ş' => 's',
+            'ť' | 'ţ' => 't',
+            'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => 'u',
+            'ý' | 'ÿ' => 'y',
+            'ź' | 'ż' | 'ž' => 'z',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(id: i64, name: &str, priority: i64) -> Ticket {
+        Ticket {
+            id,
+            name: name.into(),
+            requester: None,
+            category: None,
+            priority: Some(priority),
+            date: None,
+            documents: None,
+            entity: None,
+            time_to_own: None,
+            location: None,
+            major_incident: None,
+            instance: None,
+            language: None,
+        }
+    }
+
+    #[test]
+    fn sorts_titles_ignoring_accents_and_case() {
+        let mut tickets = [ticket(1, "Imprimante", 3), ticket(2, "école", 3), ticket(3, "Écran noir", 5)];
+        sort(&mut tickets, SortKey::Title);
+        assert_eq!(tickets.iter().map(|t| t.id).collect::<Vec<_>>(), [2, 3, 1]);
+
+        sort(&mut tickets, SortKey::Priority);
+        assert_eq!(tickets.iter().map(|t| t.id).collect::<Vec<_>>(), [3, 2, 1]);
+        assert!(parse("colour").is_err());
+    }
+}
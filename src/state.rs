@@ -1,36 +1,281 @@
+use crate::diff::Snapshot;
+use crate::focus::Focus;
+
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Persisted state between runs (ids of already-notified tickets).
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct SeenState {
     pub seen_ticket_ids: BTreeSet<i64>,
+    /// Ticket id -> UNIX timestamp until which its toast is snoozed.
+    #[serde(default)]
+    pub snoozed: BTreeMap<i64, u64>,
+    /// Active mutes (requester/category), pruned once expired.
+    #[serde(default)]
+    pub mutes: Vec<Mute>,
+    /// Running time-tracking timers: ticket id -> UNIX start timestamp.
+    #[serde(default)]
+    pub timers: BTreeMap<i64, u64>,
+    /// Highest ticket id ever observed in GLPI (detects id restarts after a purge/reinstall).
+    #[serde(default)]
+    pub max_ticket_id: i64,
+    /// Tickets whose toast may still sit in Action Center, withdrawn once they leave the New list.
+    #[serde(default)]
+    pub shown: BTreeSet<i64>,
+    /// When each shown ticket was last notified (for REPEAT_MINUTES).
+    #[serde(default)]
+    pub notified_at: BTreeMap<i64, u64>,
+    /// Consecutive failed notification attempts per ticket (retried until the limit, then given up).
+    #[serde(default)]
+    pub notify_failures: BTreeMap<i64, u32>,
+    /// Notified tickets picked up per technician (checked when a ticket leaves the New list).
+    #[serde(default)]
+    pub pickups: BTreeMap<String, u64>,
+    /// Tickets held back during focus mode, shown as a digest when it ends.
+    #[serde(default)]
+    pub focus_held: Vec<i64>,
+    /// Initial sync in progress (interrupted first run): New tickets below this id are still to be marked seen.
+    #[serde(default)]
+    pub sync_below: Option<i64>,
+    /// Ids in the New list at the last poll (None until the first poll with this field), to spot tickets
+    /// that come back to New.
+    #[serde(default)]
+    pub last_new: Option<BTreeSet<i64>>,
+    /// Satisfaction surveys already handled (reminded, or not ours), by TicketSatisfaction id.
+    #[serde(default)]
+    pub surveys_seen: BTreeSet<i64>,
+    /// Unseen New tickets the search window (newest 200) left out, evaluated on the next polls.
+    #[serde(default)]
+    pub deferred: BTreeSet<i64>,
+    /// New tickets outside the search window at this poll, still counted as New (not persisted).
+    #[serde(skip)]
+    pub beyond_window: BTreeSet<i64>,
+    /// Tickets deferred since the current catch-up started, for its progress toast (not persisted).
+    #[serde(skip)]
+    pub backlog: usize,
+    /// Notified Critical tickets awaiting the phone escalation: id -> UNIX time first notified.
+    #[serde(default)]
+    pub phone_pending: BTreeMap<i64, u64>,
+    /// Tickets the on-call person was already called or texted about.
+    #[serde(default)]
+    pub phoned: BTreeSet<i64>,
+    /// My open tickets watched for requester replies (CUSTOMER_REPLIED): id -> (last-update date when its
+    /// follow-ups were read, newest requester follow-up id seen).
+    #[serde(default)]
+    pub followups: BTreeMap<i64, (String, i64)>,
+    /// Open tickets I requested or am assigned to, watched for follow-ups (FOLLOWUPS): id -> (last-update date
+    /// when its follow-ups were read, newest follow-up id seen).
+    #[serde(default)]
+    pub followed: BTreeMap<i64, (String, i64)>,
+    /// My open tickets watched for changes (TICKET_UPDATES): status, assignees and priority as last read.
+    #[serde(default)]
+    pub snapshots: BTreeMap<i64, Snapshot>,
+    /// To-do tasks of my groups already toasted (GROUP_TASKS); None until the first check, which records the
+    /// existing ones without toasting.
+    #[serde(default)]
+    pub group_tasks: Option<BTreeSet<i64>>,
+    /// My open tickets at the last check (ASSIGNED_TO_ME); None until the first check, which records the
+    /// tickets already assigned without toasting.
+    #[serde(default)]
+    pub assigned: Option<BTreeSet<i64>>,
+    /// Last known status of the tickets watched for STATUS_TRANSITIONS, until they are closed.
+    #[serde(default)]
+    pub statuses: BTreeMap<i64, i64>,
+    /// Maintenance windows (begin, end) read from the GLPI reminders of MAINTENANCE_REMINDER, until they end.
+    #[serde(default)]
+    pub maintenance: Vec<(u64, u64)>,
+    /// When the GLPI reminders were last read (UNIX timestamp).
+    #[serde(default)]
+    pub maintenance_read: u64,
+    /// Depth level of the New queue at the last poll (QUEUE_WARN_AT / QUEUE_CRIT_AT).
+    #[serde(default)]
+    pub queue_level: crate::depth::Level,
+    /// Last state edit of the control channel applied here (see [`crate::control::StateEdit`]).
+    #[serde(default)]
+    pub edits_applied: u64,
+    /// Focus mode for this poll, read from focus.json (not persisted here).
+    #[serde(skip)]
+    pub focus: Option<Focus>,
+}
+
+/// What a mute applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum MuteTarget {
+    Requester(String),
+    Category(String),
+}
+
+impl std::fmt::Display for MuteTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MuteTarget::Requester(r) => write!(f, "requester '{r}'"),
+            MuteTarget::Category(c) => write!(f, "category '{c}'"),
+        }
+    }
+}
+
+/// A mute with its expiry (UNIX timestamp).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mute {
+    pub target: MuteTarget,
+    pub until: u64,
+}
+
+impl SeenState {
+    /// Record the newest ticket id currently in GLPI. Returns the previous maximum when ids
+    /// went back below half of it, which only happens when GLPI was purged or reinstalled.
+    pub fn observe_latest_id(&mut self, latest: i64) -> Option<i64> {
+        let known = self.max_ticket_id.max(self.seen_ticket_ids.last().copied().unwrap_or(0));
+        if latest < known / 2 {
+            return Some(known);
+        }
+        self.max_ticket_id = known.max(latest);
+        None
+    }
+
+    /// Forget every per-ticket entry (seen ids, snoozes, timers) after GLPI ids restarted.
+    pub fn reset_ticket_ids(&mut self, latest: i64) {
+        self.seen_ticket_ids.clear();
+        self.snoozed.clear();
+        self.timers.clear();
+        self.max_ticket_id = latest;
+    }
+
+    /// Add (or extend) a mute for `secs` seconds from now.
+    pub fn mute(&mut self, target: MuteTarget, secs: u64) {
+        let until = now_secs().saturating_add(secs);
+        self.mutes.retain(|m| m.target != target);
+        self.mutes.push(Mute { target, until });
+    }
+
+    /// Drop expired mutes. Returns true if anything was removed.
+    pub fn prune_mutes(&mut self) -> bool {
+        let now = now_secs();
+        let before = self.mutes.len();
+        self.mutes.retain(|m| m.until > now);
+        self.mutes.len() != before
+    }
+
+    /// The active mute matching this requester/category, if any.
+    pub fn muted_by(&self, requester: Option<&str>, category: Option<&str>) -> Option<&Mute> {
+        let now = now_secs();
+        self.mutes.iter().filter(|m| m.until > now).find(|m| match &m.target {
+            MuteTarget::Requester(r) => requester == Some(r.as_str()),
+            MuteTarget::Category(c) => category == Some(c.as_str()),
+        })
+    }
+}
+
+/// Current UNIX timestamp in seconds.
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
 }
 
 fn state_path() -> Option<PathBuf> {
     let dir = dirs::data_dir()?;
-    let p = dir.join("GlpiNotifier").join("state.json");
+    // Each instance of config.toml has its own seen tickets
+    let file = match crate::instance::current() {
+        Some(instance) => format!("state-{}.json", instance.name),
+        None => "state.json".to_string(),
+    };
+    let p = dir.join("GlpiNotifier").join(file);
     let _ = std::fs::create_dir_all(p.parent().unwrap());
     Some(p)
 }
 
+/// state.json once compressed (STATE_COMPRESS_KB); only one of the two is kept.
+fn compressed(p: &Path) -> PathBuf {
+    p.with_extension("json.zst")
+}
+
 pub fn load_state() -> anyhow::Result<SeenState> {
-    if let Some(p) = state_path() {
-        if p.exists() {
-            let data = fs::read(p)?;
-            let st: SeenState = serde_json::from_slice(&data)?;
-            return Ok(st);
-        }
+    match state_path() {
+        Some(p) => load_from(&p),
+        None => Ok(SeenState::default()),
     }
-    Ok(SeenState::default())
 }
 
+/// The state saved at `p`, or compressed next to it.
+fn load_from(p: &Path) -> anyhow::Result<SeenState> {
+    // Both only exist if a save was interrupted: the newer one wins
+    let newest = [p.to_path_buf(), compressed(p)]
+        .into_iter()
+        .filter_map(|f| Some((fs::metadata(&f).and_then(|m| m.modified()).ok()?, f)))
+        .max();
+    let Some((_, file)) = newest else { return Ok(SeenState::default()) };
+    let data = fs::read(&file)?;
+    let data = if file == p { data } else { zstd::decode_all(data.as_slice())? };
+    Ok(serde_json::from_slice(&data)?)
+}
+
+/// Warned once per run about the state size (STATE_WARN_KB).
+static SIZE_WARNED: AtomicBool = AtomicBool::new(false);
+
 pub fn save_state(st: &SeenState) -> anyhow::Result<()> {
     if let Some(p) = state_path() {
         let data = serde_json::to_vec_pretty(st)?;
-        fs::write(p, data)?;
+        let config = crate::config::get();
+        let warn_at = usize::try_from(config.state_warn_kb.saturating_mul(1024)).unwrap_or(usize::MAX);
+        if warn_at > 0 && data.len() > warn_at && !SIZE_WARNED.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "state.json is {} KiB (STATE_WARN_KB={}): `reset-seen` forgets the seen ticket ids",
+                data.len() / 1024,
+                warn_at / 1024
+            );
+        }
+        save_to(&p, &data, usize::try_from(config.state_compress_kb.saturating_mul(1024)).unwrap_or(usize::MAX))?;
     }
     Ok(())
 }
+
+/// Write the serialized state to `p`, or compressed next to it above `compress_at` bytes (0: never), through a
+/// temporary file renamed over the previous one: a save cut short leaves the last state whole.
+fn save_to(p: &Path, data: &[u8], compress_at: usize) -> anyhow::Result<()> {
+    let zst = compressed(p);
+    let compress = compress_at > 0 && data.len() > compress_at;
+    let (file, stale) = if compress { (zst.as_path(), p) } else { (p, zst.as_path()) };
+    let mut tmp = file.as_os_str().to_owned();
+    tmp.push(".tmp");
+    if compress {
+        fs::write(&tmp, zstd::encode_all(data, 3)?)?;
+    } else {
+        fs::write(&tmp, data)?;
+    }
+    fs::rename(&tmp, file)?;
+    if stale.exists() {
+        fs::remove_file(stale)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_survives_a_save_plain_and_compressed() {
+        let dir = std::env::temp_dir().join(format!("glpi-notifier-state-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("state.json");
+        let st = SeenState { seen_ticket_ids: BTreeSet::from([7, 4711]), ..SeenState::default() };
+        let data = serde_json::to_vec_pretty(&st).unwrap();
+
+        save_to(&p, &data, 0).unwrap();
+        assert!(p.exists() && !compressed(&p).exists());
+        assert_eq!(load_from(&p).unwrap().seen_ticket_ids, st.seen_ticket_ids);
+
+        save_to(&p, &data, 1).unwrap();
+        assert!(!p.exists() && compressed(&p).exists());
+        assert_eq!(load_from(&p).unwrap().seen_ticket_ids, st.seen_ticket_ids);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1, "no temporary file left behind");
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(load_from(&p).unwrap().seen_ticket_ids.is_empty());
+    }
+}
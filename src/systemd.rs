@@ -0,0 +1,104 @@
+use anyhow::{anyhow, Result};
+use log::{debug, info};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set on SIGTERM/SIGINT; checked by the poll loop's stop flag.
+pub static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+const UNIT_NAME: &str = "glpi-notifier.service";
+
+/// Send a state string (e.g. `READY=1`) to systemd when running under `Type=notify`.
+/// No-op when NOTIFY_SOCKET is not set (console, Task Scheduler, other platforms).
+pub fn sd_notify(state: &str) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::net::UnixDatagram;
+
+        let Ok(path) = std::env::var("NOTIFY_SOCKET") else { return };
+        let Ok(sock) = UnixDatagram::unbound() else { return };
+        let sent = if let Some(name) = path.strip_prefix('@') {
+            send_abstract(&sock, name, state)
+        } else {
+            sock.send_to(state.as_bytes(), &path).map(|_| ())
+        };
+        if let Err(e) = sent {
+            debug!("sd_notify({state}) failed: {e}");
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = state;
+}
+
+#[cfg(target_os = "linux")]
+fn send_abstract(sock: &std::os::unix::net::UnixDatagram, name: &str, state: &str) -> std::io::Result<()> {
+    use std::os::linux::net::SocketAddrExt;
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+    sock.send_to_addr(state.as_bytes(), &addr).map(|_| ())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn send_abstract(_sock: &std::os::unix::net::UnixDatagram, _name: &str, _state: &str) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "abstract sockets are Linux-only"))
+}
+
+/// Map SIGTERM/SIGINT to the stop flag so `systemctl stop` ends the loop cleanly (exit 0).
+pub fn listen_for_stop_signals() {
+    #[cfg(unix)]
+    tokio::spawn(async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let (Ok(mut term), Ok(mut int)) = (signal(SignalKind::terminate()), signal(SignalKind::interrupt())) else {
+            return;
+        };
+        tokio::select! {
+            _ = term.recv() => {}
+            _ = int.recv() => {}
+        }
+        info!("Stop signal received; finishing current iteration.");
+        sd_notify("STOPPING=1");
+        STOP_REQUESTED.store(true, Ordering::Relaxed);
+    });
+}
+
+pub fn stop_requested() -> bool {
+    STOP_REQUESTED.load(Ordering::Relaxed)
+}
+
+/// `install-systemd-user-unit`: write ~/.config/systemd/user/glpi-notifier.service for this executable.
+pub fn install_user_unit() -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        return Err(anyhow!("systemd units are only supported on Linux"));
+    }
+    let exe = std::env::current_exe()?;
+    let workdir = exe.parent().ok_or_else(|| anyhow!("executable has no parent directory"))?;
+    let dir = dirs::config_dir().ok_or_else(|| anyhow!("no user config directory"))?.join("systemd").join("user");
+    std::fs::create_dir_all(&dir)?;
+
+    // Exit codes 2 (config) and 3 (auth) need a human; restarting would only spin.
+    let unit = format!(
+        "[Unit]
+Description=GLPI ticket notifier
+After=graphical-session.target network-online.target
+
+[Service]
+Type=notify
+WorkingDirectory={workdir}
+ExecStart={exe}
+Environment=RUST_LOG=info
+Restart=on-failure
+RestartSec=30
+RestartPreventExitStatus=2 3
+
+[Install]
+WantedBy=default.target
+",
+        workdir = workdir.display(),
+        exe = exe.display(),
+    );
+
+    let path = dir.join(UNIT_NAME);
+    std::fs::write(&path, unit)?;
+    println!("Wrote {}", path.display());
+    println!("Enable it with:\n  systemctl --user daemon-reload\n  systemctl --user enable --now {UNIT_NAME}");
+    Ok(())
+}
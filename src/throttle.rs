@@ -0,0 +1,35 @@
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+struct Host {
+    slots: Arc<Semaphore>,
+    last_start: Arc<tokio::sync::Mutex<Option<Instant>>>,
+}
+
+static HOSTS: Lazy<Mutex<HashMap<String, Host>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Wait for a request slot on `host` (and for the politeness delay); the slot is released when dropped.
+pub async fn acquire(host: &str) -> OwnedSemaphorePermit {
+    let (slots, last_start) = {
+        let mut hosts = HOSTS.lock().unwrap();
+        let h = hosts.entry(host.to_string()).or_insert_with(|| Host {
+            slots: Arc::new(Semaphore::new(crate::config::get().max_concurrent.max(1))),
+            last_start: Arc::new(tokio::sync::Mutex::new(None)),
+        });
+        (h.slots.clone(), h.last_start.clone())
+    };
+    let permit = slots.acquire_owned().await.expect("request semaphore is never closed");
+
+    let delay = crate::config::get().request_delay;
+    if !delay.is_zero() {
+        let mut last = last_start.lock().await;
+        if let Some(t) = *last {
+            tokio::time::sleep((t + delay).saturating_duration_since(Instant::now())).await;
+        }
+        *last = Some(Instant::now());
+    }
+    permit
+}
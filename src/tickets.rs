@@ -0,0 +1,131 @@
+use crate::cache::Cache;
+use crate::glpi::Ticket;
+use crate::state::now_secs;
+
+use log::warn;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Last known ticket objects by id, refreshed by every poll and read by the other features (satisfaction
+/// reminders, `explain`) instead of asking GLPI again. With TICKET_CACHE=persist it is also kept in
+/// tickets.json, so one-shot commands see what the running poller last saw. Keyed by instance (see
+/// [`crate::instance`]) and id, as two GLPI servers number their tickets independently.
+pub static TICKETS: Lazy<Cache<TicketKey, Known>> =
+    Lazy::new(|| Cache::new("tickets", 1000, Duration::from_secs(86400)));
+
+/// A ticket whatever the server: its instance (None: the top-level server) and id.
+pub type TicketKey = (Option<String>, i64);
+
+/// A ticket as last read from GLPI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Known {
+    pub ticket: Ticket,
+    /// GLPI status at that time (1 New), when the read included it.
+    pub status: Option<i64>,
+    /// UNIX time it was read.
+    pub at: u64,
+}
+
+impl Known {
+    /// Seconds since the ticket was read from GLPI.
+    pub fn age(&self) -> u64 {
+        now_secs().saturating_sub(self.at)
+    }
+}
+
+fn persist() -> bool {
+    crate::config::get().ticket_cache_persist
+}
+
+fn cache_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("GlpiNotifier");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("tickets.json"))
+}
+
+/// Record tickets just read from GLPI (with their status when known).
+pub fn record(tickets: &[Ticket], status: Option<i64>) {
+    let at = now_secs();
+    for t in tickets {
+        TICKETS.insert((t.instance.clone(), t.id), Known { ticket: t.clone(), status, at });
+    }
+}
+
+/// Last known state of a ticket of the current instance, however old (check [`Known::age`]).
+pub fn get(id: i64) -> Option<Known> {
+    get_in(crate::instance::current_name(), id)
+}
+
+/// Last known state of ticket `id` of `instance` (None: the top-level server).
+pub fn get_in(instance: Option<String>, id: i64) -> Option<Known> {
+    TICKETS.get(&(instance, id))
+}
+
+/// With TICKET_CACHE=persist, load the tickets saved by the last run (or by the running poller).
+pub fn load() {
+    if !persist() {
+        return;
+    }
+    let Some(data) = cache_path().and_then(|p| std::fs::read(p).ok()) else { return };
+    match serde_json::from_slice::<Vec<Known>>(&data) {
+        Ok(known) => known.into_iter().for_each(|k| TICKETS.insert((k.ticket.instance.clone(), k.ticket.id), k)),
+        Err(e) => warn!("Ignoring unreadable tickets.json: {e}"),
+    }
+}
+
+/// With TICKET_CACHE=persist, save the cache (after each poll).
+pub fn save() {
+    if !persist() {
+        return;
+    }
+    let Some(path) = cache_path() else { return };
+    let mut known: Vec<Known> = TICKETS.values();
+    known.sort_by(|a, b| (&a.ticket.instance, a.ticket.id).cmp(&(&b.ticket.instance, b.ticket.id)));
+    let tmp = path.with_extension("json.tmp");
+    let written = serde_json::to_vec(&known).map_err(anyhow::Error::from).and_then(|data| {
+        std::fs::write(&tmp, data)?;
+        Ok(std::fs::rename(&tmp, &path)?)
+    });
+    if let Err(e) = written {
+        warn!("Could not save tickets.json: {e:#}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tickets_are_kept_per_instance() {
+        // TICKETS is process-wide: ids no other test uses
+        let (top, ticket) = crate::toast::tests::sample_tickets().remove(0);
+        let ticket = Ticket { id: 910_001, ..ticket };
+        let other = Ticket { instance: Some("lab".into()), name: format!("{top} in the lab"), ..ticket.clone() };
+        record(std::slice::from_ref(&ticket), Some(1));
+        record(std::slice::from_ref(&other), None);
+
+        let known = get_in(None, 910_001).unwrap();
+        assert_eq!((known.ticket.name.as_str(), known.status), (ticket.name.as_str(), Some(1)));
+        assert!(known.age() <= 1);
+        let lab = get_in(Some("lab".into()), 910_001).unwrap();
+        assert_eq!((lab.ticket.name, lab.status), (other.name.clone(), None));
+        assert!(get_in(Some("prod".into()), 910_001).is_none());
+
+        // A later read replaces the earlier one
+        record(&[Ticket { name: "Renamed".into(), ..ticket }], Some(2));
+        let known = get_in(None, 910_001).unwrap();
+        assert_eq!((known.ticket.name.as_str(), known.status), ("Renamed", Some(2)));
+    }
+
+    #[test]
+    fn persisted_tickets_read_back() {
+        let (_, ticket) = crate::toast::tests::sample_tickets().remove(0);
+        let saved = vec![Known { ticket, status: Some(1), at: 1_760_000_000 }];
+        let read: Vec<Known> = serde_json::from_slice(&serde_json::to_vec(&saved).unwrap()).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!((read[0].ticket.id, read[0].status, read[0].at), (4711, Some(1), 1_760_000_000));
+        assert_eq!(read[0].ticket.location, saved[0].ticket.location);
+    }
+}
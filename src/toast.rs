@@ -0,0 +1,992 @@
+// Builds without a desktop backend only use the ticket URL and browser helpers
+#![cfg_attr(
+    not(any(
+        feature = "toast-winrt",
+        feature = "toast-snoretoast",
+        feature = "notify-dbus",
+        feature = "notify-macos"
+    )),
+    allow(dead_code)
+)]
+
+use crate::accessibility::Verbosity;
+use crate::glpi::{Severity, Ticket};
+
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use once_cell::sync::OnceCell;
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod shortcut;
+#[cfg(all(windows, feature = "toast-winrt"))]
+mod winrt;
+
+// URL template (e.g. https://your-glpi/front/ticket.form.php?id={id})
+pub static URL_TEMPLATE: OnceCell<Option<String>> = OnceCell::new();
+
+/// Per-entity URL templates (GLPI_TICKET_URL_TEMPLATES) as (entity full name, template), resolved at startup.
+pub static ENTITY_URL_TEMPLATES: OnceCell<Vec<(String, String)>> = OnceCell::new();
+
+const APP_ID: &str = "GlpiNotifier";
+
+/// Windows renders at most 5 toast buttons.
+const MAX_BUTTONS: usize = 5;
+
+/// Progress toast: values bound by name, so it is updated in place.
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+const PROGRESS_XML: &str = r#"<toast><visual><binding template="ToastGeneric"><text>{title}</text><progress value="{value}" status="{status}" valueStringOverride="{label}"/></binding></visual></toast>"#;
+
+/// How toasts are shown (TOAST_BACKEND).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    /// Native WinRT toasts, falling back to SnoreToast if they fail (default).
+    Auto,
+    /// Native WinRT toasts only.
+    Native,
+    /// snoretoast.exe only (also the only choice in builds without the toast-winrt feature).
+    SnoreToast,
+}
+
+/// Set once native toasts failed under TOAST_BACKEND=auto: SnoreToast is used for the rest of the run.
+static NATIVE_FAILED: AtomicBool = AtomicBool::new(false);
+
+fn backend() -> Backend {
+    if !cfg!(all(windows, feature = "toast-winrt")) {
+        return Backend::SnoreToast;
+    }
+    match crate::config::get().toast_backend {
+        "winrt" => Backend::Native,
+        "snoretoast" => Backend::SnoreToast,
+        _ if NATIVE_FAILED.load(Ordering::Relaxed) => Backend::SnoreToast,
+        _ => Backend::Auto,
+    }
+}
+
+/// Native toasts failed: an error with TOAST_BACKEND=winrt, else a warning (once) and SnoreToast from now on.
+#[cfg(all(windows, feature = "toast-winrt"))]
+fn native_failed(e: anyhow::Error) -> Result<()> {
+    if backend() == Backend::Native {
+        return Err(e);
+    }
+    if !NATIVE_FAILED.swap(true, Ordering::Relaxed) {
+        warn!("Native toasts failed, using SnoreToast from now on: {e:#}");
+    }
+    ensure_snore_shortcut(APP_ID);
+    Ok(())
+}
+
+/// Per-ticket actions offered as toast buttons and routed back to the poll loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastAction {
+    Open,
+    AssignToMe,
+    Snooze,
+    MuteRequester,
+    MuteCategory,
+    StartTimer,
+}
+
+impl ToastAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            ToastAction::Open => "Open",
+            ToastAction::AssignToMe => "Assign to me",
+            ToastAction::Snooze => "Snooze",
+            ToastAction::MuteRequester => "Mute requester",
+            ToastAction::MuteCategory => "Mute category",
+            ToastAction::StartTimer => "Start timer",
+        }
+    }
+
+    /// SnoreToast prints the label of the pressed button on stdout.
+    fn from_output(stdout: &str, offered: &[ToastAction]) -> Option<Self> {
+        let line = stdout.lines().map(str::trim).rfind(|l| !l.is_empty())?;
+        offered.iter().copied().find(|a| line.ends_with(a.label()))
+    }
+}
+
+/// Toast scenario (Windows `scenario` attribute): how long and how insistently the toast stays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastScenario {
+    /// Short toast that goes to Action Center on its own.
+    Default,
+    /// Important notification that may break through Focus Assist / Do not disturb (used for High tickets,
+    /// GLPI's Very high priority).
+    Urgent,
+    /// Stays on screen until dismissed (used for Critical tickets, GLPI's Major priority).
+    Reminder,
+}
+
+impl ToastScenario {
+    pub fn for_severity(severity: Severity) -> Self {
+        match severity {
+            Severity::Critical => ToastScenario::Reminder,
+            Severity::High => ToastScenario::Urgent,
+            Severity::Low | Severity::Normal => ToastScenario::Default,
+        }
+    }
+}
+
+/// Ticket page URL built from GLPI_TICKET_URL_TEMPLATE (the current instance's, in its poll loop), if configured.
+pub fn ticket_url(id: i64) -> Option<String> {
+    if let Some(instance) = crate::instance::current() {
+        return instance_url(instance, id);
+    }
+    URL_TEMPLATE.get().and_then(|tpl| tpl.as_ref()).map(|tpl| tpl.replace("{id}", &id.to_string()))
+}
+
+fn instance_url(instance: &crate::instance::Instance, id: i64) -> Option<String> {
+    instance.value("GLPI_TICKET_URL_TEMPLATE").map(|tpl| tpl.trim().replace("{id}", &id.to_string()))
+}
+
+/// Ticket page URL on its entity's vhost: the template of the closest configured entity (the ticket's own,
+/// else its nearest parent), falling back to GLPI_TICKET_URL_TEMPLATE. A ticket of an instance uses that
+/// instance's template.
+pub fn ticket_url_for(t: &Ticket) -> Option<String> {
+    if let Some(instance) = t.instance.as_deref().and_then(crate::instance::get) {
+        return instance_url(instance, t.id);
+    }
+    let templates = ENTITY_URL_TEMPLATES.get().map(Vec::as_slice).unwrap_or_default();
+    match (t.entity.as_deref(), entity_template(templates, t.entity.as_deref())) {
+        (Some(_), Some(tpl)) => Some(tpl.replace("{id}", &t.id.to_string())),
+        _ => ticket_url(t.id),
+    }
+}
+
+/// `url` opening the ticket form on its tab `tab` (`forcetab`), with or without a query string in the template.
+pub fn with_tab(url: &str, tab: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut u) => {
+            u.query_pairs_mut().append_pair("forcetab", tab);
+            u.into()
+        }
+        Err(_) => format!("{url}{}forcetab={tab}", if url.contains('?') { '&' } else { '?' }),
+    }
+}
+
+fn entity_template<'a>(templates: &'a [(String, String)], entity: Option<&str>) -> Option<&'a str> {
+    let entity = entity?;
+    templates
+        .iter()
+        .filter(|(name, _)| entity == name || entity.starts_with(&format!("{name} > ")))
+        .max_by_key(|(name, _)| name.len())
+        .map(|(_, tpl)| tpl.as_str())
+}
+
+/// Parse GLPI_TICKET_URL_TEMPLATES: `entity-id=template` pairs separated by `;`.
+pub fn parse_entity_templates(raw: &str) -> Result<Vec<(i64, String)>> {
+    raw.split(';')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|pair| {
+            let (id, tpl) = pair.split_once('=').ok_or_else(|| anyhow!("'{pair}': expected entity-id=template"))?;
+            let id = id.trim().parse().map_err(|_| anyhow!("'{pair}': entity id must be a number"))?;
+            let tpl = tpl.trim();
+            if !tpl.contains("{id}") {
+                return Err(anyhow!("'{pair}': template must contain {{id}}"));
+            }
+            Ok((id, tpl.to_string()))
+        })
+        .collect()
+}
+
+/// What a ticket notification shows, whatever the backend renders it with.
+pub struct TicketToast {
+    pub title: String,
+    pub body: String,
+    pub actions: Vec<ToastAction>,
+    pub scenario: ToastScenario,
+}
+
+/// Title, subject + requester and the per-ticket action buttons of a ticket notification.
+pub fn ticket_toast(t: &Ticket) -> TicketToast {
+    ticket_toast_as(t, crate::accessibility::mode())
+}
+
+/// [`ticket_toast`], with the labelled text of the accessibility mode when `accessible` is set.
+fn ticket_toast_as(t: &Ticket, accessible: Option<Verbosity>) -> TicketToast {
+    let scenario = ToastScenario::for_severity(t.severity());
+    let mut title = match (scenario, accessible) {
+        (ToastScenario::Reminder, None) => format!("GLPI: CRITICAL ticket #{}", t.id),
+        (ToastScenario::Reminder, Some(_)) => format!("GLPI: Critical ticket #{}", t.id),
+        (ToastScenario::Urgent, _) => format!("GLPI: Urgent ticket #{}", t.id),
+        (ToastScenario::Default, _) => format!("GLPI: New ticket #{}", t.id),
+    };
+    if let Some(instance) = &t.instance {
+        title = format!("[{instance}] {title}");
+    }
+    let actions = ticket_actions(t);
+    if let Some(verbosity) = accessible {
+        let body = crate::accessibility::ticket_body(t, verbosity);
+        return TicketToast { title, body, actions, scenario };
+    }
+    let requester = t.requester.as_deref().unwrap_or("Unknown");
+    let mut by = match t.opened_display() {
+        Some(opened) => format!("By: {requester}, opened {opened}"),
+        None => format!("By: {requester}"),
+    };
+    if let Some(n) = t.documents.filter(|n| *n > 0) {
+        by.push_str(&format!(" · 📎 {n}")); // screenshots/logs already attached
+    }
+    if let Some(tto) = t.time_to_own.as_deref().and_then(|d| crate::calendar::countdown("TTO", d)) {
+        by.push_str(&format!("\n{tto}"));
+    }
+    let mut name = if t.name.is_empty() { "New ticket" } else { t.name.as_str() }.to_string();
+    // Hostnames, asset tags... picked out of the title (TICKET_REFS)
+    if let Some(refs) = crate::refs::summary(&crate::refs::of(t)) {
+        name.push_str(&format!("\n🏷 {refs}"));
+    }
+    // Field technicians dispatch by place: the location comes right under the title
+    let mut msg = match t.location.as_deref() {
+        Some(location) => format!("📍 {location}\n{name}\n{by}"),
+        None => format!("{name}\n{by}"),
+    };
+    // Why a low-priority ticket is Critical: it is part of a major incident
+    if let Some(mi) = t.major_incident {
+        msg = format!("🔗 linked to MI #{mi}\n{msg}");
+    }
+
+    TicketToast { title, body: msg, actions, scenario }
+}
+
+/// The action buttons of a ticket notification.
+fn ticket_actions(t: &Ticket) -> Vec<ToastAction> {
+    let mut actions = Vec::new();
+    if ticket_url_for(t).is_some() {
+        actions.push(ToastAction::Open);
+    }
+    actions.push(ToastAction::AssignToMe);
+    if crate::config::get().timer_action {
+        actions.push(ToastAction::StartTimer);
+    }
+    actions.push(ToastAction::Snooze);
+    if t.requester.is_some() {
+        actions.push(ToastAction::MuteRequester);
+    }
+    if t.category.is_some() {
+        actions.push(ToastAction::MuteCategory);
+    }
+    actions.truncate(MAX_BUTTONS);
+    if crate::control::kiosk() {
+        actions.clear(); // wallboard: read-only
+    }
+    actions
+}
+
+/// Build and show a toast (title + subject + requester) with the per-ticket action buttons.
+/// Returns the action picked by the user, if snoretoast waited for it; a native toast returns at once and hands
+/// the button pressed later to the poll loop, like the tray flyout's.
+pub fn show_toast(t: &Ticket) -> Result<Option<ToastAction>> {
+    let toast = ticket_toast(t);
+    let ticket = t.clone();
+    let answered = move |action| crate::pipeline::dispatcher().push_action(ticket, action);
+    show(&toast.title, &toast.body, t.id, &toast.actions, toast.scenario, answered)
+}
+
+/// Title and body telling what changed on a ticket, one change per line.
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast", feature = "notify-dbus", feature = "notify-macos"))]
+pub fn update_text(t: &Ticket, changes: &[crate::diff::Change]) -> (String, String) {
+    update_text_as(t, changes, crate::accessibility::enabled())
+}
+
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast", feature = "notify-dbus", feature = "notify-macos"))]
+fn update_text_as(t: &Ticket, changes: &[crate::diff::Change], accessible: bool) -> (String, String) {
+    let mut body = if accessible { format!("Title: {}", t.name) } else { t.name.clone() };
+    for c in changes {
+        let line = if accessible {
+            crate::accessibility::change(c.field, &c.from, &c.to)
+        } else {
+            format!("{}: {} → {}", c.field, c.from, c.to)
+        };
+        body.push_str(&format!("\n{line}"));
+    }
+    (format!("Ticket #{} updated", t.id), body)
+}
+
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+/// Show what changed on one of my tickets, one change per line, with an Open button when the ticket has a URL.
+pub fn show_update(t: &Ticket, changes: &[crate::diff::Change]) -> Result<()> {
+    let (title, body) = update_text(t, changes);
+    match ticket_url_for(t) {
+        Some(url) => show_link(&title, &body, &url),
+        None => show_message(&title, &body),
+    }
+}
+
+/// Show a plain informational toast (no ticket, no buttons).
+pub fn show_message(title: &str, body: &str) -> Result<()> {
+    show(title, body, 0, &[], ToastScenario::Default, drop).map(|_| ())
+}
+
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+/// Show an informational toast with an Open button that leads to `url`.
+pub fn show_link(title: &str, body: &str, url: &str) -> Result<()> {
+    let mut actions = vec![ToastAction::Open];
+    if crate::control::kiosk() {
+        actions.clear();
+    }
+    let target = url.to_string();
+    let open = move |_| {
+        if let Err(e) = open_in_browser(&target) {
+            log::warn!("Could not open {target}: {e:#}");
+        }
+    };
+    if show(title, body, 0, &actions, ToastScenario::Default, open)? == Some(ToastAction::Open) {
+        open_in_browser(url)?;
+    }
+    Ok(())
+}
+
+/// Show a toast with the backend in use ([`backend`]); `ticket_id` tags it for [`close_toast`]. snoretoast waits
+/// for the button pressed and returns it; a native toast returns once shown and hands it to `answered` later.
+#[cfg_attr(not(all(windows, feature = "toast-winrt")), allow(unused_variables))]
+fn show(
+    title: &str,
+    body: &str,
+    ticket_id: i64,
+    actions: &[ToastAction],
+    scenario: ToastScenario,
+    answered: impl FnOnce(ToastAction) + Send + 'static,
+) -> Result<Option<ToastAction>> {
+    #[cfg(all(windows, feature = "toast-winrt"))]
+    if backend() != Backend::SnoreToast {
+        let xml = toast_xml(title, body, actions, scenario, ensure_logo_file().as_deref());
+        match winrt::show(&xml, &ticket_id.to_string(), actions, answered) {
+            Ok(()) => return Ok(None),
+            Err(e) => native_failed(e)?,
+        }
+    }
+    show_toast_snoretoast(APP_ID, title, body, ticket_id, actions, scenario)
+}
+
+#[cfg_attr(not(all(windows, feature = "toast-winrt")), allow(dead_code))]
+/// Toast XML for the WinRT API: title, body and logo, and one button per action (its label comes back as the
+/// activation argument).
+fn toast_xml(title: &str, body: &str, actions: &[ToastAction], scenario: ToastScenario, logo: Option<&str>) -> String {
+    let scenario_attr = match scenario {
+        ToastScenario::Reminder => r#" scenario="reminder""#,
+        ToastScenario::Urgent => r#" scenario="urgent""#,
+        ToastScenario::Default => "",
+    };
+    let mut xml = format!(
+        r#"<toast duration="{}"{scenario_attr}><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text>"#,
+        duration(scenario),
+        xml_escape(title),
+        xml_escape(body)
+    );
+    if let Some(logo) = logo {
+        let uri = format!("file:///{}", logo.replace('\\', "/"));
+        xml.push_str(&format!(r#"<image placement="appLogoOverride" src="{}"/>"#, xml_escape(&uri)));
+    }
+    xml.push_str("</binding></visual>");
+    if !actions.is_empty() {
+        xml.push_str("<actions>");
+        for a in actions {
+            let label = xml_escape(a.label());
+            xml.push_str(&format!(r#"<action content="{label}" arguments="{label}"/>"#));
+        }
+        xml.push_str("</actions>");
+    }
+    xml.push_str("</toast>");
+    xml
+}
+
+#[cfg_attr(not(all(windows, feature = "toast-winrt")), allow(dead_code))]
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+/// snoretoast.exe command line for a toast (buttons are `;`-separated labels).
+fn snore_args(
+    app_id: &str,
+    title: &str,
+    body: &str,
+    ticket_id: i64,
+    actions: &[ToastAction],
+    scenario: ToastScenario,
+    logo: Option<&str>,
+) -> Vec<String> {
+    let mut args: Vec<String> = ["-appID", app_id, "-id", &ticket_id.to_string(), "-t", title, "-m", body]
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    args.extend(["-d".to_string(), duration(scenario).to_string()]);
+    if let Some(img) = logo {
+        args.extend(["-p".to_string(), img.to_string()]);
+    }
+    if !actions.is_empty() {
+        let buttons: Vec<&str> = actions.iter().map(|a| a.label()).collect();
+        args.extend(["-b".to_string(), buttons.join(";")]);
+    }
+    args
+}
+
+/// Call snoretoast.exe to display a Windows toast with optional buttons and image.
+fn show_toast_snoretoast(
+    app_id: &str,
+    title: &str,
+    body: &str,
+    ticket_id: i64,
+    actions: &[ToastAction],
+    scenario: ToastScenario,
+) -> Result<Option<ToastAction>> {
+    if !cfg!(feature = "toast-snoretoast") {
+        return Err(anyhow!("toasts are not available: built without the toast-snoretoast feature"));
+    }
+    let snore =
+        find_snoretoast().ok_or_else(|| anyhow!("snoretoast.exe not found (place it next to the .exe or in PATH)"))?;
+
+    let logo = ensure_logo_file();
+    if let Some(img) = &logo {
+        log::info!("SnoreToast: attaching image {}", img);
+    }
+    let mut cmd = Command::new(snore);
+    cmd.args(snore_args(app_id, title, body, ticket_id, actions, scenario, logo.as_deref()));
+
+    let out = cmd.output()?;
+    let code = out.status.code().unwrap_or(-1);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+
+    // Accept all documented statuses
+    if (0..=5).contains(&code) {
+        let label = match code {
+            0 => "Success",
+            1 => "Hidden",
+            2 => "Dismissed",
+            3 => "TimedOut",
+            4 => "ButtonPressed",
+            5 => "TextEntered",
+            _ => "Unknown",
+        };
+        log::debug!("SnoreToast: {}", label);
+        if code == 4 {
+            let action = ToastAction::from_output(&stdout, actions);
+            if action.is_none() {
+                debug!("SnoreToast: unrecognized button output: {}", stdout.trim());
+            }
+            return Ok(action);
+        }
+        return Ok(None);
+    }
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    Err(anyhow!("snoretoast failed (code {:?}). STDOUT:\n{}\nSTDERR:\n{}", out.status.code(), stdout, stderr))
+}
+
+/// Toast duration (SnoreToast `-d`, WinRT `duration`): TOAST_DURATION (`short`/`long`), else derived from the
+/// scenario.
+fn duration(scenario: ToastScenario) -> &'static str {
+    match crate::config::get().toast_duration {
+        "short" => "short",
+        "long" => "long",
+        // SnoreToast has no `scenario` switch: the long duration is the closest it gets to a reminder toast
+        // (native toasts get both)
+        _ => match scenario {
+            ToastScenario::Default => "short",
+            ToastScenario::Urgent | ToastScenario::Reminder => "long",
+        },
+    }
+}
+
+#[cfg_attr(not(any(feature = "toast-winrt", feature = "toast-snoretoast", feature = "notify-dbus")), allow(dead_code))]
+/// ACTION_CENTER_CLEANUP=true removes a ticket's toast once it is no longer New.
+pub fn cleanup_enabled() -> bool {
+    crate::config::get().action_center_cleanup
+}
+
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+/// Remove the toast shown for `ticket_id` (on screen or in Action Center).
+pub fn close_toast(ticket_id: i64) -> Result<()> {
+    #[cfg(all(windows, feature = "toast-winrt"))]
+    if backend() != Backend::SnoreToast {
+        match winrt::close(&ticket_id.to_string()) {
+            Ok(()) => return Ok(()),
+            Err(e) => native_failed(e)?,
+        }
+    }
+    let snore = find_snoretoast().ok_or_else(|| anyhow!("snoretoast.exe not found"))?;
+    let status = Command::new(snore).arg("-appID").arg(APP_ID).arg("-close").arg(ticket_id.to_string()).status()?;
+    debug!("SnoreToast: closed toast #{ticket_id} ({status})");
+    Ok(())
+}
+
+/// Show or update in place the progress toast (initial sync, backlog catch-up); values are data-bound, and the
+/// toast is shown again if it was dismissed in the meantime. SnoreToast has no progress bar, so without native
+/// toasts this goes through the WinRT toast API from PowerShell under the same AUMID.
+#[cfg(any(feature = "toast-winrt", feature = "toast-snoretoast"))]
+pub fn show_progress(title: &str, status: &str, done: usize, total: usize) -> Result<()> {
+    let ratio = if total == 0 { 1.0 } else { (done as f64 / total as f64).min(1.0) };
+    let values = [
+        ("title", title.to_string()),
+        ("status", status.to_string()),
+        ("value", format!("{ratio:.2}")),
+        ("label", format!("{}%", (ratio * 100.0).round())),
+    ];
+    #[cfg(all(windows, feature = "toast-winrt"))]
+    if backend() != Backend::SnoreToast {
+        match winrt::progress(PROGRESS_XML, &values, done as u32 + 1) {
+            Ok(()) => return Ok(()),
+            Err(e) => native_failed(e)?,
+        }
+    }
+
+    const SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null
+[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] > $null
+$notifier = [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($env:GLPI_TOAST_APPID)
+$data = New-Object Windows.UI.Notifications.NotificationData
+foreach ($k in 'title', 'status', 'value', 'label') { $data.Values[$k] = [Environment]::GetEnvironmentVariable("GLPI_TOAST_$k") }
+$data.SequenceNumber = [uint32]$env:GLPI_TOAST_SEQ
+if ($notifier.Update($data, 'progress', 'glpi') -ne 'Succeeded') {
+    $xml = New-Object Windows.Data.Xml.Dom.XmlDocument
+    $xml.LoadXml($env:GLPI_TOAST_XML)
+    $toast = New-Object Windows.UI.Notifications.ToastNotification $xml
+    $toast.Tag = 'progress'; $toast.Group = 'glpi'; $toast.Data = $data
+    $notifier.Show($toast)
+}
+"#;
+    let mut cmd = Command::new("powershell");
+    cmd.args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("GLPI_TOAST_APPID", APP_ID)
+        .env("GLPI_TOAST_XML", PROGRESS_XML)
+        .env("GLPI_TOAST_SEQ", (done + 1).to_string());
+    for (key, value) in &values {
+        cmd.env(format!("GLPI_TOAST_{key}"), value);
+    }
+    let status = cmd.status()?;
+    if !status.success() {
+        return Err(anyhow!("progress toast failed ({status})"));
+    }
+    Ok(())
+}
+
+/// Open the ticket page in the default browser (no-op when no URL template is set).
+pub fn open_ticket(t: &Ticket) {
+    if let Some(url) = ticket_url_for(t) {
+        if let Err(e) = open_in_browser(&url) {
+            warn!("Failed to open ticket URL: {e:#}");
+        }
+    }
+}
+
+/// Open a GLPI page with BROWSER_COMMAND (and BROWSER_PROFILE) when set, e.g. the Edge work profile where
+/// GLPI SSO is signed in, else with the system default handler.
+pub fn open_in_browser(url: &str) -> Result<()> {
+    let browser = env::var("BROWSER_COMMAND").unwrap_or_default();
+    let browser = browser.trim();
+    if browser.is_empty() {
+        if cfg!(windows) {
+            return open_url_windows(url);
+        }
+        let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+        Command::new(opener).arg(url).spawn().map_err(|e| anyhow!("{opener} failed to start: {e}"))?;
+        return Ok(());
+    }
+    let mut cmd = Command::new(browser);
+    cmd.args(profile_args(browser, env::var("BROWSER_PROFILE").unwrap_or_default().trim()));
+    cmd.arg(url).spawn().map_err(|e| anyhow!("BROWSER_COMMAND '{browser}' failed to start: {e}"))?;
+    Ok(())
+}
+
+/// Profile switch for the browser family: Firefox takes a profile name, Chromium-based browsers
+/// (Edge, Chrome, Brave) a profile directory such as `Profile 1`.
+fn profile_args(browser: &str, profile: &str) -> Vec<String> {
+    if profile.is_empty() {
+        return Vec::new();
+    }
+    let exe = std::path::Path::new(browser).file_stem().and_then(|s| s.to_str()).unwrap_or(browser);
+    if exe.to_lowercase().contains("firefox") {
+        vec!["-P".into(), profile.into()]
+    } else {
+        vec![format!("--profile-directory={profile}")]
+    }
+}
+
+pub fn open_url_windows(url: &str) -> Result<()> {
+    let mut cmd = Command::new("cmd");
+    // Passed as is: Rust's own quoting leaves a URL without spaces bare, and cmd would split it at `&`
+    #[cfg(windows)]
+    std::os::windows::process::CommandExt::raw_arg(&mut cmd, start_line(url));
+    #[cfg(not(windows))]
+    cmd.arg(start_line(url));
+    cmd.spawn()?;
+    Ok(())
+}
+
+/// `cmd` command line opening `url`: `start` needs an empty title "" first, and the URL in quotes so that cmd
+/// leaves its `&`, `^` and `|` alone.
+fn start_line(url: &str) -> String {
+    format!(r#"/C start "" "{}""#, url.replace('"', "%22"))
+}
+
+/// Try to locate snoretoast.exe in common places (next to exe, default install dir, PATH).
+fn find_snoretoast() -> Option<String> {
+    if !cfg!(feature = "toast-snoretoast") {
+        return None;
+    }
+    // 1) next to the notifier exe
+    if let Ok(exe) = std::env::current_exe() {
+        if let Some(dir) = exe.parent() {
+            let cand = dir.join("snoretoast.exe");
+            if cand.exists() {
+                return Some(cand.to_string_lossy().into_owned());
+            }
+        }
+    }
+    // 2) typical Program Files location
+    if let Ok(pf) = std::env::var("ProgramFiles") {
+        let cand = std::path::Path::new(&pf).join("SnoreToast").join("snoretoast.exe");
+        if cand.exists() {
+            return Some(cand.to_string_lossy().into_owned());
+        }
+    }
+    // 3) let PATH resolve it
+    Some("snoretoast.exe".to_string())
+}
+
+/// Outcome of [`ensure_snore_shortcut`]: the shortcut installed, or why none could be.
+static SHORTCUT: OnceCell<Result<PathBuf, String>> = OnceCell::new();
+
+/// Where the AUMID shortcut may go, in order: SHORTCUT_PATH, the per-user Start Menu, then its Startup folder
+/// (any shortcut under Start Menu\Programs registers the AUMID; one in Startup also starts the app at logon).
+fn shortcut_candidates() -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = crate::config::get().shortcut_path.iter().cloned().collect();
+    if let Some(programs) =
+        dirs::data_dir().map(|d| d.join("Microsoft").join("Windows").join("Start Menu").join("Programs"))
+    {
+        out.push(programs.join(format!("{APP_ID}.lnk")));
+        out.push(programs.join("Startup").join(format!("{APP_ID}.lnk")));
+    }
+    out
+}
+
+/// SHORTCUT_PATH: an absolute Windows path (`C:\...` or `\\server\...`) under a Start Menu\Programs folder,
+/// the only place where a shortcut registers the AUMID.
+pub fn check_shortcut_path(raw: &str) -> Result<()> {
+    let path = raw.trim().replace('/', "\\").to_lowercase();
+    let drive = path.as_bytes().get(1..3) == Some(b":\\") && path.as_bytes()[0].is_ascii_alphabetic();
+    if !drive && !path.starts_with("\\\\") {
+        return Err(anyhow!("an absolute path is expected (C:\\...\\GlpiNotifier.lnk)"));
+    }
+    if !path.contains("\\start menu\\programs\\") {
+        return Err(anyhow!("the shortcut must be under a Start Menu\\Programs folder to register the AUMID"));
+    }
+    Ok(())
+}
+
+/// Register the toast AUMID for the backend in use: in the registry for native toasts, as a Start Menu shortcut
+/// for SnoreToast, after repairing the shortcuts of earlier installs. Tried once per run; `doctor` reports the
+/// outcome ([`app_id_status`]).
+pub fn ensure_app_id() {
+    if !cfg!(windows) {
+        return;
+    }
+    repair_shortcuts();
+    #[cfg(all(windows, feature = "toast-winrt"))]
+    if backend() != Backend::SnoreToast {
+        winrt::ensure_registered(ensure_logo_file().as_deref());
+        record_registration();
+        return;
+    }
+    ensure_snore_shortcut(APP_ID);
+    record_registration();
+}
+
+/// Re-point or remove the AUMID shortcuts left by another exe (see [`shortcut`]), once per run.
+fn repair_shortcuts() {
+    static ONCE: std::sync::Once = std::sync::Once::new();
+    ONCE.call_once(|| {
+        let Ok(exe) = std::env::current_exe() else { return };
+        let snore = find_snoretoast();
+        shortcut::repair(&exe, &shortcut_candidates(), |lnk| {
+            let Some(snore) = &snore else { return false };
+            Command::new(snore).arg("-install").arg(lnk).arg(&exe).arg(APP_ID).status().is_ok() && lnk.exists()
+        });
+    });
+}
+
+/// Remember this exe and its shortcut in app-id.json for the next run's [`repair_shortcuts`].
+fn record_registration() {
+    let Ok(exe) = std::env::current_exe() else { return };
+    let registration = shortcut::Registration { exe, shortcut: SHORTCUT.get().and_then(|s| s.as_ref().ok()).cloned() };
+    if shortcut::load().as_ref() != Some(&registration) {
+        shortcut::save(&registration);
+    }
+}
+
+/// How toasts are shown and whether their AUMID is set up, as (backend, outcome); None when not on Windows.
+pub fn app_id_status() -> Option<(&'static str, Result<String, String>)> {
+    let name = match backend() {
+        Backend::Auto => "native, SnoreToast as fallback",
+        Backend::Native => "native",
+        Backend::SnoreToast => "SnoreToast",
+    };
+    if !cfg!(windows) {
+        return None;
+    }
+    #[cfg(all(windows, feature = "toast-winrt"))]
+    if backend() != Backend::SnoreToast {
+        let registered = winrt::ensure_registered(ensure_logo_file().as_deref()).clone();
+        return Some((name, registered.map(|()| format!("AUMID {APP_ID} registered"))));
+    }
+    let shortcut = SHORTCUT.get()?.clone();
+    Some((name, shortcut.map(|path| format!("shortcut {}", path.display()))))
+}
+
+/// Ensure a Start Menu shortcut exists with an AUMID so SnoreToast shows buttons. Tried once per run; a failure
+/// is logged with the reason for each location.
+fn ensure_snore_shortcut(app_id: &str) {
+    if !cfg!(windows) {
+        return;
+    }
+    SHORTCUT.get_or_init(|| {
+        let result = install_shortcut(app_id);
+        match &result {
+            Ok(path) => debug!("Toast shortcut: {}", path.display()),
+            Err(why) => warn!("No toast shortcut, so toast buttons may not render: {why}"),
+        }
+        result
+    });
+}
+
+fn install_shortcut(app_id: &str) -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("cannot locate the notifier exe: {e}"))?;
+    let snore = find_snoretoast().ok_or("SnoreToast support is not built in")?;
+    let mut failures = Vec::new();
+    for path in shortcut_candidates() {
+        // SnoreToast takes an absolute .lnk path as is (a relative one goes under Start Menu\Programs)
+        let status = Command::new(&snore).arg("-install").arg(&path).arg(&exe).arg(app_id).status();
+        match status {
+            Err(e) => return Err(format!("cannot run {snore}: {e}")),
+            Ok(_) if path.exists() => return Ok(path),
+            Ok(status) => {
+                failures.push(format!("{} not created ({status}; folder read-only or redirected?)", path.display()))
+            }
+        }
+    }
+    if failures.is_empty() {
+        return Err("no Start Menu folder found; set SHORTCUT_PATH".into());
+    }
+    Err(failures.join("; ") + "; set SHORTCUT_PATH to a writable folder under a Start Menu\\Programs tree")
+}
+
+/// Resolve a toast image to use:
+/// 1) GLPI_LOGO_PATH (.env) if valid PNG
+/// 2) assets/logo.png next to the exe
+/// 3) %LOCALAPPDATA%/GlpiNotifier/logo.png
+///
+/// In accessibility mode, the high-contrast variant next to each (`logo-contrast.png`) comes first.
+/// If none found, no image is attached.
+pub fn ensure_logo_file() -> Option<String> {
+    use std::path::Path;
+
+    let contrast = crate::accessibility::enabled();
+    let found = |p: PathBuf| {
+        let variant = contrast.then(|| contrast_variant(&p)).flatten().filter(|v| v.exists());
+        variant.or_else(|| p.exists().then_some(p)).map(|p| p.to_string_lossy().into_owned())
+    };
+
+    // 1) explicit path from .env
+    if let Ok(p) = env::var("GLPI_LOGO_PATH") {
+        let p = p.trim().to_string();
+        if !p.is_empty() && Path::new(&p).exists() {
+            return found(PathBuf::from(p));
+        }
+    }
+
+    // 2) assets/logo.png next to exe
+    if let Ok(exe) = std::env::current_exe() {
+        if let Some(dir) = exe.parent() {
+            if let Some(logo) = found(dir.join("assets").join("logo.png")).or_else(|| found(dir.join("logo.png"))) {
+                return Some(logo);
+            }
+        }
+    }
+
+    // 3) LOCALAPPDATA cache
+    dirs::data_dir().and_then(|ld| found(ld.join("GlpiNotifier").join("logo.png")))
+}
+
+/// High-contrast variant of a logo: `logo.png` -> `logo-contrast.png`, in the same folder.
+fn contrast_variant(logo: &std::path::Path) -> Option<PathBuf> {
+    let stem = logo.file_stem()?.to_string_lossy();
+    let name = match logo.extension() {
+        Some(ext) => format!("{stem}-contrast.{}", ext.to_string_lossy()),
+        None => format!("{stem}-contrast"),
+    };
+    Some(logo.with_file_name(name))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Representative tickets for the payload snapshots (no dates: their rendering depends on the clock).
+    pub(crate) fn sample_tickets() -> Vec<(&'static str, Ticket)> {
+        let normal = Ticket {
+            id: 4711,
+            name: "Printer on floor 2 jams".into(),
+            requester: Some("Ana Silva".into()),
+            category: Some("Hardware > Printers".into()),
+            priority: Some(3),
+            date: None,
+            documents: Some(2),
+            entity: Some("Root entity > Lisbon".into()),
+            time_to_own: None,
+            location: Some("Lisbon > Building B > Floor 2".into()),
+            major_incident: None,
+            instance: None,
+            language: None,
+        };
+        let critical = Ticket {
+            id: 4712,
+            name: "ERP <prod> down & \"urgent\" – João's team".into(),
+            requester: None,
+            category: None,
+            priority: Some(6),
+            documents: None,
+            entity: None,
+            location: None,
+            ..normal.clone()
+        };
+        let linked =
+            Ticket { id: 4713, name: "Cannot log in to the ERP".into(), major_incident: Some(980), ..normal.clone() };
+        vec![("normal", normal), ("critical", critical), ("linked", linked)]
+    }
+
+    #[test]
+    fn tab_joins_the_query_string() {
+        let tab = "Ticket$3";
+        assert_eq!(
+            with_tab("https://glpi/front/ticket.form.php?id=7", tab),
+            "https://glpi/front/ticket.form.php?id=7&forcetab=Ticket%243"
+        );
+        assert_eq!(with_tab("https://glpi/tickets/7", tab), "https://glpi/tickets/7?forcetab=Ticket%243");
+        assert_eq!(with_tab("https://glpi/#/ticket/7", tab), "https://glpi/?forcetab=Ticket%243#/ticket/7");
+    }
+
+    #[test]
+    fn ticket_payloads_match_snapshots() {
+        let logo = Some(r"C:\Users\tech\AppData\Local\GlpiNotifier\logo.png");
+        for (name, t) in sample_tickets() {
+            let c = ticket_toast(&t);
+            insta::assert_snapshot!(
+                format!("{name}_toast_xml"),
+                toast_xml(&c.title, &c.body, &c.actions, c.scenario, logo)
+            );
+            let args = snore_args(APP_ID, &c.title, &c.body, t.id, &c.actions, c.scenario, logo);
+            insta::assert_snapshot!(format!("{name}_snoretoast_args"), args.join("\n"));
+        }
+    }
+
+    #[test]
+    fn accessible_text_matches_snapshot() {
+        let texts: Vec<String> = sample_tickets()
+            .iter()
+            .map(|(_, t)| {
+                let c = ticket_toast_as(t, Some(Verbosity::Full));
+                format!("{}\n{}", c.title, c.body)
+            })
+            .collect();
+        insta::assert_snapshot!("accessible_text", texts.join("\n\n"));
+        let brief = ticket_toast_as(&sample_tickets()[0].1, Some(Verbosity::Brief));
+        assert_eq!(brief.body, "Title: Printer on floor 2 jams");
+        assert_eq!(
+            contrast_variant(std::path::Path::new(r"C:\assets\logo.png")),
+            Some(PathBuf::from(r"C:\assets\logo-contrast.png"))
+        );
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "toast-winrt",
+        feature = "toast-snoretoast",
+        feature = "notify-dbus",
+        feature = "notify-macos"
+    ))]
+    fn update_text_matches_snapshot() {
+        let changes = crate::diff::diff(
+            &crate::diff::Snapshot { status: Some(4), assignee: None, priority: Some(3) },
+            &crate::diff::Snapshot { status: Some(2), assignee: Some("J. Costa".into()), priority: Some(4) },
+        );
+        let (title, body) = update_text(&sample_tickets()[0].1, &changes);
+        insta::assert_snapshot!("update_text", format!("{title}\n{body}"));
+        let (_, body) = update_text_as(&sample_tickets()[0].1, &changes, true);
+        assert!(body.starts_with("Title: Printer on floor 2 jams\nStatus changed from Pending to Processing"));
+    }
+
+    #[test]
+    fn toast_xml_escapes_text_and_lists_buttons() {
+        let xml = toast_xml(
+            "CRITICAL #7",
+            "Printer <3F> & \"scanner\"",
+            &[ToastAction::Open, ToastAction::Snooze],
+            ToastScenario::Reminder,
+            Some(r"C:\assets\logo.png"),
+        );
+        assert!(xml.starts_with(r#"<toast duration="long" scenario="reminder">"#));
+        assert!(xml.contains("<text>Printer &lt;3F&gt; &amp; &quot;scanner&quot;</text>"));
+        assert!(xml.contains(r#"src="file:///C:/assets/logo.png""#));
+        let buttons =
+            format!(r#"arguments="{}"/><action content="{}""#, ToastAction::Open.label(), ToastAction::Snooze.label());
+        assert!(xml.contains(&buttons));
+        assert!(!toast_xml("t", "b", &[], ToastScenario::Default, None).contains("<actions>"));
+    }
+
+    #[test]
+    fn very_high_tickets_get_the_urgent_scenario() {
+        let normal = sample_tickets().remove(0).1;
+        let at = |priority| ticket_toast(&Ticket { priority: Some(priority), ..normal.clone() });
+        let (routine, urgent, major) = (at(3), at(5), at(6));
+        assert_eq!(routine.scenario, ToastScenario::Default);
+        assert_eq!(urgent.scenario, ToastScenario::Urgent);
+        assert_eq!(major.scenario, ToastScenario::Reminder);
+        assert_eq!(urgent.title, "GLPI: Urgent ticket #4711");
+        let xml = toast_xml(&urgent.title, &urgent.body, &urgent.actions, urgent.scenario, None);
+        assert!(xml.starts_with(r#"<toast duration="long" scenario="urgent">"#));
+        let args = snore_args(APP_ID, &urgent.title, &urgent.body, 4711, &urgent.actions, urgent.scenario, None);
+        assert!(args.windows(2).any(|w| w == ["-d", "long"]));
+    }
+
+    #[test]
+    fn start_line_quotes_the_url() {
+        assert_eq!(
+            start_line("https://glpi/front/ticket.form.php?id=7&forcetab=Ticket$1"),
+            r#"/C start "" "https://glpi/front/ticket.form.php?id=7&forcetab=Ticket$1""#
+        );
+        assert_eq!(start_line(r#"https://glpi/a"b"#), r#"/C start "" "https://glpi/a%22b""#);
+    }
+
+    #[test]
+    fn entity_template_prefers_the_closest_entity() {
+        let templates = parse_entity_templates("1=https://a/t?id={id}; 2=https://b/t?id={id}").unwrap();
+        let named: Vec<(String, String)> = templates
+            .into_iter()
+            .map(|(id, tpl)| (if id == 1 { "Root > Site A" } else { "Root > Site A > Lab" }.to_string(), tpl))
+            .collect();
+        assert_eq!(entity_template(&named, Some("Root > Site A > Lab > Bench")), Some("https://b/t?id={id}"));
+        assert_eq!(entity_template(&named, Some("Root > Site A")), Some("https://a/t?id={id}"));
+        assert_eq!(entity_template(&named, Some("Root > Site AB")), None);
+        assert!(parse_entity_templates("1=https://a/t").is_err());
+    }
+
+    #[test]
+    fn browser_profile_switch_depends_on_the_family() {
+        assert_eq!(profile_args("msedge", "Profile 1"), vec!["--profile-directory=Profile 1"]);
+        assert_eq!(profile_args(r"C:\Program Files\Mozilla Firefox\firefox.exe", "work"), vec!["-P", "work"]);
+        assert!(profile_args("chrome", "").is_empty());
+    }
+
+    #[test]
+    fn shortcut_path_must_be_under_the_start_menu() {
+        assert!(check_shortcut_path(r"C:\Users\you\AppData\Roaming\Microsoft\Windows\Start Menu\Programs\Tools\G.lnk")
+            .is_ok());
+        assert!(check_shortcut_path(r"\\srv\profiles\you\Start Menu\Programs\G").is_ok());
+        assert!(check_shortcut_path("C:/ProgramData/Microsoft/Windows/Start Menu/Programs/G.lnk").is_ok());
+        assert!(check_shortcut_path(r"Start Menu\Programs\G.lnk").is_err());
+        assert!(check_shortcut_path(r"C:\Users\you\Desktop\G.lnk").is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Toast registrations left behind by earlier installs. The AUMID shortcut points at the exe that created it:
+//! after an upgrade into a new folder, or a move, it still launches the old binary when a toast is clicked.
+//! At startup the notifier reads the target of every shortcut it may have created (the current locations, plus
+//! the one recorded by the last run in `app-id.json`), and
+//!
+//! - keeps the ones that point at the running exe;
+//! - re-creates the stale ones (another exe, present or not) at the same place, pointing at the running exe;
+//! - removes the orphaned ones: left where the last run put them but no longer a candidate (SHORTCUT_PATH changed).
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What the last run registered, in `app-id.json` in the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Registration {
+    pub exe: PathBuf,
+    /// AUMID shortcut in use, when toasts go through SnoreToast.
+    #[serde(default)]
+    pub shortcut: Option<PathBuf>,
+}
+
+/// Fate of a shortcut found at startup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fate {
+    Keep,
+    /// Points at another exe (its target): re-create it for the running one.
+    Stale(PathBuf),
+    /// Not a candidate location any more: remove it.
+    Orphaned,
+}
+
+fn registration_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("GlpiNotifier");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("app-id.json"))
+}
+
+pub fn load() -> Option<Registration> {
+    serde_json::from_slice(&std::fs::read(registration_path()?).ok()?).ok()
+}
+
+pub fn save(registration: &Registration) {
+    let Some(path) = registration_path() else { return };
+    if let Err(e) = serde_json::to_vec_pretty(registration).map_err(anyhow::Error::from).and_then(|data| {
+        std::fs::write(&path, data)?;
+        Ok(())
+    }) {
+        warn!("Could not save {}: {e:#}", path.display());
+    }
+}
+
+/// Windows paths compare without regard to case or separator.
+pub fn same_path(a: &Path, b: &Path) -> bool {
+    let norm = |p: &Path| p.to_string_lossy().replace('/', "\\").to_lowercase();
+    norm(a) == norm(b)
+}
+
+/// What to do with the shortcut at `lnk`, whose target is `target` (None: unreadable, left alone).
+pub fn fate(lnk: &Path, target: Option<&Path>, exe: &Path, candidates: &[PathBuf]) -> Fate {
+    if !candidates.iter().any(|c| same_path(c, lnk)) {
+        return Fate::Orphaned;
+    }
+    match target {
+        Some(target) if !same_path(target, exe) => Fate::Stale(target.to_path_buf()),
+        _ => Fate::Keep,
+    }
+}
+
+/// Check the shortcuts of the candidate locations and of the last run against the running `exe`. `reinstall`
+/// re-creates a stale shortcut at its path (false when it cannot, e.g. without SnoreToast: it is removed then,
+/// as it would keep sending clicks to the other exe).
+pub fn repair(exe: &Path, candidates: &[PathBuf], reinstall: impl Fn(&Path) -> bool) {
+    let last = load();
+    if let Some(last) = last.as_ref().filter(|last| !same_path(&last.exe, exe)) {
+        info!("Notifier moved from {} to {}: updating its toast registration", last.exe.display(), exe.display());
+    }
+    let mut shortcuts: Vec<PathBuf> = candidates.to_vec();
+    if let Some(previous) = last.and_then(|last| last.shortcut) {
+        if !shortcuts.iter().any(|s| same_path(s, &previous)) {
+            shortcuts.push(previous);
+        }
+    }
+    for lnk in shortcuts.iter().filter(|lnk| lnk.exists()) {
+        let target = std::fs::read(lnk).ok().and_then(|data| lnk_target(&data));
+        match fate(lnk, target.as_deref(), exe, candidates) {
+            Fate::Keep => {}
+            Fate::Stale(old) => {
+                let _ = std::fs::remove_file(lnk);
+                if reinstall(lnk) {
+                    info!("Toast shortcut {} now points at this exe (was {})", lnk.display(), old.display());
+                } else {
+                    info!("Removed toast shortcut {} pointing at {}", lnk.display(), old.display());
+                }
+            }
+            Fate::Orphaned => match std::fs::remove_file(lnk) {
+                Ok(()) => info!("Removed toast shortcut {} left by an earlier install", lnk.display()),
+                Err(e) => warn!("Could not remove the old toast shortcut {}: {e}", lnk.display()),
+            },
+        }
+    }
+}
+
+/// Target path of a Shell Link (.lnk) file, from its LinkInfo structure (MS-SHLLINK 2.3): the local base path,
+/// in Unicode when present, followed by the common path suffix. None for links without one (e.g. to a network
+/// share or a shell item only).
+pub fn lnk_target(data: &[u8]) -> Option<PathBuf> {
+    const HEADER_SIZE: usize = 0x4c;
+    const HAS_ID_LIST: u32 = 0x1;
+    const HAS_LINK_INFO: u32 = 0x2;
+    const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+
+    let u16_at = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?) as usize);
+    if u32_at(0)? != HEADER_SIZE {
+        return None;
+    }
+    let flags = u32_at(0x14)? as u32;
+    let mut at = HEADER_SIZE;
+    if flags & HAS_ID_LIST != 0 {
+        at += 2 + usize::from(u16_at(at)?);
+    }
+    if flags & HAS_LINK_INFO == 0 {
+        return None;
+    }
+    let info = data.get(at..at + u32_at(at)?)?;
+    let field = |offset: usize| Some(u32::from_le_bytes(info.get(offset..offset + 4)?.try_into().ok()?) as usize);
+    if field(8)? as u32 & VOLUME_ID_AND_LOCAL_BASE_PATH == 0 {
+        return None;
+    }
+    let ansi = |offset: usize| {
+        let bytes = info.get(offset..)?;
+        let end = bytes.iter().position(|b| *b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+    };
+    let unicode = |offset: usize| {
+        let units: Vec<u16> = info
+            .get(offset..)?
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|u| *u != 0)
+            .collect();
+        Some(String::from_utf16_lossy(&units))
+    };
+    // Header of 0x24 bytes or more: with the Unicode offsets
+    let (base, suffix) = match (field(4)? >= 0x24).then(|| (field(0x1c), field(0x20))) {
+        Some((Some(base), Some(suffix))) if base != 0 => (unicode(base)?, unicode(suffix).unwrap_or_default()),
+        _ => (ansi(field(0x10)?)?, ansi(field(0x18)?).unwrap_or_default()),
+    };
+    (!base.is_empty()).then(|| PathBuf::from(base + &suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A minimal .lnk: header, an ID list to skip, and a LinkInfo with an ANSI local base path.
+    fn lnk(target: &str) -> Vec<u8> {
+        let mut data = vec![0u8; 0x4c];
+        data[0] = 0x4c;
+        data[0x14] = 0x1 | 0x2; // HasLinkTargetIDList | HasLinkInfo
+        data.extend([4, 0, 0xaa, 0xbb, 0xcc, 0xdd]);
+        let header = 0x1c;
+        let base = header + 4;
+        let suffix = base + target.len() + 1;
+        let size = suffix + 1;
+        for value in [size, header, 1, header, base, 0, suffix] {
+            data.extend((value as u32).to_le_bytes());
+        }
+        data.extend([0, 0, 0, 0]); // empty VolumeID
+        data.extend(target.as_bytes());
+        data.extend([0, 0]);
+        data
+    }
+
+    #[test]
+    fn shortcut_targets_are_read_from_link_info() {
+        let exe = r"C:\Program Files\GlpiNotifier\glpi-notifier-rs.exe";
+        assert_eq!(lnk_target(&lnk(exe)), Some(PathBuf::from(exe)));
+        assert_eq!(lnk_target(b"not a shortcut"), None);
+        assert_eq!(lnk_target(&lnk(exe)[..0x60]), None);
+    }
+
+    #[test]
+    fn stale_and_orphaned_shortcuts_are_told_apart() {
+        let exe = Path::new(r"C:\Users\tech\AppData\Local\Programs\GlpiNotifier\glpi-notifier-rs.exe");
+        let start_menu =
+            PathBuf::from(r"C:\Users\tech\AppData\Roaming\Microsoft\Windows\Start Menu\Programs\GlpiNotifier.lnk");
+        let candidates = [start_menu.clone()];
+        let same = Path::new(r"c:/users/TECH/AppData/Local/Programs/GlpiNotifier/glpi-notifier-rs.exe");
+        assert_eq!(fate(&start_menu, Some(same), exe, &candidates), Fate::Keep);
+        assert_eq!(fate(&start_menu, None, exe, &candidates), Fate::Keep);
+        let old = Path::new(r"C:\Tools\glpi-notifier-rs.exe");
+        assert_eq!(fate(&start_menu, Some(old), exe, &candidates), Fate::Stale(old.to_path_buf()));
+        assert_eq!(fate(Path::new(r"D:\Menu\GlpiNotifier.lnk"), Some(exe), exe, &candidates), Fate::Orphaned);
+    }
+}
@@ -0,0 +1,156 @@
+//! Native Windows toasts through the WinRT API (`Windows.UI.Notifications`), without snoretoast.exe: the
+//! pressed button comes back through the toast's Activated event, and the AUMID is registered in the user's
+//! registry instead of through a Start Menu shortcut.
+
+use super::{ToastAction, APP_ID};
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use std::sync::mpsc;
+use std::time::Duration;
+use windows::core::{IInspectable, Interface, HSTRING};
+use windows::Data::Xml::Dom::XmlDocument;
+use windows::Foundation::{Collections::StringMap, TypedEventHandler};
+use windows::UI::Notifications::{
+    NotificationData, NotificationUpdateResult, ToastActivatedEventArgs, ToastDismissedEventArgs, ToastFailedEventArgs,
+    ToastNotification, ToastNotificationManager, ToastNotifier,
+};
+
+/// Group of every toast the notifier shows; the tag tells them apart (ticket id, `progress`).
+const GROUP: &str = "glpi";
+
+/// Longest wait for the user's answer (a reminder toast stays until dismissed).
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// How a shown toast ended.
+enum Outcome {
+    /// Clicked: the button's arguments (empty for the toast body).
+    Activated(String),
+    Dismissed,
+    Failed(String),
+}
+
+static REGISTERED: OnceCell<Result<(), String>> = OnceCell::new();
+
+/// Register the AUMID for the current user, once per run (HKCU\Software\Classes\AppUserModelId): Windows only
+/// shows toasts of an unpackaged app under a known AUMID, with this display name and icon.
+pub fn ensure_registered(logo: Option<&str>) -> &'static Result<(), String> {
+    REGISTERED.get_or_init(|| {
+        let register = || -> windows_registry::Result<()> {
+            let key = windows_registry::CURRENT_USER.create(format!(r"Software\Classes\AppUserModelId\{APP_ID}"))?;
+            key.set_string("DisplayName", "GLPI Notifier")?;
+            match logo {
+                Some(logo) => key.set_string("IconUri", logo)?,
+                // An icon registered by an earlier install, maybe in a folder since removed
+                None => {
+                    let _ = key.remove_value("IconUri");
+                }
+            }
+            Ok(())
+        };
+        let result = register().map_err(|e| format!("cannot register the AUMID {APP_ID}: {e}"));
+        if let Err(why) = &result {
+            log::warn!("Native toasts unavailable: {why}");
+        }
+        result
+    })
+}
+
+fn notifier() -> Result<ToastNotifier> {
+    Ok(ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?)
+}
+
+/// Show a toast built by [`super::toast_xml`] and return once it is on screen; the button pressed among
+/// `actions` goes to `answered`, from a thread that waits for it (nothing when the toast is dismissed or expires).
+pub fn show(
+    xml: &str,
+    tag: &str,
+    actions: &[ToastAction],
+    answered: impl FnOnce(ToastAction) + Send + 'static,
+) -> Result<()> {
+    if let Err(why) = REGISTERED.get().unwrap_or(&Ok(())) {
+        return Err(anyhow!("{why}"));
+    }
+    let doc = XmlDocument::new()?;
+    doc.LoadXml(&HSTRING::from(xml))?;
+    let toast = ToastNotification::CreateToastNotification(&doc)?;
+    toast.SetTag(&HSTRING::from(tag))?;
+    let tag = tag.to_string();
+    toast.SetGroup(&HSTRING::from(GROUP))?;
+
+    let (tx, rx) = mpsc::channel();
+    let sender = tx.clone();
+    toast.Activated(&TypedEventHandler::<ToastNotification, IInspectable>::new(move |_, args| {
+        let arguments = args
+            .as_ref()
+            .and_then(|a| a.cast::<ToastActivatedEventArgs>().ok())
+            .and_then(|a| a.Arguments().ok())
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        let _ = sender.send(Outcome::Activated(arguments));
+        Ok(())
+    }))?;
+    let sender = tx.clone();
+    toast.Dismissed(&TypedEventHandler::<ToastNotification, ToastDismissedEventArgs>::new(move |_, _| {
+        let _ = sender.send(Outcome::Dismissed);
+        Ok(())
+    }))?;
+    toast.Failed(&TypedEventHandler::<ToastNotification, ToastFailedEventArgs>::new(move |_, args| {
+        let why = args.as_ref().and_then(|a| a.ErrorCode().ok()).map(|code| code.message()).unwrap_or_default();
+        let _ = tx.send(Outcome::Failed(why));
+        Ok(())
+    }))?;
+    notifier()?.Show(&toast)?;
+
+    // Nothing to answer: the toast lives on in Action Center without us
+    if actions.is_empty() {
+        return Ok(());
+    }
+    let actions = actions.to_vec();
+    std::thread::spawn(move || {
+        // The handlers above live as long as the toast: keep it until the answer comes
+        let _toast = toast;
+        match rx.recv_timeout(ANSWER_TIMEOUT) {
+            Ok(Outcome::Activated(arguments)) => {
+                if let Some(action) = actions.iter().copied().find(|a| a.label() == arguments) {
+                    answered(action);
+                }
+            }
+            Ok(Outcome::Dismissed) | Err(_) => {}
+            Ok(Outcome::Failed(why)) => log::warn!("Toast {tag} not shown: {why}"),
+        }
+    });
+    Ok(())
+}
+
+/// Remove the toast tagged `tag` (on screen or in Action Center).
+pub fn close(tag: &str) -> Result<()> {
+    let history = ToastNotificationManager::History()?;
+    history.RemoveGroupedTagWithId(&HSTRING::from(tag), &HSTRING::from(GROUP), &HSTRING::from(APP_ID))?;
+    Ok(())
+}
+
+/// Update the progress toast in place through its bound values, or show it (again) from `xml` when it is not
+/// on screen.
+pub fn progress(xml: &str, values: &[(&str, String)], sequence: u32) -> Result<()> {
+    let data = || -> Result<NotificationData> {
+        let map = StringMap::new()?;
+        for (key, value) in values {
+            map.Insert(&HSTRING::from(*key), &HSTRING::from(value.as_str()))?;
+        }
+        Ok(NotificationData::CreateNotificationDataWithValuesAndSequenceNumber(&map, sequence)?)
+    };
+    let notifier = notifier()?;
+    let (tag, group) = (HSTRING::from("progress"), HSTRING::from(GROUP));
+    if notifier.UpdateWithTagAndGroup(&data()?, &tag, &group)? == NotificationUpdateResult::Succeeded {
+        return Ok(());
+    }
+    let doc = XmlDocument::new()?;
+    doc.LoadXml(&HSTRING::from(xml))?;
+    let toast = ToastNotification::CreateToastNotification(&doc)?;
+    toast.SetTag(&tag)?;
+    toast.SetGroup(&group)?;
+    toast.SetData(&data()?)?;
+    notifier.Show(&toast)?;
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use crate::state::now_secs;
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Bodies longer than this are cut in the trace file.
+const MAX_BODY_CHARS: usize = 2000;
+
+/// Correlation id of the current poll iteration (0 = startup, before the first tick).
+static TICK: AtomicU64 = AtomicU64::new(0);
+
+/// Sequence of outgoing requests, for X-Correlation-Id.
+static REQUEST_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// X-Correlation-Id of the last request sent.
+static LAST_CORRELATION_ID: Mutex<Option<String>> = Mutex::new(None);
+
+/// Headers whose values must never reach the trace file.
+const SECRET_HEADERS: [&str; 3] = ["authorization", "session-token", "app-token"];
+
+pub fn enabled() -> bool {
+    crate::config::get().trace_http
+}
+
+/// Start a new poll iteration; subsequent exchanges are tagged with its id.
+pub fn next_tick() -> u64 {
+    TICK.fetch_add(1, Ordering::Relaxed) + 1
+}
+
+/// New X-Correlation-Id value, unique per process and request (`gn-<pid>-<tick>-<seq>`), remembered as the last one.
+pub fn next_correlation_id() -> String {
+    let seq = REQUEST_SEQ.fetch_add(1, Ordering::Relaxed) + 1;
+    let id = format!("gn-{:x}-{}-{seq}", std::process::id(), TICK.load(Ordering::Relaxed));
+    *LAST_CORRELATION_ID.lock().unwrap() = Some(id.clone());
+    id
+}
+
+/// X-Correlation-Id of the most recent GLPI request, to quote in error messages.
+pub fn last_correlation_id() -> Option<String> {
+    LAST_CORRELATION_ID.lock().unwrap().clone()
+}
+
+fn trace_path() -> Option<std::path::PathBuf> {
+    let dir = dirs::data_dir()?;
+    let p = dir.join("GlpiNotifier").join("http-trace.log");
+    let _ = std::fs::create_dir_all(p.parent().unwrap());
+    Some(p)
+}
+
+fn append(text: &str) {
+    if let Some(p) = trace_path() {
+        if let Ok(mut f) = std::fs::OpenOptions::new().create(true).append(true).open(p) {
+            let _ = f.write_all(crate::scrub::scrub(text).as_bytes());
+        }
+    }
+}
+
+/// Method, URL and (redacted) headers of an outgoing request, with the tick correlation id.
+pub fn describe_request(req: &reqwest::Request) -> String {
+    format!(
+        "[{} tick-{}] {} {}\n{}",
+        now_secs(),
+        TICK.load(Ordering::Relaxed),
+        req.method(),
+        req.url(),
+        describe_headers(req.headers(), "  > ")
+    )
+}
+
+fn describe_headers(headers: &HeaderMap, prefix: &str) -> String {
+    let mut out = String::new();
+    for (name, value) in headers {
+        let value = if SECRET_HEADERS.contains(&name.as_str()) {
+            "<redacted>".to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        out.push_str(&format!("{prefix}{name}: {value}\n"));
+    }
+    out
+}
+
+/// Mask the value of `"session_token": "..."` so initSession replies can be traced safely.
+fn redact_body(body: &str) -> String {
+    let key = "\"session_token\"";
+    let Some(k) = body.find(key) else { return body.to_string() };
+    let rest = &body[k + key.len()..];
+    let Some(open) = rest.find('"') else { return body.to_string() };
+    let Some(close) = rest[open + 1..].find('"') else { return body.to_string() };
+    let start = k + key.len() + open + 1;
+    format!("{}<redacted>{}", &body[..start], &body[start + close..])
+}
+
+pub fn record_reply(request: &str, status: StatusCode, headers: &HeaderMap, body: &[u8], took: Duration) {
+    let body = redact_body(&String::from_utf8_lossy(body));
+    let shown: String = body.chars().take(MAX_BODY_CHARS).collect();
+    let cut = if shown.len() < body.len() { format!(" ... ({} bytes total)", body.len()) } else { String::new() };
+    append(&format!(
+        "{request}  <- {status} in {} ms\n{}  body: {shown}{cut}\n\n",
+        took.as_millis(),
+        describe_headers(headers, "  < ")
+    ));
+}
+
+pub fn record_error(request: &str, err: &dyn std::fmt::Display, took: Duration) {
+    append(&format!("{request}  <- error after {} ms: {err}\n\n", took.as_millis()));
+}
@@ -0,0 +1,73 @@
+//! Status transitions worth a toast (STATUS_TRANSITIONS), e.g. `new>assigned; pending>solved; *>closed`.
+//!
+//! The watched tickets are the New tickets this notifier saw arrive and my open tickets (assigned to me): their
+//! last known status is kept in the state, and each poll compares it with GLPI's. A ticket stays watched until
+//! it is closed, deleted or no longer visible; besides my open tickets, only the newest [`WATCHED`] are, which
+//! keeps each poll to a few searches.
+
+use crate::exit::ConfigError;
+
+use anyhow::Result;
+
+/// Status names usable in STATUS_TRANSITIONS, with their GLPI values.
+const NAMES: &[(&str, i64)] =
+    &[("new", 1), ("assigned", 2), ("planned", 3), ("pending", 4), ("solved", 5), ("closed", 6)];
+
+/// Closed tickets are not watched any more.
+pub const CLOSED: i64 = 6;
+
+/// How many tickets are watched besides my open tickets, the newest first.
+pub const WATCHED: usize = 200;
+
+/// One `from>to` rule; None matches any status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+fn status(raw: &str) -> Option<Option<i64>> {
+    let raw = raw.trim().to_lowercase();
+    if raw == "*" {
+        return Some(None);
+    }
+    NAMES.iter().find(|(name, _)| *name == raw).map(|(_, v)| Some(*v)).or_else(|| raw.parse().ok().map(Some))
+}
+
+/// Parse `from>to` rules separated by `;` or `,`; statuses are names (new, assigned, planned, pending, solved,
+/// closed), GLPI values or `*`.
+pub fn parse(raw: &str) -> Result<Vec<Transition>> {
+    raw.split([';', ','])
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(|rule| {
+            let bad = || ConfigError(format!("'{rule}': expected from>to, e.g. pending>solved"));
+            let (from, to) = rule.split_once('>').ok_or_else(bad)?;
+            Ok(Transition { from: status(from).ok_or_else(bad)?, to: status(to).ok_or_else(bad)? })
+        })
+        .collect()
+}
+
+/// True when going from `from` to `to` is one of `rules` (a ticket that did not change never is).
+pub fn wanted(rules: &[Transition], from: i64, to: i64) -> bool {
+    from != to && rules.iter().any(|r| r.from.is_none_or(|f| f == from) && r.to.is_none_or(|t| t == to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_match_their_transitions_only() {
+        let rules = parse("new>assigned; Pending>SOLVED, *>closed").unwrap();
+        assert_eq!(rules[0], Transition { from: Some(1), to: Some(2) });
+        assert!(wanted(&rules, 1, 2));
+        assert!(!wanted(&rules, 1, 4));
+        assert!(wanted(&rules, 4, 5));
+        assert!(wanted(&rules, 2, 6));
+        assert!(!wanted(&parse("*>*").unwrap(), 3, 3));
+        assert_eq!(parse("4>5").unwrap(), parse("pending>solved").unwrap());
+        assert!(parse("pending").is_err());
+        assert!(parse("waiting>solved").is_err());
+    }
+}
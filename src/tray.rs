@@ -0,0 +1,357 @@
+//! Tray icon of the resident poller (Windows, TRAY=true by default): the icon's colour and tooltip show the
+//! last poll (green: no New ticket, amber: New tickets waiting, red: GLPI unreachable, grey: paused or not
+//! polled yet), and its menu has New tickets, Pause/Resume, Poll now, Open GLPI, Open logs and Exit. A left click,
+//! or New tickets (the menu is keyboard-reachable), opens the [flyout](crate::flyout) of the New tickets.
+//!
+//! The icon lives on its own thread (a Win32 message loop). The poll loop sends it [`Update`]s and takes its
+//! [`Command`]s over channels; Pause/Resume goes through control.json like `ctl pause`, so `ctl status` agrees.
+#![cfg_attr(not(all(windows, feature = "tray")), allow(dead_code))]
+
+use crate::control;
+use crate::toast::ToastAction;
+
+use log::info;
+use once_cell::sync::OnceCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Mutex;
+
+/// Poll loop → tray.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Update {
+    /// A poll ended: `queue` New tickets, or None when it failed.
+    Polled(Option<usize>),
+    Paused(bool),
+}
+
+/// Tray menu → poll loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    TogglePause,
+    PollNow,
+    Exit,
+    /// A button of the flyout on a ticket of an instance (Open, Assign to me).
+    Ticket(Option<String>, i64, ToastAction),
+}
+
+/// What the icon shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrayState {
+    /// A poll has ended since the start.
+    pub polled: bool,
+    /// The last poll succeeded.
+    pub ok: bool,
+    /// New tickets at the last successful poll.
+    pub queue: usize,
+    pub paused: bool,
+}
+
+struct Channels {
+    updates: Sender<Update>,
+    commands: Mutex<Receiver<Command>>,
+}
+
+static TRAY: OnceCell<Channels> = OnceCell::new();
+/// Exit chosen in the menu; part of the poller's stop flag.
+static EXIT: AtomicBool = AtomicBool::new(false);
+/// Menu commands served so far, compared by each poll loop with the count it last saw.
+static SERVED: AtomicU64 = AtomicU64::new(0);
+
+/// Show the tray icon (resident poller only). No-op off Windows or with TRAY=false.
+pub fn start() {
+    if !crate::config::get().tray || !cfg!(all(windows, feature = "tray")) {
+        return;
+    }
+    let (updates, from_loop) = std::sync::mpsc::channel();
+    let (to_loop, commands) = std::sync::mpsc::channel();
+    if TRAY.set(Channels { updates, commands: Mutex::new(commands) }).is_ok() {
+        #[cfg(all(windows, feature = "tray"))]
+        std::thread::spawn(move || {
+            if let Err(e) = icon::run(from_loop, to_loop) {
+                log::warn!("Tray icon unavailable: {e:#}");
+            }
+        });
+        #[cfg(not(all(windows, feature = "tray")))]
+        drop((from_loop, to_loop));
+    }
+}
+
+/// Tell the tray about the poller (no-op without a tray).
+pub fn update(update: Update) {
+    if let Some(tray) = TRAY.get() {
+        let _ = tray.updates.send(update);
+    }
+}
+
+/// Poller side: serve the menu commands sent since the last call. Returns true when the calling poll loop should
+/// stop waiting for the next poll (Poll now, Pause/Resume, Exit): a command served by any instance's loop wakes
+/// them all, each keeping the count of commands it `seen`. Flyout buttons go to the dispatcher's deliveries,
+/// which the loop applies like toast buttons.
+pub fn take_commands(seen: &mut u64) -> bool {
+    let Some(tray) = TRAY.get() else { return false };
+    for command in tray.commands.lock().unwrap().try_iter() {
+        SERVED.fetch_add(1, Ordering::Relaxed);
+        match command {
+            Command::PollNow => info!("Poll requested (tray)"),
+            Command::TogglePause => {
+                let mut c = control::load();
+                c.paused_until = if c.paused() { None } else { Some(u64::MAX) };
+                if let Err(e) = control::save(&c) {
+                    log::warn!("Could not pause from the tray: {e:#}");
+                }
+            }
+            Command::Exit => {
+                info!("Exit requested (tray)");
+                EXIT.store(true, Ordering::Relaxed);
+            }
+            Command::Ticket(instance, id, action) => match crate::tickets::get_in(instance, id) {
+                Some(known) => crate::pipeline::dispatcher().push_action(known.ticket, action),
+                None => log::warn!("Tray: #{id} is no longer in the ticket cache"),
+            },
+        }
+    }
+    let served = SERVED.load(Ordering::Relaxed);
+    std::mem::replace(seen, served) != served
+}
+
+pub fn exit_requested() -> bool {
+    EXIT.load(Ordering::Relaxed)
+}
+
+impl TrayState {
+    pub fn apply(&mut self, update: Update) {
+        match update {
+            Update::Polled(queue) => {
+                self.polled = true;
+                self.ok = queue.is_some();
+                self.queue = queue.unwrap_or(self.queue);
+            }
+            Update::Paused(paused) => self.paused = paused,
+        }
+    }
+
+    pub fn tooltip(&self) -> String {
+        let status = match (self.polled, self.ok, self.queue) {
+            _ if self.paused => "paused".to_string(),
+            (false, _, _) => "starting".to_string(),
+            (true, false, _) => "GLPI unreachable".to_string(),
+            (true, true, 0) => "no new ticket".to_string(),
+            (true, true, 1) => "1 new ticket".to_string(),
+            (true, true, n) => format!("{n} new tickets"),
+        };
+        format!("GLPI Notifier: {status}")
+    }
+
+    /// Icon colour (RGB).
+    pub fn color(&self) -> [u8; 3] {
+        match (self.polled, self.ok, self.queue) {
+            _ if self.paused => GREY,
+            (false, _, _) => GREY,
+            (true, false, _) => [0xd1, 0x34, 0x38],
+            (true, true, 0) => [0x10, 0x7c, 0x10],
+            (true, true, _) => [0xf7, 0xa8, 0x00],
+        }
+    }
+}
+
+const GREY: [u8; 3] = [0x8a, 0x88, 0x86];
+pub const ICON_SIZE: u32 = 32;
+
+/// A filled disc of `color` on a transparent square, as RGBA.
+pub fn icon_rgba(color: [u8; 3]) -> Vec<u8> {
+    let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+    let radius = ICON_SIZE as f32 / 2.0 - 1.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let d = ((x as f32 - center).powi(2) + (y as f32 - center).powi(2)).sqrt();
+            // One pixel of anti-aliasing at the edge
+            let alpha = (radius + 0.5 - d).clamp(0.0, 1.0);
+            rgba.extend(color);
+            rgba.push((alpha * 255.0) as u8);
+        }
+    }
+    rgba
+}
+
+/// Page opened by Open GLPI: the ticket list, on the host of GLPI_TICKET_URL_TEMPLATE when set (the web UI may
+/// not be served where the API is), else next to the API endpoint of GLPI_BASE_URL.
+pub fn glpi_home(url_template: Option<&str>, base_url: &str) -> Option<String> {
+    let root = match url_template.and_then(|tpl| tpl.split_once("/front/")) {
+        Some((root, _)) => root.to_string(),
+        None => {
+            let base = base_url.trim().trim_end_matches('/');
+            let root = ["/apirest.php", "/api.php/v2", "/api.php"].iter().find_map(|api| base.strip_suffix(api));
+            root.unwrap_or(base).to_string()
+        }
+    };
+    (!root.is_empty()).then(|| format!("{root}/front/ticket.php"))
+}
+
+/// Target of Open logs: LOG_FILE (the file the launcher redirects the log to) when set, else the data
+/// directory (crash reports, service.log, status.json).
+pub fn logs_target() -> Option<std::path::PathBuf> {
+    match std::env::var("LOG_FILE").ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()) {
+        Some(file) => Some(file.into()),
+        None => dirs::data_dir().map(|d| d.join("GlpiNotifier")),
+    }
+}
+
+#[cfg(all(windows, feature = "tray"))]
+mod icon {
+    use super::{glpi_home, icon_rgba, logs_target, Command, TrayState, Update, ICON_SIZE};
+
+    use anyhow::Result;
+    use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+    use std::time::Duration;
+    use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+    use tray_icon::{Icon, MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+    use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+
+    fn icon(state: &TrayState) -> Result<Icon> {
+        Ok(Icon::from_rgba(icon_rgba(state.color()), ICON_SIZE, ICON_SIZE)?)
+    }
+
+    /// The tray thread: create the icon, then pump its window messages and serve the menu until the poller
+    /// exits (the update channel closes with the process).
+    pub fn run(updates: Receiver<Update>, commands: Sender<Command>) -> Result<()> {
+        // With [instances], Open GLPI opens the first one
+        let home = match crate::instance::all().first() {
+            Some(first) => glpi_home(
+                first.value("GLPI_TICKET_URL_TEMPLATE").as_deref(),
+                &first.value("GLPI_BASE_URL").unwrap_or_default(),
+            ),
+            None => glpi_home(
+                crate::toast::URL_TEMPLATE.get().and_then(|t| t.as_deref()),
+                &std::env::var("GLPI_BASE_URL").unwrap_or_default(),
+            ),
+        };
+        let tickets = MenuItem::new("New tickets", true, None);
+        let pause = MenuItem::new("Pause", true, None);
+        let poll_now = MenuItem::new("Poll now", true, None);
+        let open = MenuItem::new("Open GLPI", home.is_some(), None);
+        let logs = MenuItem::new("Open logs", true, None);
+        let exit = MenuItem::new("Exit", true, None);
+        let menu = Menu::with_items(&[
+            &tickets,
+            &PredefinedMenuItem::separator(),
+            &pause,
+            &poll_now,
+            &PredefinedMenuItem::separator(),
+            &open,
+            &logs,
+            &PredefinedMenuItem::separator(),
+            &exit,
+        ])?;
+        let mut state = TrayState { paused: crate::control::load().paused(), ..TrayState::default() };
+        let flyout = crate::flyout::Flyout::new(commands.clone())?;
+        let tray = TrayIconBuilder::new()
+            .with_menu(Box::new(menu))
+            .with_menu_on_left_click(false)
+            .with_tooltip(state.tooltip())
+            .with_icon(icon(&state)?)
+            .build()?;
+        pause.set_text(if state.paused { "Resume" } else { "Pause" });
+
+        loop {
+            let mut msg = MSG::default();
+            // SAFETY: plain Win32 message pump of this thread's windows (the tray icon's hidden window)
+            while unsafe { PeekMessageW(&mut msg, None, 0, 0, PM_REMOVE) }.as_bool() {
+                if flyout.dialog_message(&msg) {
+                    continue;
+                }
+                unsafe {
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+            }
+            while let Ok(event) = TrayIconEvent::receiver().try_recv() {
+                if let TrayIconEvent::Click {
+                    button: MouseButton::Left,
+                    button_state: MouseButtonState::Up,
+                    position,
+                    ..
+                } = event
+                {
+                    flyout.toggle(Some((position.x, position.y)));
+                }
+            }
+            while let Ok(event) = MenuEvent::receiver().try_recv() {
+                let id = event.id();
+                let command = if id == pause.id() {
+                    Some(Command::TogglePause)
+                } else if id == poll_now.id() {
+                    Some(Command::PollNow)
+                } else if id == exit.id() {
+                    Some(Command::Exit)
+                } else {
+                    if id == tickets.id() {
+                        flyout.toggle(None);
+                    } else if id == open.id() {
+                        if let Some(url) = &home {
+                            crate::toast::open_in_browser(url).unwrap_or_else(|e| log::warn!("Open GLPI: {e:#}"));
+                        }
+                    } else if id == logs.id() {
+                        if let Some(target) = logs_target() {
+                            let _ = std::process::Command::new("explorer").arg(target).spawn();
+                        }
+                    }
+                    None
+                };
+                if let Some(command) = command {
+                    let _ = commands.send(command);
+                }
+            }
+            match updates.recv_timeout(Duration::from_millis(50)) {
+                Ok(update) => {
+                    state.apply(update);
+                    for more in updates.try_iter() {
+                        state.apply(more);
+                    }
+                    tray.set_icon(Some(icon(&state)?))?;
+                    tray.set_tooltip(Some(state.tooltip()))?;
+                    pause.set_text(if state.paused { "Resume" } else { "Pause" });
+                    flyout.refresh();
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tray_follows_the_polls() {
+        let mut state = TrayState::default();
+        assert_eq!((state.tooltip().as_str(), state.color()), ("GLPI Notifier: starting", GREY));
+        state.apply(Update::Polled(Some(3)));
+        assert_eq!(state.tooltip(), "GLPI Notifier: 3 new tickets");
+        state.apply(Update::Polled(None));
+        assert_eq!(state.tooltip(), "GLPI Notifier: GLPI unreachable");
+        assert_eq!(state.queue, 3);
+        state.apply(Update::Paused(true));
+        assert_eq!((state.tooltip().as_str(), state.color()), ("GLPI Notifier: paused", GREY));
+
+        let rgba = icon_rgba([1, 2, 3]);
+        assert_eq!(rgba.len(), (ICON_SIZE * ICON_SIZE * 4) as usize);
+        assert_eq!(rgba[3], 0); // transparent corner
+        let middle = ((ICON_SIZE / 2 * ICON_SIZE + ICON_SIZE / 2) * 4) as usize;
+        assert_eq!(&rgba[middle..middle + 4], &[1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn glpi_home_is_the_ticket_list() {
+        let home = |tpl, base| glpi_home(tpl, base).unwrap();
+        assert_eq!(
+            home(Some("https://helpdesk.example/front/ticket.form.php?id={id}"), "https://api.example/apirest.php"),
+            "https://helpdesk.example/front/ticket.php"
+        );
+        assert_eq!(home(None, "https://glpi.example/glpi/apirest.php/"), "https://glpi.example/glpi/front/ticket.php");
+        assert_eq!(home(None, "https://glpi.example/api.php/v2"), "https://glpi.example/front/ticket.php");
+        assert!(glpi_home(None, "").is_none());
+    }
+}
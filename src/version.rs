@@ -0,0 +1,67 @@
+//! `--version [--json]`: what this binary is, for support requests and fleet inventory.
+//! The same report is embedded in heartbeat.json and status.json.
+
+use serde::{Deserialize, Serialize};
+
+/// GLPI versions whose legacy REST API (`apirest.php`) this notifier is tested against.
+pub const SUPPORTED_GLPI: &[&str] = &["9.5", "10.0", "11.0"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub commit: String,
+    /// UTC build date (`YYYY-MM-DD HH:MM:SS`).
+    pub built: String,
+    pub target: String,
+    pub glpi: Vec<String>,
+    /// Cargo features compiled in.
+    pub features: Vec<String>,
+    /// Notification backends selectable with NOTIFIER.
+    pub sinks: Vec<String>,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        let built = env!("BUILD_TIMESTAMP").parse().ok().and_then(|ts| chrono::DateTime::from_timestamp(ts, 0));
+        Self {
+            version: env!("CARGO_PKG_VERSION").into(),
+            commit: env!("GIT_COMMIT").into(),
+            built: built.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default(),
+            target: env!("BUILD_TARGET").into(),
+            glpi: SUPPORTED_GLPI.iter().map(|v| v.to_string()).collect(),
+            features: enabled_features(),
+            sinks: crate::notify::BACKENDS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+/// Optional cargo features of this build.
+fn enabled_features() -> Vec<String> {
+    let features = [
+        ("toast-winrt", cfg!(feature = "toast-winrt")),
+        ("toast-snoretoast", cfg!(feature = "toast-snoretoast")),
+        ("notify-dbus", cfg!(feature = "notify-dbus")),
+        ("notify-macos", cfg!(feature = "notify-macos")),
+        ("windows-service", cfg!(feature = "windows-service")),
+        ("tray", cfg!(feature = "tray")),
+        ("webhook", cfg!(feature = "webhook")),
+        ("mqtt", cfg!(feature = "mqtt")),
+    ];
+    features.iter().filter(|(_, on)| *on).map(|(name, _)| name.to_string()).collect()
+}
+
+/// Print the report as text, or as JSON (`--version --json`).
+pub fn print(json: bool) -> anyhow::Result<()> {
+    let info = BuildInfo::current();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+    println!("glpi-notifier-rs {} ({})", info.version, info.commit);
+    println!("Built:     {} UTC for {}", info.built, info.target);
+    println!("GLPI:      {} (REST API apirest.php)", info.glpi.join(", "));
+    let features = if info.features.is_empty() { "none".to_string() } else { info.features.join(", ") };
+    println!("Features:  {features}");
+    println!("Notifiers: {}", info.sinks.join(", "));
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use crate::glpi::GlpiClient;
+use crate::state::now_secs;
+
+use log::{error, warn};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Consecutive restarts without a completed tick before giving up.
+const MAX_RESTARTS: u32 = 3;
+/// How long a stalled poller has to stop by itself (sign out, save its state) before it is aborted.
+const GRACE: Duration = Duration::from_secs(30);
+
+/// Set while the watchdog waits for a stalled poller to stop; part of the poller's stop flag.
+static RESTART: AtomicBool = AtomicBool::new(false);
+
+/// The watchdog asks the poller to stop, to start it again.
+pub fn restart_requested() -> bool {
+    RESTART.load(Ordering::Relaxed)
+}
+
+/// Liveness shared between the poll loop and the watchdog.
+pub static LIVENESS: Liveness = Liveness::new();
+
+#[derive(Debug)]
+pub struct Liveness {
+    last_tick: AtomicU64,
+    ticks: AtomicU64,
+    client: Mutex<Option<GlpiClient>>,
+}
+
+impl Liveness {
+    const fn new() -> Self {
+        Self { last_tick: AtomicU64::new(0), ticks: AtomicU64::new(0), client: Mutex::new(None) }
+    }
+
+    /// Mark the poller as alive (loop start, completed tick, or progress inside a long tick).
+    pub fn touch(&self) {
+        self.last_tick.store(now_secs(), Ordering::Relaxed);
+    }
+
+    /// Record a completed tick and remember the client so its session can be killed on a stall.
+    pub fn tick_done(&self, client: &GlpiClient) {
+        self.touch();
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+        *self.client.lock().unwrap() = Some(client.clone());
+    }
+
+    fn stalled_for(&self) -> u64 {
+        now_secs().saturating_sub(self.last_tick.load(Ordering::Relaxed))
+    }
+}
+
+/// The poller stopped completing ticks too many times in a row.
+#[derive(Debug)]
+pub struct Stalled(pub u32);
+
+impl std::fmt::Display for Stalled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "poller stalled {} times in a row", self.0)
+    }
+}
+
+impl std::error::Error for Stalled {}
+
+/// Run the poller produced by `spawn`, restarting it when no tick completes within `3 × poll_secs`: it is asked
+/// to stop through [`restart_requested`], and aborted when it has not within [`GRACE`]. Returns the poller's own result when it exits, or [`Stalled`] after `MAX_RESTARTS` consecutive stalls.
+pub async fn supervise<F>(poll_secs: u64, mut spawn: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> JoinHandle<anyhow::Result<()>>,
+{
+    let live = &LIVENESS;
+    let limit = 3 * poll_secs.max(1);
+    let mut restarts = 0;
+
+    loop {
+        live.touch();
+        let ticks_before = live.ticks.load(Ordering::Relaxed);
+        let mut handle = spawn();
+
+        loop {
+            tokio::select! {
+                res = &mut handle => return res?,
+                _ = tokio::time::sleep(Duration::from_secs(poll_secs.clamp(1, 30))) => {}
+            }
+            if live.stalled_for() > limit {
+                break;
+            }
+        }
+
+        warn!("Watchdog: no completed tick for over {limit}s; restarting poller.");
+        RESTART.store(true, Ordering::Relaxed);
+        let stopped = tokio::time::timeout(GRACE, &mut handle).await.is_ok();
+        RESTART.store(false, Ordering::Relaxed);
+        if !stopped {
+            // Stuck where it does not check its stop flag (hung request, deadlock)
+            warn!("Watchdog: poller still busy after {}s; aborting it.", GRACE.as_secs());
+            handle.abort();
+            let stale = live.client.lock().unwrap().take();
+            if let Some(mut client) = stale {
+                let _ = tokio::time::timeout(Duration::from_secs(10), client.kill_session()).await;
+            }
+        }
+
+        if live.ticks.load(Ordering::Relaxed) > ticks_before {
+            restarts = 0;
+        }
+        restarts += 1;
+        if restarts > MAX_RESTARTS {
+            error!("Watchdog: poller stalled {MAX_RESTARTS} times in a row; exiting.");
+            return Err(Stalled(MAX_RESTARTS).into());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! Push mode (WEBHOOK_LISTEN): a small HTTP listener that a GLPI webhook (GLPI 11 Setup > Webhooks, or the
+//! webhook plugin) POSTs ticket events to, so a new ticket is notified within a second instead of at the next
+//! poll. An event wakes the poll loop, whose tick reads the New queue as usual: the same filters, seen state and
+//! notifications, and nothing to trust in the payload. Polling stays the fallback for missed events; with push
+//! mode, POLL_SECONDS can be raised (e.g. 600).
+//!
+//! - `POST /webhook` wakes every poll loop, `POST /webhook/<instance>` the loop of one `[instances]` table;
+//! - `Authorization: Bearer <WEBHOOK_SECRET>` (or `X-Webhook-Token: <WEBHOOK_SECRET>`), required unless the
+//!   listener is on a loopback address;
+//! - a JSON body; its ticket id (`items_id`, `item.id`, `id`), if any, is only logged.
+//!
+//! Replies: 202 accepted, 400 not JSON, 401 bad token, 404 unknown path, 405 not a POST, 413 body over 1 MiB,
+//! 431 a line over 8 KiB or headers over 64 KiB. A minimal HTTP/1.1 server on a thread of its own, one request
+//! per connection, which has 5 seconds to arrive whole: no HTTPS, so put a reverse proxy in front of it when
+//! GLPI is on another network.
+
+use crate::exit::ConfigError;
+
+use anyhow::{anyhow, Result};
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+
+#[cfg(feature = "webhook")]
+mod server;
+
+/// Poll loops to wake, by instance (None: the top-level server).
+static WOKEN: Mutex<BTreeSet<Option<String>>> = Mutex::new(BTreeSet::new());
+
+/// Address of WEBHOOK_LISTEN (`127.0.0.1:8089`, `0.0.0.0:8089`...); an error in builds without the receiver.
+pub fn parse_listen(raw: &str) -> Result<SocketAddr> {
+    if cfg!(not(feature = "webhook")) {
+        return Err(unavailable().into());
+    }
+    raw.trim().parse().map_err(|_| anyhow!("expected ip:port, e.g. 127.0.0.1:8089"))
+}
+
+/// Start the listener of WEBHOOK_LISTEN, if set. Without the `webhook` feature any WEBHOOK_* setting is an error.
+pub fn start() -> Result<()> {
+    let raw = std::env::var("WEBHOOK_LISTEN").unwrap_or_default();
+    let secret = std::env::var("WEBHOOK_SECRET").ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    if cfg!(not(feature = "webhook")) && (!raw.trim().is_empty() || secret.is_some()) {
+        return Err(unavailable().into());
+    }
+    if raw.trim().is_empty() {
+        return Ok(());
+    }
+    let addr = parse_listen(&raw).map_err(|e| ConfigError(format!("WEBHOOK_LISTEN: {e}")))?;
+    if secret.is_none() && !addr.ip().is_loopback() {
+        return Err(
+            ConfigError(format!("WEBHOOK_LISTEN={addr} is reachable from other hosts: set WEBHOOK_SECRET")).into()
+        );
+    }
+    #[cfg(feature = "webhook")]
+    server::listen(addr, secret)?;
+    Ok(())
+}
+
+fn unavailable() -> ConfigError {
+    ConfigError("WEBHOOK_LISTEN / WEBHOOK_SECRET: this build has no webhook receiver (cargo feature webhook)".into())
+}
+
+/// Poller side: true when an event arrived for the calling poll loop's instance since the last call.
+pub fn take_event() -> bool {
+    WOKEN.lock().unwrap().remove(&crate::instance::current_name())
+}
@@ -0,0 +1,222 @@
+//! The HTTP/1.1 listener of push mode: one request per connection, read within [`TIMEOUT`].
+
+use super::WOKEN;
+
+use anyhow::{Context, Result};
+use log::{info, warn};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+const MAX_BODY: usize = 1 << 20;
+/// Longest request line or header line, and longest request head (request line and headers).
+const MAX_LINE: usize = 8 << 10;
+const MAX_HEAD: usize = 64 << 10;
+/// Time a connection has to send its whole request, and to take the reply.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request as read off the connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header names in lowercase.
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Read one line of the request head into `line`, within what is left of `head` bytes. Err is the status to
+/// reply with.
+fn read_head_line(reader: &mut impl BufRead, line: &mut String, head: &mut usize) -> Result<usize, u16> {
+    let limit = MAX_LINE.min(*head);
+    let read = reader.take(limit as u64 + 1).read_line(line).map_err(|_| 400u16)?;
+    if read > limit {
+        return Err(431);
+    }
+    *head -= read;
+    Ok(read)
+}
+
+/// Read one request; Err is the status to reply with.
+pub fn read_request(reader: &mut impl BufRead) -> Result<Request, u16> {
+    let mut line = String::new();
+    let mut head = MAX_HEAD;
+    read_head_line(reader, &mut line, &mut head)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else { return Err(400) };
+    let (method, path) = (method.to_string(), target.split('?').next().unwrap_or_default().to_string());
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        if read_head_line(reader, &mut line, &mut head)? == 0 || headers.len() > 100 {
+            return Err(400);
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header.split_once(':').ok_or(400u16)?;
+        headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+    }
+    let mut request = Request { method, path, headers, body: Vec::new() };
+    let length = match request.header("content-length") {
+        Some(n) => n.parse::<usize>().map_err(|_| 400u16)?,
+        None => 0,
+    };
+    if length > MAX_BODY {
+        return Err(413);
+    }
+    request.body.resize(length, 0);
+    reader.read_exact(&mut request.body).map_err(|_| 400u16)?;
+    Ok(request)
+}
+
+/// Equal strings, in a time that does not depend on where they differ.
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Ticket id of an event, where GLPI webhooks and the plugin put it.
+pub fn ticket_id(event: &Value) -> Option<i64> {
+    ["/items_id", "/item/id", "/ticket/id", "/tickets_id", "/id"].iter().find_map(|p| event.pointer(p)?.as_i64())
+}
+
+/// Status of the reply to `request`, and the poll loops to wake when accepted. `instances`: the names of the
+/// `[instances]` tables (empty without).
+pub fn handle(request: &Request, secret: Option<&str>, instances: &[&str]) -> (u16, Vec<Option<String>>) {
+    let targets = match request.path.trim_end_matches('/').strip_prefix("/webhook") {
+        Some("") if instances.is_empty() => vec![None],
+        Some("") => instances.iter().map(|i| Some(i.to_string())).collect(),
+        Some(rest) => match rest.strip_prefix('/').filter(|name| instances.contains(name)) {
+            Some(name) => vec![Some(name.to_string())],
+            None => return (404, Vec::new()),
+        },
+        None => return (404, Vec::new()),
+    };
+    if request.method != "POST" {
+        return (405, Vec::new());
+    }
+    if let Some(secret) = secret {
+        let bearer = request.header("authorization").and_then(|v| v.strip_prefix("Bearer "));
+        let token = bearer.or_else(|| request.header("x-webhook-token")).unwrap_or_default();
+        if !same_secret(token.trim(), secret) {
+            return (401, Vec::new());
+        }
+    }
+    let Ok(event) = serde_json::from_slice::<Value>(&request.body) else { return (400, Vec::new()) };
+    let event_name = event.get("event").and_then(Value::as_str).unwrap_or("event");
+    match ticket_id(&event) {
+        Some(id) => info!("Webhook: {event_name} on ticket #{id}"),
+        None => info!("Webhook: {event_name}"),
+    }
+    (202, targets)
+}
+
+/// Listen on `addr` on a thread of its own.
+pub fn listen(addr: SocketAddr, secret: Option<String>) -> Result<()> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("WEBHOOK_LISTEN: cannot listen on {addr}"))?;
+    info!("Webhook receiver listening on http://{addr}/webhook");
+    std::thread::spawn(move || {
+        let instances: Vec<&str> = crate::instance::all().iter().map(|i| i.name.as_str()).collect();
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => serve(stream, secret.as_deref(), &instances),
+                Err(e) => warn!("Webhook: {e}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// A connection read until a deadline: a client sending a byte now and then cannot hold the listener.
+struct Timed<'a> {
+    stream: &'a TcpStream,
+    until: Instant,
+}
+
+impl Read for Timed<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+fn serve(stream: TcpStream, secret: Option<&str>, instances: &[&str]) {
+    let _ = stream.set_write_timeout(Some(TIMEOUT));
+    let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+    let mut reader = BufReader::new(Timed { stream: &stream, until: Instant::now() + TIMEOUT });
+    let status = match read_request(&mut reader) {
+        Ok(request) => {
+            let (status, targets) = handle(&request, secret, instances);
+            WOKEN.lock().unwrap().extend(targets);
+            status
+        }
+        Err(status) => status,
+    };
+    if status >= 400 {
+        warn!("Webhook: request from {peer} refused ({status})");
+    }
+    let reason = match status {
+        202 => "Accepted",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Bad Request",
+    };
+    let reply = format!("HTTP/1.1 {status} {reason}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    let _ = (&stream).write_all(reply.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(raw: &str) -> Result<Request, u16> {
+        read_request(&mut raw.as_bytes())
+    }
+
+    #[test]
+    fn events_wake_the_poll_loop_of_their_instance() {
+        let body = r#"{"event":"new","itemtype":"Ticket","items_id":101}"#;
+        let raw = format!(
+            "POST /webhook HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer s3cret\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        let req = request(&raw).unwrap();
+        assert_eq!(req.body, body.as_bytes());
+        assert_eq!(ticket_id(&serde_json::from_slice(&req.body).unwrap()), Some(101));
+        assert_eq!(handle(&req, Some("s3cret"), &[]), (202, vec![None]));
+        assert_eq!(handle(&req, Some("other"), &[]).0, 401);
+        assert_eq!(handle(&req, None, &["prod", "sub"]), (202, vec![Some("prod".into()), Some("sub".into())]));
+
+        let to = |path: &str| Request { path: path.into(), ..req.clone() };
+        assert_eq!(handle(&to("/webhook/prod"), None, &["prod"]), (202, vec![Some("prod".into())]));
+        assert_eq!(handle(&to("/webhook/other"), None, &["prod"]).0, 404);
+        assert_eq!(handle(&to("/"), None, &[]).0, 404);
+        assert_eq!(handle(&Request { method: "GET".into(), ..req.clone() }, None, &[]).0, 405);
+        assert_eq!(handle(&Request { body: b"<xml/>".to_vec(), ..req.clone() }, None, &[]).0, 400);
+
+        assert_eq!(request("POST /webhook HTTP/1.1\r\nContent-Length: 9999999\r\n\r\n"), Err(413));
+        assert_eq!(request("garbage"), Err(400));
+        let long = format!("POST /webhook HTTP/1.1\r\nX-Pad: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        assert_eq!(request(&long), Err(431));
+        let many = format!("POST /webhook HTTP/1.1\r\n{}\r\n", format!("X-Pad: {}\r\n", "a".repeat(7000)).repeat(10));
+        assert_eq!(request(&many), Err(431));
+        let token =
+            request("POST /webhook HTTP/1.1\r\nX-Webhook-Token: s3cret\r\nContent-Length: 2\r\n\r\n{}").unwrap();
+        assert_eq!(handle(&token, Some("s3cret"), &[]).0, 202);
+    }
+}
@@ -0,0 +1,184 @@
+//! Local status file for companion widgets (Rainmeter, Polybar, PowerToys...).
+//!
+//! `%LOCALAPPDATA%\GlpiNotifier\status.json` is rewritten atomically (temp file + rename) after every
+//! poll, so widgets can watch the file for changes and never read a partial document. Schema version 1:
+//!
+//! ```json
+//! {
+//!   "schema": 1,            // bumped on incompatible changes only; fields may be added within a version
+//!   "updated": 1730970000,  // UNIX time of the last poll
+//!   "ok": true,             // last poll succeeded
+//!   "changed": 1730969940,  // UNIX time the New queue last changed
+//!   "queue": 3,             // tickets with status New, all pages (as of the last successful poll)
+//!   "newest": [{ "id": 101, "name": "Printer down", "priority": 4, "requester": "alice",
+//!                "tto": "TTO: 1h 20m business time left",     // tto only when an SLA applies
+//!                "refs": { "host": ["PRD-DB01"] },            // refs only when TICKET_REFS matches
+//!                "language": "eng" }],                         // language only when LANGUAGES detects it
+//!   "snoozed": 1,           // snoozed tickets
+//!   "mutes": 0,             // active mutes
+//!   "timers": 0,            // running time-tracking timers
+//!   "build": { "version": "0.2.0", "commit": "…", … }  // same report as `--version --json`
+//! }
+//! ```
+//!
+//! With `[instances]` in config.toml, the counts add up the instances, `ok` holds when each one's last poll
+//! succeeded, and `newest` entries carry their `"instance"`.
+
+use crate::glpi::Ticket;
+use crate::state::{now_secs, SeenState};
+use crate::tickets::TicketKey;
+use crate::version::BuildInfo;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Version of the status.json layout.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Tickets listed in `newest`.
+const NEWEST: usize = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WidgetStatus {
+    pub schema: u32,
+    pub updated: u64,
+    pub ok: bool,
+    pub changed: u64,
+    pub queue: usize,
+    pub newest: Vec<WidgetTicket>,
+    pub snoozed: usize,
+    pub mutes: usize,
+    pub timers: usize,
+    #[serde(default)]
+    pub build: BuildInfo,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WidgetTicket {
+    pub id: i64,
+    pub name: String,
+    pub priority: Option<i64>,
+    pub requester: Option<String>,
+    /// Time-to-own countdown as of the last poll (SLA_CALENDAR business hours).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tto: Option<String>,
+    /// References found in the title (TICKET_REFS), by rule name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub refs: BTreeMap<String, Vec<String>>,
+    /// Instance of config.toml the ticket comes from, when several are polled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Language of the ticket (ISO 639-3), when LANGUAGES is set and it was detected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// What the last polls of one instance (None: the top-level server) left to publish.
+#[derive(Default)]
+struct Polled {
+    ok: bool,
+    /// Queue of the last successful poll and when it changed, kept across failed polls.
+    queue: Option<(Vec<i64>, u64)>,
+    /// New tickets in GLPI at the last successful poll, beyond the ones listed in `queue` too.
+    count: usize,
+    newest: Vec<WidgetTicket>,
+    snoozed: usize,
+    mutes: usize,
+    timers: usize,
+}
+
+static LAST: Mutex<BTreeMap<Option<String>, Polled>> = Mutex::new(BTreeMap::new());
+
+/// Instance and id of the tickets of the New queue at the last successful polls, newest first.
+pub fn queue_ids() -> Vec<TicketKey> {
+    let last = LAST.lock().unwrap();
+    let mut ids: Vec<TicketKey> = last
+        .iter()
+        .flat_map(|(instance, p)| p.queue.iter().flat_map(|(ids, _)| ids).map(|id| (instance.clone(), *id)))
+        .collect();
+    ids.sort_by_key(|(_, id)| std::cmp::Reverse(*id));
+    ids
+}
+
+fn status_path() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("GlpiNotifier");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("status.json"))
+}
+
+/// Publish the result of a poll; `polled` is the New queue read and GLPI's count of New tickets (more when the
+/// queue is longer than a search page), or None when the poll failed.
+pub fn publish(polled: Option<(&[Ticket], u64)>, st: &SeenState) {
+    let tickets = polled.map(|(tickets, _)| tickets);
+    let now = now_secs();
+    let mut all = LAST.lock().unwrap();
+    let last = all.entry(crate::instance::current_name()).or_default();
+    last.ok = tickets.is_some();
+    last.snoozed = st.snoozed.len();
+    last.mutes = st.mutes.iter().filter(|m| m.until > now).count();
+    last.timers = st.timers.len();
+
+    if let Some((tickets, count)) = polled {
+        let mut ids: Vec<i64> = tickets.iter().map(|t| t.id).collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        let count = usize::try_from(count).unwrap_or(usize::MAX).max(ids.len());
+        if last.queue.as_ref().is_none_or(|(prev, _)| *prev != ids) || last.count != count {
+            last.queue = Some((ids, now));
+        }
+        last.count = count;
+        let mut sorted: Vec<&Ticket> = tickets.iter().collect();
+        sorted.sort_by_key(|t| -t.id);
+        last.newest = sorted
+            .into_iter()
+            .take(NEWEST)
+            .map(|t| WidgetTicket {
+                id: t.id,
+                name: t.name.clone(),
+                priority: t.priority,
+                requester: t.requester.clone(),
+                tto: t.time_to_own.as_deref().and_then(|d| crate::calendar::countdown("TTO", d)),
+                refs: crate::refs::of(t),
+                instance: t.instance.clone(),
+                language: t.language.clone(),
+            })
+            .collect();
+    }
+
+    let queue = all.values().filter(|p| p.queue.is_some()).map(|p| p.count).sum();
+    let changed = all.values().filter_map(|p| p.queue.as_ref()).map(|(_, at)| *at).max().unwrap_or(0);
+    let mut newest: Vec<WidgetTicket> = all.values().flat_map(|p| p.newest.iter().cloned()).collect();
+    newest.sort_by_key(|t| -t.id);
+    newest.truncate(NEWEST);
+    let status = WidgetStatus {
+        schema: SCHEMA_VERSION,
+        updated: now,
+        ok: all.values().all(|p| p.ok),
+        changed,
+        queue,
+        newest,
+        snoozed: all.values().map(|p| p.snoozed).sum(),
+        mutes: all.values().map(|p| p.mutes).sum(),
+        timers: all.values().map(|p| p.timers).sum(),
+        build: BuildInfo::current(),
+    };
+    drop(all);
+    write_atomic(&status);
+    crate::mqtt::publish_status(&status);
+    crate::tray::update(crate::tray::Update::Polled(tickets.map(|_| queue)));
+}
+
+/// Last status written by the poller, if readable.
+pub fn read_status() -> Option<WidgetStatus> {
+    serde_json::from_slice(&std::fs::read(status_path()?).ok()?).ok()
+}
+
+fn write_atomic(status: &WidgetStatus) {
+    let Some(path) = status_path() else { return };
+    let Ok(payload) = serde_json::to_vec_pretty(status) else { return };
+    let tmp = path.with_extension("json.tmp");
+    if std::fs::write(&tmp, payload).is_ok() {
+        let _ = std::fs::rename(&tmp, &path);
+    }
+}
@@ -0,0 +1,82 @@
+//! `configure-filters`: builds TICKET_FILTER from checklists of the entities, categories and groups read live
+//! from GLPI, so nobody has to look up search option or item ids by hand.
+//!
+//! Only the `entity`, `category` and `group` clauses are managed here; other clauses of the current filter
+//! (`priority morethan 3`...) are kept as they are. Current selections are pre-checked.
+
+use crate::config::Connection;
+use crate::exit::ConfigError;
+use crate::filter::{Criterion, FieldRef, SearchType};
+use crate::glpi::GlpiClient;
+
+use anyhow::Result;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, MultiSelect};
+use std::io::IsTerminal;
+
+/// Checklists offered, in order: (itemtype, label, filter field, operator).
+const LISTS: &[(&str, &str, FieldRef, SearchType)] = &[
+    ("Entity", "Entities (and their sub-entities)", FieldRef::Entity, SearchType::Under),
+    ("ITILCategory", "Categories (and their sub-categories)", FieldRef::Category, SearchType::Under),
+    ("Group", "Assigned groups", FieldRef::Group, SearchType::Equals),
+];
+
+pub async fn run() -> Result<()> {
+    if !std::io::stdin().is_terminal() {
+        return Err(ConfigError("configure-filters is interactive: run it in a terminal".into()).into());
+    }
+    // TICKET_FILTER alone: MY_LOCATIONS stays a setting of its own
+    let current = crate::filter::parse(&std::env::var("TICKET_FILTER").unwrap_or_default())?;
+    let conn = Connection::from_env()?;
+
+    let mut client = conn.connect().await?;
+    let items = fetch_lists(&mut client).await;
+    let _ = client.kill_session().await;
+    let items = items?;
+
+    let theme = ColorfulTheme::default();
+    let mut filter: Vec<Criterion> =
+        current.iter().filter(|c| !LISTS.iter().any(|(_, _, field, _)| c.field == *field)).cloned().collect();
+    for ((_, label, field, searchtype), items) in LISTS.iter().zip(&items) {
+        if items.is_empty() {
+            println!("{label}: none visible with this profile, skipped.");
+            continue;
+        }
+        let checked = selected(&current, field);
+        let choices: Vec<(&str, bool)> = items.iter().map(|(id, name)| (name.as_str(), checked.contains(id))).collect();
+        let picked = MultiSelect::with_theme(&theme)
+            .with_prompt(format!("{label} to watch (space toggles, enter confirms, none = all)"))
+            .items_checked(&choices)
+            .max_length(15)
+            .interact()?;
+        if !picked.is_empty() {
+            let value = picked.iter().map(|&i| items[i].0.to_string()).collect::<Vec<_>>().join(",");
+            filter.push(Criterion { field: field.clone(), searchtype: *searchtype, value });
+        }
+    }
+
+    let value = filter.iter().map(Criterion::to_string).collect::<Vec<_>>().join("; ");
+    println!("\nTICKET_FILTER={value}");
+    if filter == current {
+        println!("Unchanged.");
+        return Ok(());
+    }
+    if Confirm::with_theme(&theme).with_prompt("Save it?").default(true).interact()? {
+        let file = crate::config::save_setting("TICKET_FILTER", &value)?;
+        println!("Saved to {}; restart the notifier to apply it.", file.display());
+    }
+    Ok(())
+}
+
+async fn fetch_lists(client: &mut GlpiClient) -> Result<Vec<Vec<(i64, String)>>> {
+    let mut lists = Vec::new();
+    for (itemtype, ..) in LISTS {
+        lists.push(client.dropdown_items(itemtype).await?);
+    }
+    Ok(lists)
+}
+
+/// Item ids chosen by the current filter for this field.
+fn selected(current: &[Criterion], field: &FieldRef) -> Vec<i64> {
+    current.iter().filter(|c| c.field == *field).flat_map(|c| c.values()).filter_map(|v| v.parse().ok()).collect()
+}